@@ -1,11 +1,141 @@
-use crate::scalar::Scalar;
+use crate::scalar::ScalarType;
+#[cfg(not(target_arch = "spirv"))]
+use bytemuck::Pod;
 #[cfg(not(target_arch = "spirv"))]
 use core::marker::PhantomData;
 use core::ops::Index;
+#[cfg(not(target_arch = "spirv"))]
+use core::sync::atomic::{self, Ordering};
 #[cfg(target_arch = "spirv")]
 use core::{arch::asm, mem::MaybeUninit};
 #[cfg(target_arch = "spirv")]
-use spirv_std::arch::IndexUnchecked;
+use spirv_std::arch::{self, IndexUnchecked};
+
+/** Layout of an [`Element`].
+
+The `size` and `align` are in bytes, with `align` following the std430 rules used for storage
+buffers. The `scalar_type` is used to view the buffer as a scalar buffer, ie for copies. */
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+pub struct ElementLayout {
+    /// The scalar type that the element is composed of.
+    pub scalar_type: ScalarType,
+    /// The size in bytes.
+    pub size: usize,
+    /// The std430 alignment in bytes.
+    pub align: usize,
+}
+
+impl ElementLayout {
+    /// The layout of a scalar.
+    #[inline]
+    pub const fn scalar(scalar_type: ScalarType) -> Self {
+        let size = scalar_type.size();
+        Self {
+            scalar_type,
+            size,
+            align: size,
+        }
+    }
+    /// The layout of an array with `len` elements.
+    #[inline]
+    pub const fn array(elem: Self, len: usize) -> Self {
+        Self {
+            size: elem.size * len,
+            ..elem
+        }
+    }
+    // For derive macro.
+    #[doc(hidden)]
+    pub const fn from_fields(fields: &[Self], size: usize) -> Self {
+        if fields.is_empty() {
+            panic!("Element must have at least one field!");
+        }
+        let mut offset = 0;
+        let mut align = 1;
+        let mut i = 0;
+        while i < fields.len() {
+            let field = fields[i];
+            if offset % field.align != 0 {
+                panic!("Element field offset is not aligned for std430!");
+            }
+            offset += field.size;
+            if field.align > align {
+                align = field.align;
+            }
+            i += 1;
+        }
+        if offset != size {
+            panic!("Element must not have padding!");
+        }
+        if size % align != 0 {
+            panic!("Element size is not a multiple of its std430 alignment!");
+        }
+        let scalar_type = match align {
+            1 => ScalarType::U8,
+            2 => ScalarType::U16,
+            _ => ScalarType::U32,
+        };
+        Self {
+            scalar_type,
+            size,
+            align,
+        }
+    }
+}
+
+/** Element of a buffer.
+
+# Safety
+[`LAYOUT`](Element::LAYOUT) must match the type. */
+#[cfg(target_arch = "spirv")]
+pub unsafe trait Element: Copy + Send + Sync + 'static {
+    /// The layout of the element.
+    const LAYOUT: ElementLayout;
+}
+
+/** Element of a buffer.
+
+Implemented for [scalars](crate::scalar::Scalar), arrays of elements, and `#[repr(C)]` structs
+with `#[derive(Element)]`.
+
+The derive checks at compile time that each field is an [`Element`], aligned for std430, and that
+there is no padding. It also implements [`Pod`](bytemuck::Pod) and [`Zeroable`](bytemuck::Zeroable) on the host.
+
+On the host, the derive refers to `::krnl`, or to the krnl of the enclosing `#[module]`. This can be
+overridden with `#[krnl(crate = ..)]`.
+```ignore
+use krnl::macros::Element;
+
+#[derive(Clone, Copy, Element)]
+#[repr(C)]
+pub struct Particle {
+    pub position: [f32; 3],
+    pub mass: f32,
+}
+```
+
+# Safety
+[`LAYOUT`](Element::LAYOUT) must match the type. Prefer the derive, which is checked. */
+#[cfg(not(target_arch = "spirv"))]
+#[cfg_attr(doc_cfg, doc(cfg(all())))]
+pub unsafe trait Element: Copy + Send + Sync + 'static + Pod {
+    /// The layout of the element.
+    const LAYOUT: ElementLayout;
+}
+
+#[cfg(target_arch = "spirv")]
+unsafe impl<T: Element, const N: usize> Element for [T; N] {
+    const LAYOUT: ElementLayout = ElementLayout::array(T::LAYOUT, N);
+}
+
+#[cfg(not(target_arch = "spirv"))]
+unsafe impl<T: Element, const N: usize> Element for [T; N]
+where
+    [T; N]: Pod,
+{
+    const LAYOUT: ElementLayout = ElementLayout::array(T::LAYOUT, N);
+}
 
 /** Unsafe Index trait.
 
@@ -56,8 +186,8 @@ use sealed::Sealed;
 /// Base trait for [`BufferBase`] representation.
 #[allow(clippy::len_without_is_empty)]
 pub trait DataBase: Sealed {
-    /// The numerical type of the buffer.
-    type Elem: Scalar;
+    /// The element type of the buffer.
+    type Elem: Element;
     #[doc(hidden)]
     fn len(&self) -> usize;
 }
@@ -86,7 +216,7 @@ pub struct SliceRepr<'a, T> {
 
 impl<T> Sealed for SliceRepr<'_, T> {}
 
-impl<T: Scalar> DataBase for SliceRepr<'_, T> {
+impl<T: Element> DataBase for SliceRepr<'_, T> {
     type Elem = T;
     #[cfg(not(target_arch = "spirv"))]
     #[inline]
@@ -100,7 +230,7 @@ impl<T: Scalar> DataBase for SliceRepr<'_, T> {
     }
 }
 
-impl<T: Scalar> Index<usize> for SliceRepr<'_, T> {
+impl<T: Element> Index<usize> for SliceRepr<'_, T> {
     type Output = T;
     #[inline]
     fn index(&self, index: usize) -> &Self::Output {
@@ -116,7 +246,7 @@ impl<T: Scalar> Index<usize> for SliceRepr<'_, T> {
     }
 }
 
-impl<T: Scalar> Data for SliceRepr<'_, T> {}
+impl<T: Element> Data for SliceRepr<'_, T> {}
 
 /// [`UnsafeSlice`] representation.
 #[derive(Clone, Copy)]
@@ -136,7 +266,7 @@ pub struct UnsafeSliceRepr<'a, T> {
 
 impl<T> Sealed for UnsafeSliceRepr<'_, T> {}
 
-impl<T: Scalar> DataBase for UnsafeSliceRepr<'_, T> {
+impl<T: Element> DataBase for UnsafeSliceRepr<'_, T> {
     type Elem = T;
     #[inline]
     fn len(&self) -> usize {
@@ -144,7 +274,7 @@ impl<T: Scalar> DataBase for UnsafeSliceRepr<'_, T> {
     }
 }
 
-impl<T: Scalar> UnsafeIndex<usize> for UnsafeSliceRepr<'_, T> {
+impl<T: Element> UnsafeIndex<usize> for UnsafeSliceRepr<'_, T> {
     type Output = T;
     #[inline]
     unsafe fn unsafe_index(&self, index: usize) -> &Self::Output {
@@ -180,7 +310,7 @@ impl<T: Scalar> UnsafeIndex<usize> for UnsafeSliceRepr<'_, T> {
     }
 }
 
-impl<T: Scalar> UnsafeData for UnsafeSliceRepr<'_, T> {}
+impl<T: Element> UnsafeData for UnsafeSliceRepr<'_, T> {}
 
 unsafe impl<T: Send> Send for UnsafeSliceRepr<'_, T> {}
 unsafe impl<T: Sync> Sync for UnsafeSliceRepr<'_, T> {}
@@ -239,7 +369,7 @@ impl<S: UnsafeData> UnsafeIndex<usize> for BufferBase<S> {
     }
 }
 
-impl<'a, T: Scalar> Slice<'a, T> {
+impl<'a, T: Element> Slice<'a, T> {
     // For kernel macro.
     #[doc(hidden)]
     #[cfg(target_arch = "spirv")]
@@ -256,7 +386,7 @@ impl<'a, T: Scalar> Slice<'a, T> {
     }
 }
 
-impl<'a, T: Scalar> UnsafeSlice<'a, T> {
+impl<'a, T: Element> UnsafeSlice<'a, T> {
     // For kernel macro.
     #[doc(hidden)]
     #[cfg(target_arch = "spirv")]
@@ -278,7 +408,7 @@ impl<'a, T: Scalar> UnsafeSlice<'a, T> {
 }
 
 #[cfg(not(target_arch = "spirv"))]
-impl<'a, T: Scalar> From<&'a [T]> for Slice<'a, T> {
+impl<'a, T: Element> From<&'a [T]> for Slice<'a, T> {
     #[inline]
     fn from(slice: &'a [T]) -> Self {
         let data = SliceRepr { inner: slice };
@@ -287,7 +417,7 @@ impl<'a, T: Scalar> From<&'a [T]> for Slice<'a, T> {
 }
 
 #[cfg(not(target_arch = "spirv"))]
-impl<'a, T: Scalar> From<Slice<'a, T>> for &'a [T] {
+impl<'a, T: Element> From<Slice<'a, T>> for &'a [T] {
     #[inline]
     fn from(slice: Slice<'a, T>) -> &'a [T] {
         slice.data.inner
@@ -295,7 +425,7 @@ impl<'a, T: Scalar> From<Slice<'a, T>> for &'a [T] {
 }
 
 #[cfg(not(target_arch = "spirv"))]
-impl<'a, T: Scalar> From<&'a mut [T]> for UnsafeSlice<'a, T> {
+impl<'a, T: Element> From<&'a mut [T]> for UnsafeSlice<'a, T> {
     #[inline]
     fn from(slice: &'a mut [T]) -> Self {
         let data = UnsafeSliceRepr {
@@ -306,3 +436,432 @@ impl<'a, T: Scalar> From<&'a mut [T]> for UnsafeSlice<'a, T> {
         Self { data }
     }
 }
+
+#[cfg(target_arch = "spirv")]
+const ATOMIC_SCOPE: u32 = spirv_std::memory::Scope::Device as u32;
+#[cfg(target_arch = "spirv")]
+const ATOMIC_SEMANTICS: u32 = spirv_std::memory::Semantics::NONE.bits();
+
+impl<T: Element> UnsafeSliceRepr<'_, T> {
+    #[cfg(target_arch = "spirv")]
+    #[allow(clippy::mut_from_ref)]
+    #[inline]
+    fn atomic_ptr(&self, index: usize) -> &mut T {
+        // Only accessed atomically.
+        unsafe { self.unsafe_index_mut(index) }
+    }
+    /// # Safety
+    /// `A` must be the atomic type for `T`.
+    #[cfg(not(target_arch = "spirv"))]
+    #[inline]
+    unsafe fn atomic<A>(&self, index: usize) -> &A {
+        if index < self.len {
+            let ptr = unsafe { self.ptr.add(index) };
+            assert_eq!(
+                ptr as usize % core::mem::align_of::<A>(),
+                0,
+                "atomic is not aligned"
+            );
+            unsafe { &*(ptr as *const A) }
+        } else {
+            let len = self.len;
+            panic!("index out of bounds: the len is {index} but the index is {len}")
+        }
+    }
+}
+
+macro_rules! impl_atomic_int {
+    ($($t:ty => $atomic:ident, $min:ident, $max:ident;)*) => {
+        $(
+            /** Atomics.
+
+            Atomics are relaxed, they do not synchronize other memory accesses. Can also be used
+            with group buffers. 64 bit atomics require the `BUFFER_INT64_ATOMICS` feature, or
+            `GROUP_INT64_ATOMICS` for group buffers.
+
+            Panics if `index` is out of bounds. */
+            impl UnsafeSlice<'_, $t> {
+                /// Adds `value`, returning the previous value.
+                #[inline]
+                pub fn atomic_add(&self, index: usize, value: $t) -> $t {
+                    #[cfg(target_arch = "spirv")]
+                    unsafe {
+                        arch::atomic_i_add::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                            self.data.atomic_ptr(index),
+                            value,
+                        )
+                    }
+                    #[cfg(not(target_arch = "spirv"))]
+                    unsafe {
+                        self.data
+                            .atomic::<atomic::$atomic>(index)
+                            .fetch_add(value, Ordering::Relaxed)
+                    }
+                }
+                /// Stores the minimum of the value and `value`, returning the previous value.
+                #[inline]
+                pub fn atomic_min(&self, index: usize, value: $t) -> $t {
+                    #[cfg(target_arch = "spirv")]
+                    unsafe {
+                        arch::$min::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                            self.data.atomic_ptr(index),
+                            value,
+                        )
+                    }
+                    #[cfg(not(target_arch = "spirv"))]
+                    unsafe {
+                        self.data
+                            .atomic::<atomic::$atomic>(index)
+                            .fetch_min(value, Ordering::Relaxed)
+                    }
+                }
+                /// Stores the maximum of the value and `value`, returning the previous value.
+                #[inline]
+                pub fn atomic_max(&self, index: usize, value: $t) -> $t {
+                    #[cfg(target_arch = "spirv")]
+                    unsafe {
+                        arch::$max::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                            self.data.atomic_ptr(index),
+                            value,
+                        )
+                    }
+                    #[cfg(not(target_arch = "spirv"))]
+                    unsafe {
+                        self.data
+                            .atomic::<atomic::$atomic>(index)
+                            .fetch_max(value, Ordering::Relaxed)
+                    }
+                }
+                /// Bitwise and with `value`, returning the previous value.
+                #[inline]
+                pub fn atomic_and(&self, index: usize, value: $t) -> $t {
+                    #[cfg(target_arch = "spirv")]
+                    unsafe {
+                        arch::atomic_and::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                            self.data.atomic_ptr(index),
+                            value,
+                        )
+                    }
+                    #[cfg(not(target_arch = "spirv"))]
+                    unsafe {
+                        self.data
+                            .atomic::<atomic::$atomic>(index)
+                            .fetch_and(value, Ordering::Relaxed)
+                    }
+                }
+                /// Bitwise or with `value`, returning the previous value.
+                #[inline]
+                pub fn atomic_or(&self, index: usize, value: $t) -> $t {
+                    #[cfg(target_arch = "spirv")]
+                    unsafe {
+                        arch::atomic_or::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                            self.data.atomic_ptr(index),
+                            value,
+                        )
+                    }
+                    #[cfg(not(target_arch = "spirv"))]
+                    unsafe {
+                        self.data
+                            .atomic::<atomic::$atomic>(index)
+                            .fetch_or(value, Ordering::Relaxed)
+                    }
+                }
+                /// Bitwise xor with `value`, returning the previous value.
+                #[inline]
+                pub fn atomic_xor(&self, index: usize, value: $t) -> $t {
+                    #[cfg(target_arch = "spirv")]
+                    unsafe {
+                        arch::atomic_xor::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                            self.data.atomic_ptr(index),
+                            value,
+                        )
+                    }
+                    #[cfg(not(target_arch = "spirv"))]
+                    unsafe {
+                        self.data
+                            .atomic::<atomic::$atomic>(index)
+                            .fetch_xor(value, Ordering::Relaxed)
+                    }
+                }
+                /// Stores `value`, returning the previous value.
+                #[inline]
+                pub fn atomic_exchange(&self, index: usize, value: $t) -> $t {
+                    #[cfg(target_arch = "spirv")]
+                    unsafe {
+                        arch::atomic_exchange::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                            self.data.atomic_ptr(index),
+                            value,
+                        )
+                    }
+                    #[cfg(not(target_arch = "spirv"))]
+                    unsafe {
+                        self.data
+                            .atomic::<atomic::$atomic>(index)
+                            .swap(value, Ordering::Relaxed)
+                    }
+                }
+                /// Stores `new` if the value is `current`.
+                ///
+                /// Returns the previous value, `Ok` if it was `current`.
+                #[inline]
+                pub fn compare_exchange(
+                    &self,
+                    index: usize,
+                    current: $t,
+                    new: $t,
+                ) -> Result<$t, $t> {
+                    #[cfg(target_arch = "spirv")]
+                    {
+                        let previous = unsafe {
+                            arch::atomic_compare_exchange::<
+                                _,
+                                ATOMIC_SCOPE,
+                                ATOMIC_SEMANTICS,
+                                ATOMIC_SEMANTICS,
+                            >(self.data.atomic_ptr(index), new, current)
+                        };
+                        if previous == current {
+                            Ok(previous)
+                        } else {
+                            Err(previous)
+                        }
+                    }
+                    #[cfg(not(target_arch = "spirv"))]
+                    unsafe {
+                        self.data.atomic::<atomic::$atomic>(index).compare_exchange(
+                            current,
+                            new,
+                            Ordering::Relaxed,
+                            Ordering::Relaxed,
+                        )
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_atomic_int! {
+    u32 => AtomicU32, atomic_u_min, atomic_u_max;
+    i32 => AtomicI32, atomic_s_min, atomic_s_max;
+    u64 => AtomicU64, atomic_u_min, atomic_u_max;
+    i64 => AtomicI64, atomic_s_min, atomic_s_max;
+}
+
+/** Atomics.
+
+Atomics are relaxed, they do not synchronize other memory accesses. Can also be used with group
+buffers. Requires the `BUFFER_FLOAT32_ATOMIC_ADD`, `BUFFER_FLOAT32_ATOMIC_MIN_MAX`, and
+`BUFFER_FLOAT32_ATOMICS` features respectively, or the `GROUP_` features for group buffers.
+
+Panics if `index` is out of bounds. */
+impl UnsafeSlice<'_, f32> {
+    /// Adds `value`, returning the previous value.
+    #[inline]
+    pub fn atomic_add(&self, index: usize, value: f32) -> f32 {
+        #[cfg(target_arch = "spirv")]
+        unsafe {
+            arch::atomic_f_add::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                self.data.atomic_ptr(index),
+                value,
+            )
+        }
+        #[cfg(not(target_arch = "spirv"))]
+        self.atomic_update(index, |x| x + value)
+    }
+    /// Stores the minimum of the value and `value`, returning the previous value.
+    #[inline]
+    pub fn atomic_min(&self, index: usize, value: f32) -> f32 {
+        #[cfg(target_arch = "spirv")]
+        unsafe {
+            arch::atomic_f_min::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                self.data.atomic_ptr(index),
+                value,
+            )
+        }
+        #[cfg(not(target_arch = "spirv"))]
+        self.atomic_update(index, |x| x.min(value))
+    }
+    /// Stores the maximum of the value and `value`, returning the previous value.
+    #[inline]
+    pub fn atomic_max(&self, index: usize, value: f32) -> f32 {
+        #[cfg(target_arch = "spirv")]
+        unsafe {
+            arch::atomic_f_max::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                self.data.atomic_ptr(index),
+                value,
+            )
+        }
+        #[cfg(not(target_arch = "spirv"))]
+        self.atomic_update(index, |x| x.max(value))
+    }
+    /// Stores `value`, returning the previous value.
+    #[inline]
+    pub fn atomic_exchange(&self, index: usize, value: f32) -> f32 {
+        #[cfg(target_arch = "spirv")]
+        unsafe {
+            arch::atomic_exchange::<_, ATOMIC_SCOPE, ATOMIC_SEMANTICS>(
+                self.data.atomic_ptr(index),
+                value,
+            )
+        }
+        #[cfg(not(target_arch = "spirv"))]
+        self.atomic_update(index, |_| value)
+    }
+    #[cfg(not(target_arch = "spirv"))]
+    #[inline]
+    fn atomic_update(&self, index: usize, mut f: impl FnMut(f32) -> f32) -> f32 {
+        let atomic = unsafe { self.data.atomic::<atomic::AtomicU32>(index) };
+        let previous = atomic
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| {
+                Some(f(f32::from_bits(x)).to_bits())
+            })
+            .unwrap();
+        f32::from_bits(previous)
+    }
+}
+
+/** Group memory with checked access.
+
+Declared with `#[group] x: GroupSlice<T, N>`. Access is split into phases separated by
+[barriers](GroupSlice::barrier), so that group memory can be used without `unsafe`:
+
+- [`GroupSlice`]: Each thread writes its own slots, `thread_id + i * threads`.
+- [`GroupSliceRead`]: After a barrier, any thread can read any element.
+
+Barriers must be reached by all threads in the group, ie not within a branch that depends on the
+thread id. krnlc warns when this can be detected. */
+pub struct GroupSlice<'a, T> {
+    inner: UnsafeSlice<'a, T>,
+    thread_id: usize,
+    threads: usize,
+}
+
+/** Read phase of a [`GroupSlice`].
+
+Implements [`Index`]. See [`GroupSlice`]. */
+pub struct GroupSliceRead<'a, T> {
+    inner: UnsafeSlice<'a, T>,
+    thread_id: usize,
+    threads: usize,
+}
+
+#[inline]
+fn group_barrier() {
+    #[cfg(target_arch = "spirv")]
+    unsafe {
+        arch::workgroup_memory_barrier_with_group_sync();
+    }
+}
+
+impl<'a, T: Element> GroupSlice<'a, T> {
+    // For kernel macro.
+    #[doc(hidden)]
+    #[inline]
+    pub unsafe fn from_unsafe_slice(
+        inner: UnsafeSlice<'a, T>,
+        thread_id: usize,
+        threads: usize,
+    ) -> Self {
+        Self {
+            inner,
+            thread_id,
+            threads,
+        }
+    }
+    /// The length of the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    /// Whether the buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+    /// The number of slots owned by this thread.
+    ///
+    /// `thread_slots = (len - thread_id).div_ceil(threads)`
+    #[inline]
+    pub fn thread_slots(&self) -> usize {
+        let len = self.len();
+        if self.thread_id < len {
+            (len - self.thread_id + self.threads - 1) / self.threads
+        } else {
+            0
+        }
+    }
+    /// Writes `value` to the slot at `thread_id`.
+    ///
+    /// Panics if `thread_id` is out of bounds.
+    #[inline]
+    pub fn write(&self, value: T) {
+        self.write_strided(0, value);
+    }
+    /// Writes `value` to the slot at `thread_id + i * threads`.
+    ///
+    /// Panics if the slot is out of bounds.
+    #[inline]
+    pub fn write_strided(&self, i: usize, value: T) {
+        let index = self.thread_id + i * self.threads;
+        unsafe {
+            *self.inner.unsafe_index_mut(index) = value;
+        }
+    }
+    /// Synchronizes the group, so that all writes are visible.
+    ///
+    /// Must be reached by all threads in the group.
+    #[inline]
+    pub fn barrier(self) -> GroupSliceRead<'a, T> {
+        group_barrier();
+        let Self {
+            inner,
+            thread_id,
+            threads,
+        } = self;
+        GroupSliceRead {
+            inner,
+            thread_id,
+            threads,
+        }
+    }
+}
+
+impl<'a, T: Element> GroupSliceRead<'a, T> {
+    /// The length of the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    /// Whether the buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+    /// Synchronizes the group, so that all reads are finished.
+    ///
+    /// Must be reached by all threads in the group.
+    #[inline]
+    pub fn barrier(self) -> GroupSlice<'a, T> {
+        group_barrier();
+        let Self {
+            inner,
+            thread_id,
+            threads,
+        } = self;
+        GroupSlice {
+            inner,
+            thread_id,
+            threads,
+        }
+    }
+}
+
+impl<T: Element> Index<usize> for GroupSliceRead<'_, T> {
+    type Output = T;
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        // no writes occur until the next barrier
+        unsafe { self.inner.unsafe_index(index) }
+    }
+}
@@ -5,15 +5,16 @@ pub mod __private {
     use core::mem::size_of;
 
     pub struct KernelArgs {
-        pub global_id: u32,
-        pub groups: u32,
-        pub group_id: u32,
+        pub global_id: [u32; 3],
+        pub groups: [u32; 3],
+        pub group_id: [u32; 3],
         pub subgroups: u32,
         pub subgroup_id: u32,
         //pub subgroup_threads: u32,
         pub subgroup_thread_id: u32,
-        pub threads: u32,
-        pub thread_id: u32,
+        pub threads: [u32; 3],
+        pub thread_id: [u32; 3],
+        pub thread_index: u32,
     }
 
     #[allow(deprecated)]
@@ -30,18 +31,31 @@ pub mod __private {
                 subgroup_thread_id,
                 threads,
                 thread_id,
+                thread_index,
             } = self;
+            let [groups_x, groups_y, groups_z] = groups;
+            let [threads_x, threads_y, threads_z] = threads;
+            let [group_id_x, group_id_y, group_id_z] = group_id;
+            let groups_linear = groups_x * groups_y * groups_z;
+            let threads_linear = threads_x * threads_y * threads_z;
+            // the host limits global threads to u32::MAX, so these do not overflow
+            let group_index = group_id_x + groups_x * (group_id_y + groups_y * group_id_z);
             Kernel {
-                global_threads: groups * threads,
-                global_id,
-                groups,
-                group_id,
+                global_threads: groups_linear * threads_linear,
+                global_id: group_index * threads_linear + thread_index,
+                groups: groups_linear,
+                group_id: group_index,
                 subgroups,
                 subgroup_id,
                 //subgroup_threads,
                 subgroup_thread_id,
-                threads,
-                thread_id,
+                threads: threads_linear,
+                thread_id: thread_index,
+                global_id_xyz: global_id,
+                groups_xyz: groups,
+                group_id_xyz: group_id,
+                threads_xyz: threads,
+                thread_id_xyz: thread_id,
             }
         }
     }
@@ -142,6 +156,11 @@ pub struct Kernel {
     subgroup_thread_id: u32,
     threads: u32,
     thread_id: u32,
+    global_id_xyz: [u32; 3],
+    groups_xyz: [u32; 3],
+    group_id_xyz: [u32; 3],
+    threads_xyz: [u32; 3],
+    thread_id_xyz: [u32; 3],
 }
 
 impl Kernel {
@@ -160,11 +179,15 @@ impl Kernel {
         self.global_id as usize
     }
     /// The number of thread groups.
+    ///
+    /// `groups = groups_x * groups_y * groups_z`
     #[inline]
     pub fn groups(&self) -> usize {
         self.groups as usize
     }
     /// The group id.
+    ///
+    /// `group_id = group_id_x + groups_x * (group_id_y + groups_y * group_id_z)`
     #[inline]
     pub fn group_id(&self) -> usize {
         self.group_id as usize
@@ -193,15 +216,64 @@ impl Kernel {
         self.subgroup_thread_id as usize
     }
     /// The number of threads per group.
+    ///
+    /// `threads = threads_x * threads_y * threads_z`
     #[inline]
     pub fn threads(&self) -> usize {
         self.threads as usize
     }
     /// The thread id.
+    ///
+    /// `thread_id = thread_id_x + threads_x * (thread_id_y + threads_y * thread_id_z)`
     #[inline]
     pub fn thread_id(&self) -> usize {
         self.thread_id as usize
     }
+    /// The number of global threads in each dimension.
+    ///
+    /// `global_threads_xyz = groups_xyz * threads_xyz`
+    #[inline]
+    pub fn global_threads_xyz(&self) -> [usize; 3] {
+        let [groups_x, groups_y, groups_z] = self.groups_xyz();
+        let [threads_x, threads_y, threads_z] = self.threads_xyz();
+        [
+            groups_x * threads_x,
+            groups_y * threads_y,
+            groups_z * threads_z,
+        ]
+    }
+    /// The global thread id in each dimension.
+    ///
+    /// `global_id_xyz = group_id_xyz * threads_xyz + thread_id_xyz`
+    #[inline]
+    pub fn global_id_xyz(&self) -> [usize; 3] {
+        usize_xyz(self.global_id_xyz)
+    }
+    /// The number of thread groups in each dimension.
+    #[inline]
+    pub fn groups_xyz(&self) -> [usize; 3] {
+        usize_xyz(self.groups_xyz)
+    }
+    /// The group id in each dimension.
+    #[inline]
+    pub fn group_id_xyz(&self) -> [usize; 3] {
+        usize_xyz(self.group_id_xyz)
+    }
+    /// The number of threads per group in each dimension.
+    #[inline]
+    pub fn threads_xyz(&self) -> [usize; 3] {
+        usize_xyz(self.threads_xyz)
+    }
+    /// The thread id in each dimension.
+    #[inline]
+    pub fn thread_id_xyz(&self) -> [usize; 3] {
+        usize_xyz(self.thread_id_xyz)
+    }
+}
+
+#[inline]
+fn usize_xyz([x, y, z]: [u32; 3]) -> [usize; 3] {
+    [x as usize, y as usize, z as usize]
 }
 
 pub struct ItemKernel {
@@ -7,6 +7,9 @@
 #![cfg_attr(doc_cfg, feature(doc_cfg, doc_auto_cfg))]
 #![deny(unsafe_op_in_unsafe_fn)]
 
+/// bytemuck
+#[cfg(not(target_arch = "spirv"))]
+pub extern crate bytemuck;
 /// half
 pub extern crate half;
 /// krnl-macros
@@ -21,5 +24,11 @@ pub mod buffer;
 /// Kernel structs passed to kernels.
 #[cfg_attr(doc_cfg, doc(cfg(target_arch = "spirv")))]
 pub mod kernel;
+/// Math functions.
+pub mod math;
+/// Random number generation.
+pub mod random;
 /// Numerical types.
 pub mod scalar;
+/// Subgroup operations.
+pub mod subgroup;
@@ -0,0 +1,299 @@
+/*!
+Counter based random number generation.
+
+[`philox4x32`] is Philox4x32-10, from "Parallel Random Numbers: As Easy as 1, 2, 3" (Salmon et al. 2011).
+It only requires 32 bit integer arithmetic, and produces identical streams on the host and on devices.
+
+Floats are built from the random bits with integer arithmetic and exact conversions, and [`affine_f32`]
+and [`affine_f64`] round the multiply and add separately on devices, so floats are also identical.
+
+Each element of a stream is generated independently from the seed and its index, so streams can be
+split between threads or resumed at an offset.
+*/
+
+#[cfg(target_arch = "spirv")]
+use core::arch::asm;
+use half::{bf16, f16};
+
+const PHILOX_M0: u32 = 0xD251_1F53;
+const PHILOX_M1: u32 = 0xCD9E_8D57;
+const PHILOX_W0: u32 = 0x9E37_79B9;
+const PHILOX_W1: u32 = 0xBB67_AE85;
+
+/// The high and low words of `a * b`, without 64 bit integers.
+#[inline]
+fn mul_hi_lo(a: u32, b: u32) -> (u32, u32) {
+    let (a0, a1) = (a & 0xFFFF, a >> 16);
+    let (b0, b1) = (b & 0xFFFF, b >> 16);
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+    let mid = (p00 >> 16) + (p01 & 0xFFFF) + (p10 & 0xFFFF);
+    let hi = p11 + (p01 >> 16) + (p10 >> 16) + (mid >> 16);
+    (hi, a.wrapping_mul(b))
+}
+
+/// Philox4x32-10.
+#[inline]
+pub fn philox4x32(counter: [u32; 4], key: [u32; 2]) -> [u32; 4] {
+    let [mut c0, mut c1, mut c2, mut c3] = counter;
+    let [mut k0, mut k1] = key;
+    let mut round = 0;
+    while round < 10 {
+        let (hi0, lo0) = mul_hi_lo(PHILOX_M0, c0);
+        let (hi1, lo1) = mul_hi_lo(PHILOX_M1, c2);
+        c0 = hi1 ^ c1 ^ k0;
+        c1 = lo1;
+        c2 = hi0 ^ c3 ^ k1;
+        c3 = lo0;
+        k0 = k0.wrapping_add(PHILOX_W0);
+        k1 = k1.wrapping_add(PHILOX_W1);
+        round += 1;
+    }
+    [c0, c1, c2, c3]
+}
+
+/// Adds `index` to the 64 bit `offset`, stored as `[lo, hi]`.
+#[inline]
+pub fn index_add(offset: [u32; 2], index: u32) -> [u32; 2] {
+    let lo = offset[0].wrapping_add(index);
+    let carry = (lo < index) as u32;
+    [lo, offset[1].wrapping_add(carry)]
+}
+
+/// Random bits for the element at `index` of the stream for `seed`.
+///
+/// `seed` and `index` are 64 bit integers stored as `[lo, hi]`.
+#[inline]
+pub fn random_bits(seed: [u32; 2], index: [u32; 2]) -> [u32; 4] {
+    philox4x32([index[0], index[1], 0, 0], seed)
+}
+
+/// Uniform in [0, 1).
+#[inline]
+pub fn uniform_f32(x: u32) -> f32 {
+    (x >> 8) as f32 * (1. / 16_777_216.)
+}
+
+/// Uniform in [0, 1).
+#[inline]
+pub fn uniform_f64(x0: u32, x1: u32) -> f64 {
+    let a = (x0 >> 5) as f64;
+    let b = (x1 >> 6) as f64;
+    (a * 67_108_864. + b) * (1. / 9_007_199_254_740_992.)
+}
+
+/// `-log2(x / 2^32)` for `x > 0`, with 26 fractional bits.
+#[inline]
+fn neg_log2_q26(x: u32) -> u32 {
+    let lz = x.leading_zeros();
+    // [1, 2) with 31 fractional bits
+    let mut m = x << lz;
+    let mut frac = 0;
+    let mut i = 0;
+    // squaring doubles the log, so each step produces 1 bit
+    while i < 26 {
+        let (hi, lo) = mul_hi_lo(m, m);
+        frac <<= 1;
+        if hi >= 1 << 31 {
+            frac |= 1;
+            m = hi;
+        } else {
+            m = (hi << 1) | (lo >> 31);
+        }
+        i += 1;
+    }
+    // log2(x) = 31 - lz + frac
+    ((1 + lz) << 26) - frac
+}
+
+/// `sqrt(x)` for `x` with 26 fractional bits, with 29 fractional bits.
+#[inline]
+fn sqrt_q26(x: u32) -> u32 {
+    // the largest y with y^2 <= x * 2^32
+    let mut y = 0;
+    let mut i = 32;
+    while i > 0 {
+        i -= 1;
+        let candidate = y | (1 << i);
+        let (hi, lo) = mul_hi_lo(candidate, candidate);
+        if hi < x || (hi == x && lo == 0) {
+            y = candidate;
+        }
+    }
+    y
+}
+
+/// `1 - y / n0 * (1 - y / n1 * (..))`, for `y` with 32 fractional bits, with 31 fractional bits.
+#[inline]
+fn alternating_series(y: u32, divisors: [u32; 6]) -> u32 {
+    const ONE: u32 = 1 << 31;
+    let mut output = ONE;
+    let mut i = 0;
+    while i < divisors.len() {
+        output = ONE - mul_hi_lo(y / divisors[i], output).0;
+        i += 1;
+    }
+    output
+}
+
+/// `(cos(pi / 2 * x), sin(pi / 2 * x))` for `x` in [0, 1/2] with 32 fractional bits, with 31
+/// fractional bits.
+#[inline]
+fn cos_sin_q32(x: u32) -> (u32, u32) {
+    // pi / 2 with 31 fractional bits
+    const FRAC_PI_2: u32 = 3_373_259_426;
+    let (hi, lo) = mul_hi_lo(x, FRAC_PI_2);
+    // x <= pi / 4, with 32 fractional bits
+    let x = (hi << 1) | (lo >> 31);
+    let y = mul_hi_lo(x, x).0;
+    // Taylor series, the next terms are less than 2^-32
+    let cos = alternating_series(y, [132, 90, 56, 30, 12, 2]);
+    let sin = mul_hi_lo(x, alternating_series(y, [156, 110, 72, 42, 20, 6])).0;
+    (cos, sin)
+}
+
+/// `cos(2 * pi * x / 2^32)` as `(magnitude, negative)`, with 31 fractional bits.
+#[inline]
+fn cos_turns(x: u32) -> (u32, bool) {
+    let quadrant = x >> 30;
+    let x = x << 2;
+    let (cos, sin) = if x <= 1 << 31 {
+        cos_sin_q32(x)
+    } else {
+        let (cos, sin) = cos_sin_q32(x.wrapping_neg());
+        (sin, cos)
+    };
+    match quadrant {
+        0 => (cos, false),
+        1 => (sin, true),
+        2 => (cos, true),
+        _ => (sin, false),
+    }
+}
+
+/// Standard normal as `(magnitude, negative)`, with 29 fractional bits.
+///
+/// Computes the Box-Muller transform in 32 bit fixed point, so that it is identical on the host
+/// and on devices. The absolute error is typically about 2^-29, and at most about 2^-19.
+#[inline]
+pub fn normal_q29(x0: u32, x1: u32) -> (u32, bool) {
+    // 2 * ln(2) with 30 fractional bits
+    const LN_4: u32 = 1_488_522_236;
+    // u1 = 1 - x0 / 2^32 in (0, 1], 0 is 2^32
+    let u1 = x0.wrapping_neg();
+    let t = if u1 == 0 { 0 } else { neg_log2_q26(u1) };
+    // -2 * ln(u1) < 45
+    let (hi, lo) = mul_hi_lo(t, LN_4);
+    let r = sqrt_q26((hi << 2) | (lo >> 30));
+    let (cos, negative) = cos_turns(x1);
+    let (hi, lo) = mul_hi_lo(r, cos);
+    ((hi << 1) | (lo >> 31), negative)
+}
+
+/// Standard normal, with the Box-Muller transform.
+///
+/// See [`normal_q29`]. Has 21 fractional bits.
+#[inline]
+pub fn normal_f32(x0: u32, x1: u32) -> f32 {
+    let (x, negative) = normal_q29(x0, x1);
+    // rounded to 21 fractional bits, so that it is exact, x < 2^32 - 2^7
+    let x = ((x + (1 << 7)) >> 8) as f32 * (1. / 2_097_152.);
+    if negative {
+        -x
+    } else {
+        x
+    }
+}
+
+/// Standard normal, with the Box-Muller transform.
+///
+/// See [`normal_q29`].
+#[inline]
+pub fn normal_f64(x0: u32, x1: u32) -> f64 {
+    let (x, negative) = normal_q29(x0, x1);
+    let x = x as f64 * (1. / 536_870_912.);
+    if negative {
+        -x
+    } else {
+        x
+    }
+}
+
+#[cfg(target_arch = "spirv")]
+macro_rules! affine {
+    ($T:ty, $alpha:expr, $beta:expr, $x:expr) => {{
+        let mut y = <$T>::default();
+        unsafe {
+            asm! {
+                "%product = OpFMul _ {beta} {x}",
+                "OpDecorate %product NoContraction",
+                "%y = OpFAdd _ {alpha} %product",
+                "OpDecorate %y NoContraction",
+                "OpStore {y} %y",
+                alpha = in(reg) $alpha,
+                beta = in(reg) $beta,
+                x = in(reg) $x,
+                y = in(reg) &mut y,
+            }
+        }
+        y
+    }};
+}
+
+/// `alpha + beta * x`.
+///
+/// Devices may fuse a multiply and an add, rounding once. The operations are decorated with
+/// `NoContraction`, so that they are rounded separately, as on the host.
+#[inline]
+pub fn affine_f32(alpha: f32, beta: f32, x: f32) -> f32 {
+    #[cfg(target_arch = "spirv")]
+    return affine!(f32, alpha, beta, x);
+    #[cfg(not(target_arch = "spirv"))]
+    {
+        alpha + beta * x
+    }
+}
+
+/// `alpha + beta * x`.
+///
+/// See [`affine_f32`].
+#[inline]
+pub fn affine_f64(alpha: f64, beta: f64, x: f64) -> f64 {
+    #[cfg(target_arch = "spirv")]
+    return affine!(f64, alpha, beta, x);
+    #[cfg(not(target_arch = "spirv"))]
+    {
+        alpha + beta * x
+    }
+}
+
+macro_rules! impl_next_down {
+    ($($f:ident: $T:ident => $U:ident),* $(,)?) => {
+        $(
+            /// The largest value less than `x`, which must be finite.
+            #[inline]
+            pub fn $f(x: $T) -> $T {
+                let bits = x.to_bits();
+                let sign_bit: $U = 1 << ($U::BITS - 1);
+                let bits = if bits & !sign_bit == 0 {
+                    // the smallest negative subnormal
+                    sign_bit | 1
+                } else if bits & sign_bit == 0 {
+                    bits - 1
+                } else {
+                    bits + 1
+                };
+                $T::from_bits(bits)
+            }
+        )*
+    };
+}
+
+impl_next_down! {
+    next_down_f16: f16 => u16,
+    next_down_bf16: bf16 => u16,
+    next_down_f32: f32 => u32,
+    next_down_f64: f64 => u64,
+}
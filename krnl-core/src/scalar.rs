@@ -1,3 +1,4 @@
+use crate::buffer::{Element, ElementLayout};
 #[cfg(not(target_arch = "spirv"))]
 use bytemuck::Pod;
 #[cfg(not(target_arch = "spirv"))]
@@ -16,7 +17,13 @@ use std::{
     str::FromStr,
 };
 
+mod complex;
+pub use complex::{c32, c64};
+mod fp8;
+pub use fp8::{f8e4m3, f8e5m2};
+
 mod sealed {
+    use super::{c32, c64, f8e4m3, f8e5m2};
     use half::{bf16, f16};
 
     #[doc(hidden)]
@@ -30,7 +37,9 @@ mod sealed {
         };
     }
 
-    impl_sealed!(u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64);
+    impl_sealed!(
+        u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2
+    );
 }
 use sealed::Sealed;
 
@@ -53,6 +62,10 @@ pub enum ScalarType {
     U64 = 10,
     I64 = 11,
     F64 = 12,
+    C32 = 13,
+    C64 = 14,
+    F8E4M3 = 15,
+    F8E5M2 = 16,
 }
 
 impl ScalarType {
@@ -60,19 +73,28 @@ impl ScalarType {
     #[inline]
     fn iter() -> impl Iterator<Item = Self> {
         use ScalarType::*;
-        [U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64].into_iter()
+        [
+            U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64, C32, C64, F8E4M3, F8E5M2,
+        ]
+        .into_iter()
     }
     /// Size of the type in bytes.
     #[inline]
-    pub fn size(&self) -> usize {
+    pub const fn size(&self) -> usize {
         use ScalarType::*;
         match self {
-            U8 | I8 => 1,
+            U8 | I8 | F8E4M3 | F8E5M2 => 1,
             U16 | I16 | F16 | BF16 => 2,
             U32 | I32 | F32 => 4,
-            U64 | I64 | F64 => 8,
+            U64 | I64 | F64 | C32 => 8,
+            C64 => 16,
         }
     }
+    /// Whether the type is [`c32`] or [`c64`].
+    #[inline]
+    pub fn is_complex(&self) -> bool {
+        matches!(self, ScalarType::C32 | ScalarType::C64)
+    }
     /// Name of the type.
     ///
     /// Lowercase, ie "f16", "i32", etc.
@@ -93,6 +115,10 @@ impl ScalarType {
             U64 => "u64",
             I64 => "i64",
             F64 => "f64",
+            C32 => "c32",
+            C64 => "c64",
+            F8E4M3 => "f8e4m3",
+            F8E5M2 => "f8e5m2",
         }
     }
     /// Name of the variant.
@@ -115,6 +141,10 @@ impl ScalarType {
             U64 => "U64",
             I64 => "I64",
             F64 => "F64",
+            C32 => "C32",
+            C64 => "C64",
+            F8E4M3 => "F8E4M3",
+            F8E5M2 => "F8E5M2",
         }
     }
 }
@@ -143,6 +173,10 @@ impl TryFrom<u32> for ScalarType {
             10 => U64,
             11 => I64,
             12 => F64,
+            13 => C32,
+            14 => C64,
+            15 => F8E4M3,
+            16 => F8E5M2,
             _ => {
                 return Err(());
             }
@@ -220,6 +254,10 @@ pub enum ScalarElem {
     U64(u64),
     I64(i64),
     F64(f64),
+    C32(c32),
+    C64(c64),
+    F8E4M3(f8e4m3),
+    F8E5M2(f8e5m2),
 }
 
 #[cfg(not(target_arch = "spirv"))]
@@ -242,6 +280,10 @@ impl ScalarElem {
             S::U64 => E::U64(0),
             S::I64 => E::I64(0),
             S::F64 => E::F64(0.),
+            S::C32 => E::C32(c32::ZERO),
+            S::C64 => E::C64(c64::ZERO),
+            S::F8E4M3 => E::F8E4M3(f8e4m3::ZERO),
+            S::F8E5M2 => E::F8E5M2(f8e5m2::ZERO),
         }
     }
     /// One.
@@ -262,6 +304,10 @@ impl ScalarElem {
             S::U64 => E::U64(1),
             S::I64 => E::I64(1),
             S::F64 => E::F64(1.),
+            S::C32 => E::C32(c32::ONE),
+            S::C64 => E::C64(c64::ONE),
+            S::F8E4M3 => E::F8E4M3(f8e4m3::ONE),
+            S::F8E5M2 => E::F8E5M2(f8e5m2::ONE),
         }
     }
     /// Casts to `scalar_type`.
@@ -285,6 +331,10 @@ impl ScalarElem {
             S::U64 => E::U64(x.cast()),
             S::I64 => E::I64(x.cast()),
             S::F64 => E::F64(x.cast()),
+            S::C32 => E::C32(x.cast()),
+            S::C64 => E::C64(x.cast()),
+            S::F8E4M3 => E::F8E4M3(x.cast()),
+            S::F8E5M2 => E::F8E5M2(x.cast()),
         }
     }
     /// Casts to `T`.
@@ -306,6 +356,10 @@ impl ScalarElem {
             U64(x) => x.cast(),
             I64(x) => x.cast(),
             F64(x) => x.cast(),
+            C32(x) => x.cast(),
+            C64(x) => x.cast(),
+            F8E4M3(x) => x.cast(),
+            F8E5M2(x) => x.cast(),
         }
     }
     /// The [`ScalarType`].
@@ -326,9 +380,15 @@ impl ScalarElem {
             U64(_) => T::U64,
             I64(_) => T::I64,
             F64(_) => T::F64,
+            C32(_) => T::C32,
+            C64(_) => T::C64,
+            F8E4M3(_) => T::F8E4M3,
+            F8E5M2(_) => T::F8E5M2,
         }
     }
     /// The bits of the elem, ie u8, u16, u32, or u64.
+    ///
+    /// [`c32`] is returned as u64, [`c64`] is returned as is.
     #[inline]
     pub fn to_scalar_bits(&self) -> Self {
         use ScalarElem::*;
@@ -345,6 +405,10 @@ impl ScalarElem {
             U64(_) => *self,
             I64(x) => (*x as u64).into(),
             F64(x) => x.to_bits().into(),
+            C32(x) => bytemuck::cast::<_, u64>(*x).into(),
+            C64(_) => *self,
+            F8E4M3(x) => x.to_bits().into(),
+            F8E5M2(x) => x.to_bits().into(),
         }
     }
     /// The bytes as as slice.
@@ -354,7 +418,7 @@ impl ScalarElem {
     pub fn as_bytes(&self) -> &[u8] {
         use ScalarElem::*;
         macro_wrap!(match self {
-            macro_for!($E in [U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64] {
+            macro_for!($E in [U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64, C32, C64, F8E4M3, F8E5M2] {
                 $E(x) => bytemuck::bytes_of(x),
             })
         })
@@ -368,7 +432,7 @@ impl<T: Scalar> From<T> for ScalarElem {
     }
 }
 
-macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
     #[cfg(not(target_arch = "spirv"))]
     impl TryFrom<ScalarElem> for $T {
         type Error = ();
@@ -431,8 +495,8 @@ macro_for!($X in [f16, bf16] {
     });
 });
 
-macro_for!($X in [f16, bf16] {
-    macro_for!($Y in [f16, bf16]  {
+macro_for!($X in [f16, bf16, f8e4m3, f8e5m2] {
+    macro_for!($Y in [f16, bf16, f8e4m3, f8e5m2]  {
         impl AsScalar<$Y> for $X {
             #[inline]
             fn as_scalar(self) -> $Y {
@@ -442,6 +506,71 @@ macro_for!($X in [f16, bf16] {
     });
 });
 
+macro_for!($X in [u8, i8, u16, i16, u32, i32, f32, u64, i64] {
+    macro_for!($Y in [f8e4m3, f8e5m2] {
+        impl AsScalar<$Y> for $X {
+            #[inline]
+            fn as_scalar(self) -> $Y {
+                $Y::from_f32(self as f32)
+            }
+        }
+    });
+});
+
+macro_for!($Y in [f8e4m3, f8e5m2] {
+    impl AsScalar<$Y> for f64 {
+        #[inline]
+        fn as_scalar(self) -> $Y {
+            $Y::from_f64(self)
+        }
+    }
+});
+
+macro_for!($X in [f8e4m3, f8e5m2] {
+    macro_for!($Y in [u8, i8, u16, i16, u32, i32, f32, u64, i64, f64] {
+        impl AsScalar<$Y> for $X {
+            #[inline]
+            fn as_scalar(self) -> $Y {
+                self.to_f32() as _
+            }
+        }
+    });
+});
+
+macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, f8e4m3, f8e5m2] {
+    macro_for!($Y in [c32, c64] {
+        impl AsScalar<$Y> for $X {
+            #[inline]
+            fn as_scalar(self) -> $Y {
+                $Y::new(self.as_scalar(), 0.)
+            }
+        }
+    });
+});
+
+// Complex to real takes the real part.
+macro_for!($X in [c32, c64] {
+    macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, f8e4m3, f8e5m2] {
+        impl AsScalar<$Y> for $X {
+            #[inline]
+            fn as_scalar(self) -> $Y {
+                self.re().as_scalar()
+            }
+        }
+    });
+});
+
+macro_for!($X in [c32, c64] {
+    macro_for!($Y in [c32, c64] {
+        impl AsScalar<$Y> for $X {
+            #[inline]
+            fn as_scalar(self) -> $Y {
+                $Y::new(self.re().as_scalar(), self.im().as_scalar())
+            }
+        }
+    });
+});
+
 #[cfg(target_arch = "spirv")]
 /// Base trait for numerical types.
 pub trait Scalar:
@@ -455,6 +584,7 @@ pub trait Scalar:
     + NumAssign
     + PartialEq
     + PartialOrd
+    + Element
     + Sealed
 {
     /// The [`ScalarType`] of the scalar.
@@ -477,6 +607,7 @@ pub trait Scalar:
     + NumAssign
     + PartialEq
     + PartialOrd
+    + Element
     + Pod
     + Debug
     + Display
@@ -505,6 +636,7 @@ pub trait Scalar:
     + NumAssign
     + PartialEq
     + PartialOrd
+    + Element
     + Pod
     + Debug
     + Display
@@ -521,8 +653,12 @@ pub trait Scalar:
     fn cast<T: Scalar>(self) -> T;
 }
 
-macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
     paste! {
+        unsafe impl Element for $X {
+            const LAYOUT: ElementLayout = ElementLayout::scalar(ScalarType::[<$X:upper>]);
+        }
+
         impl Scalar for $X {
             const SCALAR_TYPE: ScalarType = ScalarType::[<$X:upper>];
             #[cfg(not(target_arch = "spirv"))]
@@ -534,7 +670,7 @@ macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
             #[inline]
             fn cast<T: Scalar>(self) -> T {
                 macro_wrap!(match T::SCALAR_TYPE {
-                    macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+                    macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
                         $Y::SCALAR_TYPE => bytemuck::cast(AsScalar::<$Y>::as_scalar(self)),
                     })
                 })
@@ -0,0 +1,372 @@
+#[cfg(not(target_arch = "spirv"))]
+use bytemuck::{Pod, Zeroable};
+use core::{
+    cmp::Ordering,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign},
+};
+use num_traits::{
+    float::FloatErrorKind, Float, FromPrimitive, Num, NumCast, One, ParseFloatError, ToPrimitive,
+    Zero,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use spirv_std::glam::{DVec2, Vec2};
+#[cfg(not(target_arch = "spirv"))]
+use std::fmt::{self, Debug, Display};
+
+macro_rules! impl_complex {
+    ($c:ident, $f:ident, $v:ident) => {
+        #[doc = concat!("Complex number with [`", stringify!($f), "`] parts.")]
+        ///
+        /// Stored as a 2 component vector, so that arithmetic lowers to SPIR-V vector ops.
+        /// Casting to a real type takes the real part. Ordering is lexicographic, by the real
+        /// part then the imaginary part.
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Default, PartialEq)]
+        #[repr(transparent)]
+        pub struct $c($v);
+
+        impl $c {
+            /// Zero.
+            pub const ZERO: Self = Self::new(0., 0.);
+            /// One.
+            pub const ONE: Self = Self::new(1., 0.);
+            /// The imaginary unit.
+            pub const I: Self = Self::new(0., 1.);
+            /// Creates a complex number from its real and imaginary parts.
+            #[inline]
+            pub const fn new(re: $f, im: $f) -> Self {
+                Self($v::new(re, im))
+            }
+            /// The real part.
+            #[inline]
+            pub fn re(self) -> $f {
+                self.0.x
+            }
+            /// The imaginary part.
+            #[inline]
+            pub fn im(self) -> $f {
+                self.0.y
+            }
+            /// The complex conjugate.
+            #[inline]
+            pub fn conj(self) -> Self {
+                Self::new(self.re(), -self.im())
+            }
+            /// The squared magnitude, `re * re + im * im`.
+            #[inline]
+            pub fn norm_sqr(self) -> $f {
+                self.0.dot(self.0)
+            }
+            /// The magnitude.
+            #[inline]
+            pub fn norm(self) -> $f {
+                Float::sqrt(self.norm_sqr())
+            }
+            /// The angle in radians, in `[-pi, pi]`.
+            #[inline]
+            pub fn arg(self) -> $f {
+                Float::atan2(self.im(), self.re())
+            }
+            /// Creates a complex number from polar coordinates.
+            #[inline]
+            pub fn from_polar(r: $f, theta: $f) -> Self {
+                Self::new(r * Float::cos(theta), r * Float::sin(theta))
+            }
+        }
+
+        impl From<$f> for $c {
+            #[inline]
+            fn from(re: $f) -> Self {
+                Self::new(re, 0.)
+            }
+        }
+
+        impl Add for $c {
+            type Output = Self;
+            #[inline]
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $c {
+            type Output = Self;
+            #[inline]
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl Mul for $c {
+            type Output = Self;
+            #[inline]
+            fn mul(self, rhs: Self) -> Self {
+                // (a + bi)(c + di) = (a, b) * c + (-b, a) * d
+                Self(self.0 * rhs.0.x + self.0.perp() * rhs.0.y)
+            }
+        }
+
+        impl Div for $c {
+            type Output = Self;
+            #[inline]
+            fn div(self, rhs: Self) -> Self {
+                // (a + bi)(c - di) / (c * c + d * d)
+                Self((self.0 * rhs.0.x - self.0.perp() * rhs.0.y) / rhs.0.dot(rhs.0))
+            }
+        }
+
+        impl Rem for $c {
+            type Output = Self;
+            /// The remainder after division rounded toward zero, as in
+            /// [num-complex](https://docs.rs/num-complex).
+            #[inline]
+            fn rem(self, rhs: Self) -> Self {
+                let q = self / rhs;
+                self - rhs * Self::new(Float::trunc(q.re()), Float::trunc(q.im()))
+            }
+        }
+
+        impl Neg for $c {
+            type Output = Self;
+            #[inline]
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl Mul<$f> for $c {
+            type Output = Self;
+            #[inline]
+            fn mul(self, rhs: $f) -> Self {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl Div<$f> for $c {
+            type Output = Self;
+            #[inline]
+            fn div(self, rhs: $f) -> Self {
+                Self(self.0 / rhs)
+            }
+        }
+
+        impl AddAssign for $c {
+            #[inline]
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $c {
+            #[inline]
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl MulAssign for $c {
+            #[inline]
+            fn mul_assign(&mut self, rhs: Self) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl DivAssign for $c {
+            #[inline]
+            fn div_assign(&mut self, rhs: Self) {
+                *self = *self / rhs;
+            }
+        }
+
+        impl RemAssign for $c {
+            #[inline]
+            fn rem_assign(&mut self, rhs: Self) {
+                *self = *self % rhs;
+            }
+        }
+
+        impl PartialOrd for $c {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                match self.re().partial_cmp(&other.re()) {
+                    Some(Ordering::Equal) => self.im().partial_cmp(&other.im()),
+                    ordering => ordering,
+                }
+            }
+        }
+
+        impl Zero for $c {
+            #[inline]
+            fn zero() -> Self {
+                Self::ZERO
+            }
+            #[inline]
+            fn is_zero(&self) -> bool {
+                *self == Self::ZERO
+            }
+        }
+
+        impl One for $c {
+            #[inline]
+            fn one() -> Self {
+                Self::ONE
+            }
+        }
+
+        impl Num for $c {
+            type FromStrRadixErr = ParseFloatError;
+            /// Parses "a", "bi", or "a+bi", where the imaginary part may be "i" or "-i".
+            fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+                let (re, im) = complex_from_str_radix::<$f>(s, radix)?;
+                Ok(Self::new(re, im))
+            }
+        }
+
+        /// Converts the real part if the imaginary part is zero.
+        impl ToPrimitive for $c {
+            #[inline]
+            fn to_i64(&self) -> Option<i64> {
+                if self.im() == 0. {
+                    self.re().to_i64()
+                } else {
+                    None
+                }
+            }
+            #[inline]
+            fn to_u64(&self) -> Option<u64> {
+                if self.im() == 0. {
+                    self.re().to_u64()
+                } else {
+                    None
+                }
+            }
+            #[inline]
+            fn to_f64(&self) -> Option<f64> {
+                if self.im() == 0. {
+                    self.re().to_f64()
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl NumCast for $c {
+            #[inline]
+            fn from<T: ToPrimitive>(n: T) -> Option<Self> {
+                <$f as NumCast>::from(n).map(Self::from)
+            }
+        }
+
+        impl FromPrimitive for $c {
+            #[inline]
+            fn from_i64(n: i64) -> Option<Self> {
+                $f::from_i64(n).map(Self::from)
+            }
+            #[inline]
+            fn from_u64(n: u64) -> Option<Self> {
+                $f::from_u64(n).map(Self::from)
+            }
+            #[inline]
+            fn from_f64(n: f64) -> Option<Self> {
+                $f::from_f64(n).map(Self::from)
+            }
+        }
+
+        #[cfg(not(target_arch = "spirv"))]
+        unsafe impl Zeroable for $c {}
+
+        #[cfg(not(target_arch = "spirv"))]
+        unsafe impl Pod for $c {}
+
+        /// Formats as `re+imi`, ie "1+2i".
+        #[cfg(not(target_arch = "spirv"))]
+        impl Display for $c {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let (re, im) = (self.re(), self.im());
+                if im.is_sign_negative() {
+                    write!(f, "{re}-{}i", -im)
+                } else {
+                    write!(f, "{re}+{im}i")
+                }
+            }
+        }
+
+        #[cfg(not(target_arch = "spirv"))]
+        impl Debug for $c {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                Display::fmt(self, f)
+            }
+        }
+
+        /// Serialized as `(re, im)`.
+        #[cfg(feature = "serde")]
+        impl Serialize for $c {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                (self.re(), self.im()).serialize(serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> Deserialize<'de> for $c {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let (re, im) = <($f, $f)>::deserialize(deserializer)?;
+                Ok(Self::new(re, im))
+            }
+        }
+    };
+}
+
+/// Parses the real and imaginary parts of a complex number.
+fn complex_from_str_radix<F: Float + Num<FromStrRadixErr = ParseFloatError>>(
+    s: &str,
+    radix: u32,
+) -> Result<(F, F), ParseFloatError> {
+    let invalid = ParseFloatError {
+        kind: FloatErrorKind::Invalid,
+    };
+    let s = s.trim();
+    let Some(s) = s.strip_suffix('i') else {
+        return Ok((F::from_str_radix(s, radix)?, F::zero()));
+    };
+    // The last sign that does not start the string or an exponent splits the parts.
+    let mut prev = None;
+    let mut split = None;
+    for (i, c) in s.char_indices() {
+        if i > 0 && matches!(c, '+' | '-') && !(radix == 10 && matches!(prev, Some('e' | 'E'))) {
+            split = Some(i);
+        }
+        prev = Some(c);
+    }
+    let (re, im) = if let Some(split) = split {
+        let re = s[..split].trim();
+        if re.is_empty() {
+            return Err(invalid);
+        }
+        (F::from_str_radix(re, radix)?, s[split..].trim())
+    } else {
+        (F::zero(), s)
+    };
+    let im = match im {
+        "" | "+" => F::one(),
+        "-" => -F::one(),
+        _ => {
+            let (sign, digits) = if let Some(digits) = im.strip_prefix('-') {
+                (-F::one(), digits)
+            } else {
+                (F::one(), im.strip_prefix('+').unwrap_or(im))
+            };
+            sign * F::from_str_radix(digits.trim_start(), radix)?
+        }
+    };
+    Ok((re, im))
+}
+
+impl_complex!(c32, f32, Vec2);
+impl_complex!(c64, f64, DVec2);
@@ -0,0 +1,355 @@
+#[cfg(not(target_arch = "spirv"))]
+use bytemuck::{Pod, Zeroable};
+use core::{
+    cmp::Ordering,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign},
+};
+use half::f16;
+use num_traits::{FromPrimitive, Num, NumCast, One, ToPrimitive, Zero};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "spirv"))]
+use std::fmt::{self, Debug, Display};
+
+/// Decodes `bits` with `m` mantissa bits and exponent `bias`.
+///
+/// Only uses integer ops, so that decoding on device only requires INT8.
+#[inline]
+fn decode(bits: u8, m: u32, bias: u32, inf: bool) -> f32 {
+    let sign = ((bits & 0x80) as u32) << 24;
+    let bits = (bits & 0x7F) as u32;
+    let exp = bits >> m;
+    let man = bits & ((1 << m) - 1);
+    let max_exp = (1 << (7 - m)) - 1;
+    if exp == max_exp && (inf || man == (1 << m) - 1) {
+        return if man == 0 {
+            f32::from_bits(sign | 0x7F80_0000)
+        } else {
+            f32::from_bits(sign | 0x7FC0_0000)
+        };
+    }
+    if exp == 0 {
+        // subnormal, man * 2^(1 - bias - m) is exact in f32
+        let x = man as f32 * f32::from_bits((128 - bias - m) << 23);
+        f32::from_bits(sign | x.to_bits())
+    } else {
+        f32::from_bits(sign | ((exp + 127 - bias) << 23) | (man << (23 - m)))
+    }
+}
+
+/// Encodes `x` with `m` mantissa bits and exponent `bias`, rounding to nearest even.
+///
+/// Finite values larger than `max` are encoded as `overflow`.
+#[inline]
+fn encode(x: f32, m: u32, bias: u32, max: u8, overflow: u8, nan: u8) -> u8 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 24) & 0x80) as u8;
+    let abs = bits & 0x7FFF_FFFF;
+    if abs > 0x7F80_0000 {
+        return sign | nan;
+    }
+    let exp = abs >> 23;
+    let man = abs & 0x7F_FFFF;
+    let (code, shift, man) = if exp + bias >= 128 {
+        // normal
+        ((exp + bias - 127) << m, 23 - m, man)
+    } else {
+        // subnormal or zero
+        let shift = 24 - m + (127 - bias - exp);
+        if shift > 25 {
+            return sign;
+        }
+        (0, shift, man | 0x80_0000)
+    };
+    let code = code | (man >> shift);
+    let rem = man & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    let code = code + (rem > half || (rem == half && code & 1 == 1)) as u32;
+    if code > max as u32 {
+        sign | overflow
+    } else {
+        sign | code as u8
+    }
+}
+
+/// Rounds `x` to f32 with round to odd, so that rounding again to fp8 is correct.
+#[inline]
+fn f64_to_f32_odd(x: f64) -> f32 {
+    let y = x as f32;
+    if x.is_nan() || y as f64 == x {
+        return y;
+    }
+    let bits = y.to_bits();
+    // Compares magnitudes with the sign bit masked, since abs is not in core.
+    let abs = |x: f64| x.to_bits() & !(1 << 63);
+    let bits = if abs(y as f64) > abs(x) {
+        bits - 1
+    } else {
+        bits
+    };
+    f32::from_bits(bits | 1)
+}
+
+macro_rules! impl_fp8 {
+    ($(#[$meta:meta])* $t:ident, m = $m:literal, bias = $bias:literal, inf = $inf:literal, max = $max:literal, overflow = $overflow:literal, nan = $nan:literal) => {
+        $(#[$meta])*
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Default)]
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        #[repr(transparent)]
+        pub struct $t(u8);
+
+        impl $t {
+            /// Zero.
+            pub const ZERO: Self = Self(0);
+            /// One.
+            pub const ONE: Self = Self(($bias as u8) << $m);
+            /// Largest finite value.
+            pub const MAX: Self = Self($max);
+            /// Smallest finite value.
+            pub const MIN: Self = Self($max | 0x80);
+            /// Smallest positive normal value.
+            pub const MIN_POSITIVE: Self = Self(1 << $m);
+            /// Difference between one and the next larger value.
+            pub const EPSILON: Self = Self((($bias - $m) as u8) << $m);
+            /// Not a number.
+            pub const NAN: Self = Self($nan);
+            /// Creates from the raw bits.
+            #[inline]
+            pub const fn from_bits(bits: u8) -> Self {
+                Self(bits)
+            }
+            /// The raw bits.
+            #[inline]
+            pub const fn to_bits(self) -> u8 {
+                self.0
+            }
+            /// Converts from [`f32`], rounding to nearest even.
+            #[inline]
+            pub fn from_f32(x: f32) -> Self {
+                Self(encode(x, $m, $bias, $max, $overflow, $nan))
+            }
+            /// Converts from [`f64`], rounding to nearest even.
+            #[inline]
+            pub fn from_f64(x: f64) -> Self {
+                Self::from_f32(f64_to_f32_odd(x))
+            }
+            /// Converts from [`f16`], rounding to nearest even.
+            #[inline]
+            pub fn from_f16(x: f16) -> Self {
+                Self::from_f32(x.to_f32())
+            }
+            /// Converts to [`f32`] exactly.
+            #[inline]
+            pub fn to_f32(self) -> f32 {
+                decode(self.0, $m, $bias, $inf)
+            }
+            /// Converts to [`f64`] exactly.
+            #[inline]
+            pub fn to_f64(self) -> f64 {
+                self.to_f32() as f64
+            }
+            /// Converts to [`f16`] exactly.
+            #[inline]
+            pub fn to_f16(self) -> f16 {
+                f16::from_f32(self.to_f32())
+            }
+            /// Whether the value is NaN.
+            #[inline]
+            pub fn is_nan(self) -> bool {
+                self.to_f32().is_nan()
+            }
+        }
+
+        impl From<$t> for f32 {
+            #[inline]
+            fn from(x: $t) -> Self {
+                x.to_f32()
+            }
+        }
+
+        impl From<$t> for f64 {
+            #[inline]
+            fn from(x: $t) -> Self {
+                x.to_f64()
+            }
+        }
+
+        impl From<$t> for f16 {
+            #[inline]
+            fn from(x: $t) -> Self {
+                x.to_f16()
+            }
+        }
+
+        impl PartialEq for $t {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                $t::to_f32(*self) == $t::to_f32(*other)
+            }
+        }
+
+        impl PartialOrd for $t {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                $t::to_f32(*self).partial_cmp(&$t::to_f32(*other))
+            }
+        }
+
+        impl_fp8!(@binary $t, Add, add, AddAssign, add_assign, +);
+        impl_fp8!(@binary $t, Sub, sub, SubAssign, sub_assign, -);
+        impl_fp8!(@binary $t, Mul, mul, MulAssign, mul_assign, *);
+        impl_fp8!(@binary $t, Div, div, DivAssign, div_assign, /);
+        impl_fp8!(@binary $t, Rem, rem, RemAssign, rem_assign, %);
+
+        impl Neg for $t {
+            type Output = Self;
+            #[inline]
+            fn neg(self) -> Self {
+                Self(self.0 ^ 0x80)
+            }
+        }
+
+        impl Zero for $t {
+            #[inline]
+            fn zero() -> Self {
+                Self::ZERO
+            }
+            #[inline]
+            fn is_zero(&self) -> bool {
+                self.0 & 0x7F == 0
+            }
+        }
+
+        impl One for $t {
+            #[inline]
+            fn one() -> Self {
+                Self::ONE
+            }
+        }
+
+        impl Num for $t {
+            type FromStrRadixErr = <f32 as Num>::FromStrRadixErr;
+            fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+                f32::from_str_radix(s, radix).map(Self::from_f32)
+            }
+        }
+
+        impl ToPrimitive for $t {
+            #[inline]
+            fn to_i64(&self) -> Option<i64> {
+                $t::to_f32(*self).to_i64()
+            }
+            #[inline]
+            fn to_u64(&self) -> Option<u64> {
+                $t::to_f32(*self).to_u64()
+            }
+            #[inline]
+            fn to_f32(&self) -> Option<f32> {
+                Some($t::to_f32(*self))
+            }
+            #[inline]
+            fn to_f64(&self) -> Option<f64> {
+                Some($t::to_f64(*self))
+            }
+        }
+
+        impl NumCast for $t {
+            #[inline]
+            fn from<T: ToPrimitive>(n: T) -> Option<Self> {
+                n.to_f32().map(Self::from_f32)
+            }
+        }
+
+        impl FromPrimitive for $t {
+            #[inline]
+            fn from_i64(n: i64) -> Option<Self> {
+                Some(Self::from_f32(n as f32))
+            }
+            #[inline]
+            fn from_u64(n: u64) -> Option<Self> {
+                Some(Self::from_f32(n as f32))
+            }
+            #[inline]
+            fn from_f32(n: f32) -> Option<Self> {
+                Some(Self::from_f32(n))
+            }
+            #[inline]
+            fn from_f64(n: f64) -> Option<Self> {
+                Some(Self::from_f64(n))
+            }
+        }
+
+        #[cfg(not(target_arch = "spirv"))]
+        unsafe impl Zeroable for $t {}
+
+        #[cfg(not(target_arch = "spirv"))]
+        unsafe impl Pod for $t {}
+
+        #[cfg(not(target_arch = "spirv"))]
+        impl Display for $t {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                Display::fmt(&$t::to_f32(*self), f)
+            }
+        }
+
+        #[cfg(not(target_arch = "spirv"))]
+        impl Debug for $t {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                Debug::fmt(&$t::to_f32(*self), f)
+            }
+        }
+    };
+    (@binary $t:ident, $op:ident, $f:ident, $op_assign:ident, $f_assign:ident, $tok:tt) => {
+        // computed as f32, which is exact or correctly rounded before rounding to fp8
+        impl $op for $t {
+            type Output = Self;
+            #[inline]
+            fn $f(self, rhs: Self) -> Self {
+                Self::from_f32(self.to_f32() $tok rhs.to_f32())
+            }
+        }
+
+        impl $op_assign for $t {
+            #[inline]
+            fn $f_assign(&mut self, rhs: Self) {
+                *self = *self $tok rhs;
+            }
+        }
+    };
+}
+
+impl_fp8!(
+    /// 8-bit float with 4 exponent bits and 3 mantissa bits.
+    ///
+    /// The OCP FP8 E4M3 format, also known as "E4M3FN". There are no infinities, so values
+    /// that overflow (including infinity) are converted to NaN. The largest finite value is 448.
+    f8e4m3,
+    m = 3,
+    bias = 7,
+    inf = false,
+    max = 0x7E,
+    overflow = 0x7F,
+    nan = 0x7F
+);
+
+impl_fp8!(
+    /// 8-bit float with 5 exponent bits and 2 mantissa bits.
+    ///
+    /// The OCP FP8 E5M2 format, which follows IEEE 754 and is the upper byte of an [`f16`].
+    /// Values that overflow are converted to infinity. The largest finite value is 57344.
+    f8e5m2,
+    m = 2,
+    bias = 15,
+    inf = true,
+    max = 0x7B,
+    overflow = 0x7C,
+    nan = 0x7E
+);
+
+impl f8e5m2 {
+    /// Infinity.
+    pub const INFINITY: Self = Self(0x7C);
+    /// Negative infinity.
+    pub const NEG_INFINITY: Self = Self(0xFC);
+}
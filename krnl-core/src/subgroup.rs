@@ -0,0 +1,402 @@
+/*!
+Operations across the active threads of a [subgroup](crate::kernel::Kernel::subgroup_id), without
+group memory or barriers. Kernels using these declare the corresponding subgroup features
+automatically, ie `SUBGROUP_ARITHMETIC` for [`reduce_add`].
+
+8 and 16 bit integers are computed in 32 bits, and [`f16`], [`bf16`], [`f8e4m3`] and [`f8e5m2`] in
+[`f32`]. [`u64`] and [`i64`] additionally require `SUBGROUP_EXTENDED_TYPES`. On the host, each
+thread is its own subgroup.
+
+```no_run
+# #[cfg(target_arch = "spirv")]
+# fn foo(x: f32) {
+use krnl_core::subgroup;
+
+let sum = subgroup::reduce_add(x);
+let first = subgroup::broadcast(x, 0);
+# }
+```
+*/
+
+use crate::scalar::{c32, c64, f8e4m3, f8e5m2, Scalar};
+#[cfg(target_arch = "spirv")]
+use core::arch::asm;
+use half::{bf16, f16};
+use spirv_std::glam::UVec4;
+
+mod sealed {
+    pub trait Sealed {}
+}
+use sealed::Sealed;
+
+/// Scalars with subgroup operations.
+///
+/// Implemented for all [`Scalar`]s.
+pub trait SubgroupScalar: Scalar + Sealed {
+    #[doc(hidden)]
+    fn __reduce_add(self) -> Self;
+    #[doc(hidden)]
+    fn __reduce_min(self) -> Self;
+    #[doc(hidden)]
+    fn __reduce_max(self) -> Self;
+    #[doc(hidden)]
+    fn __inclusive_scan(self) -> Self;
+    #[doc(hidden)]
+    fn __exclusive_scan(self) -> Self;
+    #[doc(hidden)]
+    fn __broadcast(self, id: u32) -> Self;
+    #[doc(hidden)]
+    fn __shuffle(self, id: u32) -> Self;
+}
+
+/** Sum of `x` across the subgroup.
+
+Requires `SUBGROUP_ARITHMETIC`. */
+#[inline]
+pub fn reduce_add<T: SubgroupScalar>(x: T) -> T {
+    x.__reduce_add()
+}
+
+/** Minimum of `x` across the subgroup.
+
+Requires `SUBGROUP_ARITHMETIC`. */
+#[inline]
+pub fn reduce_min<T: SubgroupScalar>(x: T) -> T {
+    x.__reduce_min()
+}
+
+/** Maximum of `x` across the subgroup.
+
+Requires `SUBGROUP_ARITHMETIC`. */
+#[inline]
+pub fn reduce_max<T: SubgroupScalar>(x: T) -> T {
+    x.__reduce_max()
+}
+
+/** Sum of `x` for threads with a lower or equal subgroup thread id.
+
+Requires `SUBGROUP_ARITHMETIC`. */
+#[inline]
+pub fn inclusive_scan<T: SubgroupScalar>(x: T) -> T {
+    x.__inclusive_scan()
+}
+
+/** Sum of `x` for threads with a lower subgroup thread id.
+
+Requires `SUBGROUP_ARITHMETIC`. */
+#[inline]
+pub fn exclusive_scan<T: SubgroupScalar>(x: T) -> T {
+    x.__exclusive_scan()
+}
+
+/** `x` of the thread with subgroup thread id `id`.
+
+`id` must be the same for all threads in the subgroup, and the thread must be active, otherwise
+the result is undefined.
+
+Requires `SUBGROUP_BALLOT`. */
+#[inline]
+pub fn broadcast<T: SubgroupScalar>(x: T, id: u32) -> T {
+    x.__broadcast(id)
+}
+
+/** `x` of the thread with subgroup thread id `id`.
+
+Unlike [`broadcast`], `id` may differ per thread. The result is undefined if that thread is
+not active.
+
+Requires `SUBGROUP_SHUFFLE`. */
+#[inline]
+pub fn shuffle<T: SubgroupScalar>(x: T, id: u32) -> T {
+    x.__shuffle(id)
+}
+
+/** Bitmask of threads where `predicate` is true.
+
+Bit `i` of the mask, ie `mask[i / 32] & (1 << (i % 32))`, is set for subgroup thread id `i`.
+
+Requires `SUBGROUP_BALLOT`. */
+#[inline]
+pub fn ballot(predicate: bool) -> UVec4 {
+    #[cfg(target_arch = "spirv")]
+    {
+        let mut y = UVec4::default();
+        unsafe {
+            asm! {
+                "%u32 = OpTypeInt 32 0",
+                "%uvec4 = OpTypeVector %u32 4",
+                "%subgroup = OpConstant %u32 3",
+                "%y = OpGroupNonUniformBallot %uvec4 %subgroup {predicate}",
+                "OpStore {y} %y",
+                predicate = in(reg) predicate,
+                y = in(reg) &mut y,
+            }
+        }
+        y
+    }
+    #[cfg(not(target_arch = "spirv"))]
+    UVec4::new(predicate as u32, 0, 0, 0)
+}
+
+/** Whether `predicate` is true for all threads.
+
+Requires `SUBGROUP_VOTE`. */
+#[inline]
+pub fn all(predicate: bool) -> bool {
+    #[cfg(target_arch = "spirv")]
+    {
+        let mut y = false;
+        unsafe {
+            asm! {
+                "%bool = OpTypeBool",
+                "%u32 = OpTypeInt 32 0",
+                "%subgroup = OpConstant %u32 3",
+                "%y = OpGroupNonUniformAll %bool %subgroup {predicate}",
+                "OpStore {y} %y",
+                predicate = in(reg) predicate,
+                y = in(reg) &mut y,
+            }
+        }
+        y
+    }
+    #[cfg(not(target_arch = "spirv"))]
+    predicate
+}
+
+/** Whether `predicate` is true for any thread.
+
+Requires `SUBGROUP_VOTE`. */
+#[inline]
+pub fn any(predicate: bool) -> bool {
+    #[cfg(target_arch = "spirv")]
+    {
+        let mut y = false;
+        unsafe {
+            asm! {
+                "%bool = OpTypeBool",
+                "%u32 = OpTypeInt 32 0",
+                "%subgroup = OpConstant %u32 3",
+                "%y = OpGroupNonUniformAny %bool %subgroup {predicate}",
+                "OpStore {y} %y",
+                predicate = in(reg) predicate,
+                y = in(reg) &mut y,
+            }
+        }
+        y
+    }
+    #[cfg(not(target_arch = "spirv"))]
+    predicate
+}
+
+#[cfg(target_arch = "spirv")]
+macro_rules! group_op {
+    ($op:literal, $group_op:literal, $x:expr) => {{
+        let mut y = Self::default();
+        unsafe {
+            asm! {
+                "%u32 = OpTypeInt 32 0",
+                "%subgroup = OpConstant %u32 3",
+                concat!("%y = ", $op, " _ %subgroup ", $group_op, " {x}"),
+                "OpStore {y} %y",
+                x = in(reg) $x,
+                y = in(reg) &mut y,
+            }
+        }
+        y
+    }};
+}
+
+#[cfg(target_arch = "spirv")]
+macro_rules! id_op {
+    ($op:literal, $x:expr, $id:expr) => {{
+        let mut y = Self::default();
+        unsafe {
+            asm! {
+                "%u32 = OpTypeInt 32 0",
+                "%subgroup = OpConstant %u32 3",
+                concat!("%y = ", $op, " _ %subgroup {x} {id}"),
+                "OpStore {y} %y",
+                x = in(reg) $x,
+                id = in(reg) $id,
+                y = in(reg) &mut y,
+            }
+        }
+        y
+    }};
+}
+
+// Types with native subgroup operations.
+macro_rules! impl_native {
+    ($($t:ty => $add:literal, $min:literal, $max:literal;)*) => {
+        $(
+            impl Sealed for $t {}
+
+            impl SubgroupScalar for $t {
+                #[inline]
+                fn __reduce_add(self) -> Self {
+                    #[cfg(target_arch = "spirv")]
+                    return group_op!($add, "Reduce", self);
+                    #[cfg(not(target_arch = "spirv"))]
+                    self
+                }
+                #[inline]
+                fn __reduce_min(self) -> Self {
+                    #[cfg(target_arch = "spirv")]
+                    return group_op!($min, "Reduce", self);
+                    #[cfg(not(target_arch = "spirv"))]
+                    self
+                }
+                #[inline]
+                fn __reduce_max(self) -> Self {
+                    #[cfg(target_arch = "spirv")]
+                    return group_op!($max, "Reduce", self);
+                    #[cfg(not(target_arch = "spirv"))]
+                    self
+                }
+                #[inline]
+                fn __inclusive_scan(self) -> Self {
+                    #[cfg(target_arch = "spirv")]
+                    return group_op!($add, "InclusiveScan", self);
+                    #[cfg(not(target_arch = "spirv"))]
+                    self
+                }
+                #[inline]
+                fn __exclusive_scan(self) -> Self {
+                    #[cfg(target_arch = "spirv")]
+                    return group_op!($add, "ExclusiveScan", self);
+                    #[cfg(not(target_arch = "spirv"))]
+                    Self::default()
+                }
+                #[inline]
+                fn __broadcast(self, id: u32) -> Self {
+                    #[cfg(target_arch = "spirv")]
+                    return id_op!("OpGroupNonUniformBroadcast", self, id);
+                    #[cfg(not(target_arch = "spirv"))]
+                    {
+                        let _ = id;
+                        self
+                    }
+                }
+                #[inline]
+                fn __shuffle(self, id: u32) -> Self {
+                    #[cfg(target_arch = "spirv")]
+                    return id_op!("OpGroupNonUniformShuffle", self, id);
+                    #[cfg(not(target_arch = "spirv"))]
+                    {
+                        let _ = id;
+                        self
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_native! {
+    u32 => "OpGroupNonUniformIAdd", "OpGroupNonUniformUMin", "OpGroupNonUniformUMax";
+    i32 => "OpGroupNonUniformIAdd", "OpGroupNonUniformSMin", "OpGroupNonUniformSMax";
+    f32 => "OpGroupNonUniformFAdd", "OpGroupNonUniformFMin", "OpGroupNonUniformFMax";
+    u64 => "OpGroupNonUniformIAdd", "OpGroupNonUniformUMin", "OpGroupNonUniformUMax";
+    i64 => "OpGroupNonUniformIAdd", "OpGroupNonUniformSMin", "OpGroupNonUniformSMax";
+    f64 => "OpGroupNonUniformFAdd", "OpGroupNonUniformFMin", "OpGroupNonUniformFMax";
+}
+
+// Types computed with a wider native type.
+macro_rules! impl_widened {
+    ($($t:ty => $w:ty),* $(,)?) => {
+        $(
+            impl Sealed for $t {}
+
+            impl SubgroupScalar for $t {
+                #[inline]
+                fn __reduce_add(self) -> Self {
+                    self.cast::<$w>().__reduce_add().cast()
+                }
+                #[inline]
+                fn __reduce_min(self) -> Self {
+                    self.cast::<$w>().__reduce_min().cast()
+                }
+                #[inline]
+                fn __reduce_max(self) -> Self {
+                    self.cast::<$w>().__reduce_max().cast()
+                }
+                #[inline]
+                fn __inclusive_scan(self) -> Self {
+                    self.cast::<$w>().__inclusive_scan().cast()
+                }
+                #[inline]
+                fn __exclusive_scan(self) -> Self {
+                    self.cast::<$w>().__exclusive_scan().cast()
+                }
+                #[inline]
+                fn __broadcast(self, id: u32) -> Self {
+                    self.cast::<$w>().__broadcast(id).cast()
+                }
+                #[inline]
+                fn __shuffle(self, id: u32) -> Self {
+                    self.cast::<$w>().__shuffle(id).cast()
+                }
+            }
+        )*
+    };
+}
+
+impl_widened! {
+    u8 => u32,
+    i8 => i32,
+    u16 => u32,
+    i16 => i32,
+    f16 => f32,
+    bf16 => f32,
+    f8e4m3 => f32,
+    f8e5m2 => f32,
+}
+
+// Complex numbers are computed per component, and ordered by the real then the imaginary part.
+macro_rules! impl_complex {
+    ($($t:ident => $f:ty),* $(,)?) => {
+        $(
+            impl Sealed for $t {}
+
+            impl SubgroupScalar for $t {
+                #[inline]
+                fn __reduce_add(self) -> Self {
+                    $t::new(self.re().__reduce_add(), self.im().__reduce_add())
+                }
+                #[inline]
+                fn __reduce_min(self) -> Self {
+                    let re = self.re().__reduce_min();
+                    let im = if self.re() == re { self.im() } else { <$f>::INFINITY };
+                    $t::new(re, im.__reduce_min())
+                }
+                #[inline]
+                fn __reduce_max(self) -> Self {
+                    let re = self.re().__reduce_max();
+                    let im = if self.re() == re { self.im() } else { <$f>::NEG_INFINITY };
+                    $t::new(re, im.__reduce_max())
+                }
+                #[inline]
+                fn __inclusive_scan(self) -> Self {
+                    $t::new(self.re().__inclusive_scan(), self.im().__inclusive_scan())
+                }
+                #[inline]
+                fn __exclusive_scan(self) -> Self {
+                    $t::new(self.re().__exclusive_scan(), self.im().__exclusive_scan())
+                }
+                #[inline]
+                fn __broadcast(self, id: u32) -> Self {
+                    $t::new(self.re().__broadcast(id), self.im().__broadcast(id))
+                }
+                #[inline]
+                fn __shuffle(self, id: u32) -> Self {
+                    $t::new(self.re().__shuffle(id), self.im().__shuffle(id))
+                }
+            }
+        )*
+    };
+}
+
+impl_complex! {
+    c32 => f32,
+    c64 => f64,
+}
@@ -4,12 +4,15 @@
 use derive_syn_parse::Parse;
 use fxhash::FxHashMap;
 use proc_macro::TokenStream;
-use proc_macro2::{Literal, Span as Span2, TokenStream as TokenStream2};
+use proc_macro2::{Delimiter, Literal, Span as Span2, TokenStream as TokenStream2, TokenTree};
 use quote::{format_ident, quote, ToTokens};
 use semver::Version;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
-    fmt::{self, Debug}, slice::from_raw_parts, str::FromStr, sync::OnceLock
+    fmt::{self, Debug},
+    slice::from_raw_parts,
+    str::FromStr,
+    sync::OnceLock,
 };
 use syn::{
     parse::{Parse, ParseStream},
@@ -71,20 +74,7 @@ pub fn module(attr: TokenStream, item: TokenStream) -> TokenStream {
             let args = syn::parse_macro_input!(tokens as ModuleKrnlArgs);
             for arg in args.args.iter() {
                 if let Some(krnl_crate) = arg.krnl_crate.as_ref() {
-                    krnl = if krnl_crate.leading_colon.is_some()
-                        || krnl_crate
-                            .to_token_stream()
-                            .to_string()
-                            .starts_with("crate")
-                    {
-                        quote! {
-                            #krnl_crate
-                        }
-                    } else {
-                        quote! {
-                            ::#krnl_crate
-                        }
-                    };
+                    krnl = krnl_crate_path(krnl_crate);
                 } else if let Some(ident) = &arg.ident {
                     if ident == "no_build" {
                         build = false;
@@ -135,6 +125,12 @@ pub fn module(attr: TokenStream, item: TokenStream) -> TokenStream {
                             Some(#krnl::macros::__krnl_cache!($v, #ident, $k, $x))
                         };
                     }
+                    #[doc(hidden)]
+                    macro_rules! __krnl_kernels {
+                        () => {
+                            #krnl::macros::__krnl_cache!($v, #ident, $x)
+                        };
+                    }
                 };
             }
             #[cfg(not(krnlc))]
@@ -146,6 +142,13 @@ pub fn module(attr: TokenStream, item: TokenStream) -> TokenStream {
                     None
                 };
             }
+            #[doc(hidden)]
+            #[cfg(krnlc)]
+            macro_rules! __krnl_kernels {
+                () => {
+                    &[]
+                };
+            }
             #tokens
         };
     } else {
@@ -157,12 +160,84 @@ pub fn module(attr: TokenStream, item: TokenStream) -> TokenStream {
                     None
                 };
             }
+            #[doc(hidden)]
+            macro_rules! __krnl_kernels {
+                () => {
+                    &[]
+                };
+            }
             #tokens
         }
     }
+    {
+        let tokens = element_krnl_crate(item.tokens, &krnl);
+        item.tokens = quote! {
+            #tokens
+            /// Kernels of the module, including submodules.
+            ///
+            /// Empty if the module was not compiled with krnlc.
+            #[cfg(not(target_arch = "spirv"))]
+            #[allow(dead_code)]
+            pub fn kernel_infos() -> &'static [#krnl::kernel::KernelInfo] {
+                use #krnl::kernel::{KernelInfo, __private::module_kernels};
+                use ::std::sync::OnceLock;
+
+                static KERNELS: OnceLock<Vec<KernelInfo>> = OnceLock::new();
+                KERNELS.get_or_init(|| module_kernels(::std::module_path!(), __krnl_kernels!()))
+            }
+        };
+    }
     item.into_token_stream().into()
 }
 
+fn krnl_crate_path(krnl_crate: &syn::Path) -> TokenStream2 {
+    if krnl_crate.leading_colon.is_some()
+        || krnl_crate
+            .to_token_stream()
+            .to_string()
+            .starts_with("crate")
+    {
+        quote! {
+            #krnl_crate
+        }
+    } else {
+        quote! {
+            ::#krnl_crate
+        }
+    }
+}
+
+/// Adds `#[krnl(crate = ..)]` to items with `#[derive(Element)]`, so that the derive uses the
+/// path to krnl of the module.
+fn element_krnl_crate(tokens: TokenStream2, krnl: &TokenStream2) -> TokenStream2 {
+    fn is_derive_element(attr: &proc_macro2::Group) -> bool {
+        let mut iter = attr.stream().into_iter();
+        matches!(iter.next(), Some(TokenTree::Ident(ident)) if ident == "derive")
+            && matches!(iter.next(), Some(TokenTree::Group(derives)) if derives
+                .stream()
+                .into_iter()
+                .any(|x| matches!(x, TokenTree::Ident(ident) if ident == "Element")))
+    }
+    let mut output = TokenStream2::new();
+    let mut iter = tokens.into_iter().peekable();
+    while let Some(token) = iter.next() {
+        let pound = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '#');
+        output.extend([token]);
+        if !pound {
+            continue;
+        }
+        if let Some(TokenTree::Group(attr)) = iter.peek() {
+            if attr.delimiter() == Delimiter::Bracket && is_derive_element(attr) {
+                output.extend(iter.next());
+                output.extend(quote! {
+                    #[krnl(crate = #krnl)]
+                });
+            }
+        }
+    }
+    output
+}
+
 #[derive(Parse, Debug)]
 struct ModuleKrnlArgs {
     #[allow(unused)]
@@ -214,17 +289,59 @@ impl ToTokens for ModuleItem {
 
 #[proc_macro_attribute]
 pub fn kernel(attr: TokenStream, item: TokenStream) -> TokenStream {
-    if !attr.is_empty() {
-        return Error::new_spanned(&TokenStream2::from(attr), "unexpected tokens")
-            .into_compile_error()
-            .into();
-    }
-    match kernel_impl(item.into()) {
+    let attr = parse_macro_input!(attr as KernelAttr);
+    match kernel_impl(attr, item.into()) {
         Ok(tokens) => tokens.into(),
         Err(e) => e.into_compile_error().into(),
     }
 }
 
+#[derive(Default, Debug)]
+struct KernelAttr {
+    threads: Option<Vec<u32>>,
+}
+
+impl KernelAttr {
+    fn dims(&self) -> usize {
+        self.threads.as_ref().map_or(1, Vec::len)
+    }
+}
+
+impl Parse for KernelAttr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut attr = Self::default();
+        if input.is_empty() {
+            return Ok(attr);
+        }
+        let ident: Ident = input.parse()?;
+        if ident != "threads" {
+            return Err(Error::new(ident.span(), "expected `threads`"));
+        }
+        let content;
+        let paren = syn::parenthesized!(content in input);
+        let dims = Punctuated::<LitInt, Comma>::parse_terminated(&content)?;
+        if dims.is_empty() || dims.len() > 3 {
+            return Err(Error::new(
+                paren.span,
+                "expected threads for 1, 2, or 3 dimensions",
+            ));
+        }
+        let mut threads = Vec::with_capacity(dims.len());
+        for dim in dims.iter() {
+            let x: u32 = dim.base10_parse()?;
+            if x == 0 {
+                return Err(Error::new(dim.span(), "threads must be greater than 0"));
+            }
+            threads.push(x);
+        }
+        attr.threads.replace(threads);
+        if !input.is_empty() {
+            return Err(input.error("unexpected tokens"));
+        }
+        Ok(attr)
+    }
+}
+
 #[derive(Parse, Debug)]
 struct KernelItem {
     #[call(Attribute::parse_outer)]
@@ -259,6 +376,14 @@ impl KernelItem {
         };
         let mut spec_id = 0;
         if let Some(generics) = self.generics.as_ref() {
+            for x in generics.specs.iter() {
+                if matches!(x.ty.scalar_type, ScalarType::C32 | ScalarType::C64) {
+                    return Err(Error::new(
+                        x.ty.ident.span(),
+                        "complex specialization constants are not supported",
+                    ));
+                }
+            }
             meta.spec_metas = generics
                 .specs
                 .iter()
@@ -387,6 +512,32 @@ impl Parse for KernelTypeScalar {
     }
 }
 
+#[derive(Clone, Debug)]
+enum KernelTypeElement {
+    Scalar(KernelTypeScalar),
+    Element(syn::Type),
+}
+
+impl Parse for KernelTypeElement {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        if input.fork().parse::<KernelTypeScalar>().is_ok() {
+            return Ok(Self::Scalar(input.parse()?));
+        }
+        let ty: syn::Type = input.parse()?;
+        if let syn::Type::Path(path) = &ty {
+            if let Some(segment) = path.path.segments.first() {
+                if segment.ident == "self" || segment.ident == "super" {
+                    return Err(Error::new_spanned(
+                        &ty,
+                        "relative paths are not supported, import the element type with `use`",
+                    ));
+                }
+            }
+        }
+        Ok(Self::Element(ty))
+    }
+}
+
 #[derive(Parse, Debug)]
 struct KernelArg {
     kind: KernelArgKind,
@@ -406,6 +557,8 @@ struct KernelArg {
 impl KernelArg {
     fn meta(&self) -> Result<KernelArgMeta> {
         let kind = self.kind;
+        let mut element = None;
+        let mut group_slice = false;
         let (scalar_ty, mutable, len) = if let Some(slice_ty) = self.slice_ty.as_ref() {
             let slice_ty_ident = &slice_ty.ty;
             let mutable = if slice_ty.ty == "Slice" {
@@ -420,8 +573,28 @@ impl KernelArg {
                     "expected `Slice` or `UnsafeSlice`",
                 ));
             };
-            (slice_ty.scalar_ty.clone(), mutable, None)
+            let scalar_ty = match &slice_ty.elem_ty {
+                KernelTypeElement::Scalar(scalar_ty) => scalar_ty.clone(),
+                KernelTypeElement::Element(ty) => {
+                    element.replace(ty.clone());
+                    // the scalar type is provided by the element layout on the host
+                    KernelTypeScalar {
+                        ident: format_ident!("u8"),
+                        scalar_type: ScalarType::U8,
+                    }
+                }
+            };
+            (scalar_ty, mutable, None)
         } else if let Some(array_ty) = self.array_ty.as_ref() {
+            let array_ty_ident = &array_ty.ty;
+            if array_ty.ty == "GroupSlice" {
+                group_slice = true;
+            } else if array_ty.ty != "UnsafeSlice" {
+                return Err(Error::new_spanned(
+                    array_ty_ident,
+                    "expected `UnsafeSlice` or `GroupSlice`",
+                ));
+            }
             let len = array_ty.len.to_token_stream();
             (array_ty.scalar_ty.clone(), true, Some(len))
         } else if let Some(item_ty) = self.item_ty.as_ref() {
@@ -435,7 +608,9 @@ impl KernelArg {
             kind,
             ident: self.ident.clone(),
             scalar_ty,
+            element,
             mutable,
+            group_slice,
             binding: None,
             len,
         };
@@ -448,15 +623,24 @@ struct KernelArgMeta {
     kind: KernelArgKind,
     ident: Ident,
     scalar_ty: KernelTypeScalar,
+    element: Option<syn::Type>,
     mutable: bool,
+    group_slice: bool,
     binding: Option<u32>,
     len: Option<TokenStream2>,
 }
 
 impl KernelArgMeta {
+    fn ty(&self) -> TokenStream2 {
+        if let Some(element) = self.element.as_ref() {
+            element.to_token_stream()
+        } else {
+            self.scalar_ty.ident.to_token_stream()
+        }
+    }
     fn compute_def_tokens(&self) -> Option<TokenStream2> {
         let ident = &self.ident;
-        let ty = &self.scalar_ty.ident;
+        let ty = self.ty();
         if let Some(binding) = self.binding.as_ref() {
             let set = LitInt::new("0", Span2::call_site());
             let binding = LitInt::new(&binding.to_string(), Span2::call_site());
@@ -474,7 +658,7 @@ impl KernelArgMeta {
     }
     fn device_fn_def_tokens(&self) -> TokenStream2 {
         let ident = &self.ident;
-        let ty = &self.scalar_ty.ident;
+        let ty = self.ty();
         let mutable = self.mutable;
         use KernelArgKind::*;
         match self.kind {
@@ -500,9 +684,17 @@ impl KernelArgMeta {
                     }
                 }
             }
-            Group => quote! {
-                #ident: ::krnl_core::buffer::UnsafeSlice<#ty>
-            },
+            Group => {
+                if self.group_slice {
+                    quote! {
+                        #ident: ::krnl_core::buffer::GroupSlice<#ty>
+                    }
+                } else {
+                    quote! {
+                        #ident: ::krnl_core::buffer::UnsafeSlice<#ty>
+                    }
+                }
+            }
             Push => quote! {
                 #ident: #ty
             },
@@ -536,12 +728,26 @@ impl KernelArgMeta {
                 let len = format_ident!("__krnl_len_{ident}");
                 let scalar_name = self.scalar_ty.scalar_type.name();
                 let array = format_ident!("__krnl_group_array_{scalar_name}");
-                quote! {
-                    let #ident = {
-                        unsafe {
-                            ::krnl_core::buffer::UnsafeSlice::from_unsafe_raw_parts(#array, #offset, #len)
-                        }
-                    };
+                if self.group_slice {
+                    quote! {
+                        let #ident = {
+                            unsafe {
+                                ::krnl_core::buffer::GroupSlice::from_unsafe_slice(
+                                    ::krnl_core::buffer::UnsafeSlice::from_unsafe_raw_parts(#array, #offset, #len),
+                                    kernel.thread_id(),
+                                    kernel.threads(),
+                                )
+                            }
+                        };
+                    }
+                } else {
+                    quote! {
+                        let #ident = {
+                            unsafe {
+                                ::krnl_core::buffer::UnsafeSlice::from_unsafe_raw_parts(#array, #offset, #len)
+                            }
+                        };
+                    }
                 }
             }
             Push => TokenStream2::new(),
@@ -636,14 +842,13 @@ struct KernelTypeSlice {
     ty: Ident,
     #[allow(unused)]
     lt: Lt,
-    scalar_ty: KernelTypeScalar,
+    elem_ty: KernelTypeElement,
     #[allow(unused)]
     gt: Gt,
 }
 
 #[derive(Parse, Debug)]
 struct KernelTypeArray {
-    #[allow(unused)]
     ty: Ident,
     #[allow(unused)]
     lt: Lt,
@@ -735,6 +940,10 @@ impl KernelMeta {
                         scalar_type,
                         mutable: arg_meta.mutable,
                         item: kind.is_item(),
+                        element: arg_meta
+                            .element
+                            .as_ref()
+                            .map(|x| x.to_token_stream().to_string()),
                     });
                 }
                 Group => (),
@@ -961,7 +1170,7 @@ impl KernelMeta {
         let mut tokens = TokenStream2::new();
         for arg in self.arg_metas.iter() {
             let ident = &arg.ident;
-            let ty = &arg.scalar_ty.ident;
+            let ty = arg.ty();
             if arg.binding.is_some() {
                 let slice_ty = if arg.mutable {
                     format_ident!("SliceMut")
@@ -1007,12 +1216,19 @@ enum ScalarType {
     U64,
     I64,
     F64,
+    C32,
+    C64,
+    F8E4M3,
+    F8E5M2,
 }
 
 impl ScalarType {
     fn iter() -> impl Iterator<Item = Self> {
         use ScalarType::*;
-        [U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64].into_iter()
+        [
+            U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64, C32, C64, F8E4M3, F8E5M2,
+        ]
+        .into_iter()
     }
     fn name(&self) -> &'static str {
         use ScalarType::*;
@@ -1029,6 +1245,10 @@ impl ScalarType {
             U64 => "u64",
             I64 => "i64",
             F64 => "f64",
+            C32 => "c32",
+            C64 => "c64",
+            F8E4M3 => "f8e4m3",
+            F8E5M2 => "f8e5m2",
         }
     }
     fn as_str(&self) -> &'static str {
@@ -1046,15 +1266,20 @@ impl ScalarType {
             U64 => "U64",
             I64 => "I64",
             F64 => "F64",
+            C32 => "C32",
+            C64 => "C64",
+            F8E4M3 => "F8E4M3",
+            F8E5M2 => "F8E5M2",
         }
     }
     fn size(&self) -> usize {
         use ScalarType::*;
         match self {
-            U8 | I8 => 1,
+            U8 | I8 | F8E4M3 | F8E5M2 => 1,
             U16 | I16 | F16 | BF16 => 2,
             U32 | I32 | F32 => 4,
-            U64 | I64 | F64 => 8,
+            U64 | I64 | F64 | C32 => 8,
+            C64 => 16,
         }
     }
 }
@@ -1122,12 +1347,20 @@ struct KernelDesc {
     spirv: Vec<u32>,
     #[serde(skip_serializing)]
     features: Features,
+    #[serde(skip_serializing)]
+    emulated: Option<EmulatedDesc>,
     safe: bool,
     spec_descs: Vec<SpecDesc>,
     slice_descs: Vec<SliceDesc>,
     push_descs: Vec<PushDesc>,
 }
 
+#[derive(Deserialize, Debug)]
+struct EmulatedDesc {
+    spirv: Vec<u32>,
+    features: Features,
+}
+
 impl KernelDesc {
     fn encode(&self) -> Result<String> {
         let bytes = bincode2::serialize(self).map_err(|e| Error::new(Span2::call_site(), e))?;
@@ -1190,6 +1423,8 @@ impl Features {
     pub const BUFFER16: Self = Self::new(1 << 9);
     pub const PUSH_CONSTANT8: Self = Self::new(1 << 10);
     pub const PUSH_CONSTANT16: Self = Self::new(1 << 11);
+    pub const BUFFER_INT64_ATOMICS: Self = Self::new(1 << 12);
+    pub const GROUP_INT64_ATOMICS: Self = Self::new(1 << 13);
     pub const SUBGROUP_BASIC: Self = Self::new(1 << 16);
     pub const SUBGROUP_VOTE: Self = Self::new(1 << 17);
     pub const SUBGROUP_ARITHMETIC: Self = Self::new(1 << 18);
@@ -1198,6 +1433,12 @@ impl Features {
     pub const SUBGROUP_SHUFFLE_RELATIVE: Self = Self::new(1 << 21);
     pub const SUBGROUP_CLUSTERED: Self = Self::new(1 << 22);
     pub const SUBGROUP_QUAD: Self = Self::new(1 << 23);
+    pub const BUFFER_FLOAT32_ATOMICS: Self = Self::new(1 << 24);
+    pub const GROUP_FLOAT32_ATOMICS: Self = Self::new(1 << 25);
+    pub const BUFFER_FLOAT32_ATOMIC_ADD: Self = Self::new(1 << 26);
+    pub const GROUP_FLOAT32_ATOMIC_ADD: Self = Self::new(1 << 27);
+    pub const BUFFER_FLOAT32_ATOMIC_MIN_MAX: Self = Self::new(1 << 28);
+    pub const GROUP_FLOAT32_ATOMIC_MIN_MAX: Self = Self::new(1 << 29);
 
     #[inline]
     const fn new(bits: u32) -> Self {
@@ -1259,6 +1500,8 @@ impl Features {
             BUFFER16,
             PUSH_CONSTANT8,
             PUSH_CONSTANT16,
+            BUFFER_INT64_ATOMICS,
+            GROUP_INT64_ATOMICS,
             SUBGROUP_BASIC,
             SUBGROUP_VOTE,
             SUBGROUP_ARITHMETIC,
@@ -1266,7 +1509,13 @@ impl Features {
             SUBGROUP_SHUFFLE,
             SUBGROUP_SHUFFLE_RELATIVE,
             SUBGROUP_CLUSTERED,
-            SUBGROUP_QUAD
+            SUBGROUP_QUAD,
+            BUFFER_FLOAT32_ATOMICS,
+            GROUP_FLOAT32_ATOMICS,
+            BUFFER_FLOAT32_ATOMIC_ADD,
+            GROUP_FLOAT32_ATOMIC_ADD,
+            BUFFER_FLOAT32_ATOMIC_MIN_MAX,
+            GROUP_FLOAT32_ATOMIC_MIN_MAX
         )
         .into_iter()
         .filter_map(move |(name, features)| {
@@ -1338,6 +1587,8 @@ struct SliceDesc {
     scalar_type: ScalarType,
     mutable: bool,
     item: bool,
+    // Element type, ie "Particle".
+    element: Option<String>,
 }
 
 impl ToTokens for SliceDesc {
@@ -1347,13 +1598,36 @@ impl ToTokens for SliceDesc {
             scalar_type,
             mutable,
             item,
+            element,
         } = self;
+        let (scalar_type, element) = if let Some(element) = element.as_ref() {
+            let ty: TokenStream2 = element.parse().unwrap();
+            let layout = quote! {
+                <#ty as __krnl::buffer::Element>::LAYOUT
+            };
+            (
+                quote! {
+                    #layout.scalar_type
+                },
+                quote! {
+                    Some(#layout)
+                },
+            )
+        } else {
+            (
+                scalar_type.to_token_stream(),
+                quote! {
+                    None
+                },
+            )
+        };
         tokens.extend(quote! {
             SliceDesc {
                 name: #name,
                 scalar_type: #scalar_type,
                 mutable: #mutable,
                 item: #item,
+                element: #element,
             }
         })
     }
@@ -1377,9 +1651,16 @@ impl ToTokens for PushDesc {
     }
 }
 
-fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
+fn kernel_impl(attr: KernelAttr, item_tokens: TokenStream2) -> Result<TokenStream2> {
     let item: KernelItem = syn::parse2(item_tokens.clone())?;
     let kernel_meta = item.meta()?;
+    let dims = attr.dims();
+    if kernel_meta.itemwise && dims > 1 {
+        return Err(Error::new(
+            kernel_meta.ident.span(),
+            "item kernels cannot have multi-dimensional threads",
+        ));
+    }
     let kernel_desc = kernel_meta.desc()?;
     let item_attrs = &item.attrs;
     let unsafe_token = kernel_meta.unsafe_token;
@@ -1389,8 +1670,9 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
         let block = &kernel_meta.block;
         let compute_def_args = kernel_meta.compute_def_args();
         let declare_specs = kernel_meta.declare_specs();
-        let threads_spec_id =
-            Literal::u32_unsuffixed(kernel_desc.spec_descs.len().try_into().unwrap());
+        let threads_spec_id: u32 = kernel_desc.spec_descs.len().try_into().unwrap();
+        let [threads_x_spec_id, threads_y_spec_id, threads_z_spec_id] =
+            [0, 1, 2].map(|i| Literal::u32_unsuffixed(threads_spec_id + i));
         let items = kernel_meta.device_items();
         let device_arrays = kernel_meta.device_arrays();
         let device_slices = kernel_meta.device_slices();
@@ -1468,9 +1750,13 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
                 __krnl_subgroup_id: u32,
                 #[spirv(subgroup_local_invocation_id)]
                 __krnl_subgroup_thread_id: u32,
-                #[spirv(spec_constant(id = #threads_spec_id, default = 1))] __krnl_threads: u32,
+                #[spirv(spec_constant(id = #threads_x_spec_id, default = 1))] __krnl_threads_x: u32,
+                #[spirv(spec_constant(id = #threads_y_spec_id, default = 1))] __krnl_threads_y: u32,
+                #[spirv(spec_constant(id = #threads_z_spec_id, default = 1))] __krnl_threads_z: u32,
                 #[spirv(local_invocation_id)]
                 __krnl_thread_id: ::krnl_core::spirv_std::glam::UVec3,
+                #[spirv(local_invocation_index)]
+                __krnl_thread_index: u32,
                 #[spirv(storage_buffer, descriptor_set = 1, binding = 0)]
                 #kernel_data: &mut [u32],
                 #compute_def_args
@@ -1489,14 +1775,15 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
                     #declare_specs
                     let mut kernel = unsafe {
                         ::krnl_core::kernel::__private::KernelArgs {
-                            global_id: __krnl_global_id.x,
-                            groups: __krnl_groups.x,
-                            group_id: __krnl_group_id.x,
+                            global_id: __krnl_global_id.to_array(),
+                            groups: __krnl_groups.to_array(),
+                            group_id: __krnl_group_id.to_array(),
                             subgroups: __krnl_subgroups,
                             subgroup_id: __krnl_subgroup_id,
                             subgroup_thread_id: __krnl_subgroup_thread_id,
-                            threads: __krnl_threads,
-                            thread_id: __krnl_thread_id.x,
+                            threads: [__krnl_threads_x, __krnl_threads_y, __krnl_threads_z],
+                            thread_id: __krnl_thread_id.to_array(),
+                            thread_index: __krnl_thread_index,
                         }.into_kernel()
                     };
                     #device_arrays
@@ -1524,6 +1811,14 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
             }
         };
         let host_array_length_checks = kernel_meta.host_array_length_checks();
+        let use_elements = if kernel_meta.arg_metas.iter().any(|x| x.element.is_some()) {
+            quote! {
+                #[allow(unused_imports)]
+                use super::*;
+            }
+        } else {
+            TokenStream2::new()
+        };
         let specialize = !kernel_desc.spec_descs.is_empty();
         let specialized = [format_ident!("S")];
         let specialized = if specialize {
@@ -1576,9 +1871,58 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
         } else {
             TokenStream2::new()
         };
+        let (dim_ty, to_xyz, from_xyz) = match dims {
+            1 => (
+                quote! { u32 },
+                quote! { fn __krnl_to_xyz(x: u32) -> [u32; 3] { [x, 1, 1] } },
+                quote! { fn __krnl_from_xyz([x, _, _]: [u32; 3]) -> u32 { x } },
+            ),
+            2 => (
+                quote! { [u32; 2] },
+                quote! { fn __krnl_to_xyz([x, y]: [u32; 2]) -> [u32; 3] { [x, y, 1] } },
+                quote! { fn __krnl_from_xyz([x, y, _]: [u32; 3]) -> [u32; 2] { [x, y] } },
+            ),
+            _ => (
+                quote! { [u32; 3] },
+                quote! { fn __krnl_to_xyz(xyz: [u32; 3]) -> [u32; 3] { xyz } },
+                quote! { fn __krnl_from_xyz(xyz: [u32; 3]) -> [u32; 3] { xyz } },
+            ),
+        };
+        let (default_threads, with_threads_docs) = if let Some(threads) = attr.threads.as_ref() {
+            let threads_lits: Vec<_> = threads
+                .iter()
+                .map(|x| Literal::u32_unsuffixed(*x))
+                .collect();
+            let threads_tokens = if let [x] = threads_lits.as_slice() {
+                quote! { #x }
+            } else {
+                quote! { [#(#threads_lits),*] }
+            };
+            let threads_string = threads
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            (
+                quote! { .map(|inner| inner.with_threads(__krnl_to_xyz(#threads_tokens))) },
+                format!("Defaults to `threads({threads_string})`."),
+            )
+        } else {
+            (
+                TokenStream2::new(),
+                "Defaults to [`DeviceInfo::default_threads()`](DeviceInfo::default_threads)."
+                    .to_string(),
+            )
+        };
+        let kernel_attr = if let Some(threads) = attr.threads.as_ref() {
+            let threads = threads.iter().map(|x| Literal::u32_unsuffixed(*x));
+            quote! { #[kernel(threads(#(#threads),*))] }
+        } else {
+            quote! { #[kernel] }
+        };
         let input_docs = {
             let input_tokens_string = prettyplease::unparse(&syn::parse2(quote! {
-                #[kernel]
+                #kernel_attr
                 #item_tokens
             })?);
             let input_doc_string = format!("```\n{input_tokens_string}\n```");
@@ -1612,7 +1956,8 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
                     krnl_core::half::{f16, bf16},
                     buffer::{Slice, SliceMut},
                     device::{Device, Features},
-                    scalar::ScalarType,
+                    scalar::{c32, c64, f8e4m3, f8e5m2, ScalarElem, ScalarType},
+                    kernel::KernelSliceArg,
                     kernel::__private::{
                         Kernel as KernelBase,
                         KernelBuilder as KernelBuilderBase,
@@ -1628,6 +1973,7 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
                     anyhow::format_err,
                 };
                 use ::std::{sync::OnceLock, marker::PhantomData};
+                #use_elements
                 #[cfg(not(krnlc))]
                 #[doc(hidden)]
                 use __krnl::macros::__krnl_cache;
@@ -1636,6 +1982,14 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
 
                 #host_array_length_checks
 
+                #[doc(hidden)]
+                #[inline]
+                #to_xyz
+
+                #[doc(hidden)]
+                #[inline]
+                #from_xyz
+
                 /// Builder for creating a [`Kernel`].
                 ///
                 /// See [`builder()`](builder).
@@ -1657,7 +2011,7 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
                     let builder = BUILDER.get_or_init(|| {
                         const DESC: Option<KernelDesc> = validate_kernel(__krnl_kernel!(#ident), #safety, &[#(#spec_descs),*], &[#(#slice_descs),*], &[#(#push_descs),*]);
                         if let Some(desc) = DESC.as_ref() {
-                            KernelBuilderBase::from_desc(desc.clone())
+                            KernelBuilderBase::from_desc(desc.clone())#default_threads
                         } else {
                             Err(format!("Kernel `{}` not compiled!", ::std::module_path!()))
                         }
@@ -1674,10 +2028,10 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
                 impl #(<#specialized>)* KernelBuilder #(<#specialized>)* {
                     /// Threads per group.
                     ///
-                    /// Defaults to [`DeviceInfo::default_threads()`](DeviceInfo::default_threads).
-                    pub fn with_threads(self, threads: u32) -> Self {
+                    #[doc = #with_threads_docs]
+                    pub fn with_threads(self, threads: #dim_ty) -> Self {
                         Self {
-                            inner: self.inner.with_threads(threads),
+                            inner: self.inner.with_threads(__krnl_to_xyz(threads)),
                             _m: PhantomData,
                         }
                     }
@@ -1687,6 +2041,14 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
                     pub fn __features(&self) -> Features {
                         self.inner.features()
                     }
+                    #[doc(hidden)]
+                    #[inline]
+                    pub fn __emulate(self) -> Self {
+                        Self {
+                            inner: self.inner.emulate(),
+                            _m: PhantomData,
+                        }
+                    }
                 }
 
                 impl KernelBuilder #kernel_builder_build_generics {
@@ -1717,24 +2079,24 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
 
                 impl #(<#with_groups>)* Kernel #(<#with_groups>)* {
                     /// Threads per group.
-                    pub fn threads(&self) -> u32 {
-                        self.inner.threads()
+                    pub fn threads(&self) -> #dim_ty {
+                        __krnl_from_xyz(self.inner.threads())
                     }
                     /// Global threads to dispatch.
                     ///
                     /// Implicitly declares groups by rounding up to the next multiple of threads.
-                    pub fn with_global_threads(self, global_threads: u32) -> Kernel #kernel_dispatch_generics {
+                    pub fn with_global_threads(self, global_threads: #dim_ty) -> Kernel #kernel_dispatch_generics {
                         Kernel {
-                            inner: self.inner.with_global_threads(global_threads),
+                            inner: self.inner.with_global_threads(__krnl_to_xyz(global_threads)),
                             _m: PhantomData,
                         }
                     }
                     /// Groups to dispatch.
                     ///
                     /// For item kernels, if not provided, is inferred based on item arguments.
-                    pub fn with_groups(self, groups: u32) -> Kernel #kernel_dispatch_generics {
+                    pub fn with_groups(self, groups: #dim_ty) -> Kernel #kernel_dispatch_generics {
                         Kernel {
-                            inner: self.inner.with_groups(groups),
+                            inner: self.inner.with_groups(__krnl_to_xyz(groups)),
                             _m: PhantomData,
                         }
                     }
@@ -1748,11 +2110,32 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
                     /// - Blocks until the kernel is queued.
                     ///
                     /// # Errors
+                    /// - The groups exceed the device's `max_groups_xyz`.
+                    /// - The global threads exceed `u32::MAX`.
                     /// - [`DeviceLost`].
                     /// - The kernel could not be queued.
                     pub #unsafe_token fn dispatch(&self, #dispatch_args) -> Result<()> {
                         unsafe { self.inner.dispatch(&[#dispatch_slice_args], &[#(#dispatch_push_args.into()),*]) }
                     }
+                    /// Dispatches the kernel with dynamically typed arguments.
+                    ///
+                    /// Slices are in declaration order. Push constants are ordered by the size of
+                    /// their scalar type, largest first, and then in declaration order.
+                    ///
+                    /// - Waits for immutable access to slice arguments.
+                    /// - Waits for mutable access to mutable slice arguments.
+                    /// - Blocks until the kernel is queued.
+                    ///
+                    /// # Errors
+                    /// - The number, scalar types, elements or mutability of the arguments don't match the kernel.
+                    /// - A slice is empty, not on the kernel's device, or not aligned to its element size.
+                    /// - The groups exceed the device's `max_groups_xyz`.
+                    /// - The global threads exceed `u32::MAX`.
+                    /// - [`DeviceLost`].
+                    /// - The kernel could not be queued.
+                    pub #unsafe_token fn dispatch_dyn(&self, slices: &[KernelSliceArg], push_consts: &[ScalarElem]) -> Result<()> {
+                        unsafe { self.inner.dispatch(slices, push_consts) }
+                    }
                 }
             }
         }
@@ -1766,6 +2149,103 @@ fn kernel_impl(item_tokens: TokenStream2) -> Result<TokenStream2> {
     Ok(tokens)
 }
 
+#[proc_macro_derive(Element, attributes(krnl))]
+pub fn derive_element(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    match derive_element_impl(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn derive_element_impl(input: syn::DeriveInput) -> Result<TokenStream2> {
+    let ident = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Element cannot be derived for generic types",
+        ));
+    }
+    let repr_c = input.attrs.iter().any(|attr| {
+        if !attr.path.is_ident("repr") {
+            return false;
+        }
+        if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+            list.nested.iter().any(
+                |meta| matches!(meta, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("C")),
+            )
+        } else {
+            false
+        }
+    });
+    if !repr_c {
+        return Err(Error::new_spanned(
+            ident,
+            "Element can only be derived for `#[repr(C)]` structs",
+        ));
+    }
+    let fields = if let syn::Data::Struct(data) = &input.data {
+        &data.fields
+    } else {
+        return Err(Error::new_spanned(
+            ident,
+            "Element can only be derived for structs",
+        ));
+    };
+    let mut krnl = quote! { ::krnl };
+    for attr in input.attrs.iter() {
+        if !attr.path.is_ident("krnl") {
+            continue;
+        }
+        let args: ModuleKrnlArgs = syn::parse2(attr.tokens.clone())?;
+        for arg in args.args.iter() {
+            if let Some(krnl_crate) = arg.krnl_crate.as_ref() {
+                krnl = krnl_crate_path(krnl_crate);
+            } else if let Some(ident) = &arg.ident {
+                return Err(Error::new_spanned(
+                    ident,
+                    format!("unknown krnl arg `{ident}`, expected `crate`"),
+                ));
+            }
+        }
+    }
+    let field_layouts: Vec<_> = fields
+        .iter()
+        .map(|field| {
+            let ty = &field.ty;
+            quote! {
+                <#ty as __krnl_core::buffer::Element>::LAYOUT
+            }
+        })
+        .collect();
+    let element_impl = quote! {
+        unsafe impl __krnl_core::buffer::Element for #ident {
+            const LAYOUT: __krnl_core::buffer::ElementLayout = __krnl_core::buffer::ElementLayout::from_fields(
+                &[#(#field_layouts),*],
+                ::core::mem::size_of::<Self>(),
+            );
+        }
+        // Checks the layout at compile time.
+        const _: __krnl_core::buffer::ElementLayout = <#ident as __krnl_core::buffer::Element>::LAYOUT;
+    };
+    Ok(quote! {
+        #[cfg(not(target_arch = "spirv"))]
+        const _: () = {
+            use #krnl::krnl_core as __krnl_core;
+
+            #element_impl
+            unsafe impl __krnl_core::bytemuck::Zeroable for #ident {}
+            unsafe impl __krnl_core::bytemuck::Pod for #ident {}
+        };
+        #[cfg(target_arch = "spirv")]
+        const _: () = {
+            use ::krnl_core as __krnl_core;
+
+            #element_impl
+        };
+    })
+}
+
 #[doc(hidden)]
 #[proc_macro]
 pub fn __krnl_cache(input: TokenStream) -> TokenStream {
@@ -1782,11 +2262,18 @@ struct KrnlCacheInput {
     __comma1: Comma,
     module: Ident,
     _comma2: Comma,
-    kernel: Ident,
-    _comma3: Comma,
+    // all kernels within the module if not provided
+    #[peek(Ident)]
+    kernel: Option<KrnlCacheKernel>,
     data: LitStr,
 }
 
+#[derive(Parse)]
+struct KrnlCacheKernel {
+    ident: Ident,
+    _comma: Comma,
+}
+
 fn __krnl_cache_impl(input: TokenStream2) -> Result<TokenStream2> {
     use flate2::{
         read::{GzDecoder, GzEncoder},
@@ -1831,7 +2318,6 @@ fn __krnl_cache_impl(input: TokenStream2) -> Result<TokenStream2> {
             let name = &kernel.name;
             let mut iter = name.rsplit("::");
 
-    
             let bytes = unsafe {
                 from_raw_parts(
                     kernel.spirv.as_ptr() as *const u8,
@@ -1839,10 +2325,12 @@ fn __krnl_cache_impl(input: TokenStream2) -> Result<TokenStream2> {
                 )
             };
 
-
             std::fs::write(format!("/tmp/shaders/{}.spv", name), bytes).unwrap();
-            if input.kernel != iter.next().unwrap() {
-                return false;
+            let kernel = iter.next().unwrap();
+            if let Some(input_kernel) = input.kernel.as_ref() {
+                if input_kernel.ident != kernel {
+                    return false;
+                }
             }
             iter.any(|x| input.module == x)
         })
@@ -1852,20 +2340,39 @@ fn __krnl_cache_impl(input: TokenStream2) -> Result<TokenStream2> {
                 spirv,
                 safe,
                 features,
+                emulated,
                 spec_descs,
                 slice_descs,
                 push_descs,
             } = kernel;
-            let mut bytes = Vec::new();
-            GzEncoder::new(bytemuck::cast_slice(spirv), Compression::best())
-                .read_to_end(&mut bytes)
-                .unwrap();
-            let spirv = LitByteStr::new(&bytes, span);
+            let encode_spirv = |spirv: &[u32]| {
+                let mut bytes = Vec::new();
+                // Modules only need metadata for reflection, the spirv is embedded per kernel.
+                if input.kernel.is_some() {
+                    GzEncoder::new(bytemuck::cast_slice(spirv), Compression::best())
+                        .read_to_end(&mut bytes)
+                        .unwrap();
+                }
+                LitByteStr::new(&bytes, span)
+            };
+            let spirv = encode_spirv(spirv);
+            let emulated = if let Some(EmulatedDesc { spirv, features }) = emulated {
+                let spirv = encode_spirv(spirv);
+                quote! {
+                    Some(EmulatedDesc {
+                        spirv: #spirv,
+                        features: #features,
+                    })
+                }
+            } else {
+                quote!(None)
+            };
             quote! {
                 KernelDesc::from_args(KernelDescArgs {
                     name: #name,
                     spirv: #spirv,
                     features: #features,
+                    emulated: #emulated,
                     safe: #safe,
                     spec_descs: &[#(#spec_descs),*],
                     slice_descs: &[#(#slice_descs),*],
@@ -1873,15 +2380,25 @@ fn __krnl_cache_impl(input: TokenStream2) -> Result<TokenStream2> {
                 })
             }
         });
+    let find_kernel = if input.kernel.is_some() {
+        quote! {
+            __krnl::kernel::__private::find_kernel(std::module_path!(), KERNELS)
+        }
+    } else {
+        quote! {
+            KERNELS
+        }
+    };
     let tokens = quote! {
         {
             __krnl_module_arg!(use crate as __krnl);
             use __krnl::{
                 device::Features,
-                kernel::__private::{find_kernel, KernelDesc, KernelDescArgs, Safety, SpecDesc, SliceDesc, PushDesc},
+                kernel::__private::{EmulatedDesc, KernelDesc, KernelDescArgs, Safety, SpecDesc, SliceDesc, PushDesc},
             };
 
-            find_kernel(std::module_path!(), &[#(#kernels),*])
+            const KERNELS: &[KernelDesc] = &[#(#kernels),*];
+            #find_kernel
         }
     };
     Ok(tokens)
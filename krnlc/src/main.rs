@@ -657,11 +657,20 @@ crate-type = ["dylib"]
             GroupNonUniformShuffleRelative,
             GroupNonUniformClustered,
             GroupNonUniformQuad,
+            Int64Atomics,
+            AtomicFloat32AddEXT,
+            AtomicFloat32MinMaxEXT,
         ]
     };
     for cap in capabilites {
         builder = builder.capability(cap);
     }
+    for ext in [
+        "SPV_EXT_shader_atomic_float_add",
+        "SPV_EXT_shader_atomic_float_min_max",
+    ] {
+        builder = builder.extension(ext);
+    }
     let output = builder.build()?;
     let spirv_path = output.module.unwrap_single();
     let mut spirv_module = rspirv::dr::load_bytes(std::fs::read(spirv_path)?)
@@ -803,7 +812,7 @@ fn kernel_post_process(
     use rspirv::{
         binary::Assemble,
         dr::{Instruction, Module, Operand},
-        spirv::{BuiltIn, Decoration, GroupOperation, Op, StorageClass},
+        spirv::{BuiltIn, Decoration, Op, StorageClass},
     };
 
     let entry_id = entry_point.operands[1].unwrap_id_ref();
@@ -1068,16 +1077,19 @@ fn kernel_post_process(
         {
             let mut builder = rspirv::dr::Builder::new_from_module(std::mem::take(&mut spirv_module));
             let uint = builder.type_int(32, 0);
-            let one = builder.constant_u32(uint, 1);
-            let threads = builder.spec_constant_u32(uint, 1);
             let spec_id = kernel_desc.spec_descs.len() as u32;
-            builder.decorate(
-                threads,
-                Decoration::SpecId,
-                [Operand::LiteralInt32(spec_id)],
-            );
+            // threads x, y, and z follow the spec constants
+            let threads = [0, 1, 2].map(|i| {
+                let threads = builder.spec_constant_u32(uint, 1);
+                builder.decorate(
+                    threads,
+                    Decoration::SpecId,
+                    [Operand::LiteralInt32(spec_id + i)],
+                );
+                threads
+            });
             let uvec3 = builder.type_vector(uint, 3);
-            let workgroup_size = builder.spec_constant_composite(uvec3, [threads, one, one]);
+            let workgroup_size = builder.spec_constant_composite(uvec3, threads);
             builder.decorate(workgroup_size, Decoration::BuiltIn, [Operand::BuiltIn(BuiltIn::WorkgroupSize)]);
             spirv_module = builder.module();
         }
@@ -1093,6 +1105,9 @@ fn kernel_post_process(
         kernel_desc.name = format!("{crate_name_ident}::{kernel_name}");
         let mut features = Features::empty();
         for slice_desc in kernel_desc.slice_descs.iter() {
+            if slice_desc.element.is_some() {
+                continue;
+            }
             let width = slice_desc.scalar_type.size();
             if width == 1 {
                 features = features.union(Features::BUFFER8);
@@ -1100,6 +1115,51 @@ fn kernel_post_process(
                 features = features.union(Features::BUFFER16);
             }
         }
+        if kernel_desc.slice_descs.iter().any(|x| x.element.is_some()) {
+            // element fields may be 8 or 16 bit, find types reachable from storage buffers
+            let types: FxHashMap<u32, &Instruction> = spirv_module
+                .types_global_values
+                .iter()
+                .filter_map(|inst| Some((inst.result_id?, inst)))
+                .collect();
+            let mut stack: Vec<u32> = spirv_module
+                .types_global_values
+                .iter()
+                .filter(|inst| {
+                    inst.class.opcode == Op::TypePointer
+                        && inst.operands.first()
+                            == Some(&Operand::StorageClass(StorageClass::StorageBuffer))
+                })
+                .map(|inst| inst.operands[1].unwrap_id_ref())
+                .collect();
+            let mut visited = FxHashSet::default();
+            while let Some(id) = stack.pop() {
+                if !visited.insert(id) {
+                    continue;
+                }
+                let Some(inst) = types.get(&id) else {
+                    continue;
+                };
+                match (inst.class.opcode, inst.operands.first()) {
+                    (Op::TypeInt | Op::TypeFloat, Some(Operand::LiteralInt32(8))) => {
+                        features |= Features::BUFFER8;
+                    }
+                    (Op::TypeInt | Op::TypeFloat, Some(Operand::LiteralInt32(16))) => {
+                        features |= Features::BUFFER16;
+                    }
+                    (Op::TypeStruct, _) => {
+                        stack.extend(inst.operands.iter().map(|x| x.unwrap_id_ref()));
+                    }
+                    (
+                        Op::TypeArray | Op::TypeRuntimeArray | Op::TypeVector,
+                        Some(Operand::IdRef(elem)),
+                    ) => {
+                        stack.push(*elem);
+                    }
+                    _ => (),
+                }
+            }
+        }
         for push_desc in kernel_desc.push_descs.iter() {
             let width = push_desc.scalar_type.size();
             if width == 1 {
@@ -1108,126 +1168,65 @@ fn kernel_post_process(
                 features |= Features::PUSH_CONSTANT16;
             }
         }
-        for inst in spirv_module.types_global_values.iter() {
-            match (inst.class.opcode, inst.operands.first()) {
-                (Op::TypeInt, Some(Operand::LiteralInt32(8))) => {
-                    features |= Features::INT8;
-                }
-                (Op::TypeInt, Some(Operand::LiteralInt32(16))) => {
-                    features |= Features::INT16;
-                }
-                (Op::TypeInt | Op::TypeFloat, Some(Operand::LiteralInt32(32))) => (),
-                (Op::TypeInt, Some(Operand::LiteralInt32(64))) => {
-                    features |= Features::INT64;
-                }
-                (Op::TypeFloat, Some(Operand::LiteralInt32(16))) => {
-                    features |= Features::FLOAT16;
-                }
-                (Op::TypeFloat, Some(Operand::LiteralInt32(64))) => {
-                    features |= Features::FLOAT64;
+        features |= module_features(&spirv_module);
+        if non_uniform_group_barrier(&spirv_module) {
+            eprintln!(
+                "warning: kernel `{}` may call a group barrier in non-uniform control flow, barriers must be reached by all threads in the group",
+                kernel_desc.name,
+            );
+        }
+        retain_capabilities(&mut spirv_module, features);
+        let spirv = spirv_module.assemble();
+        spirv_val(&spirv)?;
+        kernel_desc.features = features;
+        const EMULATED_NARROW: Features = Features::INT8
+            .union(Features::INT16)
+            .union(Features::FLOAT16);
+        if features.intersects(EMULATED_NARROW.union(Features::INT64)) {
+            let mut module = spirv_module.clone();
+            let mut emulated_features = features;
+            if features.contains(Features::INT64) {
+                if let Some(emulated) = emulate_int64(&module) {
+                    module = emulated;
+                    emulated_features = emulated_features.difference(Features::INT64);
                 }
-                (Op::TypeInt | Op::TypeFloat, _) => unreachable!(),
-                _ => (),
-            }
-        }
-        for inst in spirv_module.functions.iter().flat_map(|f| f.blocks.iter().flat_map(|b| b.instructions.iter())) {
-            let op = inst.class.opcode;
-            let operands = inst.operands.as_slice();
-            match op {
-                Op::GroupNonUniformAll
-                | Op::GroupNonUniformAny
-                | Op::GroupNonUniformAllEqual => {
-                    features |= Features::SUBGROUP_VOTE;
+            }
+            if features.intersects(EMULATED_NARROW) {
+                if let Some(emulated) = emulate_narrow_arithmetic(&module) {
+                    module = emulated;
+                    emulated_features = emulated_features.difference(EMULATED_NARROW);
                 }
-                Op::GroupNonUniformBallotBitCount
-                | Op::GroupNonUniformIAdd
-                | Op::GroupNonUniformFAdd
-                | Op::GroupNonUniformIMul
-                | Op::GroupNonUniformFMul
-                | Op::GroupNonUniformSMin
-                | Op::GroupNonUniformUMin
-                | Op::GroupNonUniformFMin
-                | Op::GroupNonUniformSMax
-                | Op::GroupNonUniformUMax
-                | Op::GroupNonUniformFMax
-                | Op::GroupNonUniformBitwiseAnd
-                | Op::GroupNonUniformBitwiseOr
-                | Op::GroupNonUniformBitwiseXor => {
-                    if let [Operand::IdScope(_scope), Operand::GroupOperation(group_op), ..] = operands {
-                        if op == Op::GroupNonUniformBallotBitCount {
-                            features |= Features::SUBGROUP_BALLOT;
-                        } else if matches!(group_op, GroupOperation::Reduce | GroupOperation::InclusiveScan | GroupOperation::ExclusiveScan) {
-                            features |= Features::SUBGROUP_ARITHMETIC;
-                        } else if *group_op == GroupOperation::ClusteredReduce {
-                            features |= Features::SUBGROUP_CLUSTERED;
-                        }
+            }
+            // emulated subgroup operations may no longer use extended types
+            if !module_features(&module).contains(Features::SUBGROUP_EXTENDED_TYPES) {
+                emulated_features =
+                    emulated_features.difference(Features::SUBGROUP_EXTENDED_TYPES);
+            }
+            if emulated_features != features {
+                retain_capabilities(&mut module, emulated_features);
+                let spirv = spirv_opt(&module.assemble(), SpirvOptKind::Performance)
+                    .map(|spirv| spirv.as_words().to_vec())
+                    .and_then(|spirv| {
+                        spirv_val(&spirv)?;
+                        Ok(spirv)
+                    });
+                // the variant is optional, the kernel is still usable with the native features
+                match spirv {
+                    Ok(spirv) => {
+                        kernel_desc.emulated.replace(EmulatedDesc {
+                            spirv,
+                            features: emulated_features,
+                        });
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "warning: kernel `{}` emulated variant failed to validate and was skipped: {e:#}",
+                            kernel_desc.name,
+                        );
                     }
                 }
-                Op::GroupNonUniformBroadcast
-                | Op::GroupNonUniformBroadcastFirst
-                | Op::GroupNonUniformBallot
-                | Op::GroupNonUniformInverseBallot
-                | Op::GroupNonUniformBallotBitExtract
-                | Op::GroupNonUniformBallotFindLSB
-                | Op::GroupNonUniformBallotFindMSB => {
-                    features |= Features::SUBGROUP_BALLOT;
-                }
-                Op::GroupNonUniformShuffle
-                | Op::GroupNonUniformShuffleXor => {
-                    features |= Features::SUBGROUP_SHUFFLE;
-                }
-                Op::GroupNonUniformShuffleUp
-                | Op::GroupNonUniformShuffleDown => {
-                    features |= Features::SUBGROUP_SHUFFLE_RELATIVE;
-                }
-                _ => (),
-            }
-        }
-        if [
-            Features::SUBGROUP_VOTE,
-            Features::SUBGROUP_ARITHMETIC,
-            Features::SUBGROUP_BALLOT,
-            Features::SUBGROUP_SHUFFLE,
-            Features::SUBGROUP_SHUFFLE_RELATIVE,
-            Features::SUBGROUP_CLUSTERED,
-            Features::SUBGROUP_QUAD,
-        ].into_iter().any(|f| features.contains(f))
-        || spirv_module.annotations.iter().any(|inst| {
-            inst.class.opcode == Op::Decorate && matches!(
-                inst.operands.as_slice(),
-                [Operand::IdRef(_), Operand::Decoration(Decoration::BuiltIn), Operand::BuiltIn(
-                    BuiltIn::SubgroupSize | BuiltIn::NumSubgroups | BuiltIn::SubgroupId | BuiltIn::SubgroupLocalInvocationId
-            )])
-        }) {
-            features |= Features::SUBGROUP_BASIC;
-        }
-        spirv_module.capabilities.retain(|inst| {
-            use rspirv::spirv::Capability::*;
-            match inst.operands.first().unwrap().unwrap_capability() {
-                Shader | VulkanMemoryModel => true,
-                Int8 => features.contains(Features::INT8),
-                Int16 => features.contains(Features::INT16),
-                Int64 => features.contains(Features::INT64),
-                Float16 => features.contains(Features::FLOAT16),
-                Float64 => features.contains(Features::FLOAT64),
-                StorageBuffer8BitAccess => features.contains(Features::BUFFER8),
-                StorageBuffer16BitAccess => features.contains(Features::BUFFER16),
-                StoragePushConstant8 => features.contains(Features::PUSH_CONSTANT8),
-                StoragePushConstant16 => features.contains(Features::PUSH_CONSTANT16),
-                GroupNonUniform => features.contains(Features::SUBGROUP_BASIC),
-                GroupNonUniformVote => features.contains(Features::SUBGROUP_VOTE),
-                GroupNonUniformArithmetic => features.contains(Features::SUBGROUP_ARITHMETIC),
-                GroupNonUniformBallot => features.contains(Features::SUBGROUP_BALLOT),
-                GroupNonUniformShuffle => features.contains(Features::SUBGROUP_SHUFFLE),
-                GroupNonUniformShuffleRelative => features.contains(Features::SUBGROUP_SHUFFLE_RELATIVE),
-                GroupNonUniformClustered => features.contains(Features::SUBGROUP_CLUSTERED),
-                GroupNonUniformQuad => features.contains(Features::SUBGROUP_QUAD),
-                _ => unreachable!(),
             }
-        });
-        let spirv = spirv_module.assemble();
-        spirv_val(&spirv)?;
-        kernel_desc.features = features;
+        }
         if dump_kernels {
             let path = kernels_dir.join(kernel_desc.name.replace("::", "/"));
             std::fs::create_dir_all(path.parent().unwrap())?;
@@ -1237,6 +1236,12 @@ fn kernel_post_process(
                 path.with_extension("spv"),
                 bytemuck::cast_slice(spirv.as_slice()),
             )?;
+            if let Some(emulated) = kernel_desc.emulated.as_ref() {
+                std::fs::write(
+                    path.with_extension("emulated.spv"),
+                    bytemuck::cast_slice(emulated.spirv.as_slice()),
+                )?;
+            }
         }
         kernel_desc.spirv = spirv;
         Ok(kernel_desc)
@@ -1246,201 +1251,2134 @@ fn kernel_post_process(
     Ok(kernel_desc)
 }
 
-#[derive(Clone, Copy, Debug)]
-enum SpirvOptKind {
-    DeadCodeElimination,
-    Performance,
-}
+/// Rewrites 8 and 16 bit arithmetic to 32 bits.
+///
+/// Storage buffers and push constants keep their types, values are converted when loaded and
+/// stored. Integers are kept sign or zero extended according to their type, and truncated after
+/// operations that may overflow.
+///
+/// Returns `None` if the kernel uses an instruction that can not be emulated.
+fn emulate_narrow_arithmetic(module: &rspirv::dr::Module) -> Option<rspirv::dr::Module> {
+    use rspirv::{
+        dr::{Instruction, Operand},
+        spirv::{Decoration, Op, StorageClass},
+    };
 
-fn spirv_val(spirv: &[u32]) -> Result<()> {
-    use spirv_tools::{val::Validator, TargetEnv};
-    let target_env = TargetEnv::Vulkan_1_2;
-    let validator = spirv_tools::val::create(Some(target_env));
-    validator.validate(spirv, None)?;
-    Ok(())
-}
+    #[derive(Clone, Copy)]
+    struct Narrow {
+        width: u32,
+        // None for floats
+        signed: Option<bool>,
+        len: u32,
+        wide: u32,
+        wide_scalar: u32,
+    }
 
-fn spirv_opt(spirv: &[u32], kind: SpirvOptKind) -> Result<spirv_tools::binary::Binary> {
-    use spirv_tools::{
-        opt::{Optimizer, Passes},
-        val::Validator,
-        TargetEnv,
-    };
-    let target_env = TargetEnv::Vulkan_1_2;
-    let validator = spirv_tools::val::create(Some(target_env));
-    validator.validate(spirv, None)?;
-    let mut optimizer = spirv_tools::opt::create(Some(target_env));
-    match kind {
-        SpirvOptKind::DeadCodeElimination => {
-            let passes = {
-                use Passes::*;
-                [
-                    EliminateDeadFunctions,
-                    DeadVariableElimination,
-                    EliminateDeadConstant,
-                    CombineAccessChains,
-                    CompactIds,
-                ]
-            };
-            for pass in passes {
-                optimizer.register_pass(pass);
-            }
-        }
-        SpirvOptKind::Performance => {
-            optimizer.register_performance_passes();
-        }
+    struct Emulator {
+        next_id: u32,
+        narrow: FxHashMap<u32, Narrow>,
+        value_types: FxHashMap<u32, u32>,
+        constants: Vec<Instruction>,
+        splats: FxHashMap<(u32, u32), u32>,
     }
-    Ok(optimizer.optimize(spirv, &mut |_| (), None)?)
-}
 
-fn add_spec_constant_ops(module: &mut rspirv::dr::Module) {
-    use rspirv::{
-        dr::{Instruction, Operand},
-        spirv::Op,
-    };
-    let mut constants = FxHashSet::default();
-    for inst in module.types_global_values.iter() {
-        if matches!(
-            inst.class.opcode,
-            Op::Constant
-                | Op::ConstantTrue
-                | Op::ConstantFalse
-                | Op::ConstantNull
-                | Op::ConstantComposite
-                | Op::SpecConstant
-                | Op::SpecConstantTrue
-                | Op::SpecConstantFalse
-                | Op::SpecConstantComposite
-                | Op::SpecConstantOp
+    impl Emulator {
+        fn id(&mut self) -> u32 {
+            let id = self.next_id;
+            self.next_id += 1;
+            id
+        }
+        fn value_narrow(&self, id: u32) -> Option<Narrow> {
+            self.narrow.get(self.value_types.get(&id)?).copied()
+        }
+        fn splat(&mut self, narrow: Narrow, value: u32) -> u32 {
+            if let Some(id) = self.splats.get(&(narrow.wide, value)) {
+                return *id;
+            }
+            let scalar = if let Some(id) = self.splats.get(&(narrow.wide_scalar, value)) {
+                *id
+            } else {
+                let id = self.id();
+                self.constants.push(Instruction::new(
+                    Op::Constant,
+                    Some(narrow.wide_scalar),
+                    Some(id),
+                    vec![Operand::LiteralInt32(value)],
+                ));
+                self.splats.insert((narrow.wide_scalar, value), id);
+                id
+            };
+            let id = if narrow.len > 1 {
+                let id = self.id();
+                self.constants.push(Instruction::new(
+                    Op::ConstantComposite,
+                    Some(narrow.wide),
+                    Some(id),
+                    vec![Operand::IdRef(scalar); narrow.len as usize],
+                ));
+                id
+            } else {
+                scalar
+            };
+            self.splats.insert((narrow.wide, value), id);
+            id
+        }
+        /// Extends the low bits of `value` into `output`.
+        fn extend(
+            &mut self,
+            insts: &mut Vec<Instruction>,
+            value: u32,
+            narrow: Narrow,
+            signed: bool,
+            output: u32,
         ) {
-            if let Some(result_id) = inst.result_id {
-                constants.insert(result_id);
+            let ty = Some(narrow.wide);
+            if signed {
+                let shift = self.splat(narrow, 32 - narrow.width);
+                let shifted = self.id();
+                insts.push(Instruction::new(
+                    Op::ShiftLeftLogical,
+                    ty,
+                    Some(shifted),
+                    vec![Operand::IdRef(value), Operand::IdRef(shift)],
+                ));
+                insts.push(Instruction::new(
+                    Op::ShiftRightArithmetic,
+                    ty,
+                    Some(output),
+                    vec![Operand::IdRef(shifted), Operand::IdRef(shift)],
+                ));
+            } else {
+                let mask = self.splat(narrow, (1 << narrow.width) - 1);
+                insts.push(Instruction::new(
+                    Op::BitwiseAnd,
+                    ty,
+                    Some(output),
+                    vec![Operand::IdRef(value), Operand::IdRef(mask)],
+                ));
             }
         }
-    }
-    for function in module.functions.iter_mut() {
-        for block in function.blocks.iter_mut() {
-            block.instructions.retain(|inst| {
-                if matches!(
-                    inst.class.opcode,
-                    Op::SConvert
-                        | Op::UConvert
-                        | Op::FConvert
-                        | Op::SNegate
-                        | Op::Not
-                        | Op::IAdd
-                        | Op::ISub
-                        | Op::IMul
-                        | Op::UDiv
-                        | Op::SDiv
-                        | Op::UMod
-                        | Op::SRem
-                        | Op::SMod
-                  /* | Op::ShiftRightLogical
-                        | Op::ShiftRightArithmetic
-                        | Op::ShiftLeftLogical
-                        | Op::BitwiseOr
-                        | Op::BitwiseAnd
-                        | Op::VectorShuffle
-                        | Op::CompositeExtract
-                        | Op::CompositeInsert
-                        | Op::LogicalOr
-                        | Op::LogicalAnd
-                        | Op::LogicalNot
-                        | Op::LogicalEqual
-                        | Op::LogicalNotEqual */
-                        | Op::Select
-                        | Op::IEqual
-                        | Op::INotEqual
-                        | Op::ULessThan
-                        | Op::SLessThan
-                        | Op::UGreaterThan
-                        | Op::SGreaterThan
-                        | Op::ULessThanEqual
-                        | Op::SLessThanEqual
-                        | Op::UGreaterThanEqual
-                        | Op::SGreaterThanEqual /* | Op::QuantizeToF16 */
-                ) {
-                    if let Some(result_id) = inst.result_id {
-                        let mut used_constants = FxHashSet::default();
-                        for operand in inst.operands.iter() {
-                            if let Operand::IdRef(id) = operand {
-                                if !constants.contains(id) {
-                                    return true;
-                                }
-                                used_constants.insert(*id);
-                            }
-                        }
-                        for (i, global_inst) in module.types_global_values.iter().enumerate().rev()
-                        {
-                            if let Some(global_result_id) = global_inst.result_id {
-                                if inst.result_type == global_inst.result_id
-                                    || used_constants.contains(&global_result_id)
-                                {
-                                    module.types_global_values.insert(
-                                        i + 1,
-                                        Instruction::new(
-                                            Op::SpecConstantOp,
-                                            inst.result_type,
-                                            inst.result_id,
-                                            [Operand::LiteralInt32(inst.class.opcode as u32)]
-                                                .into_iter()
-                                                .chain(inst.operands.clone())
-                                                .collect(),
-                                        ),
-                                    );
-                                    constants.insert(result_id);
-                                    return false;
-                                }
-                            }
+        /// Extends integer operands as signed or unsigned.
+        fn adjust_operands(
+            &mut self,
+            insts: &mut Vec<Instruction>,
+            inst: &mut Instruction,
+            signed: bool,
+        ) {
+            for operand in inst.operands.iter_mut() {
+                if let Operand::IdRef(id) = operand {
+                    if let Some(narrow) = self.value_narrow(*id) {
+                        if narrow.signed == Some(!signed) {
+                            let output = self.id();
+                            self.extend(insts, *id, narrow, signed, output);
+                            *id = output;
                         }
                     }
                 }
-                true
-            });
+            }
+        }
+        /// Pushes `inst` and truncates the result to its narrow type.
+        fn push_truncated(
+            &mut self,
+            insts: &mut Vec<Instruction>,
+            mut inst: Instruction,
+            narrow: Narrow,
+        ) {
+            let output = inst.result_id.unwrap();
+            let value = self.id();
+            inst.result_type = Some(narrow.wide);
+            inst.result_id = Some(value);
+            insts.push(inst);
+            self.extend(insts, value, narrow, narrow.signed.unwrap(), output);
         }
     }
-}
 
-fn strip_unused_types(module: &mut rspirv::dr::Module) {
-    use rspirv::{dr::Operand, spirv::Op};
-    let mut used = FxHashSet::default();
-    for func in module.functions.iter() {
-        if let Some(inst) = func.def.as_ref() {
-            let func_type = inst.operands.last().unwrap().unwrap_id_ref();
-            used.insert(func_type);
-        }
-        for block in func.blocks.iter() {
-            for inst in block.instructions.iter() {
-                if let Some(result_type) = inst.result_type {
-                    used.insert(result_type);
-                }
-            }
-        }
+    fn is_interface(storage_class: StorageClass) -> bool {
+        !matches!(
+            storage_class,
+            StorageClass::Function | StorageClass::Private | StorageClass::Workgroup
+        )
     }
 
-    for inst in module.types_global_values.iter().rev() {
+    fn f16_to_f32(bits: u32) -> f32 {
+        let sign = (bits & 0x8000) << 16;
+        let exp = (bits >> 10) & 0x1F;
+        let man = bits & 0x3FF;
+        let bits = if exp == 0x1F {
+            sign | 0x7F80_0000 | (man << 13)
+        } else if exp != 0 {
+            sign | ((exp + 112) << 23) | (man << 13)
+        } else if man != 0 {
+            let shift = man.leading_zeros() - 21;
+            sign | ((113 - shift) << 23) | (((man << shift) & 0x3FF) << 13)
+        } else {
+            sign
+        };
+        f32::from_bits(bits)
+    }
+
+    let mut module = module.clone();
+    let mut value_types = FxHashMap::default();
+    for inst in module
+        .types_global_values
+        .iter()
+        .chain(module.functions.iter().flat_map(|f| {
+            f.parameters
+                .iter()
+                .chain(f.blocks.iter().flat_map(|b| b.instructions.iter()))
+        }))
+    {
+        if let Some((result_id, result_type)) = inst.result_id.zip(inst.result_type) {
+            value_types.insert(result_id, result_type);
+        }
+    }
+    // scalar, vector, and pointers to scalar or vector types don't have other dependencies,
+    // so they can be declared first, allowing existing 32 bit types to be reused
+    let mut scalars = Vec::new();
+    let mut vectors = Vec::new();
+    let mut pointers = Vec::new();
+    let mut globals = Vec::new();
+    let mut scalar_ids = FxHashSet::default();
+    for inst in std::mem::take(&mut module.types_global_values) {
         match inst.class.opcode {
             Op::TypeVoid | Op::TypeBool | Op::TypeInt | Op::TypeFloat => {
-                used.extend(inst.result_id);
+                scalar_ids.insert(inst.result_id?);
+                scalars.push(inst);
             }
-            Op::TypeArray | Op::TypeVector | Op::TypeMatrix | Op::TypeStruct | Op::TypeFunction => {
-                used.extend(inst.result_id);
-                used.extend(inst.operands.iter().filter_map(|operand| {
-                    if let Operand::IdRef(id) = operand {
-                        Some(*id)
-                    } else {
-                        None
-                    }
-                }));
+            Op::TypeVector => {
+                scalar_ids.insert(inst.result_id?);
+                vectors.push(inst);
             }
-            _ => {
-                used.extend(inst.result_id);
-                used.extend(inst.result_type);
+            Op::TypePointer if scalar_ids.contains(&inst.operands[1].unwrap_id_ref()) => {
+                pointers.push(inst);
             }
+            _ => globals.push(inst),
         }
     }
-    module
+    let mut emulator = Emulator {
+        next_id: module.header.as_ref()?.bound,
+        narrow: FxHashMap::default(),
+        value_types,
+        constants: Vec::new(),
+        splats: FxHashMap::default(),
+    };
+    let mut int32 = [None; 2];
+    let mut float32 = None;
+    for inst in scalars.iter() {
+        match (inst.class.opcode, inst.operands.as_slice()) {
+            (Op::TypeInt, [Operand::LiteralInt32(32), Operand::LiteralInt32(signed)]) => {
+                int32[*signed as usize] = inst.result_id;
+            }
+            (Op::TypeFloat, [Operand::LiteralInt32(32)]) => {
+                float32 = inst.result_id;
+            }
+            _ => (),
+        }
+    }
+    let mut new_scalars = Vec::new();
+    for inst in scalars.iter() {
+        let (width, signed) = match (inst.class.opcode, inst.operands.as_slice()) {
+            (
+                Op::TypeInt,
+                [Operand::LiteralInt32(width @ (8 | 16)), Operand::LiteralInt32(signed)],
+            ) => (*width, Some(*signed != 0)),
+            (Op::TypeFloat, [Operand::LiteralInt32(16)]) => (16, None),
+            _ => continue,
+        };
+        let (wide, operands) = if let Some(signed) = signed {
+            (
+                &mut int32[signed as usize],
+                vec![
+                    Operand::LiteralInt32(32),
+                    Operand::LiteralInt32(signed as u32),
+                ],
+            )
+        } else {
+            (&mut float32, vec![Operand::LiteralInt32(32)])
+        };
+        let wide = if let Some(wide) = *wide {
+            wide
+        } else {
+            let id = emulator.id();
+            let op = if signed.is_some() {
+                Op::TypeInt
+            } else {
+                Op::TypeFloat
+            };
+            new_scalars.push(Instruction::new(op, None, Some(id), operands));
+            wide.replace(id);
+            id
+        };
+        emulator.narrow.insert(
+            inst.result_id?,
+            Narrow {
+                width,
+                signed,
+                len: 1,
+                wide,
+                wide_scalar: wide,
+            },
+        );
+    }
+    if emulator.narrow.is_empty() {
+        return None;
+    }
+    scalars.extend(new_scalars);
+    let mut wide_types: FxHashMap<u32, u32> = emulator
+        .narrow
+        .iter()
+        .map(|(id, narrow)| (*id, narrow.wide))
+        .collect();
+    let mut vector_ids: FxHashMap<(u32, u32), u32> = vectors
+        .iter()
+        .filter_map(|inst| match inst.operands.as_slice() {
+            [Operand::IdRef(component), Operand::LiteralInt32(len)] => {
+                Some(((*component, *len), inst.result_id?))
+            }
+            _ => None,
+        })
+        .collect();
+    let mut new_vectors = Vec::new();
+    for inst in vectors.iter() {
+        let [Operand::IdRef(component), Operand::LiteralInt32(len)] = inst.operands.as_slice()
+        else {
+            continue;
+        };
+        let Some(narrow) = emulator.narrow.get(component).copied() else {
+            continue;
+        };
+        let wide = if let Some(wide) = vector_ids.get(&(narrow.wide, *len)) {
+            *wide
+        } else {
+            let id = emulator.id();
+            new_vectors.push(Instruction::new(
+                Op::TypeVector,
+                None,
+                Some(id),
+                vec![Operand::IdRef(narrow.wide), Operand::LiteralInt32(*len)],
+            ));
+            vector_ids.insert((narrow.wide, *len), id);
+            id
+        };
+        emulator.narrow.insert(
+            inst.result_id?,
+            Narrow {
+                len: *len,
+                wide,
+                ..narrow
+            },
+        );
+        wide_types.insert(inst.result_id?, wide);
+    }
+    vectors.extend(new_vectors);
+    // component widths after widening
+    let mut widths = FxHashMap::default();
+    for inst in scalars.iter().chain(vectors.iter()) {
+        let id = inst.result_id?;
+        let width = match (inst.class.opcode, inst.operands.first()) {
+            _ if emulator.narrow.contains_key(&id) => 32,
+            (Op::TypeInt | Op::TypeFloat, Some(Operand::LiteralInt32(width))) => *width,
+            (Op::TypeVector, Some(Operand::IdRef(component))) => {
+                if let Some(width) = widths.get(component) {
+                    *width
+                } else {
+                    continue;
+                }
+            }
+            _ => continue,
+        };
+        widths.insert(id, width);
+    }
+    let mut pointer_ids: FxHashMap<(u32, u32), u32> = FxHashMap::default();
+    let mut pointer_types: FxHashMap<u32, (StorageClass, u32)> = FxHashMap::default();
+    for inst in pointers.iter().chain(globals.iter()) {
+        if let (Op::TypePointer, [Operand::StorageClass(storage_class), Operand::IdRef(pointee)]) =
+            (inst.class.opcode, inst.operands.as_slice())
+        {
+            pointer_ids.insert((*storage_class as u32, *pointee), inst.result_id?);
+            pointer_types.insert(inst.result_id?, (*storage_class, *pointee));
+        }
+    }
+    let mut new_pointers = Vec::new();
+    for inst in pointers.iter() {
+        let (storage_class, pointee) = pointer_types[&inst.result_id?];
+        let Some(wide_pointee) = wide_types.get(&pointee).copied() else {
+            continue;
+        };
+        if is_interface(storage_class) {
+            continue;
+        }
+        let wide = if let Some(wide) = pointer_ids.get(&(storage_class as u32, wide_pointee)) {
+            *wide
+        } else {
+            let id = emulator.id();
+            new_pointers.push(Instruction::new(
+                Op::TypePointer,
+                None,
+                Some(id),
+                vec![
+                    Operand::StorageClass(storage_class),
+                    Operand::IdRef(wide_pointee),
+                ],
+            ));
+            pointer_ids.insert((storage_class as u32, wide_pointee), id);
+            id
+        };
+        wide_types.insert(inst.result_id?, wide);
+    }
+    pointers.extend(new_pointers);
+    let mut constants = FxHashMap::default();
+    let mut types_global_values = Vec::with_capacity(globals.len());
+    for mut inst in globals {
+        let op = inst.class.opcode;
+        let narrow = inst
+            .result_type
+            .and_then(|ty| emulator.narrow.get(&ty))
+            .copied();
+        let mut wide_inst = None;
+        match (op, narrow) {
+            (Op::TypeArray | Op::TypeRuntimeArray | Op::TypeStruct, _) => {
+                let mut operands = inst.operands.clone();
+                let mut widened = false;
+                for (i, operand) in operands.iter_mut().enumerate() {
+                    if let Operand::IdRef(id) = operand {
+                        // array length is a constant
+                        if op == Op::TypeArray && i == 1 {
+                            continue;
+                        }
+                        if let Some(wide) = wide_types.get(id) {
+                            *id = *wide;
+                            widened = true;
+                        }
+                    }
+                }
+                if widened {
+                    let id = emulator.id();
+                    wide_types.insert(inst.result_id?, id);
+                    wide_inst.replace(Instruction::new(op, None, Some(id), operands));
+                }
+            }
+            (Op::TypePointer, _) => {
+                let (storage_class, pointee) = pointer_types[&inst.result_id?];
+                if let Some(wide_pointee) = wide_types.get(&pointee).copied() {
+                    if !is_interface(storage_class) {
+                        let id = emulator.id();
+                        wide_types.insert(inst.result_id?, id);
+                        wide_inst.replace(Instruction::new(
+                            op,
+                            None,
+                            Some(id),
+                            vec![
+                                Operand::StorageClass(storage_class),
+                                Operand::IdRef(wide_pointee),
+                            ],
+                        ));
+                    }
+                }
+            }
+            (Op::TypeFunction, _) => {
+                if inst
+                    .operands
+                    .iter()
+                    .any(|x| wide_types.contains_key(&x.unwrap_id_ref()))
+                {
+                    return None;
+                }
+            }
+            (Op::Constant | Op::ConstantNull | Op::ConstantComposite | Op::Undef, Some(narrow)) => {
+                let mut operands = inst.operands.clone();
+                for operand in operands.iter_mut() {
+                    match operand {
+                        Operand::IdRef(id) => *id = *constants.get(id)?,
+                        Operand::LiteralInt32(bits) if narrow.signed.is_none() => {
+                            *operand = Operand::LiteralFloat32(f16_to_f32(*bits & 0xFFFF));
+                        }
+                        Operand::LiteralFloat32(x) => {
+                            *x = f16_to_f32(x.to_bits() & 0xFFFF);
+                        }
+                        Operand::LiteralInt32(bits) => {
+                            let shift = 32 - narrow.width;
+                            *bits = if narrow.signed == Some(true) {
+                                ((*bits << shift) as i32 >> shift) as u32
+                            } else {
+                                (*bits << shift) >> shift
+                            };
+                        }
+                        _ => return None,
+                    }
+                }
+                let id = emulator.id();
+                constants.insert(inst.result_id?, id);
+                emulator.value_types.insert(id, inst.result_type?);
+                wide_inst.replace(Instruction::new(op, Some(narrow.wide), Some(id), operands));
+            }
+            (Op::Variable, _) => {
+                if let Some(wide) = inst.result_type.and_then(|ty| wide_types.get(&ty)) {
+                    inst.result_type = Some(*wide);
+                }
+                for operand in inst.operands.iter_mut() {
+                    if let Operand::IdRef(id) = operand {
+                        if let Some(constant) = constants.get(id) {
+                            *id = *constant;
+                        }
+                    }
+                }
+            }
+            _ => {
+                if inst
+                    .result_type
+                    .map_or(false, |ty| wide_types.contains_key(&ty))
+                {
+                    return None;
+                }
+            }
+        }
+        types_global_values.push(inst);
+        types_global_values.extend(wide_inst);
+    }
+    let is_interface_pointer = |ptr: u32| -> Option<u32> {
+        let ty = emulator.value_types.get(&ptr)?;
+        let (storage_class, pointee) = pointer_types.get(ty)?;
+        if is_interface(*storage_class) {
+            Some(*pointee)
+        } else {
+            None
+        }
+    };
+    let interface_pointees: FxHashMap<u32, u32> = emulator
+        .value_types
+        .keys()
+        .filter_map(|id| Some((*id, is_interface_pointer(*id)?)))
+        .collect();
+    for function in module.functions.iter_mut() {
+        for block in function.blocks.iter_mut() {
+            let mut insts = Vec::with_capacity(block.instructions.len());
+            for mut inst in std::mem::take(&mut block.instructions) {
+                for operand in inst.operands.iter_mut() {
+                    if let Operand::IdRef(id) = operand {
+                        if let Some(constant) = constants.get(id) {
+                            *id = *constant;
+                        }
+                    }
+                }
+                let op = inst.class.opcode;
+                let result_narrow = inst
+                    .result_type
+                    .and_then(|ty| emulator.narrow.get(&ty))
+                    .copied();
+                let operand_narrow = inst.operands.iter().find_map(|operand| {
+                    if let Operand::IdRef(id) = operand {
+                        emulator.value_narrow(*id)
+                    } else {
+                        None
+                    }
+                });
+                let int_result = result_narrow.filter(|x| x.signed.is_some());
+                let int_operand = inst.operands.iter().any(|operand| {
+                    if let Operand::IdRef(id) = operand {
+                        emulator
+                            .value_narrow(*id)
+                            .map_or(false, |x| x.signed.is_some())
+                    } else {
+                        false
+                    }
+                });
+                let wide_type = inst.result_type.and_then(|ty| wide_types.get(&ty)).copied();
+                match op {
+                    Op::Load | Op::Store => {
+                        let ptr = inst.operands[0].unwrap_id_ref();
+                        if let Some(pointee) = interface_pointees.get(&ptr).copied() {
+                            let Some(narrow) = emulator.narrow.get(&pointee).copied() else {
+                                if wide_types.contains_key(&pointee) {
+                                    return None;
+                                }
+                                insts.push(inst);
+                                continue;
+                            };
+                            let convert = match narrow.signed {
+                                None => Op::FConvert,
+                                Some(true) => Op::SConvert,
+                                Some(false) => Op::UConvert,
+                            };
+                            if op == Op::Load {
+                                let output = inst.result_id?;
+                                let value = emulator.id();
+                                inst.result_id = Some(value);
+                                insts.push(inst);
+                                insts.push(Instruction::new(
+                                    convert,
+                                    Some(narrow.wide),
+                                    Some(output),
+                                    vec![Operand::IdRef(value)],
+                                ));
+                            } else {
+                                let value = emulator.id();
+                                insts.push(Instruction::new(
+                                    convert,
+                                    Some(pointee),
+                                    Some(value),
+                                    vec![inst.operands[1].clone()],
+                                ));
+                                inst.operands[1] = Operand::IdRef(value);
+                                insts.push(inst);
+                            }
+                            continue;
+                        }
+                        inst.result_type = wide_type.or(inst.result_type);
+                        insts.push(inst);
+                    }
+                    _ if result_narrow.is_none() && operand_narrow.is_none() => {
+                        let operand_wide = inst.operands.iter().any(|operand| {
+                            if let Operand::IdRef(id) = operand {
+                                emulator
+                                    .value_types
+                                    .get(id)
+                                    .map_or(false, |ty| wide_types.contains_key(ty))
+                            } else {
+                                false
+                            }
+                        });
+                        if wide_type.is_some() || operand_wide {
+                            if !matches!(
+                                op,
+                                Op::Variable
+                                    | Op::AccessChain
+                                    | Op::InBoundsAccessChain
+                                    | Op::Phi
+                                    | Op::Select
+                                    | Op::CopyObject
+                                    | Op::CompositeConstruct
+                                    | Op::CompositeExtract
+                                    | Op::CompositeInsert
+                                    | Op::Undef
+                            ) {
+                                return None;
+                            }
+                            inst.result_type = wide_type.or(inst.result_type);
+                        }
+                        insts.push(inst);
+                    }
+                    // values are passed through
+                    Op::Phi
+                    | Op::Select
+                    | Op::CopyObject
+                    | Op::CompositeConstruct
+                    | Op::CompositeExtract
+                    | Op::CompositeInsert
+                    | Op::VectorShuffle
+                    | Op::VectorExtractDynamic
+                    | Op::VectorInsertDynamic
+                    | Op::Undef
+                    | Op::Switch
+                    | Op::Variable
+                    | Op::AccessChain
+                    | Op::InBoundsAccessChain
+                    | Op::GroupNonUniformBroadcast
+                    | Op::GroupNonUniformBroadcastFirst
+                    | Op::GroupNonUniformShuffle
+                    | Op::GroupNonUniformShuffleXor
+                    | Op::GroupNonUniformShuffleUp
+                    | Op::GroupNonUniformShuffleDown => {
+                        inst.result_type = wide_type.or(inst.result_type);
+                        insts.push(inst);
+                    }
+                    // floats are computed in f32
+                    Op::FNegate
+                    | Op::FAdd
+                    | Op::FSub
+                    | Op::FMul
+                    | Op::FDiv
+                    | Op::FRem
+                    | Op::FMod
+                    | Op::Dot
+                    | Op::VectorTimesScalar
+                    | Op::FOrdEqual
+                    | Op::FUnordEqual
+                    | Op::FOrdNotEqual
+                    | Op::FUnordNotEqual
+                    | Op::FOrdLessThan
+                    | Op::FUnordLessThan
+                    | Op::FOrdGreaterThan
+                    | Op::FUnordGreaterThan
+                    | Op::FOrdLessThanEqual
+                    | Op::FUnordLessThanEqual
+                    | Op::FOrdGreaterThanEqual
+                    | Op::FUnordGreaterThanEqual
+                    | Op::IsNan
+                    | Op::IsInf
+                    | Op::GroupNonUniformFAdd
+                    | Op::GroupNonUniformFMul
+                    | Op::GroupNonUniformFMin
+                    | Op::GroupNonUniformFMax => {
+                        inst.result_type = wide_type.or(inst.result_type);
+                        insts.push(inst);
+                    }
+                    Op::ExtInst if int_result.is_none() && !int_operand => {
+                        inst.result_type = wide_type.or(inst.result_type);
+                        insts.push(inst);
+                    }
+                    // the low bits are correct, the result is truncated
+                    Op::SNegate
+                    | Op::Not
+                    | Op::IAdd
+                    | Op::ISub
+                    | Op::IMul
+                    | Op::BitwiseAnd
+                    | Op::BitwiseOr
+                    | Op::BitwiseXor
+                    | Op::ShiftLeftLogical
+                    | Op::BitFieldInsert
+                    | Op::ConvertFToU
+                    | Op::ConvertFToS
+                    | Op::GroupNonUniformIAdd
+                    | Op::GroupNonUniformIMul
+                    | Op::GroupNonUniformBitwiseAnd
+                    | Op::GroupNonUniformBitwiseOr
+                    | Op::GroupNonUniformBitwiseXor => {
+                        if let Some(narrow) = int_result {
+                            emulator.push_truncated(&mut insts, inst, narrow);
+                        } else {
+                            inst.result_type = wide_type.or(inst.result_type);
+                            insts.push(inst);
+                        }
+                    }
+                    // operands are zero or sign extended
+                    Op::UDiv
+                    | Op::UMod
+                    | Op::ShiftRightLogical
+                    | Op::IEqual
+                    | Op::INotEqual
+                    | Op::ULessThan
+                    | Op::UGreaterThan
+                    | Op::ULessThanEqual
+                    | Op::UGreaterThanEqual
+                    | Op::BitCount
+                    | Op::BitFieldUExtract
+                    | Op::ConvertUToF
+                    | Op::GroupNonUniformUMin
+                    | Op::GroupNonUniformUMax
+                    | Op::SDiv
+                    | Op::SRem
+                    | Op::SMod
+                    | Op::ShiftRightArithmetic
+                    | Op::SLessThan
+                    | Op::SGreaterThan
+                    | Op::SLessThanEqual
+                    | Op::SGreaterThanEqual
+                    | Op::BitFieldSExtract
+                    | Op::ConvertSToF
+                    | Op::GroupNonUniformSMin
+                    | Op::GroupNonUniformSMax => {
+                        let signed = matches!(
+                            op,
+                            Op::SDiv
+                                | Op::SRem
+                                | Op::SMod
+                                | Op::ShiftRightArithmetic
+                                | Op::SLessThan
+                                | Op::SGreaterThan
+                                | Op::SLessThanEqual
+                                | Op::SGreaterThanEqual
+                                | Op::BitFieldSExtract
+                                | Op::ConvertSToF
+                                | Op::GroupNonUniformSMin
+                                | Op::GroupNonUniformSMax
+                        );
+                        emulator.adjust_operands(&mut insts, &mut inst, signed);
+                        if let Some(narrow) = int_result {
+                            emulator.push_truncated(&mut insts, inst, narrow);
+                        } else {
+                            inst.result_type = wide_type.or(inst.result_type);
+                            insts.push(inst);
+                        }
+                    }
+                    Op::UConvert | Op::SConvert | Op::Bitcast => {
+                        let value = inst.operands[0].unwrap_id_ref();
+                        let input = emulator.value_types.get(&value).copied()?;
+                        let output = inst.result_type?;
+                        if op == Op::Bitcast {
+                            // only integers of the same width
+                            let (Some(a), Some(b)) = (operand_narrow, result_narrow) else {
+                                return None;
+                            };
+                            if a.signed.is_none()
+                                || b.signed.is_none()
+                                || a.width != b.width
+                                || a.len != b.len
+                            {
+                                return None;
+                            }
+                        } else {
+                            emulator.adjust_operands(&mut insts, &mut inst, op == Op::SConvert);
+                        }
+                        let wide_input = wide_types.get(&input).copied().unwrap_or(input);
+                        let wide_output = wide_types.get(&output).copied().unwrap_or(output);
+                        if op == Op::Bitcast || widths.get(&input) == widths.get(&output) {
+                            let op = if wide_input == wide_output {
+                                Op::CopyObject
+                            } else {
+                                Op::Bitcast
+                            };
+                            inst = Instruction::new(
+                                op,
+                                Some(wide_output),
+                                inst.result_id,
+                                inst.operands,
+                            );
+                        }
+                        if let Some(narrow) = int_result {
+                            emulator.push_truncated(&mut insts, inst, narrow);
+                        } else {
+                            inst.result_type = Some(wide_output);
+                            insts.push(inst);
+                        }
+                    }
+                    Op::FConvert => {
+                        let value = inst.operands[0].unwrap_id_ref();
+                        let input = emulator.value_types.get(&value).copied()?;
+                        let is_f32 = |ty: u32| widths.get(&ty) == Some(&32);
+                        let output = inst.result_id?;
+                        if let Some(narrow) = result_narrow {
+                            // round to f16
+                            let value = if is_f32(input) {
+                                inst.operands[0].unwrap_id_ref()
+                            } else {
+                                let value = emulator.id();
+                                insts.push(Instruction::new(
+                                    Op::FConvert,
+                                    Some(narrow.wide),
+                                    Some(value),
+                                    inst.operands,
+                                ));
+                                value
+                            };
+                            insts.push(Instruction::new(
+                                Op::QuantizeToF16,
+                                Some(narrow.wide),
+                                Some(output),
+                                vec![Operand::IdRef(value)],
+                            ));
+                        } else if is_f32(inst.result_type?) {
+                            insts.push(Instruction::new(
+                                Op::CopyObject,
+                                inst.result_type,
+                                Some(output),
+                                inst.operands,
+                            ));
+                        } else {
+                            insts.push(inst);
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            block.instructions = insts;
+        }
+    }
+    // wrapping decorations do not apply to the truncated results
+    module.annotations.retain(|inst| {
+        !matches!(
+            inst.operands.get(1),
+            Some(Operand::Decoration(
+                Decoration::NoSignedWrap | Decoration::NoUnsignedWrap
+            ))
+        )
+    });
+    module.types_global_values = scalars
+        .into_iter()
+        .chain(vectors)
+        .chain(pointers)
+        .chain(types_global_values)
+        .chain(emulator.constants)
+        .collect();
+    // narrow types may require capabilities that are removed, so remove unused declarations
+    let mut removable: FxHashSet<u32> = emulator
+        .narrow
+        .keys()
+        .chain(wide_types.keys())
+        .chain(constants.keys())
+        .copied()
+        .collect();
+    loop {
+        let mut used = FxHashSet::default();
+        for inst in module
+            .entry_points
+            .iter()
+            .chain(module.execution_modes.iter())
+            .chain(module.types_global_values.iter())
+            .chain(module.functions.iter().flat_map(|f| {
+                f.def
+                    .iter()
+                    .chain(f.parameters.iter())
+                    .chain(f.blocks.iter().flat_map(|b| b.instructions.iter()))
+            }))
+        {
+            used.extend(inst.result_type);
+            used.extend(inst.operands.iter().filter_map(|operand| {
+                if let Operand::IdRef(id) = operand {
+                    Some(*id)
+                } else {
+                    None
+                }
+            }));
+        }
+        removable.retain(|id| !used.contains(id));
+        let len = module.types_global_values.len();
+        module
+            .types_global_values
+            .retain(|inst| inst.result_id.map_or(true, |id| !removable.contains(&id)));
+        if module.types_global_values.len() == len {
+            break;
+        }
+    }
+    let target = |inst: &Instruction| {
+        if let Some(Operand::IdRef(id)) = inst.operands.first() {
+            !removable.contains(id)
+        } else {
+            true
+        }
+    };
+    module.annotations.retain(target);
+    module.debug_names.retain(target);
+    module.header.as_mut()?.bound = emulator.next_id;
+    Some(module)
+}
+
+/// Rewrites 64 bit integers as pairs of 32 bit integers, `uvec2(lo, hi)`.
+///
+/// A 64 bit integer has the same layout as a `uvec2` in buffers and push constants, so types are
+/// replaced in place. Addition, subtraction, multiplication, comparisons, shifts, bitwise ops and
+/// integer conversions are emulated.
+///
+/// Returns `None` if the kernel uses an instruction that can not be emulated, ie division,
+/// conversion to or from floats, atomics or subgroup arithmetic.
+fn emulate_int64(module: &rspirv::dr::Module) -> Option<rspirv::dr::Module> {
+    use rspirv::{
+        dr::{Instruction, Operand},
+        spirv::{Decoration, Op},
+    };
+
+    struct Emulator {
+        next_id: u32,
+        u32_ty: u32,
+        bool_ty: u32,
+        pair_ty: u32,
+        uvec2_ty: u32,
+        constants: Vec<Instruction>,
+        u32_constants: FxHashMap<u32, u32>,
+        insts: Vec<Instruction>,
+    }
+
+    impl Emulator {
+        fn id(&mut self) -> u32 {
+            let id = self.next_id;
+            self.next_id += 1;
+            id
+        }
+        fn constant(&mut self, value: u32) -> u32 {
+            if let Some(id) = self.u32_constants.get(&value) {
+                return *id;
+            }
+            let id = self.id();
+            self.constants.push(Instruction::new(
+                Op::Constant,
+                Some(self.u32_ty),
+                Some(id),
+                vec![Operand::LiteralInt32(value)],
+            ));
+            self.u32_constants.insert(value, id);
+            id
+        }
+        fn push(&mut self, op: Op, ty: u32, operands: &[u32]) -> u32 {
+            let id = self.id();
+            self.insts.push(Instruction::new(
+                op,
+                Some(ty),
+                Some(id),
+                operands.iter().map(|id| Operand::IdRef(*id)).collect(),
+            ));
+            id
+        }
+        fn int(&mut self, op: Op, operands: &[u32]) -> u32 {
+            self.push(op, self.u32_ty, operands)
+        }
+        fn bool(&mut self, op: Op, operands: &[u32]) -> u32 {
+            self.push(op, self.bool_ty, operands)
+        }
+        fn select(&mut self, condition: u32, a: u32, b: u32) -> u32 {
+            self.int(Op::Select, &[condition, a, b])
+        }
+        fn extract(&mut self, ty: u32, value: u32, index: u32) -> u32 {
+            let id = self.id();
+            self.insts.push(Instruction::new(
+                Op::CompositeExtract,
+                Some(ty),
+                Some(id),
+                vec![Operand::IdRef(value), Operand::LiteralInt32(index)],
+            ));
+            id
+        }
+        fn split(&mut self, value: u32) -> (u32, u32) {
+            (
+                self.extract(self.u32_ty, value, 0),
+                self.extract(self.u32_ty, value, 1),
+            )
+        }
+        fn construct(&mut self, output: u32, (lo, hi): (u32, u32)) {
+            self.insts.push(Instruction::new(
+                Op::CompositeConstruct,
+                Some(self.uvec2_ty),
+                Some(output),
+                vec![Operand::IdRef(lo), Operand::IdRef(hi)],
+            ));
+        }
+        fn add(&mut self, a: (u32, u32), b: (u32, u32)) -> (u32, u32) {
+            let sum = self.push(Op::IAddCarry, self.pair_ty, &[a.0, b.0]);
+            let lo = self.extract(self.u32_ty, sum, 0);
+            let carry = self.extract(self.u32_ty, sum, 1);
+            let hi = self.int(Op::IAdd, &[a.1, b.1]);
+            let hi = self.int(Op::IAdd, &[hi, carry]);
+            (lo, hi)
+        }
+        fn sub(&mut self, a: (u32, u32), b: (u32, u32)) -> (u32, u32) {
+            let difference = self.push(Op::ISubBorrow, self.pair_ty, &[a.0, b.0]);
+            let lo = self.extract(self.u32_ty, difference, 0);
+            let borrow = self.extract(self.u32_ty, difference, 1);
+            let hi = self.int(Op::ISub, &[a.1, b.1]);
+            let hi = self.int(Op::ISub, &[hi, borrow]);
+            (lo, hi)
+        }
+        fn mul(&mut self, a: (u32, u32), b: (u32, u32)) -> (u32, u32) {
+            let product = self.push(Op::UMulExtended, self.pair_ty, &[a.0, b.0]);
+            let lo = self.extract(self.u32_ty, product, 0);
+            let hi = self.extract(self.u32_ty, product, 1);
+            let x = self.int(Op::IMul, &[a.0, b.1]);
+            let y = self.int(Op::IMul, &[a.1, b.0]);
+            let hi = self.int(Op::IAdd, &[hi, x]);
+            let hi = self.int(Op::IAdd, &[hi, y]);
+            (lo, hi)
+        }
+        fn less_than(&mut self, a: (u32, u32), b: (u32, u32), signed: bool, equal: bool) -> u32 {
+            let hi_op = if signed { Op::SLessThan } else { Op::ULessThan };
+            let lo_op = if equal {
+                Op::ULessThanEqual
+            } else {
+                Op::ULessThan
+            };
+            let hi_less = self.bool(hi_op, &[a.1, b.1]);
+            let hi_equal = self.bool(Op::IEqual, &[a.1, b.1]);
+            let lo_less = self.bool(lo_op, &[a.0, b.0]);
+            let lo_less = self.bool(Op::LogicalAnd, &[hi_equal, lo_less]);
+            self.bool(Op::LogicalOr, &[hi_less, lo_less])
+        }
+        fn shift(&mut self, op: Op, a: (u32, u32), shift: u32) -> (u32, u32) {
+            let zero = self.constant(0);
+            let mask = self.constant(63);
+            let shift = self.int(Op::BitwiseAnd, &[shift, mask]);
+            let thirty_two = self.constant(32);
+            let high = self.bool(Op::UGreaterThanEqual, &[shift, thirty_two]);
+            let mask = self.constant(31);
+            let shift = self.int(Op::BitwiseAnd, &[shift, mask]);
+            // bits shifted between the halves, shifting by 32 is undefined
+            let no_carry = self.bool(Op::IEqual, &[shift, zero]);
+            let carry_shift = self.int(Op::ISub, &[thirty_two, shift]);
+            let carry_shift = self.int(Op::BitwiseAnd, &[carry_shift, mask]);
+            if op == Op::ShiftLeftLogical {
+                let lo = self.int(Op::ShiftLeftLogical, &[a.0, shift]);
+                let carry = self.int(Op::ShiftRightLogical, &[a.0, carry_shift]);
+                let carry = self.select(no_carry, zero, carry);
+                let hi = self.int(Op::ShiftLeftLogical, &[a.1, shift]);
+                let hi = self.int(Op::BitwiseOr, &[hi, carry]);
+                (self.select(high, zero, lo), self.select(high, lo, hi))
+            } else {
+                let hi = self.int(op, &[a.1, shift]);
+                let carry = self.int(Op::ShiftLeftLogical, &[a.1, carry_shift]);
+                let carry = self.select(no_carry, zero, carry);
+                let lo = self.int(Op::ShiftRightLogical, &[a.0, shift]);
+                let lo = self.int(Op::BitwiseOr, &[lo, carry]);
+                let fill = if op == Op::ShiftRightArithmetic {
+                    self.int(Op::ShiftRightArithmetic, &[a.1, mask])
+                } else {
+                    zero
+                };
+                (self.select(high, hi, lo), self.select(high, fill, hi))
+            }
+        }
+    }
+
+    let mut module = module.clone();
+    let mut int64 = FxHashSet::default();
+    let mut int_widths = FxHashMap::default();
+    let mut u32_ty = None;
+    let mut bool_ty = None;
+    for inst in module.types_global_values.iter() {
+        match (inst.class.opcode, inst.operands.as_slice()) {
+            (Op::TypeInt, [Operand::LiteralInt32(width), Operand::LiteralInt32(signed)]) => {
+                if *width == 64 {
+                    int64.insert(inst.result_id?);
+                } else if *width == 32 && *signed == 0 {
+                    u32_ty = inst.result_id;
+                }
+                int_widths.insert(inst.result_id?, *width);
+            }
+            (Op::TypeBool, _) => {
+                bool_ty = inst.result_id;
+            }
+            _ => (),
+        }
+    }
+    if int64.is_empty() {
+        return None;
+    }
+    let mut value_types = FxHashMap::default();
+    for inst in module
+        .types_global_values
+        .iter()
+        .chain(module.functions.iter().flat_map(|f| {
+            f.parameters
+                .iter()
+                .chain(f.blocks.iter().flat_map(|b| b.instructions.iter()))
+        }))
+    {
+        if let Some((result_id, result_type)) = inst.result_id.zip(inst.result_type) {
+            value_types.insert(result_id, result_type);
+        }
+    }
+    let mut next_id = module.header.as_ref()?.bound;
+    let mut id = || {
+        let id = next_id;
+        next_id += 1;
+        id
+    };
+    let mut new_types = Vec::new();
+    let u32_ty = if let Some(u32_ty) = u32_ty {
+        u32_ty
+    } else {
+        let u32_ty = id();
+        new_types.push(Instruction::new(
+            Op::TypeInt,
+            None,
+            Some(u32_ty),
+            vec![Operand::LiteralInt32(32), Operand::LiteralInt32(0)],
+        ));
+        u32_ty
+    };
+    let bool_ty = if let Some(bool_ty) = bool_ty {
+        bool_ty
+    } else {
+        let bool_ty = id();
+        new_types.push(Instruction::new(
+            Op::TypeBool,
+            None,
+            Some(bool_ty),
+            Vec::new(),
+        ));
+        bool_ty
+    };
+    let mut uvec2_ty = None;
+    for inst in module.types_global_values.iter() {
+        if let (Op::TypeVector, [Operand::IdRef(component), Operand::LiteralInt32(len)]) =
+            (inst.class.opcode, inst.operands.as_slice())
+        {
+            if int64.contains(component) {
+                return None;
+            }
+            if *component == u32_ty && *len == 2 {
+                uvec2_ty = inst.result_id;
+            }
+        }
+    }
+    let uvec2_ty = if let Some(uvec2_ty) = uvec2_ty {
+        uvec2_ty
+    } else {
+        let uvec2_ty = id();
+        new_types.push(Instruction::new(
+            Op::TypeVector,
+            None,
+            Some(uvec2_ty),
+            vec![Operand::IdRef(u32_ty), Operand::LiteralInt32(2)],
+        ));
+        uvec2_ty
+    };
+    // result of IAddCarry, ISubBorrow and UMulExtended
+    let pair_ty = id();
+    let mut emulator = Emulator {
+        next_id,
+        u32_ty,
+        bool_ty,
+        pair_ty,
+        uvec2_ty,
+        constants: Vec::new(),
+        u32_constants: FxHashMap::default(),
+        insts: Vec::new(),
+    };
+    let rename = |inst: &mut Instruction| {
+        if let Some(ty) = inst.result_type.as_mut() {
+            if int64.contains(ty) {
+                *ty = uvec2_ty;
+            }
+        }
+        for operand in inst.operands.iter_mut() {
+            if let Operand::IdRef(id) = operand {
+                if int64.contains(id) {
+                    *id = uvec2_ty;
+                }
+            }
+        }
+    };
+    // scalar types don't have other dependencies, so they can be declared first, followed by
+    // the new types and constants
+    let mut scalars = Vec::new();
+    let mut types_global_values = Vec::new();
+    for mut inst in std::mem::take(&mut module.types_global_values) {
+        let op = inst.class.opcode;
+        let result64 = inst.result_type.map_or(false, |ty| int64.contains(&ty));
+        match op {
+            Op::TypeInt if int64.contains(&inst.result_id?) => continue,
+            Op::TypeVoid | Op::TypeBool | Op::TypeInt | Op::TypeFloat => {
+                scalars.push(inst);
+                continue;
+            }
+            Op::Constant if result64 => {
+                let value = match inst.operands.as_slice() {
+                    [Operand::LiteralInt64(value)] => *value,
+                    [Operand::LiteralInt32(value)] => (*value).into(),
+                    _ => return None,
+                };
+                let lo = emulator.constant(value as u32);
+                let hi = emulator.constant((value >> 32) as u32);
+                inst = Instruction::new(
+                    Op::ConstantComposite,
+                    Some(uvec2_ty),
+                    inst.result_id,
+                    vec![Operand::IdRef(lo), Operand::IdRef(hi)],
+                );
+            }
+            Op::SpecConstant | Op::SpecConstantComposite | Op::SpecConstantOp if result64 => {
+                return None;
+            }
+            _ => {
+                rename(&mut inst);
+            }
+        }
+        types_global_values.push(inst);
+    }
+    let is_int64 = |id: u32| value_types.get(&id).map_or(false, |ty| int64.contains(ty));
+    for function in module.functions.iter_mut() {
+        for inst in function
+            .def
+            .iter_mut()
+            .chain(function.parameters.iter_mut())
+        {
+            rename(inst);
+        }
+        for block in function.blocks.iter_mut() {
+            for mut inst in std::mem::take(&mut block.instructions) {
+                let op = inst.class.opcode;
+                let result64 = inst.result_type.map_or(false, |ty| int64.contains(&ty));
+                let operand64 = inst
+                    .operands
+                    .iter()
+                    .any(|operand| matches!(operand, Operand::IdRef(id) if is_int64(*id)));
+                if !result64 && !operand64 {
+                    rename(&mut inst);
+                    emulator.insts.push(inst);
+                    continue;
+                }
+                let operand = |i: usize| inst.operands.get(i).and_then(|x| x.id_ref_any());
+                match op {
+                    // values are passed through
+                    Op::Load
+                    | Op::Store
+                    | Op::Phi
+                    | Op::Select
+                    | Op::CopyObject
+                    | Op::CompositeConstruct
+                    | Op::CompositeExtract
+                    | Op::CompositeInsert
+                    | Op::Undef
+                    | Op::Variable
+                    | Op::FunctionCall
+                    | Op::ReturnValue
+                    | Op::BitwiseAnd
+                    | Op::BitwiseOr
+                    | Op::BitwiseXor
+                    | Op::Not
+                    | Op::GroupNonUniformBroadcastFirst => {
+                        rename(&mut inst);
+                        emulator.insts.push(inst);
+                    }
+                    // indices are less than 2^32
+                    Op::AccessChain | Op::InBoundsAccessChain => {
+                        for operand in inst.operands.iter_mut().skip(1) {
+                            if let Operand::IdRef(id) = operand {
+                                if is_int64(*id) {
+                                    *id = emulator.extract(u32_ty, *id, 0);
+                                }
+                            }
+                        }
+                        rename(&mut inst);
+                        emulator.insts.push(inst);
+                    }
+                    Op::IAdd | Op::ISub | Op::IMul | Op::SNegate => {
+                        let a = emulator.split(operand(0)?);
+                        let b = if op == Op::SNegate {
+                            a
+                        } else {
+                            emulator.split(operand(1)?)
+                        };
+                        let value = match op {
+                            Op::IAdd => emulator.add(a, b),
+                            Op::ISub => emulator.sub(a, b),
+                            Op::IMul => emulator.mul(a, b),
+                            _ => {
+                                let zero = emulator.constant(0);
+                                emulator.sub((zero, zero), a)
+                            }
+                        };
+                        emulator.construct(inst.result_id?, value);
+                    }
+                    Op::IEqual
+                    | Op::INotEqual
+                    | Op::ULessThan
+                    | Op::ULessThanEqual
+                    | Op::UGreaterThan
+                    | Op::UGreaterThanEqual
+                    | Op::SLessThan
+                    | Op::SLessThanEqual
+                    | Op::SGreaterThan
+                    | Op::SGreaterThanEqual => {
+                        let a = emulator.split(operand(0)?);
+                        let b = emulator.split(operand(1)?);
+                        let value = match op {
+                            Op::IEqual | Op::INotEqual => {
+                                let (compare, combine) = if op == Op::IEqual {
+                                    (Op::IEqual, Op::LogicalAnd)
+                                } else {
+                                    (Op::INotEqual, Op::LogicalOr)
+                                };
+                                let lo = emulator.bool(compare, &[a.0, b.0]);
+                                let hi = emulator.bool(compare, &[a.1, b.1]);
+                                emulator.bool(combine, &[lo, hi])
+                            }
+                            Op::ULessThan => emulator.less_than(a, b, false, false),
+                            Op::ULessThanEqual => emulator.less_than(a, b, false, true),
+                            Op::UGreaterThan => emulator.less_than(b, a, false, false),
+                            Op::UGreaterThanEqual => emulator.less_than(b, a, false, true),
+                            Op::SLessThan => emulator.less_than(a, b, true, false),
+                            Op::SLessThanEqual => emulator.less_than(a, b, true, true),
+                            Op::SGreaterThan => emulator.less_than(b, a, true, false),
+                            _ => emulator.less_than(b, a, true, true),
+                        };
+                        emulator.insts.push(Instruction::new(
+                            Op::CopyObject,
+                            inst.result_type,
+                            inst.result_id,
+                            vec![Operand::IdRef(value)],
+                        ));
+                    }
+                    Op::ShiftLeftLogical | Op::ShiftRightLogical | Op::ShiftRightArithmetic => {
+                        let shift = operand(1)?;
+                        let shift = if is_int64(shift) {
+                            emulator.extract(u32_ty, shift, 0)
+                        } else if int_widths.get(value_types.get(&shift)?) != Some(&32) {
+                            emulator.push(Op::UConvert, u32_ty, &[shift])
+                        } else {
+                            shift
+                        };
+                        if result64 {
+                            let a = emulator.split(operand(0)?);
+                            let value = emulator.shift(op, a, shift);
+                            emulator.construct(inst.result_id?, value);
+                        } else {
+                            inst.operands[1] = Operand::IdRef(shift);
+                            emulator.insts.push(inst);
+                        }
+                    }
+                    Op::UConvert | Op::SConvert => {
+                        let value = operand(0)?;
+                        if result64 {
+                            let width = *int_widths.get(value_types.get(&value)?)?;
+                            let lo = if width != 32 {
+                                emulator.push(op, u32_ty, &[value])
+                            } else if value_types.get(&value) == Some(&u32_ty) {
+                                value
+                            } else {
+                                emulator.push(Op::Bitcast, u32_ty, &[value])
+                            };
+                            let hi = if op == Op::SConvert {
+                                let shift = emulator.constant(31);
+                                emulator.int(Op::ShiftRightArithmetic, &[lo, shift])
+                            } else {
+                                emulator.constant(0)
+                            };
+                            emulator.construct(inst.result_id?, (lo, hi));
+                        } else {
+                            let lo = emulator.extract(u32_ty, value, 0);
+                            let ty = inst.result_type?;
+                            // UConvert requires an unsigned result, narrowing truncates either way
+                            let op = if int_widths.get(&ty) != Some(&32) {
+                                Op::SConvert
+                            } else if ty == u32_ty {
+                                Op::CopyObject
+                            } else {
+                                Op::Bitcast
+                            };
+                            emulator.insts.push(Instruction::new(
+                                op,
+                                Some(ty),
+                                inst.result_id,
+                                vec![Operand::IdRef(lo)],
+                            ));
+                        }
+                    }
+                    // signed and unsigned 64 bit integers are both uvec2
+                    Op::Bitcast if result64 && is_int64(operand(0)?) => {
+                        emulator.insts.push(Instruction::new(
+                            Op::CopyObject,
+                            Some(uvec2_ty),
+                            inst.result_id,
+                            vec![Operand::IdRef(operand(0)?)],
+                        ));
+                    }
+                    _ => return None,
+                }
+            }
+            block.instructions = std::mem::take(&mut emulator.insts);
+        }
+    }
+    // wrapping decorations do not apply to the emulated results
+    module.annotations.retain(|inst| {
+        !matches!(
+            inst.operands.get(1),
+            Some(Operand::Decoration(
+                Decoration::NoSignedWrap | Decoration::NoUnsignedWrap
+            ))
+        ) && !matches!(inst.operands.first(), Some(Operand::IdRef(id)) if int64.contains(id))
+    });
+    module.debug_names.retain(
+        |inst| !matches!(inst.operands.first(), Some(Operand::IdRef(id)) if int64.contains(id)),
+    );
+    new_types.push(Instruction::new(
+        Op::TypeStruct,
+        None,
+        Some(pair_ty),
+        vec![Operand::IdRef(u32_ty), Operand::IdRef(u32_ty)],
+    ));
+    module.types_global_values = scalars
+        .into_iter()
+        .chain(new_types)
+        .chain(emulator.constants)
+        .chain(types_global_values)
+        .collect();
+    // replacing types may duplicate non aggregate types, ie pointers to uvec2
+    let mut replacements = FxHashMap::default();
+    let mut declared: Vec<(Op, Vec<Operand>, u32)> = Vec::new();
+    module.types_global_values.retain_mut(|inst| {
+        for operand in inst.operands.iter_mut() {
+            if let Operand::IdRef(id) = operand {
+                if let Some(replacement) = replacements.get(id) {
+                    *id = *replacement;
+                }
+            }
+        }
+        if let Some(ty) = inst.result_type.as_mut() {
+            if let Some(replacement) = replacements.get(ty) {
+                *ty = *replacement;
+            }
+        }
+        let op = inst.class.opcode;
+        if !matches!(op, Op::TypeVector | Op::TypePointer | Op::TypeFunction) {
+            return true;
+        }
+        let Some(result_id) = inst.result_id else {
+            return true;
+        };
+        if let Some((_, _, id)) = declared
+            .iter()
+            .find(|(declared_op, operands, _)| *declared_op == op && *operands == inst.operands)
+        {
+            replacements.insert(result_id, *id);
+            false
+        } else {
+            declared.push((op, inst.operands.clone(), result_id));
+            true
+        }
+    });
+    if !replacements.is_empty() {
+        module.annotations.retain(|inst| {
+            !matches!(inst.operands.first(), Some(Operand::IdRef(id)) if replacements.contains_key(id))
+        });
+        module.debug_names.retain(|inst| {
+            !matches!(inst.operands.first(), Some(Operand::IdRef(id)) if replacements.contains_key(id))
+        });
+        for inst in module.functions.iter_mut().flat_map(|function| {
+            function
+                .def
+                .iter_mut()
+                .chain(function.parameters.iter_mut())
+                .chain(
+                    function
+                        .blocks
+                        .iter_mut()
+                        .flat_map(|block| block.instructions.iter_mut()),
+                )
+        }) {
+            if let Some(ty) = inst.result_type.as_mut() {
+                if let Some(replacement) = replacements.get(ty) {
+                    *ty = *replacement;
+                }
+            }
+            for operand in inst.operands.iter_mut() {
+                if let Operand::IdRef(id) = operand {
+                    if let Some(replacement) = replacements.get(id) {
+                        *id = *replacement;
+                    }
+                }
+            }
+        }
+    }
+    module.header.as_mut()?.bound = emulator.next_id;
+    Some(module)
+}
+
+/// Whether a group barrier may be called in non-uniform control flow.
+///
+/// Values derived from thread ids are non-uniform, as are variables they are stored to. Blocks
+/// after a branch on a non-uniform value, up to its merge block, are non-uniform. This includes
+/// the rest of a loop if it has a non-uniform exit.
+fn non_uniform_group_barrier(module: &rspirv::dr::Module) -> bool {
+    use rspirv::{
+        dr::Operand,
+        spirv::{BuiltIn, Decoration, Op, Scope},
+    };
+
+    let constants: FxHashMap<u32, u32> = module
+        .types_global_values
+        .iter()
+        .filter_map(|inst| match (inst.class.opcode, inst.operands.as_slice()) {
+            (Op::Constant, [Operand::LiteralInt32(x)]) => Some((inst.result_id?, *x)),
+            _ => None,
+        })
+        .collect();
+    let mut non_uniform = FxHashSet::default();
+    for inst in module.annotations.iter() {
+        if let [Operand::IdRef(id), Operand::Decoration(Decoration::BuiltIn), Operand::BuiltIn(builtin)] =
+            inst.operands.as_slice()
+        {
+            if matches!(
+                builtin,
+                BuiltIn::GlobalInvocationId
+                    | BuiltIn::LocalInvocationId
+                    | BuiltIn::LocalInvocationIndex
+                    | BuiltIn::SubgroupId
+                    | BuiltIn::SubgroupLocalInvocationId
+            ) {
+                non_uniform.insert(*id);
+            }
+        }
+    }
+    let mut bases = FxHashMap::default();
+    for inst in module
+        .functions
+        .iter()
+        .flat_map(|f| f.blocks.iter().flat_map(|b| b.instructions.iter()))
+    {
+        if let (Op::AccessChain | Op::InBoundsAccessChain, Some(Operand::IdRef(base))) =
+            (inst.class.opcode, inst.operands.first())
+        {
+            bases.insert(inst.result_id.unwrap(), *base);
+        }
+    }
+    // propagate to a fixed point, phis and stores may refer to later values
+    loop {
+        let len = non_uniform.len();
+        for inst in module
+            .functions
+            .iter()
+            .flat_map(|f| f.blocks.iter().flat_map(|b| b.instructions.iter()))
+        {
+            if !inst
+                .operands
+                .iter()
+                .any(|x| matches!(x, Operand::IdRef(id) if non_uniform.contains(id)))
+            {
+                continue;
+            }
+            if inst.class.opcode == Op::Store {
+                let mut pointer = inst.operands[0].unwrap_id_ref();
+                non_uniform.insert(pointer);
+                while let Some(base) = bases.get(&pointer).copied() {
+                    non_uniform.insert(base);
+                    pointer = base;
+                }
+            } else if let Some(result_id) = inst.result_id {
+                non_uniform.insert(result_id);
+            }
+        }
+        if non_uniform.len() == len {
+            break;
+        }
+    }
+    for function in module.functions.iter() {
+        let blocks: FxHashMap<u32, &rspirv::dr::Block> = function
+            .blocks
+            .iter()
+            .filter_map(|block| Some((block.label.as_ref()?.result_id?, block)))
+            .collect();
+        let mut loop_headers = FxHashMap::default();
+        for (label, block) in blocks.iter() {
+            for inst in block.instructions.iter() {
+                if inst.class.opcode == Op::LoopMerge {
+                    loop_headers.insert(inst.operands[0].unwrap_id_ref(), *label);
+                }
+            }
+        }
+        for block in function.blocks.iter() {
+            let Some(branch) = block.instructions.last() else {
+                continue;
+            };
+            if !matches!(branch.class.opcode, Op::BranchConditional | Op::Switch) {
+                continue;
+            }
+            if !matches!(branch.operands.first(), Some(Operand::IdRef(condition)) if non_uniform.contains(condition))
+            {
+                continue;
+            }
+            let merge = block
+                .instructions
+                .iter()
+                .find(|inst| matches!(inst.class.opcode, Op::SelectionMerge | Op::LoopMerge))
+                .map(|inst| inst.operands[0].unwrap_id_ref());
+            let mut stack: Vec<u32> = branch.operands[1..]
+                .iter()
+                .filter_map(|x| match x {
+                    Operand::IdRef(id) if blocks.contains_key(id) => Some(*id),
+                    _ => None,
+                })
+                .collect();
+            let mut visited = FxHashSet::default();
+            while let Some(label) = stack.pop() {
+                if Some(label) == merge || !visited.insert(label) {
+                    continue;
+                }
+                // a loop exited without visiting its header, ie a break
+                if let Some(header) = loop_headers.get(&label) {
+                    if !visited.contains(header) {
+                        continue;
+                    }
+                }
+                let block = blocks[&label];
+                for inst in block.instructions.iter() {
+                    if let (Op::ControlBarrier, Some(Operand::IdScope(scope))) =
+                        (inst.class.opcode, inst.operands.first())
+                    {
+                        if constants.get(scope) == Some(&(Scope::Workgroup as u32)) {
+                            return true;
+                        }
+                    }
+                }
+                if let Some(branch) = block.instructions.last() {
+                    let targets = match branch.class.opcode {
+                        Op::Branch => &branch.operands[..],
+                        Op::BranchConditional | Op::Switch => &branch.operands[1..],
+                        _ => &[],
+                    };
+                    stack.extend(targets.iter().filter_map(|x| match x {
+                        Operand::IdRef(id) if blocks.contains_key(id) => Some(*id),
+                        _ => None,
+                    }));
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Features used by the types and instructions of a kernel.
+fn module_features(module: &rspirv::dr::Module) -> Features {
+    use rspirv::{
+        dr::Operand,
+        spirv::{BuiltIn, Decoration, GroupOperation, Op, StorageClass},
+    };
+
+    let mut features = Features::empty();
+    for inst in module.types_global_values.iter() {
+        match (inst.class.opcode, inst.operands.first()) {
+            (Op::TypeInt, Some(Operand::LiteralInt32(8))) => {
+                features |= Features::INT8;
+            }
+            (Op::TypeInt, Some(Operand::LiteralInt32(16))) => {
+                features |= Features::INT16;
+            }
+            (Op::TypeInt | Op::TypeFloat, Some(Operand::LiteralInt32(32))) => (),
+            (Op::TypeInt, Some(Operand::LiteralInt32(64))) => {
+                features |= Features::INT64;
+            }
+            (Op::TypeFloat, Some(Operand::LiteralInt32(16))) => {
+                features |= Features::FLOAT16;
+            }
+            (Op::TypeFloat, Some(Operand::LiteralInt32(64))) => {
+                features |= Features::FLOAT64;
+            }
+            (Op::TypeInt | Op::TypeFloat, _) => unreachable!(),
+            _ => (),
+        }
+    }
+    let instructions = || {
+        module
+            .functions
+            .iter()
+            .flat_map(|f| f.blocks.iter().flat_map(|b| b.instructions.iter()))
+    };
+    let mut types = FxHashMap::default();
+    let mut value_types = FxHashMap::default();
+    for inst in module.types_global_values.iter().chain(instructions()) {
+        if let Some(result_id) = inst.result_id {
+            if let Some(result_type) = inst.result_type {
+                value_types.insert(result_id, result_type);
+            } else {
+                types.insert(result_id, inst);
+            }
+        }
+    }
+    // 8, 16 and 64 bit integers and 16 bit floats, or vectors of them
+    let extended_type = |ty: u32| {
+        let mut inst = types.get(&ty).copied();
+        if let Some(vector) = inst.filter(|inst| inst.class.opcode == Op::TypeVector) {
+            inst = vector
+                .operands
+                .first()
+                .and_then(|component| types.get(&component.unwrap_id_ref()))
+                .copied();
+        }
+        match inst.map(|inst| (inst.class.opcode, inst.operands.first())) {
+            Some((Op::TypeInt, Some(Operand::LiteralInt32(width)))) => *width != 32,
+            Some((Op::TypeFloat, Some(Operand::LiteralInt32(width)))) => *width == 16,
+            _ => false,
+        }
+    };
+    for inst in instructions() {
+        let op = inst.class.opcode;
+        let operands = inst.operands.as_slice();
+        if matches!(
+            op,
+            Op::GroupNonUniformBroadcast
+                | Op::GroupNonUniformBroadcastFirst
+                | Op::GroupNonUniformShuffle
+                | Op::GroupNonUniformShuffleXor
+                | Op::GroupNonUniformShuffleUp
+                | Op::GroupNonUniformShuffleDown
+                | Op::GroupNonUniformIAdd
+                | Op::GroupNonUniformFAdd
+                | Op::GroupNonUniformIMul
+                | Op::GroupNonUniformFMul
+                | Op::GroupNonUniformSMin
+                | Op::GroupNonUniformUMin
+                | Op::GroupNonUniformFMin
+                | Op::GroupNonUniformSMax
+                | Op::GroupNonUniformUMax
+                | Op::GroupNonUniformFMax
+                | Op::GroupNonUniformBitwiseAnd
+                | Op::GroupNonUniformBitwiseOr
+                | Op::GroupNonUniformBitwiseXor
+                | Op::GroupNonUniformQuadBroadcast
+                | Op::GroupNonUniformQuadSwap
+        ) && inst.result_type.map_or(false, extended_type)
+        {
+            features |= Features::SUBGROUP_EXTENDED_TYPES;
+        }
+        match op {
+            Op::AtomicLoad
+            | Op::AtomicStore
+            | Op::AtomicExchange
+            | Op::AtomicCompareExchange
+            | Op::AtomicIIncrement
+            | Op::AtomicIDecrement
+            | Op::AtomicIAdd
+            | Op::AtomicISub
+            | Op::AtomicSMin
+            | Op::AtomicUMin
+            | Op::AtomicSMax
+            | Op::AtomicUMax
+            | Op::AtomicAnd
+            | Op::AtomicOr
+            | Op::AtomicXor
+            | Op::AtomicFAddEXT
+            | Op::AtomicFMinEXT
+            | Op::AtomicFMaxEXT => {
+                let pointer_type = operands
+                    .first()
+                    .and_then(|pointer| value_types.get(&pointer.unwrap_id_ref()))
+                    .and_then(|ty| types.get(ty));
+                let Some([Operand::StorageClass(storage_class), Operand::IdRef(pointee)]) =
+                    pointer_type.map(|inst| inst.operands.as_slice())
+                else {
+                    continue;
+                };
+                let group = *storage_class == StorageClass::Workgroup;
+                let Some(pointee) = types.get(pointee) else {
+                    continue;
+                };
+                match (pointee.class.opcode, pointee.operands.first(), op) {
+                    (Op::TypeInt, Some(Operand::LiteralInt32(64)), _) => {
+                        features |= if group {
+                            Features::GROUP_INT64_ATOMICS
+                        } else {
+                            Features::BUFFER_INT64_ATOMICS
+                        };
+                    }
+                    (Op::TypeFloat, Some(Operand::LiteralInt32(32)), Op::AtomicFAddEXT) => {
+                        features |= if group {
+                            Features::GROUP_FLOAT32_ATOMIC_ADD
+                        } else {
+                            Features::BUFFER_FLOAT32_ATOMIC_ADD
+                        };
+                    }
+                    (
+                        Op::TypeFloat,
+                        Some(Operand::LiteralInt32(32)),
+                        Op::AtomicFMinEXT | Op::AtomicFMaxEXT,
+                    ) => {
+                        features |= if group {
+                            Features::GROUP_FLOAT32_ATOMIC_MIN_MAX
+                        } else {
+                            Features::BUFFER_FLOAT32_ATOMIC_MIN_MAX
+                        };
+                    }
+                    (Op::TypeFloat, Some(Operand::LiteralInt32(32)), _) => {
+                        features |= if group {
+                            Features::GROUP_FLOAT32_ATOMICS
+                        } else {
+                            Features::BUFFER_FLOAT32_ATOMICS
+                        };
+                    }
+                    _ => (),
+                }
+            }
+            Op::GroupNonUniformAll | Op::GroupNonUniformAny | Op::GroupNonUniformAllEqual => {
+                features |= Features::SUBGROUP_VOTE;
+            }
+            Op::GroupNonUniformBallotBitCount
+            | Op::GroupNonUniformIAdd
+            | Op::GroupNonUniformFAdd
+            | Op::GroupNonUniformIMul
+            | Op::GroupNonUniformFMul
+            | Op::GroupNonUniformSMin
+            | Op::GroupNonUniformUMin
+            | Op::GroupNonUniformFMin
+            | Op::GroupNonUniformSMax
+            | Op::GroupNonUniformUMax
+            | Op::GroupNonUniformFMax
+            | Op::GroupNonUniformBitwiseAnd
+            | Op::GroupNonUniformBitwiseOr
+            | Op::GroupNonUniformBitwiseXor => {
+                if let [Operand::IdScope(_scope), Operand::GroupOperation(group_op), ..] = operands
+                {
+                    if op == Op::GroupNonUniformBallotBitCount {
+                        features |= Features::SUBGROUP_BALLOT;
+                    } else if matches!(
+                        group_op,
+                        GroupOperation::Reduce
+                            | GroupOperation::InclusiveScan
+                            | GroupOperation::ExclusiveScan
+                    ) {
+                        features |= Features::SUBGROUP_ARITHMETIC;
+                    } else if *group_op == GroupOperation::ClusteredReduce {
+                        features |= Features::SUBGROUP_CLUSTERED;
+                    }
+                }
+            }
+            Op::GroupNonUniformBroadcast
+            | Op::GroupNonUniformBroadcastFirst
+            | Op::GroupNonUniformBallot
+            | Op::GroupNonUniformInverseBallot
+            | Op::GroupNonUniformBallotBitExtract
+            | Op::GroupNonUniformBallotFindLSB
+            | Op::GroupNonUniformBallotFindMSB => {
+                features |= Features::SUBGROUP_BALLOT;
+            }
+            Op::GroupNonUniformShuffle | Op::GroupNonUniformShuffleXor => {
+                features |= Features::SUBGROUP_SHUFFLE;
+            }
+            Op::GroupNonUniformShuffleUp | Op::GroupNonUniformShuffleDown => {
+                features |= Features::SUBGROUP_SHUFFLE_RELATIVE;
+            }
+            _ => (),
+        }
+    }
+    if [
+        Features::SUBGROUP_VOTE,
+        Features::SUBGROUP_ARITHMETIC,
+        Features::SUBGROUP_BALLOT,
+        Features::SUBGROUP_SHUFFLE,
+        Features::SUBGROUP_SHUFFLE_RELATIVE,
+        Features::SUBGROUP_CLUSTERED,
+        Features::SUBGROUP_QUAD,
+    ]
+    .into_iter()
+    .any(|f| features.contains(f))
+        || module.annotations.iter().any(|inst| {
+            inst.class.opcode == Op::Decorate
+                && matches!(
+                    inst.operands.as_slice(),
+                    [
+                        Operand::IdRef(_),
+                        Operand::Decoration(Decoration::BuiltIn),
+                        Operand::BuiltIn(
+                            BuiltIn::SubgroupSize
+                                | BuiltIn::NumSubgroups
+                                | BuiltIn::SubgroupId
+                                | BuiltIn::SubgroupLocalInvocationId
+                        )
+                    ]
+                )
+        })
+    {
+        features |= Features::SUBGROUP_BASIC;
+    }
+    features
+}
+
+fn retain_capabilities(module: &mut rspirv::dr::Module, features: Features) {
+    const FLOAT32_ATOMIC_ADD: Features =
+        Features::BUFFER_FLOAT32_ATOMIC_ADD.union(Features::GROUP_FLOAT32_ATOMIC_ADD);
+    const FLOAT32_ATOMIC_MIN_MAX: Features =
+        Features::BUFFER_FLOAT32_ATOMIC_MIN_MAX.union(Features::GROUP_FLOAT32_ATOMIC_MIN_MAX);
+    module.capabilities.retain(|inst| {
+        use rspirv::spirv::Capability::*;
+        match inst.operands.first().unwrap().unwrap_capability() {
+            Shader | VulkanMemoryModel => true,
+            Int8 => features.contains(Features::INT8),
+            Int16 => features.contains(Features::INT16),
+            Int64 => features.contains(Features::INT64),
+            Float16 => features.contains(Features::FLOAT16),
+            Float64 => features.contains(Features::FLOAT64),
+            StorageBuffer8BitAccess => features.contains(Features::BUFFER8),
+            StorageBuffer16BitAccess => features.contains(Features::BUFFER16),
+            StoragePushConstant8 => features.contains(Features::PUSH_CONSTANT8),
+            StoragePushConstant16 => features.contains(Features::PUSH_CONSTANT16),
+            GroupNonUniform => features.contains(Features::SUBGROUP_BASIC),
+            GroupNonUniformVote => features.contains(Features::SUBGROUP_VOTE),
+            GroupNonUniformArithmetic => features.contains(Features::SUBGROUP_ARITHMETIC),
+            GroupNonUniformBallot => features.contains(Features::SUBGROUP_BALLOT),
+            GroupNonUniformShuffle => features.contains(Features::SUBGROUP_SHUFFLE),
+            GroupNonUniformShuffleRelative => {
+                features.contains(Features::SUBGROUP_SHUFFLE_RELATIVE)
+            }
+            GroupNonUniformClustered => features.contains(Features::SUBGROUP_CLUSTERED),
+            GroupNonUniformQuad => features.contains(Features::SUBGROUP_QUAD),
+            Int64Atomics => features
+                .intersects(Features::BUFFER_INT64_ATOMICS.union(Features::GROUP_INT64_ATOMICS)),
+            AtomicFloat32AddEXT => features.intersects(FLOAT32_ATOMIC_ADD),
+            AtomicFloat32MinMaxEXT => features.intersects(FLOAT32_ATOMIC_MIN_MAX),
+            _ => unreachable!(),
+        }
+    });
+    module.extensions.retain(
+        |inst| match inst.operands.first().unwrap().unwrap_literal_string() {
+            "SPV_EXT_shader_atomic_float_add" => features.intersects(FLOAT32_ATOMIC_ADD),
+            "SPV_EXT_shader_atomic_float_min_max" => features.intersects(FLOAT32_ATOMIC_MIN_MAX),
+            _ => true,
+        },
+    );
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SpirvOptKind {
+    DeadCodeElimination,
+    Performance,
+}
+
+fn spirv_val(spirv: &[u32]) -> Result<()> {
+    use spirv_tools::{val::Validator, TargetEnv};
+    let target_env = TargetEnv::Vulkan_1_2;
+    let validator = spirv_tools::val::create(Some(target_env));
+    validator.validate(spirv, None)?;
+    Ok(())
+}
+
+fn spirv_opt(spirv: &[u32], kind: SpirvOptKind) -> Result<spirv_tools::binary::Binary> {
+    use spirv_tools::{
+        opt::{Optimizer, Passes},
+        val::Validator,
+        TargetEnv,
+    };
+    let target_env = TargetEnv::Vulkan_1_2;
+    let validator = spirv_tools::val::create(Some(target_env));
+    validator.validate(spirv, None)?;
+    let mut optimizer = spirv_tools::opt::create(Some(target_env));
+    match kind {
+        SpirvOptKind::DeadCodeElimination => {
+            let passes = {
+                use Passes::*;
+                [
+                    EliminateDeadFunctions,
+                    DeadVariableElimination,
+                    EliminateDeadConstant,
+                    CombineAccessChains,
+                    CompactIds,
+                ]
+            };
+            for pass in passes {
+                optimizer.register_pass(pass);
+            }
+        }
+        SpirvOptKind::Performance => {
+            optimizer.register_performance_passes();
+        }
+    }
+    Ok(optimizer.optimize(spirv, &mut |_| (), None)?)
+}
+
+fn add_spec_constant_ops(module: &mut rspirv::dr::Module) {
+    use rspirv::{
+        dr::{Instruction, Operand},
+        spirv::Op,
+    };
+    let mut constants = FxHashSet::default();
+    for inst in module.types_global_values.iter() {
+        if matches!(
+            inst.class.opcode,
+            Op::Constant
+                | Op::ConstantTrue
+                | Op::ConstantFalse
+                | Op::ConstantNull
+                | Op::ConstantComposite
+                | Op::SpecConstant
+                | Op::SpecConstantTrue
+                | Op::SpecConstantFalse
+                | Op::SpecConstantComposite
+                | Op::SpecConstantOp
+        ) {
+            if let Some(result_id) = inst.result_id {
+                constants.insert(result_id);
+            }
+        }
+    }
+    for function in module.functions.iter_mut() {
+        for block in function.blocks.iter_mut() {
+            block.instructions.retain(|inst| {
+                if matches!(
+                    inst.class.opcode,
+                    Op::SConvert
+                        | Op::UConvert
+                        | Op::FConvert
+                        | Op::SNegate
+                        | Op::Not
+                        | Op::IAdd
+                        | Op::ISub
+                        | Op::IMul
+                        | Op::UDiv
+                        | Op::SDiv
+                        | Op::UMod
+                        | Op::SRem
+                        | Op::SMod
+                  /* | Op::ShiftRightLogical
+                        | Op::ShiftRightArithmetic
+                        | Op::ShiftLeftLogical
+                        | Op::BitwiseOr
+                        | Op::BitwiseAnd
+                        | Op::VectorShuffle
+                        | Op::CompositeExtract
+                        | Op::CompositeInsert
+                        | Op::LogicalOr
+                        | Op::LogicalAnd
+                        | Op::LogicalNot
+                        | Op::LogicalEqual
+                        | Op::LogicalNotEqual */
+                        | Op::Select
+                        | Op::IEqual
+                        | Op::INotEqual
+                        | Op::ULessThan
+                        | Op::SLessThan
+                        | Op::UGreaterThan
+                        | Op::SGreaterThan
+                        | Op::ULessThanEqual
+                        | Op::SLessThanEqual
+                        | Op::UGreaterThanEqual
+                        | Op::SGreaterThanEqual /* | Op::QuantizeToF16 */
+                ) {
+                    if let Some(result_id) = inst.result_id {
+                        let mut used_constants = FxHashSet::default();
+                        for operand in inst.operands.iter() {
+                            if let Operand::IdRef(id) = operand {
+                                if !constants.contains(id) {
+                                    return true;
+                                }
+                                used_constants.insert(*id);
+                            }
+                        }
+                        for (i, global_inst) in module.types_global_values.iter().enumerate().rev()
+                        {
+                            if let Some(global_result_id) = global_inst.result_id {
+                                if inst.result_type == global_inst.result_id
+                                    || used_constants.contains(&global_result_id)
+                                {
+                                    module.types_global_values.insert(
+                                        i + 1,
+                                        Instruction::new(
+                                            Op::SpecConstantOp,
+                                            inst.result_type,
+                                            inst.result_id,
+                                            [Operand::LiteralInt32(inst.class.opcode as u32)]
+                                                .into_iter()
+                                                .chain(inst.operands.clone())
+                                                .collect(),
+                                        ),
+                                    );
+                                    constants.insert(result_id);
+                                    return false;
+                                }
+                            }
+                        }
+                    }
+                }
+                true
+            });
+        }
+    }
+}
+
+fn strip_unused_types(module: &mut rspirv::dr::Module) {
+    use rspirv::{dr::Operand, spirv::Op};
+    let mut used = FxHashSet::default();
+    for func in module.functions.iter() {
+        if let Some(inst) = func.def.as_ref() {
+            let func_type = inst.operands.last().unwrap().unwrap_id_ref();
+            used.insert(func_type);
+        }
+        for block in func.blocks.iter() {
+            for inst in block.instructions.iter() {
+                if let Some(result_type) = inst.result_type {
+                    used.insert(result_type);
+                }
+            }
+        }
+    }
+
+    for inst in module.types_global_values.iter().rev() {
+        match inst.class.opcode {
+            Op::TypeVoid | Op::TypeBool | Op::TypeInt | Op::TypeFloat => {
+                used.extend(inst.result_id);
+            }
+            Op::TypeArray | Op::TypeVector | Op::TypeMatrix | Op::TypeStruct | Op::TypeFunction => {
+                used.extend(inst.result_id);
+                used.extend(inst.operands.iter().filter_map(|operand| {
+                    if let Operand::IdRef(id) = operand {
+                        Some(*id)
+                    } else {
+                        None
+                    }
+                }));
+            }
+            _ => {
+                used.extend(inst.result_id);
+                used.extend(inst.result_type);
+            }
+        }
+    }
+    module
         .types_global_values
         .retain(|inst| used.contains(&inst.result_id.unwrap()));
     module
@@ -1502,12 +3440,19 @@ enum ScalarType {
     U64,
     I64,
     F64,
+    C32,
+    C64,
+    F8E4M3,
+    F8E5M2,
 }
 
 impl ScalarType {
     fn iter() -> impl Iterator<Item = Self> {
         use ScalarType::*;
-        [U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64].into_iter()
+        [
+            U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64, C32, C64, F8E4M3, F8E5M2,
+        ]
+        .into_iter()
     }
     fn name(&self) -> &'static str {
         use ScalarType::*;
@@ -1524,6 +3469,10 @@ impl ScalarType {
             U64 => "u64",
             I64 => "i64",
             F64 => "F64",
+            C32 => "c32",
+            C64 => "c64",
+            F8E4M3 => "f8e4m3",
+            F8E5M2 => "f8e5m2",
         }
     }
     fn as_str(&self) -> &'static str {
@@ -1541,15 +3490,20 @@ impl ScalarType {
             U64 => "U64",
             I64 => "I64",
             F64 => "F64",
+            C32 => "C32",
+            C64 => "C64",
+            F8E4M3 => "F8E4M3",
+            F8E5M2 => "F8E5M2",
         }
     }
     fn size(&self) -> usize {
         use ScalarType::*;
         match self {
-            U8 | I8 => 1,
+            U8 | I8 | F8E4M3 | F8E5M2 => 1,
             U16 | I16 | F16 | BF16 => 2,
             U32 | I32 | F32 => 4,
-            U64 | I64 | F64 => 8,
+            U64 | I64 | F64 | C32 => 8,
+            C64 => 16,
         }
     }
 }
@@ -1610,12 +3564,20 @@ struct KernelDesc {
     spirv: Vec<u32>,
     #[serde(skip_deserializing)]
     features: Features,
+    #[serde(skip_deserializing)]
+    emulated: Option<EmulatedDesc>,
     safe: bool,
     spec_descs: Vec<SpecDesc>,
     slice_descs: Vec<SliceDesc>,
     push_descs: Vec<PushDesc>,
 }
 
+#[derive(Serialize, Debug)]
+struct EmulatedDesc {
+    spirv: Vec<u32>,
+    features: Features,
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub struct Features {
     bits: u32,
@@ -1631,6 +3593,8 @@ impl Features {
     pub const BUFFER16: Self = Self::new(1 << 9);
     pub const PUSH_CONSTANT8: Self = Self::new(1 << 10);
     pub const PUSH_CONSTANT16: Self = Self::new(1 << 11);
+    pub const BUFFER_INT64_ATOMICS: Self = Self::new(1 << 12);
+    pub const GROUP_INT64_ATOMICS: Self = Self::new(1 << 13);
     pub const SUBGROUP_BASIC: Self = Self::new(1 << 16);
     pub const SUBGROUP_VOTE: Self = Self::new(1 << 17);
     pub const SUBGROUP_ARITHMETIC: Self = Self::new(1 << 18);
@@ -1639,6 +3603,13 @@ impl Features {
     pub const SUBGROUP_SHUFFLE_RELATIVE: Self = Self::new(1 << 21);
     pub const SUBGROUP_CLUSTERED: Self = Self::new(1 << 22);
     pub const SUBGROUP_QUAD: Self = Self::new(1 << 23);
+    pub const SUBGROUP_EXTENDED_TYPES: Self = Self::new(1 << 30);
+    pub const BUFFER_FLOAT32_ATOMICS: Self = Self::new(1 << 24);
+    pub const GROUP_FLOAT32_ATOMICS: Self = Self::new(1 << 25);
+    pub const BUFFER_FLOAT32_ATOMIC_ADD: Self = Self::new(1 << 26);
+    pub const GROUP_FLOAT32_ATOMIC_ADD: Self = Self::new(1 << 27);
+    pub const BUFFER_FLOAT32_ATOMIC_MIN_MAX: Self = Self::new(1 << 28);
+    pub const GROUP_FLOAT32_ATOMIC_MIN_MAX: Self = Self::new(1 << 29);
 
     #[inline]
     const fn new(bits: u32) -> Self {
@@ -1660,6 +3631,8 @@ impl Features {
             .union(Self::BUFFER16)
             .union(Self::PUSH_CONSTANT8)
             .union(Self::PUSH_CONSTANT16)
+            .union(Self::BUFFER_INT64_ATOMICS)
+            .union(Self::GROUP_INT64_ATOMICS)
             .union(Self::SUBGROUP_BASIC)
             .union(Self::SUBGROUP_VOTE)
             .union(Self::SUBGROUP_ARITHMETIC)
@@ -1668,6 +3641,13 @@ impl Features {
             .union(Self::SUBGROUP_SHUFFLE_RELATIVE)
             .union(Self::SUBGROUP_CLUSTERED)
             .union(Self::SUBGROUP_QUAD)
+            .union(Self::SUBGROUP_EXTENDED_TYPES)
+            .union(Self::BUFFER_FLOAT32_ATOMICS)
+            .union(Self::GROUP_FLOAT32_ATOMICS)
+            .union(Self::BUFFER_FLOAT32_ATOMIC_ADD)
+            .union(Self::GROUP_FLOAT32_ATOMIC_ADD)
+            .union(Self::BUFFER_FLOAT32_ATOMIC_MIN_MAX)
+            .union(Self::GROUP_FLOAT32_ATOMIC_MIN_MAX)
     }
     #[inline]
     pub const fn contains(self, other: Self) -> bool {
@@ -1677,6 +3657,14 @@ impl Features {
     pub const fn union(self, other: Self) -> Self {
         Self::new(self.bits | other.bits)
     }
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        (self.bits & other.bits) != 0
+    }
+    #[inline]
+    pub const fn difference(self, other: Self) -> Self {
+        Self::new(self.bits & !other.bits)
+    }
     fn name_iter(&self) -> impl Iterator<Item = &str> {
         macro_rules! features {
             ($($f:ident),*) => {
@@ -1698,6 +3686,8 @@ impl Features {
             BUFFER16,
             PUSH_CONSTANT8,
             PUSH_CONSTANT16,
+            BUFFER_INT64_ATOMICS,
+            GROUP_INT64_ATOMICS,
             SUBGROUP_BASIC,
             SUBGROUP_VOTE,
             SUBGROUP_ARITHMETIC,
@@ -1705,7 +3695,14 @@ impl Features {
             SUBGROUP_SHUFFLE,
             SUBGROUP_SHUFFLE_RELATIVE,
             SUBGROUP_CLUSTERED,
-            SUBGROUP_QUAD
+            SUBGROUP_QUAD,
+            SUBGROUP_EXTENDED_TYPES,
+            BUFFER_FLOAT32_ATOMICS,
+            GROUP_FLOAT32_ATOMICS,
+            BUFFER_FLOAT32_ATOMIC_ADD,
+            GROUP_FLOAT32_ATOMIC_ADD,
+            BUFFER_FLOAT32_ATOMIC_MIN_MAX,
+            GROUP_FLOAT32_ATOMIC_MIN_MAX
         )
         .into_iter()
         .filter_map(|(name, features)| {
@@ -1787,6 +3784,7 @@ struct SliceDesc {
     scalar_type: ScalarType,
     mutable: bool,
     item: bool,
+    element: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
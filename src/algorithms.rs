@@ -0,0 +1,909 @@
+/*!
+Prefix scans, stream compaction, and radix sort for [buffers](crate::buffer). Host buffers use simple
+sequential implementations, which also serve as references for testing.
+
+# Example
+```
+# use krnl::{anyhow::Result, algorithms, buffer::{Buffer, Slice}, device::Device};
+# fn main() -> Result<()> {
+# let device = Device::host();
+let x = Buffer::from(vec![3u32, 1, 2]).into_device(device.clone())?;
+let y = algorithms::inclusive_scan(x.as_slice())?;
+assert_eq!(y.into_vec()?, [3, 4, 6]);
+let (keys, values) = algorithms::radix_sort_pairs(x.as_slice(), Slice::from([0u32, 1, 2].as_slice()))?;
+assert_eq!(keys.into_vec()?, [1, 2, 3]);
+assert_eq!(values.into_vec()?, [1, 2, 0]);
+# Ok(())
+# }
+```
+*/
+
+#[cfg(doc)]
+use crate::device::error::DeviceLost;
+#[cfg(all(doc, not(feature = "device")))]
+use crate::device::Features;
+use crate::{
+    buffer::{Buffer, Slice, SliceMut},
+    scalar::{Scalar, ScalarType},
+};
+#[cfg(feature = "device")]
+use crate::{
+    device::{Device, Features},
+    macros::module,
+    scalar::{f8e4m3, f8e5m2},
+};
+use anyhow::{bail, Result};
+use dry::macro_for;
+use half::{bf16, f16};
+#[cfg(feature = "device")]
+use paste::paste;
+#[cfg(feature = "device")]
+use std::mem::size_of;
+
+/** Inclusive prefix sum.
+
+`y[i] = x[0] + .. + x[i]`
+
+Integers wrap on overflow. [`f16`] and [`bf16`] are accumulated as [`f32`].
+
+# Errors
+- The length of a device buffer is greater than [`u32::MAX`].
+- [`DeviceLost`]
+- The kernel could not be dispatched.
+    - This may require [`Features`] for the type. */
+pub fn inclusive_scan<T: Scalar>(x: Slice<T>) -> Result<Buffer<T>> {
+    scan(x, true)
+}
+
+/** Exclusive prefix sum.
+
+`y[i] = x[0] + .. + x[i - 1]`, with `y[0] = 0`.
+
+See [`inclusive_scan`]. */
+pub fn exclusive_scan<T: Scalar>(x: Slice<T>) -> Result<Buffer<T>> {
+    scan(x, false)
+}
+
+fn scan<T: Scalar>(x: Slice<T>, inclusive: bool) -> Result<Buffer<T>> {
+    let mut output = unsafe { Buffer::uninit(x.device(), x.len())? };
+    if output.is_empty() {
+        return Ok(output);
+    }
+    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+            let x = Slice::<$X>::try_from(x.as_scalar_slice()).ok().unwrap();
+            let y = SliceMut::<$X>::try_from(output.as_scalar_slice_mut()).ok().unwrap();
+            <$X as Scan>::scan(x, y, inclusive)?;
+            return Ok(output);
+        }
+    });
+    bail!("Scan is not implemented for {:?}!", T::SCALAR_TYPE)
+}
+
+/** Selects the elements of `x` where `mask` is not 0.
+
+The order of the elements is preserved.
+
+# Errors
+- `mask` is not on the same device or does not have the same length.
+- The length of a device buffer is greater than [`u32::MAX`].
+- [`DeviceLost`]
+- The kernel could not be dispatched.
+    - This may require [`Features`] for the type. */
+pub fn compact<T: Scalar>(x: Slice<T>, mask: Slice<u32>) -> Result<Buffer<T>> {
+    if x.device() != mask.device() {
+        bail!(
+            "Expected mask on {:?}, found {:?}!",
+            x.device(),
+            mask.device()
+        );
+    }
+    if x.len() != mask.len() {
+        bail!("Expected mask with len {}, found {}!", x.len(), mask.len());
+    }
+    if let Some((x, mask)) = x.as_host_slice().zip(mask.as_host_slice()) {
+        let output: Vec<T> = x
+            .iter()
+            .zip(mask)
+            .filter(|(_, mask)| **mask != 0)
+            .map(|(x, _)| *x)
+            .collect();
+        return Ok(output.into());
+    }
+    #[cfg(not(feature = "device"))]
+    {
+        unreachable!()
+    }
+    #[cfg(feature = "device")]
+    {
+        device_compact(x, mask)
+    }
+}
+
+/** Sorts `keys`.
+
+The sort is a stable LSD radix sort with 4 bit digits. On the device, each pass computes the
+histogram of digits per group, scans the histograms, and scatters each key to its rank. Floats are
+ordered by [`total_cmp`](f32::total_cmp), ie -NaN < -inf < .. < -0 < 0 < .. < inf < NaN.
+
+# Errors
+- The length of a device buffer is greater than [`u32::MAX`].
+- [`DeviceLost`]
+- The kernel could not be dispatched.
+    - This may require [`Features`] for the type. */
+pub fn radix_sort<K: Scalar>(keys: Slice<K>) -> Result<Buffer<K>> {
+    radix_sort_impl::<K, u32>(keys, None).map(|(keys, _)| keys)
+}
+
+/** Sorts `keys` and `values` by `keys`.
+
+See [`radix_sort`].
+
+# Errors
+- `values` is not on the same device or does not have the same length.
+- See [`radix_sort`]. */
+pub fn radix_sort_pairs<K: Scalar, V: Scalar>(
+    keys: Slice<K>,
+    values: Slice<V>,
+) -> Result<(Buffer<K>, Buffer<V>)> {
+    if keys.device() != values.device() {
+        bail!(
+            "Expected values on {:?}, found {:?}!",
+            keys.device(),
+            values.device()
+        );
+    }
+    if keys.len() != values.len() {
+        bail!(
+            "Expected values with len {}, found {}!",
+            keys.len(),
+            values.len()
+        );
+    }
+    radix_sort_impl(keys, Some(values)).map(|(keys, values)| (keys, values.unwrap()))
+}
+
+fn radix_sort_impl<K: Scalar, V: Scalar>(
+    keys: Slice<K>,
+    values: Option<Slice<V>>,
+) -> Result<(Buffer<K>, Option<Buffer<V>>)> {
+    if K::SCALAR_TYPE.is_complex() {
+        bail!(
+            "Radix sort is not implemented for {:?} keys!",
+            K::SCALAR_TYPE
+        );
+    }
+    if let Some(keys) = keys.as_host_slice() {
+        let values = values.as_ref().map(|x| x.as_host_slice().unwrap());
+        let mut indices: Vec<usize> = (0..keys.len()).collect();
+        indices.sort_by_key(|i| radix_key(keys[*i]));
+        let keys_output: Vec<K> = indices.iter().map(|i| keys[*i]).collect();
+        let values_output: Option<Vec<V>> =
+            values.map(|values| indices.iter().map(|i| values[*i]).collect());
+        return Ok((keys_output.into(), values_output.map(Into::into)));
+    }
+    #[cfg(not(feature = "device"))]
+    {
+        unreachable!()
+    }
+    #[cfg(feature = "device")]
+    {
+        device_radix_sort(keys, values)
+    }
+}
+
+/// Maps the key to an unsigned integer with the same ordering.
+fn radix_key<K: Scalar>(key: K) -> u64 {
+    let bits = key.scalar_elem().to_scalar_bits().cast::<u64>();
+    let sign_bit = 1u64 << (K::SCALAR_TYPE.size() * 8 - 1);
+    use ScalarType::*;
+    match K::SCALAR_TYPE {
+        I8 | I16 | I32 | I64 => bits ^ sign_bit,
+        F16 | BF16 | F32 | F64 | F8E4M3 | F8E5M2 => {
+            if bits & sign_bit != 0 {
+                // the mask removes the upper bits set by the negation for smaller types
+                !bits & (sign_bit | (sign_bit - 1))
+            } else {
+                bits | sign_bit
+            }
+        }
+        _ => bits,
+    }
+}
+
+trait Accumulator: Scalar {
+    fn accumulate(self, x: Self) -> Self;
+}
+
+macro_for!($A in [u32, i32, u64, i64] {
+    impl Accumulator for $A {
+        fn accumulate(self, x: Self) -> Self {
+            self.wrapping_add(x)
+        }
+    }
+});
+
+macro_for!($A in [f32, f64] {
+    impl Accumulator for $A {
+        fn accumulate(self, x: Self) -> Self {
+            self + x
+        }
+    }
+});
+
+trait Scan: Scalar {
+    type Acc: Accumulator;
+    fn scan(x: Slice<Self>, mut y: SliceMut<Self>, inclusive: bool) -> Result<()> {
+        if let Some(x) = x.as_host_slice() {
+            let mut acc = Self::Acc::default();
+            for (x, y) in x.iter().zip(y.as_host_slice_mut().unwrap()) {
+                let next = acc.accumulate(x.cast());
+                *y = if inclusive { next } else { acc }.cast();
+                acc = next;
+            }
+            return Ok(());
+        }
+        #[cfg(feature = "device")]
+        {
+            Self::device_scan(x, y, inclusive)
+        }
+        #[cfg(not(feature = "device"))]
+        {
+            unreachable!()
+        }
+    }
+    #[cfg(feature = "device")]
+    fn device_scan(x: Slice<Self>, y: SliceMut<Self>, inclusive: bool) -> Result<()>;
+}
+
+/// Global threads to dispatch one thread per element.
+#[cfg(feature = "device")]
+fn global_threads(len: usize) -> Result<u32> {
+    if let Ok(global_threads) = u32::try_from(len) {
+        Ok(global_threads)
+    } else {
+        bail!("Expected len <= {}, found {len}!", u32::MAX)
+    }
+}
+
+/// Threads, groups, and whether to use subgroup operations.
+#[cfg(feature = "device")]
+fn scan_dims<A: Scalar>(device: &Device, len: usize) -> Result<(u32, u32, bool)> {
+    let info = device.info().unwrap();
+    let threads = info.default_threads();
+    let global_threads = global_threads(len)?;
+    let groups = global_threads / threads + u32::from(global_threads % threads != 0);
+    let mut subgroup_features = Features::SUBGROUP_BASIC | Features::SUBGROUP_ARITHMETIC;
+    if matches!(A::SCALAR_TYPE, ScalarType::U64 | ScalarType::I64) {
+        subgroup_features |= Features::SUBGROUP_EXTENDED_TYPES;
+    }
+    let subgroup = info.features().contains(subgroup_features);
+    Ok((threads, groups, subgroup))
+}
+
+macro_rules! impl_scan {
+    ($($X:ident => $A:ident),* $(,)?) => {
+        $(
+            impl Scan for $X {
+                type Acc = $A;
+                #[cfg(feature = "device")]
+                fn device_scan(x: Slice<Self>, mut y: SliceMut<Self>, inclusive: bool) -> Result<()> {
+                    let device = y.device();
+                    let (threads, groups, subgroup) = scan_dims::<$A>(&device, x.len())?;
+                    let mut partials = unsafe { Buffer::<$A>::uninit(device.clone(), groups as usize)? };
+                    paste! {
+                        if subgroup {
+                            kernels::[<scan_subgroup_ $X>]::builder()?
+                                .specialize(threads, inclusive as u32)
+                                .with_threads(threads)
+                                .build(device.clone())?
+                                .with_groups(groups)
+                                .dispatch(x, y.as_slice_mut(), partials.as_slice_mut())?;
+                        } else {
+                            kernels::[<scan_ $X>]::builder()?
+                                .specialize(threads, inclusive as u32)
+                                .with_threads(threads)
+                                .build(device.clone())?
+                                .with_groups(groups)
+                                .dispatch(x, y.as_slice_mut(), partials.as_slice_mut())?;
+                        }
+                    }
+                    if groups > 1 {
+                        let mut offsets = unsafe { Buffer::<$A>::uninit(device.clone(), groups as usize)? };
+                        <$A as Scan>::device_scan(partials.as_slice(), offsets.as_slice_mut(), false)?;
+                        paste! {
+                            kernels::[<scan_add_ $X>]::builder()?
+                                .with_threads(threads)
+                                .build(device)?
+                                .with_groups(groups)
+                                .dispatch(offsets.as_slice(), y)?;
+                        }
+                    }
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_scan! {
+    u8 => u32,
+    i8 => i32,
+    u16 => u32,
+    i16 => i32,
+    f16 => f32,
+    bf16 => f32,
+    u32 => u32,
+    i32 => i32,
+    f32 => f32,
+    u64 => u64,
+    i64 => i64,
+    f64 => f64,
+}
+
+/// Exclusive scan of the mask as 0 or 1, and the number of selected elements.
+#[cfg(feature = "device")]
+fn device_mask_offsets(mask: Slice<u32>) -> Result<(Buffer<u32>, Buffer<u32>, usize)> {
+    let device = mask.device();
+    let mut flags = unsafe { Buffer::<u32>::uninit(device.clone(), mask.len())? };
+    kernels::mask_flags::builder()?
+        .build(device.clone())?
+        .dispatch(mask, flags.as_slice_mut())?;
+    let mut offsets = unsafe { Buffer::<u32>::uninit(device, flags.len())? };
+    <u32 as Scan>::device_scan(flags.as_slice(), offsets.as_slice_mut(), false)?;
+    let last = flags.len() - 1;
+    let count =
+        offsets.slice(last..).unwrap().to_vec()?[0] + flags.slice(last..).unwrap().to_vec()?[0];
+    Ok((flags, offsets, count as usize))
+}
+
+#[cfg(feature = "device")]
+fn device_compact<T: Scalar>(x: Slice<T>, mask: Slice<u32>) -> Result<Buffer<T>> {
+    let device = x.device();
+    if x.is_empty() {
+        return Buffer::zeros(device, 0);
+    }
+    let global_threads = global_threads(x.len())?;
+    let (flags, offsets, count) = device_mask_offsets(mask)?;
+    let mut output = unsafe { Buffer::<T>::uninit(device.clone(), count)? };
+    if output.is_empty() {
+        return Ok(output);
+    }
+    macro_for!($W in [u8, u16, u32, u64] {
+        if size_of::<T>() == size_of::<$W>() {
+            let builder = paste! {
+                kernels::[<compact_ $W>]::builder()?
+            };
+            builder
+                .build(device)?
+                .with_global_threads(global_threads)
+                .dispatch(
+                    x.bitcast().unwrap(),
+                    flags.as_slice(),
+                    offsets.as_slice(),
+                    output.bitcast_mut().unwrap(),
+                )?;
+            return Ok(output);
+        }
+    });
+    bail!("Compact is not implemented for {:?}!", T::SCALAR_TYPE)
+}
+
+#[cfg(feature = "device")]
+fn device_radix_sort<K: Scalar, V: Scalar>(
+    keys: Slice<K>,
+    values: Option<Slice<V>>,
+) -> Result<(Buffer<K>, Option<Buffer<V>>)> {
+    use kernels::{RADIX, RADIX_BITS};
+    use std::mem::swap;
+
+    let device = keys.device();
+    let len = keys.len();
+    let global_threads = global_threads(len)?;
+    let mut keys = keys.to_owned()?;
+    let mut values = values.map(|x| x.to_owned()).transpose()?;
+    if len <= 1 {
+        return Ok((keys, values));
+    }
+    let threads = device.info().unwrap().default_threads();
+    let groups = global_threads / threads + u32::from(global_threads % threads != 0);
+    let mut keys_tmp = unsafe { Buffer::<K>::uninit(device.clone(), len)? };
+    let mut values_tmp = if values.is_some() {
+        Some(unsafe { Buffer::<V>::uninit(device.clone(), len)? })
+    } else {
+        None
+    };
+    let mut digits = unsafe { Buffer::<u32>::uninit(device.clone(), len)? };
+    let mut ranks = unsafe { Buffer::<u32>::uninit(device.clone(), len)? };
+    let histogram_len = RADIX as usize * groups as usize;
+    let mut histogram = unsafe { Buffer::<u32>::uninit(device.clone(), histogram_len)? };
+    let mut offsets = unsafe { Buffer::<u32>::uninit(device.clone(), histogram_len)? };
+    let ranks_kernel = kernels::radix_ranks::builder()?
+        .specialize(threads)
+        .with_threads(threads)
+        .build(device.clone())?
+        .with_groups(groups);
+    for shift in (0..(size_of::<K>() * 8) as u32).step_by(RADIX_BITS as usize) {
+        macro_for!($K in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, f8e4m3, f8e5m2] {
+            if K::SCALAR_TYPE == $K::SCALAR_TYPE {
+                let builder = paste! {
+                    kernels::[<radix_digits_ $K>]::builder()?
+                };
+                builder.build(device.clone())?.dispatch(
+                    shift,
+                    Slice::<$K>::try_from(keys.as_scalar_slice()).ok().unwrap(),
+                    digits.as_slice_mut(),
+                )?;
+            }
+        });
+        ranks_kernel.dispatch(
+            digits.as_slice(),
+            ranks.as_slice_mut(),
+            histogram.as_slice_mut(),
+        )?;
+        <u32 as Scan>::device_scan(histogram.as_slice(), offsets.as_slice_mut(), false)?;
+        let scatter = RadixScatter {
+            digits: digits.as_slice(),
+            ranks: ranks.as_slice(),
+            offsets: offsets.as_slice(),
+            tile: threads,
+            global_threads,
+        };
+        scatter.dispatch(keys.as_slice(), keys_tmp.as_slice_mut())?;
+        swap(&mut keys, &mut keys_tmp);
+        if let Some((values, values_tmp)) = values.as_mut().zip(values_tmp.as_mut()) {
+            scatter.dispatch(values.as_slice(), values_tmp.as_slice_mut())?;
+            swap(values, values_tmp);
+        }
+    }
+    Ok((keys, values))
+}
+
+/// Moves each element to its sorted position for the current digit.
+#[cfg(feature = "device")]
+struct RadixScatter<'a> {
+    digits: Slice<'a, u32>,
+    ranks: Slice<'a, u32>,
+    offsets: Slice<'a, u32>,
+    tile: u32,
+    global_threads: u32,
+}
+
+#[cfg(feature = "device")]
+impl RadixScatter<'_> {
+    fn dispatch<T: Scalar>(&self, x: Slice<T>, mut y: SliceMut<T>) -> Result<()> {
+        let device = y.device();
+        macro_for!($W in [u8, u16, u32, u64] {
+            if size_of::<T>() == size_of::<$W>() {
+                let builder = paste! {
+                    kernels::[<radix_scatter_ $W>]::builder()?
+                };
+                return builder
+                    .build(device)?
+                    .with_global_threads(self.global_threads)
+                    .dispatch(
+                        self.tile,
+                        x.bitcast().unwrap(),
+                        self.digits.as_slice(),
+                        self.ranks.as_slice(),
+                        self.offsets.as_slice(),
+                        y.bitcast_mut().unwrap(),
+                    );
+            }
+        });
+        bail!("Radix sort is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+}
+
+#[cfg(feature = "device")]
+#[module]
+#[krnl(crate=crate)]
+mod kernels {
+    use dry::macro_for;
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl_core;
+    use krnl_core::macros::kernel;
+    #[cfg(target_arch = "spirv")]
+    use krnl_core::{
+        buffer::{UnsafeIndex, UnsafeSlice},
+        half::{bf16, f16},
+        kernel::Kernel,
+        scalar::{f8e4m3, f8e5m2, Scalar},
+        spirv_std::arch::workgroup_memory_barrier_with_group_sync as group_barrier,
+        subgroup::{self, SubgroupScalar},
+    };
+    use paste::paste;
+
+    #[cfg(target_arch = "spirv")]
+    trait Accumulator: Scalar + SubgroupScalar {}
+
+    macro_for!($A in [u32, i32, f32, u64, i64, f64] {
+        #[cfg(target_arch = "spirv")]
+        impl Accumulator for $A {}
+    });
+
+    /// Returns the exclusive scan, inclusive scan, and the total of the group.
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    unsafe fn group_scan<A: Accumulator>(
+        kernel: &Kernel,
+        x: A,
+        x_group: UnsafeSlice<A>,
+    ) -> (A, A, A) {
+        let thread_id = kernel.thread_id();
+        let threads = kernel.threads();
+        unsafe {
+            *x_group.unsafe_index_mut(thread_id) = x;
+            group_barrier();
+        }
+        let mut stride = 1;
+        while stride < threads {
+            let y = if thread_id >= stride {
+                unsafe { *x_group.unsafe_index(thread_id - stride) }
+            } else {
+                A::default()
+            };
+            unsafe {
+                group_barrier();
+            }
+            if thread_id >= stride {
+                unsafe {
+                    *x_group.unsafe_index_mut(thread_id) += y;
+                }
+            }
+            unsafe {
+                group_barrier();
+            }
+            stride *= 2;
+        }
+        unsafe {
+            let exclusive = if thread_id > 0 {
+                *x_group.unsafe_index(thread_id - 1)
+            } else {
+                A::default()
+            };
+            (
+                exclusive,
+                *x_group.unsafe_index(thread_id),
+                *x_group.unsafe_index(threads - 1),
+            )
+        }
+    }
+
+    /// See [`group_scan`].
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    unsafe fn subgroup_group_scan<A: Accumulator>(
+        kernel: &Kernel,
+        x: A,
+        x_group: UnsafeSlice<A>,
+    ) -> (A, A, A) {
+        let (exclusive, inclusive, total) = (
+            subgroup::exclusive_scan(x),
+            subgroup::inclusive_scan(x),
+            subgroup::reduce_add(x),
+        );
+        let subgroup_id = kernel.subgroup_id();
+        let subgroups = kernel.subgroups();
+        if kernel.subgroup_thread_id() == 0 {
+            unsafe {
+                *x_group.unsafe_index_mut(subgroup_id) = total;
+            }
+        }
+        unsafe {
+            group_barrier();
+        }
+        if kernel.thread_id() == 0 {
+            let mut acc = A::default();
+            let mut index = 0;
+            while index < subgroups {
+                unsafe {
+                    acc += *x_group.unsafe_index(index);
+                    *x_group.unsafe_index_mut(index) = acc;
+                }
+                index += 1;
+            }
+        }
+        unsafe {
+            group_barrier();
+        }
+        let offset = if subgroup_id > 0 {
+            unsafe { *x_group.unsafe_index(subgroup_id - 1) }
+        } else {
+            A::default()
+        };
+        let total = unsafe { *x_group.unsafe_index(subgroups - 1) };
+        (exclusive + offset, inclusive + offset, total)
+    }
+
+    macro_rules! impl_scan {
+        ($($X:ident => $A:ident),* $(,)?) => {
+            $(
+                paste! {
+                    #[kernel]
+                    pub fn [<scan_ $X>]<const THREADS: u32, const INCLUSIVE: u32>(
+                        #[global] x: Slice<$X>,
+                        #[group] x_group: UnsafeSlice<$A, { THREADS as usize }>,
+                        #[global] y: UnsafeSlice<$X>,
+                        #[global] partials: UnsafeSlice<$A>,
+                    ) {
+                        let global_id = kernel.global_id();
+                        let value = if global_id < x.len() {
+                            x[global_id].cast::<$A>()
+                        } else {
+                            $A::default()
+                        };
+                        let (exclusive, inclusive, total) = unsafe { group_scan(&kernel, value, x_group) };
+                        if global_id < y.len() {
+                            unsafe {
+                                *y.unsafe_index_mut(global_id) = if INCLUSIVE == 1 { inclusive } else { exclusive }.cast();
+                            }
+                        }
+                        if kernel.thread_id() == 0 {
+                            unsafe {
+                                *partials.unsafe_index_mut(kernel.group_id()) = total;
+                            }
+                        }
+                    }
+
+                    #[kernel]
+                    pub fn [<scan_subgroup_ $X>]<const THREADS: u32, const INCLUSIVE: u32>(
+                        #[global] x: Slice<$X>,
+                        #[group] x_group: UnsafeSlice<$A, { THREADS as usize }>,
+                        #[global] y: UnsafeSlice<$X>,
+                        #[global] partials: UnsafeSlice<$A>,
+                    ) {
+                        let global_id = kernel.global_id();
+                        let value = if global_id < x.len() {
+                            x[global_id].cast::<$A>()
+                        } else {
+                            $A::default()
+                        };
+                        let (exclusive, inclusive, total) = unsafe { subgroup_group_scan(&kernel, value, x_group) };
+                        if global_id < y.len() {
+                            unsafe {
+                                *y.unsafe_index_mut(global_id) = if INCLUSIVE == 1 { inclusive } else { exclusive }.cast();
+                            }
+                        }
+                        if kernel.thread_id() == 0 {
+                            unsafe {
+                                *partials.unsafe_index_mut(kernel.group_id()) = total;
+                            }
+                        }
+                    }
+
+                    #[kernel]
+                    pub fn [<scan_add_ $X>](
+                        #[global] offsets: Slice<$A>,
+                        #[global] y: UnsafeSlice<$X>,
+                    ) {
+                        let global_id = kernel.global_id();
+                        if global_id < y.len() {
+                            unsafe {
+                                let y = y.unsafe_index_mut(global_id);
+                                *y = ((*y).cast::<$A>() + offsets[kernel.group_id()]).cast();
+                            }
+                        }
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_scan! {
+        u8 => u32,
+        i8 => i32,
+        u16 => u32,
+        i16 => i32,
+        f16 => f32,
+        bf16 => f32,
+        u32 => u32,
+        i32 => i32,
+        f32 => f32,
+        u64 => u64,
+        i64 => i64,
+        f64 => f64,
+    }
+
+    #[kernel]
+    pub fn mask_flags(#[item] mask: u32, #[item] flags: &mut u32) {
+        *flags = (mask != 0) as u32;
+    }
+
+    /// Bits per digit of the radix sort.
+    pub const RADIX_BITS: u32 = 4;
+    /// Digits per pass of the radix sort.
+    pub const RADIX: u32 = 1 << RADIX_BITS;
+
+    #[cfg(target_arch = "spirv")]
+    trait RadixKey: Scalar {
+        /// The digit of the ordered bits starting at `shift`.
+        fn radix_digit(self, shift: u32) -> u32;
+    }
+
+    macro_for!($K in [u8, u16, u32, u64] {
+        #[cfg(target_arch = "spirv")]
+        impl RadixKey for $K {
+            #[inline]
+            fn radix_digit(self, shift: u32) -> u32 {
+                ((self >> shift) as u32) & (RADIX - 1)
+            }
+        }
+    });
+
+    macro_rules! impl_radix_key {
+        ($($K:ident => $U:ident),* $(,)?) => {
+            $(
+                #[cfg(target_arch = "spirv")]
+                impl RadixKey for $K {
+                    #[inline]
+                    fn radix_digit(self, shift: u32) -> u32 {
+                        ((self as $U) ^ (1 << ($U::BITS - 1))).radix_digit(shift)
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_radix_key!(i8 => u8, i16 => u16, i32 => u32, i64 => u64);
+
+    macro_rules! impl_radix_key_float {
+        ($($K:ident => $U:ident),* $(,)?) => {
+            $(
+                #[cfg(target_arch = "spirv")]
+                impl RadixKey for $K {
+                    #[inline]
+                    fn radix_digit(self, shift: u32) -> u32 {
+                        let bits = self.to_bits();
+                        let sign_bit: $U = 1 << ($U::BITS - 1);
+                        let bits = if bits & sign_bit != 0 { !bits } else { bits | sign_bit };
+                        bits.radix_digit(shift)
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_radix_key_float!(f16 => u16, bf16 => u16, f32 => u32, f64 => u64, f8e4m3 => u8, f8e5m2 => u8);
+
+    macro_for!($K in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, f8e4m3, f8e5m2] {
+        paste! {
+            #[kernel]
+            pub fn [<radix_digits_ $K>](shift: u32, #[item] x: $K, #[item] digit: &mut u32) {
+                *digit = x.radix_digit(shift);
+            }
+        }
+    });
+
+    /// Computes the rank of each element among the elements of its group with the same digit,
+    /// and the histogram of digits of each group.
+    ///
+    /// The histogram is stored digit major, so that an exclusive scan gives the offset of each
+    /// digit of each group in the output.
+    #[kernel]
+    pub fn radix_ranks<const THREADS: u32>(
+        #[global] digits: Slice<u32>,
+        #[group] scan_group: UnsafeSlice<u32, { THREADS as usize }>,
+        #[group] entries: UnsafeSlice<u32, { THREADS as usize }>,
+        #[group] bounds: UnsafeSlice<u32, 32>,
+        #[global] ranks: UnsafeSlice<u32>,
+        #[global] histogram: UnsafeSlice<u32>,
+    ) {
+        // bounds holds the start and end of each digit
+        let thread_id = kernel.thread_id();
+        let threads = kernel.threads();
+        let group_id = kernel.group_id();
+        let groups = kernel.groups();
+        let len = digits.len();
+        let mut index = thread_id;
+        while index < bounds.len() {
+            unsafe {
+                *bounds.unsafe_index_mut(index) = 0;
+            }
+            index += threads;
+        }
+        // Elements past the end are sorted last, and removed from the histogram.
+        let global_id = kernel.global_id();
+        let digit = if global_id < len {
+            digits[global_id]
+        } else {
+            RADIX - 1
+        };
+        // The digit and the thread of the element, sorted by digit with 1 bit splits.
+        let mut entry = (digit << 16) | thread_id as u32;
+        let mut bit = 0;
+        while bit < RADIX_BITS {
+            let flag = ((entry >> (16 + bit)) & 1 == 0) as u32;
+            let (exclusive, _, zeros) = unsafe { group_scan(&kernel, flag, scan_group) };
+            let position = if flag == 1 {
+                exclusive
+            } else {
+                zeros + thread_id as u32 - exclusive
+            };
+            unsafe {
+                *entries.unsafe_index_mut(position as usize) = entry;
+                group_barrier();
+                entry = *entries.unsafe_index(thread_id);
+                group_barrier();
+            }
+            bit += 1;
+        }
+        let digit = entry >> 16;
+        let first =
+            thread_id == 0 || unsafe { *entries.unsafe_index(thread_id - 1) } >> 16 != digit;
+        let last = thread_id == threads - 1
+            || unsafe { *entries.unsafe_index(thread_id + 1) } >> 16 != digit;
+        unsafe {
+            if first {
+                *bounds.unsafe_index_mut(digit as usize) = thread_id as u32;
+            }
+            if last {
+                *bounds.unsafe_index_mut((RADIX + digit) as usize) = thread_id as u32 + 1;
+            }
+            group_barrier();
+        }
+        let source = group_id * threads + (entry & 0xFFFF) as usize;
+        if source < len {
+            unsafe {
+                *ranks.unsafe_index_mut(source) =
+                    thread_id as u32 - *bounds.unsafe_index(digit as usize);
+            }
+        }
+        if thread_id < RADIX as usize {
+            let (start, end) = unsafe {
+                (
+                    *bounds.unsafe_index(thread_id),
+                    *bounds.unsafe_index(RADIX as usize + thread_id),
+                )
+            };
+            let mut count = end - start;
+            let group_end = (group_id + 1) * threads;
+            if thread_id == RADIX as usize - 1 && group_end > len {
+                count -= (group_end - len) as u32;
+            }
+            unsafe {
+                *histogram.unsafe_index_mut(thread_id * groups + group_id) = count;
+            }
+        }
+    }
+
+    macro_for!($W in [u8, u16, u32, u64] {
+        paste! {
+            #[kernel]
+            pub fn [<radix_scatter_ $W>](
+                tile: u32,
+                #[global] x: Slice<$W>,
+                #[global] digits: Slice<u32>,
+                #[global] ranks: Slice<u32>,
+                #[global] offsets: Slice<u32>,
+                #[global] y: UnsafeSlice<$W>,
+            ) {
+                let global_id = kernel.global_id();
+                if global_id < x.len() {
+                    let groups = offsets.len() / RADIX as usize;
+                    let group = global_id / tile as usize;
+                    let offset = offsets[digits[global_id] as usize * groups + group];
+                    let index = offset + ranks[global_id];
+                    unsafe {
+                        *y.unsafe_index_mut(index as usize) = x[global_id];
+                    }
+                }
+            }
+
+            #[kernel]
+            pub fn [<compact_ $W>](
+                #[global] x: Slice<$W>,
+                #[global] flags: Slice<u32>,
+                #[global] offsets: Slice<u32>,
+                #[global] y: UnsafeSlice<$W>,
+            ) {
+                let global_id = kernel.global_id();
+                if global_id < x.len() && flags[global_id] == 1 {
+                    unsafe {
+                        *y.unsafe_index_mut(offsets[global_id] as usize) = x[global_id];
+                    }
+                }
+            }
+        }
+    });
+}
@@ -43,7 +43,7 @@ use crate::device::error::DeviceLost;
 use crate::device::Features;
 use crate::{
     device::{Device, DeviceInner},
-    scalar::{Scalar, ScalarElem, ScalarType},
+    scalar::{c32, c64, f8e4m3, f8e5m2, Scalar, ScalarElem, ScalarType},
 };
 #[cfg(feature = "device")]
 use crate::{
@@ -54,17 +54,40 @@ use anyhow::{bail, Result};
 use bytemuck::PodCastError;
 use dry::{macro_for, macro_wrap};
 use half::{bf16, f16};
+#[doc(inline)]
+pub use krnl_core::buffer::{Element, ElementLayout};
 use paste::paste;
 #[cfg(feature = "serde")]
 use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
 use std::{
+    alloc::{dealloc, Layout},
     fmt::{self, Debug},
     marker::PhantomData,
-    mem::{forget, size_of},
+    mem::{align_of, forget, size_of},
     ops::{Bound, RangeBounds},
     sync::Arc,
 };
 
+#[cfg(feature = "ndarray")]
+mod array;
+mod elementwise;
+mod index;
+mod layout;
+mod npy;
+mod random;
+mod reduce;
+#[cfg(feature = "safetensors")]
+mod safetensors;
+mod sequence;
+#[cfg(feature = "serde")]
+mod stream;
+pub use index::IndexScalar;
+#[cfg(feature = "serde")]
+pub use stream::{BufferSeed, Compressed};
+
+/// Bytes per chunk when copying to or from a device.
+const CHUNK_BYTES: usize = 1 << 24;
+
 /// Errors.
 pub mod error {
     #[cfg(feature = "device")]
@@ -152,8 +175,17 @@ impl RawSlice {
             Ok(self)
         }
     }
-    fn slice(mut self, range: impl RangeBounds<usize>, scalar_type: ScalarType) -> Option<Self> {
-        let width = scalar_type.size();
+    /// Whether the slice can be viewed as a slice of `T`.
+    fn is_element_slice<T: Element>(&self) -> bool {
+        let (index, align) = match &self.inner {
+            RawSliceInner::Host(raw) => (raw.ptr as usize, align_of::<T>()),
+            // elements are indexed from the start of the device buffer
+            #[cfg(feature = "device")]
+            RawSliceInner::Device(buffer) => (buffer.offset(), size_of::<T>()),
+        };
+        index % align == 0 && self.len() % size_of::<T>() == 0
+    }
+    fn slice(mut self, range: impl RangeBounds<usize>, width: usize) -> Option<Self> {
         let start = match range.start_bound() {
             Bound::Included(x) => x.checked_mul(width)?,
             Bound::Excluded(x) => x.checked_mul(width)?.checked_add(width)?,
@@ -199,30 +231,20 @@ struct RawBuffer {
     slice: RawSlice,
     cap: usize,
     width: usize,
+    align: usize,
 }
 
 impl Drop for RawBuffer {
     fn drop(&mut self) {
         #[cfg_attr(not(feature = "device"), allow(irrefutable_let_patterns))]
         if let RawSliceInner::Host(slice) = self.inner {
-            let ptr = slice.ptr;
-            let len = slice.len / self.width;
-            let cap = self.cap / self.width;
-            unsafe {
-                match self.width {
-                    1 => {
-                        Vec::<u8>::from_raw_parts(ptr as _, len, cap);
-                    }
-                    2 => {
-                        Vec::<u16>::from_raw_parts(ptr as _, len, cap);
-                    }
-                    4 => {
-                        Vec::<u32>::from_raw_parts(ptr as _, len, cap);
-                    }
-                    8 => {
-                        Vec::<u64>::from_raw_parts(ptr as _, len, cap);
-                    }
-                    _ => unreachable!(),
+            if self.cap > 0 {
+                // Vec<T> is allocated with the size and alignment of T
+                unsafe {
+                    dealloc(
+                        slice.ptr,
+                        Layout::from_size_align_unchecked(self.cap, self.align),
+                    );
                 }
             }
         }
@@ -320,11 +342,11 @@ pub struct ScalarBufferRepr {
     scalar_type: ScalarType,
 }
 
-impl<T: Scalar> From<BufferRepr<T>> for ScalarBufferRepr {
+impl<T: Element> From<BufferRepr<T>> for ScalarBufferRepr {
     fn from(buffer: BufferRepr<T>) -> Self {
         Self {
             raw: buffer.raw,
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
         }
     }
 }
@@ -398,17 +420,17 @@ impl<'a> ScalarSliceRepr<'a> {
     }
     fn slice(self, range: impl RangeBounds<usize>) -> Option<Self> {
         Some(Self {
-            raw: self.raw.slice(range, self.scalar_type)?,
+            raw: self.raw.slice(range, self.scalar_type.size())?,
             ..self
         })
     }
 }
 
-impl<'a, T: Scalar> From<SliceRepr<'a, T>> for ScalarSliceRepr<'a> {
+impl<'a, T: Element> From<SliceRepr<'a, T>> for ScalarSliceRepr<'a> {
     fn from(slice: SliceRepr<'a, T>) -> Self {
         Self {
             raw: slice.raw,
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         }
     }
@@ -430,50 +452,8 @@ impl Serialize for ScalarSliceRepr<'_> {
     where
         S: Serializer,
     {
-        use serde::ser::{Error, SerializeSeq, SerializeTupleStruct};
-
-        let scalar_type = self.scalar_type;
-        let slice: ScalarCowBufferRepr = if self.device().is_host() {
-            self.clone().into()
-        } else {
-            self.to_device(Device::host())
-                .map_err(S::Error::custom)?
-                .into()
-        };
-        let slice = slice.as_scalar_slice();
-        let slice = SliceRepr::<u8>::try_from(slice.bitcast(ScalarType::U8).unwrap())
-            .ok()
-            .unwrap();
-        let data = BufferData {
-            bytes: slice.as_host_slice().unwrap(),
-        };
-        let mut tuple_struct = serializer.serialize_tuple_struct("Buffer", 3)?;
-        tuple_struct.serialize_field(&scalar_type)?;
-        tuple_struct.serialize_field(&self.len())?;
-        tuple_struct.serialize_field(&data)?;
-
-        struct BufferData<'a> {
-            bytes: &'a [u8],
-        }
-
-        impl Serialize for BufferData<'_> {
-            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-            where
-                S: Serializer,
-            {
-                let len = self.bytes.len();
-                let items = len / 8 + (len % 8 != 0) as usize;
-                let mut seq = serializer.serialize_seq(Some(items))?;
-                for chunk in self.bytes.chunks(8) {
-                    let mut item = 0u64;
-                    bytemuck::bytes_of_mut(&mut item)[..chunk.len()].copy_from_slice(chunk);
-                    seq.serialize_element(&item.to_be())?;
-                }
-                seq.end()
-            }
-        }
-
-        tuple_struct.end()
+        let slice = ScalarSlice { data: self.clone() };
+        stream::serialize_scalar_slice(slice, false, serializer)
     }
 }
 
@@ -483,97 +463,7 @@ impl<'de> Deserialize<'de> for ScalarBufferRepr {
     where
         D: Deserializer<'de>,
     {
-        use serde::de::{DeserializeSeed, Error, SeqAccess, Visitor};
-
-        struct BufferVisitor;
-
-        impl<'de> Visitor<'de> for BufferVisitor {
-            type Value = ScalarBufferRepr;
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                write!(formatter, "struct Buffer")
-            }
-            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-            where
-                A: SeqAccess<'de>,
-            {
-                let scalar_type = if let Some(scalar_type) = seq.next_element::<ScalarType>()? {
-                    scalar_type
-                } else {
-                    return Err(A::Error::custom("expected ScalarType"));
-                };
-                let len = if let Some(len) = seq.next_element::<usize>()? {
-                    len
-                } else {
-                    return Err(A::Error::custom("expected usize"));
-                };
-                let visitor = BufferDataVisitor { scalar_type, len };
-                if let Some(buffer) = seq.next_element_seed(visitor)? {
-                    Ok(buffer)
-                } else {
-                    Err(A::Error::custom("expected sequence"))
-                }
-            }
-        }
-
-        struct BufferDataVisitor {
-            scalar_type: ScalarType,
-            len: usize,
-        }
-
-        impl BufferDataVisitor {
-            fn parse<'de, T: Scalar, A>(self, mut seq: A) -> Result<ScalarBufferRepr, A::Error>
-            where
-                A: SeqAccess<'de>,
-            {
-                let Self { scalar_type, len } = self;
-                let mut data = Vec::<T>::with_capacity(len);
-                while data.len() < len {
-                    if let Some(item) = seq.next_element::<u64>()? {
-                        let item = u64::from_be(item);
-                        data.extend(bytemuck::cast_slice(&[item]));
-                    } else {
-                        break;
-                    }
-                }
-                if data.len() != len {
-                    return Err(A::Error::invalid_length(
-                        data.len(),
-                        &len.to_string().as_str(),
-                    ));
-                }
-                assert_eq!(size_of::<T>(), scalar_type.size());
-                let raw = BufferRepr::from_vec(data).raw;
-                Ok(ScalarBufferRepr { raw, scalar_type })
-            }
-        }
-
-        impl<'de> Visitor<'de> for BufferDataVisitor {
-            type Value = ScalarBufferRepr;
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                write!(formatter, "buffer data")
-            }
-            fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
-            where
-                A: SeqAccess<'de>,
-            {
-                match self.scalar_type.size() {
-                    4 => self.parse::<u32, _>(seq),
-                    _ => todo!(),
-                }
-            }
-        }
-
-        impl<'de> DeserializeSeed<'de> for BufferDataVisitor {
-            type Value = ScalarBufferRepr;
-            fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-            where
-                D: Deserializer<'de>,
-            {
-                deserializer.deserialize_seq(self)
-            }
-        }
-
-        deserializer.deserialize_tuple_struct("Buffer", 3, BufferVisitor)
+        stream::deserialize_scalar_buffer(deserializer, Device::host(), None, false)
     }
 }
 
@@ -599,7 +489,7 @@ impl<'a> ScalarSliceMutRepr<'a> {
     }
     fn slice(self, range: impl RangeBounds<usize>) -> Option<Self> {
         Some(Self {
-            raw: self.raw.slice(range, self.scalar_type)?,
+            raw: self.raw.slice(range, self.scalar_type.size())?,
             ..self
         })
     }
@@ -613,7 +503,7 @@ impl<'a> ScalarSliceMutRepr<'a> {
         }
         macro_wrap!(paste! {
             match self.scalar_type() {
-                macro_for!($T in  [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+                macro_for!($T in  [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
                     ScalarType::[<$T:upper>] => {
                         SliceMutRepr::<$T>::try_from(self.as_scalar_slice_mut())
                             .ok()
@@ -627,11 +517,11 @@ impl<'a> ScalarSliceMutRepr<'a> {
     }
 }
 
-impl<'a, T: Scalar> From<SliceMutRepr<'a, T>> for ScalarSliceMutRepr<'a> {
+impl<'a, T: Element> From<SliceMutRepr<'a, T>> for ScalarSliceMutRepr<'a> {
     fn from(slice: SliceMutRepr<'a, T>) -> Self {
         Self {
             raw: slice.raw,
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         }
     }
@@ -670,11 +560,11 @@ impl From<ScalarBufferRepr> for ScalarArcBufferRepr {
     }
 }
 
-impl<T: Scalar> From<ArcBufferRepr<T>> for ScalarArcBufferRepr {
+impl<T: Element> From<ArcBufferRepr<T>> for ScalarArcBufferRepr {
     fn from(buffer: ArcBufferRepr<T>) -> Self {
         Self {
             raw: buffer.raw,
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
         }
     }
 }
@@ -763,7 +653,7 @@ impl<'a> From<ScalarBufferRepr> for ScalarCowBufferRepr<'a> {
     }
 }
 
-impl<'a, T: Scalar> From<CowBufferRepr<'a, T>> for ScalarCowBufferRepr<'a> {
+impl<'a, T: Element> From<CowBufferRepr<'a, T>> for ScalarCowBufferRepr<'a> {
     fn from(buffer: CowBufferRepr<'a, T>) -> Self {
         match buffer {
             CowBufferRepr::Borrowed(slice) => Self::Borrowed(slice.into()),
@@ -863,7 +753,7 @@ impl<S: ScalarDataOwned> ScalarBufferBase<S> {
     pub unsafe fn uninit(device: Device, len: usize, scalar_type: ScalarType) -> Result<Self> {
         macro_wrap!(paste! {
             match scalar_type {
-                macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+                macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
                     ScalarType::[<$T:upper>] => Ok(unsafe { Buffer::<$T>::uninit(device, len)? }.into()),
                 })
                 _ => unreachable!(),
@@ -884,7 +774,7 @@ impl<S: ScalarDataOwned> ScalarBufferBase<S> {
     pub fn from_elem(device: Device, len: usize, elem: ScalarElem) -> Result<Self> {
         macro_wrap!(paste! {
             match elem {
-                macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+                macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
                   ScalarElem::[<$T:upper>](elem) => Ok(Buffer::from_elem(device, len, elem)?.into()),
                 })
                 _ => unreachable!(),
@@ -1026,7 +916,7 @@ impl<S: ScalarData> ScalarBufferBase<S> {
     pub fn to_device(&self, device: Device) -> Result<ScalarBuffer> {
         let slice = self.as_scalar_slice();
         macro_wrap!(paste! { match slice.scalar_type() {
-            macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
                 ScalarType::[<$T:upper>] => Ok(Slice::<$T>::try_from(slice).ok().unwrap().to_device(device)?.into()),
             })
             _ => unreachable!(),
@@ -1063,7 +953,7 @@ impl<S: ScalarData> ScalarBufferBase<S> {
     {
         let slice = self.as_scalar_slice_mut();
         macro_wrap!(paste! { match elem {
-            macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
                 ScalarElem::[<$T:upper>](elem) => {
                     SliceMut::<$T>::try_from(slice).ok().unwrap().fill(elem)?;
                 }
@@ -1076,11 +966,11 @@ impl<S: ScalarData> ScalarBufferBase<S> {
 
     See [`BufferBase::cast`]. */
     pub fn cast(&self, scalar_type: ScalarType) -> Result<ScalarBuffer> {
-        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
             if let Ok(x) = Slice::<$X>::try_from(self.as_scalar_slice()) {
                 macro_wrap!(paste! {
                     match scalar_type {
-                        macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+                        macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
                             ScalarType::[<$Y:upper>] => {
                                 return x.cast::<$Y>().map(Into::into);
                             }
@@ -1181,29 +1071,29 @@ impl ScalarSliceMut<'_> {
     }
 }
 
-impl<T: Scalar, S: ScalarDataOwned> From<Buffer<T>> for ScalarBufferBase<S> {
+impl<T: Element, S: ScalarDataOwned> From<Buffer<T>> for ScalarBufferBase<S> {
     fn from(buffer: Buffer<T>) -> Self {
         let data = S::from_scalar_buffer(ScalarBufferRepr::from(buffer.data));
         Self { data }
     }
 }
 
-impl<'a, T: Scalar> From<Slice<'a, T>> for ScalarSlice<'a> {
+impl<'a, T: Element> From<Slice<'a, T>> for ScalarSlice<'a> {
     fn from(slice: Slice<'a, T>) -> Self {
         let data = ScalarSliceRepr {
             raw: slice.data.raw,
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         };
         Self { data }
     }
 }
 
-impl<'a, T: Scalar> From<SliceMut<'a, T>> for ScalarSliceMut<'a> {
+impl<'a, T: Element> From<SliceMut<'a, T>> for ScalarSliceMut<'a> {
     fn from(slice: SliceMut<'a, T>) -> Self {
         let data = ScalarSliceMutRepr {
             raw: slice.data.raw,
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         };
         Self { data }
@@ -1218,7 +1108,7 @@ impl From<ScalarBuffer> for ScalarArcBuffer {
     }
 }
 
-impl<T: Scalar> From<ArcBuffer<T>> for ScalarArcBuffer {
+impl<T: Element> From<ArcBuffer<T>> for ScalarArcBuffer {
     fn from(buffer: ArcBuffer<T>) -> Self {
         Self {
             data: buffer.data.into(),
@@ -1242,7 +1132,7 @@ impl<'a> From<ScalarSlice<'a>> for ScalarCowBuffer<'a> {
     }
 }
 
-impl<'a, T: Scalar> From<CowBuffer<'a, T>> for ScalarCowBuffer<'a> {
+impl<'a, T: Element> From<CowBuffer<'a, T>> for ScalarCowBuffer<'a> {
     fn from(buffer: CowBuffer<'a, T>) -> Self {
         Self {
             data: buffer.data.into(),
@@ -1283,21 +1173,21 @@ impl<'de, S: ScalarDataOwned> Deserialize<'de> for ScalarBufferBase<S> {
 }
 
 macro_for!($S in [BufferRepr, ArcBufferRepr] {
-    impl<T: Scalar> Sealed for $S<T> {}
-    unsafe impl<T: Scalar> Send for $S<T> {}
-    unsafe impl<T: Scalar> Sync for $S<T> {}
+    impl<T: Element> Sealed for $S<T> {}
+    unsafe impl<T: Element> Send for $S<T> {}
+    unsafe impl<T: Element> Sync for $S<T> {}
 });
 
 macro_for!($S in [SliceRepr, SliceMutRepr, CowBufferRepr] {
-    impl<T: Scalar> Sealed for $S<'_, T> {}
-    unsafe impl<T: Scalar> Send for $S<'_, T> {}
-    unsafe impl<T: Scalar> Sync for $S<'_, T> {}
+    impl<T: Element> Sealed for $S<'_, T> {}
+    unsafe impl<T: Element> Send for $S<'_, T> {}
+    unsafe impl<T: Element> Sync for $S<'_, T> {}
 });
 
 /// Marker trait for buffers.
 pub trait Data: ScalarData {
     /// The type of the buffer.
-    type Elem: Scalar;
+    type Elem: Element;
     #[doc(hidden)]
     fn as_slice(&self) -> SliceRepr<Self::Elem>;
     #[doc(hidden)]
@@ -1358,7 +1248,7 @@ pub struct BufferRepr<T> {
     _m: PhantomData<T>,
 }
 
-impl<T: Scalar> BufferRepr<T> {
+impl<T: Element> BufferRepr<T> {
     fn from_vec(vec: Vec<T>) -> Self {
         let width = std::mem::size_of::<T>();
         let ptr = vec.as_ptr() as *mut u8;
@@ -1371,6 +1261,7 @@ impl<T: Scalar> BufferRepr<T> {
             },
             cap,
             width,
+            align: align_of::<T>(),
         };
         Self {
             raw,
@@ -1398,6 +1289,7 @@ impl<T: Scalar> BufferRepr<T> {
                     },
                     cap,
                     width,
+                    align: align_of::<T>(),
                 };
                 Ok(Self {
                     raw,
@@ -1410,7 +1302,7 @@ impl<T: Scalar> BufferRepr<T> {
         match self.raw.inner {
             RawSliceInner::Host(raw) => {
                 let width = size_of::<T>();
-                if width == self.raw.width {
+                if width == self.raw.width && align_of::<T>() == self.raw.align {
                     let vec = unsafe {
                         Vec::from_raw_parts(raw.ptr as _, raw.len / width, self.raw.cap / width)
                     };
@@ -1429,27 +1321,27 @@ impl<T: Scalar> BufferRepr<T> {
     }
 }
 
-impl<T: Scalar> ScalarData for BufferRepr<T> {
+impl<T: Element> ScalarData for BufferRepr<T> {
     fn as_scalar_slice(&self) -> ScalarSliceRepr {
         ScalarSliceRepr {
             raw: self.raw.clone(),
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         }
     }
 }
 
-impl<T: Scalar> ScalarDataMut for BufferRepr<T> {
+impl<T: Element> ScalarDataMut for BufferRepr<T> {
     fn as_scalar_slice_mut(&mut self) -> ScalarSliceMutRepr {
         ScalarSliceMutRepr {
             raw: self.raw.clone(),
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         }
     }
 }
 
-impl<T: Scalar> Data for BufferRepr<T> {
+impl<T: Element> Data for BufferRepr<T> {
     type Elem = T;
     fn as_slice(&self) -> SliceRepr<Self::Elem> {
         SliceRepr {
@@ -1468,7 +1360,7 @@ impl<T: Scalar> Data for BufferRepr<T> {
     }
 }
 
-impl<T: Scalar> DataMut for BufferRepr<T> {
+impl<T: Element> DataMut for BufferRepr<T> {
     fn as_slice_mut(&mut self) -> SliceMutRepr<Self::Elem> {
         SliceMutRepr {
             raw: self.raw.slice.clone(),
@@ -1477,7 +1369,7 @@ impl<T: Scalar> DataMut for BufferRepr<T> {
     }
 }
 
-impl<T: Scalar> DataOwned for BufferRepr<T> {
+impl<T: Element> DataOwned for BufferRepr<T> {
     fn from_buffer(buffer: Self) -> Self {
         buffer
     }
@@ -1486,10 +1378,10 @@ impl<T: Scalar> DataOwned for BufferRepr<T> {
     }
 }
 
-impl<T: Scalar> TryFrom<ScalarBufferRepr> for BufferRepr<T> {
+impl<T: Element> TryFrom<ScalarBufferRepr> for BufferRepr<T> {
     type Error = ScalarBufferRepr;
     fn try_from(buffer: ScalarBufferRepr) -> Result<Self, Self::Error> {
-        if buffer.scalar_type() == T::SCALAR_TYPE {
+        if buffer.scalar_type() == T::LAYOUT.scalar_type && buffer.raw.is_element_slice::<T>() {
             Ok(Self {
                 raw: buffer.raw,
                 _m: Default::default(),
@@ -1507,7 +1399,7 @@ pub struct SliceRepr<'a, T> {
     _m: PhantomData<&'a T>,
 }
 
-impl<'a, T: Scalar> SliceRepr<'a, T> {
+impl<'a, T: Element> SliceRepr<'a, T> {
     fn from_host_slice(host_slice: &'a [T]) -> Self {
         let ptr = host_slice.as_ptr() as *mut u8;
         let len = std::mem::size_of_val(host_slice);
@@ -1530,6 +1422,9 @@ impl<'a, T: Scalar> SliceRepr<'a, T> {
             _ => None,
         }
     }
+    fn len(&self) -> usize {
+        self.raw.len() / size_of::<T>()
+    }
     fn to_vec(&self) -> Result<Vec<T>> {
         if let Some(slice) = self.as_host_slice() {
             Ok(slice.to_vec())
@@ -1554,33 +1449,33 @@ impl<'a, T: Scalar> SliceRepr<'a, T> {
     }
     fn slice(self, range: impl RangeBounds<usize>) -> Option<Self> {
         Some(Self {
-            raw: self.raw.slice(range, T::SCALAR_TYPE)?,
+            raw: self.raw.slice(range, size_of::<T>())?,
             ..self
         })
     }
 }
 
-impl<'a, T: Scalar> ScalarData for SliceRepr<'a, T> {
+impl<'a, T: Element> ScalarData for SliceRepr<'a, T> {
     fn as_scalar_slice(&self) -> ScalarSliceRepr {
         ScalarSliceRepr {
             raw: self.raw.clone(),
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         }
     }
 }
 
-impl<T: Scalar> Data for SliceRepr<'_, T> {
+impl<T: Element> Data for SliceRepr<'_, T> {
     type Elem = T;
     fn as_slice(&self) -> SliceRepr<T> {
         self.clone()
     }
 }
 
-impl<'a, T: Scalar> TryFrom<ScalarSliceRepr<'a>> for SliceRepr<'a, T> {
+impl<'a, T: Element> TryFrom<ScalarSliceRepr<'a>> for SliceRepr<'a, T> {
     type Error = ScalarSliceRepr<'a>;
     fn try_from(slice: ScalarSliceRepr<'a>) -> Result<Self, Self::Error> {
-        if slice.scalar_type() == T::SCALAR_TYPE {
+        if slice.scalar_type() == T::LAYOUT.scalar_type && slice.raw.is_element_slice::<T>() {
             Ok(Self {
                 raw: slice.raw,
                 _m: Default::default(),
@@ -1597,7 +1492,7 @@ pub struct SliceMutRepr<'a, T> {
     _m: PhantomData<&'a T>,
 }
 
-impl<'a, T: Scalar> SliceMutRepr<'a, T> {
+impl<'a, T: Element> SliceMutRepr<'a, T> {
     fn from_host_slice_mut(host_slice: &'a mut [T]) -> Self {
         let ptr = host_slice.as_ptr() as *mut u8;
         let len = std::mem::size_of_val(host_slice);
@@ -1621,6 +1516,9 @@ impl<'a, T: Scalar> SliceMutRepr<'a, T> {
             _ => None,
         }
     }
+    fn len(&self) -> usize {
+        self.raw.len() / size_of::<T>()
+    }
     fn copy_from_slice(&mut self, src: &SliceRepr<T>) -> Result<()> {
         if self.len() != src.len() {
             bail!(
@@ -1649,12 +1547,14 @@ impl<'a, T: Scalar> SliceMutRepr<'a, T> {
                 if dst.device() != src_buffer.device() {
                     return src_buffer.transfer(dst);
                 }
-                Slice { data: src.clone() }.cast_impl(&mut SliceMut::<T> {
-                    data: SliceMutRepr {
-                        raw: self.raw.clone(),
-                        _m: PhantomData,
+                device_scalar_buffer_cast_impl(
+                    ScalarSlice {
+                        data: src.as_scalar_slice(),
                     },
-                })
+                    ScalarSliceMut {
+                        data: self.as_scalar_slice_mut(),
+                    },
+                )
             }
         }
     }
@@ -1667,33 +1567,33 @@ impl<'a, T: Scalar> SliceMutRepr<'a, T> {
     }
     fn slice(self, range: impl RangeBounds<usize>) -> Option<Self> {
         Some(Self {
-            raw: self.raw.slice(range, T::SCALAR_TYPE)?,
+            raw: self.raw.slice(range, size_of::<T>())?,
             ..self
         })
     }
 }
 
-impl<T: Scalar> ScalarData for SliceMutRepr<'_, T> {
+impl<T: Element> ScalarData for SliceMutRepr<'_, T> {
     fn as_scalar_slice(&self) -> ScalarSliceRepr {
         ScalarSliceRepr {
             raw: self.raw.clone(),
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         }
     }
 }
 
-impl<T: Scalar> ScalarDataMut for SliceMutRepr<'_, T> {
+impl<T: Element> ScalarDataMut for SliceMutRepr<'_, T> {
     fn as_scalar_slice_mut(&mut self) -> ScalarSliceMutRepr {
         ScalarSliceMutRepr {
             raw: self.raw.clone(),
-            scalar_type: T::SCALAR_TYPE,
+            scalar_type: T::LAYOUT.scalar_type,
             _m: PhantomData,
         }
     }
 }
 
-impl<T: Scalar> Data for SliceMutRepr<'_, T> {
+impl<T: Element> Data for SliceMutRepr<'_, T> {
     type Elem = T;
     fn as_slice(&self) -> SliceRepr<T> {
         SliceRepr {
@@ -1703,7 +1603,7 @@ impl<T: Scalar> Data for SliceMutRepr<'_, T> {
     }
 }
 
-impl<T: Scalar> DataMut for SliceMutRepr<'_, T> {
+impl<T: Element> DataMut for SliceMutRepr<'_, T> {
     fn as_slice_mut(&mut self) -> SliceMutRepr<T> {
         SliceMutRepr {
             raw: self.raw.clone(),
@@ -1712,10 +1612,10 @@ impl<T: Scalar> DataMut for SliceMutRepr<'_, T> {
     }
 }
 
-impl<'a, T: Scalar> TryFrom<ScalarSliceMutRepr<'a>> for SliceMutRepr<'a, T> {
+impl<'a, T: Element> TryFrom<ScalarSliceMutRepr<'a>> for SliceMutRepr<'a, T> {
     type Error = ScalarSliceMutRepr<'a>;
     fn try_from(slice: ScalarSliceMutRepr<'a>) -> Result<Self, Self::Error> {
-        if slice.scalar_type() == T::SCALAR_TYPE {
+        if slice.scalar_type() == T::LAYOUT.scalar_type && slice.raw.is_element_slice::<T>() {
             Ok(Self {
                 raw: slice.raw,
                 _m: Default::default(),
@@ -1733,7 +1633,7 @@ pub struct ArcBufferRepr<T> {
     _m: PhantomData<T>,
 }
 
-impl<T: Scalar> From<BufferRepr<T>> for ArcBufferRepr<T> {
+impl<T: Element> From<BufferRepr<T>> for ArcBufferRepr<T> {
     fn from(buffer: BufferRepr<T>) -> Self {
         Self {
             #[allow(clippy::arc_with_non_send_sync)]
@@ -1743,10 +1643,10 @@ impl<T: Scalar> From<BufferRepr<T>> for ArcBufferRepr<T> {
     }
 }
 
-impl<T: Scalar> TryFrom<ScalarArcBufferRepr> for ArcBufferRepr<T> {
+impl<T: Element> TryFrom<ScalarArcBufferRepr> for ArcBufferRepr<T> {
     type Error = ScalarArcBufferRepr;
     fn try_from(buffer: ScalarArcBufferRepr) -> Result<Self, Self::Error> {
-        if T::SCALAR_TYPE == buffer.scalar_type {
+        if T::LAYOUT.scalar_type == buffer.scalar_type && buffer.raw.is_element_slice::<T>() {
             Ok(Self {
                 raw: buffer.raw,
                 _m: PhantomData,
@@ -1757,7 +1657,7 @@ impl<T: Scalar> TryFrom<ScalarArcBufferRepr> for ArcBufferRepr<T> {
     }
 }
 
-impl<T: Scalar> ScalarData for ArcBufferRepr<T> {
+impl<T: Element> ScalarData for ArcBufferRepr<T> {
     fn as_scalar_slice(&self) -> ScalarSliceRepr {
         self.as_slice().into()
     }
@@ -1775,7 +1675,7 @@ impl<T: Scalar> ScalarData for ArcBufferRepr<T> {
     }
 }
 
-impl<T: Scalar> Data for ArcBufferRepr<T> {
+impl<T: Element> Data for ArcBufferRepr<T> {
     type Elem = T;
     fn as_slice(&self) -> SliceRepr<T> {
         SliceRepr {
@@ -1813,7 +1713,7 @@ impl<T: Scalar> Data for ArcBufferRepr<T> {
     }
 }
 
-impl<T: Scalar> DataOwned for ArcBufferRepr<T> {
+impl<T: Element> DataOwned for ArcBufferRepr<T> {
     fn from_buffer(buffer: BufferRepr<T>) -> Self {
         Self {
             #[allow(clippy::arc_with_non_send_sync)]
@@ -1859,7 +1759,7 @@ impl<'a, T> From<BufferRepr<T>> for CowBufferRepr<'a, T> {
     }
 }
 
-impl<'a, T: Scalar> TryFrom<ScalarCowBufferRepr<'a>> for CowBufferRepr<'a, T> {
+impl<'a, T: Element> TryFrom<ScalarCowBufferRepr<'a>> for CowBufferRepr<'a, T> {
     type Error = ScalarCowBufferRepr<'a>;
     fn try_from(buffer: ScalarCowBufferRepr<'a>) -> Result<Self, Self::Error> {
         match buffer {
@@ -1873,7 +1773,7 @@ impl<'a, T: Scalar> TryFrom<ScalarCowBufferRepr<'a>> for CowBufferRepr<'a, T> {
     }
 }
 
-impl<'a, T: Scalar> ScalarData for CowBufferRepr<'a, T> {
+impl<'a, T: Element> ScalarData for CowBufferRepr<'a, T> {
     fn as_scalar_slice(&self) -> ScalarSliceRepr {
         self.as_slice().into()
     }
@@ -1885,7 +1785,7 @@ impl<'a, T: Scalar> ScalarData for CowBufferRepr<'a, T> {
     }
 }
 
-impl<'a, T: Scalar> Data for CowBufferRepr<'a, T> {
+impl<'a, T: Element> Data for CowBufferRepr<'a, T> {
     type Elem = T;
     fn as_slice(&self) -> SliceRepr<T> {
         match self {
@@ -1907,7 +1807,7 @@ impl<'a, T: Scalar> Data for CowBufferRepr<'a, T> {
     }
 }
 
-impl<'a, T: Scalar> DataOwned for CowBufferRepr<'a, T> {
+impl<'a, T: Element> DataOwned for CowBufferRepr<'a, T> {
     fn from_buffer(buffer: BufferRepr<T>) -> Self {
         Self::Owned(buffer)
     }
@@ -1956,25 +1856,25 @@ Like [`Cow`](::std::borrow::Cow), can be created from a [`Slice`] or [`Buffer`].
 See [`BufferBase`]. */
 pub type CowBuffer<'a, T> = BufferBase<CowBufferRepr<'a, T>>;
 
-impl<T: Scalar, S: DataOwned<Elem = T>> From<Vec<T>> for BufferBase<S> {
+impl<T: Element, S: DataOwned<Elem = T>> From<Vec<T>> for BufferBase<S> {
     fn from(vec: Vec<T>) -> Self {
         Self::from_vec(vec)
     }
 }
 
-impl<'a, T: Scalar> From<&'a [T]> for Slice<'a, T> {
+impl<'a, T: Element> From<&'a [T]> for Slice<'a, T> {
     fn from(host_slice: &'a [T]) -> Self {
         Self::from_host_slice(host_slice)
     }
 }
 
-impl<'a, T: Scalar> From<&'a mut [T]> for SliceMut<'a, T> {
+impl<'a, T: Element> From<&'a mut [T]> for SliceMut<'a, T> {
     fn from(host_slice: &'a mut [T]) -> Self {
         Self::from_host_slice_mut(host_slice)
     }
 }
 
-impl<T: Scalar> TryFrom<ScalarBuffer> for Buffer<T> {
+impl<T: Element> TryFrom<ScalarBuffer> for Buffer<T> {
     type Error = ScalarBuffer;
     fn try_from(buffer: ScalarBuffer) -> Result<Self, Self::Error> {
         match buffer.data.try_into() {
@@ -1984,7 +1884,7 @@ impl<T: Scalar> TryFrom<ScalarBuffer> for Buffer<T> {
     }
 }
 
-impl<'a, T: Scalar> TryFrom<ScalarSlice<'a>> for Slice<'a, T> {
+impl<'a, T: Element> TryFrom<ScalarSlice<'a>> for Slice<'a, T> {
     type Error = ScalarSlice<'a>;
     fn try_from(slice: ScalarSlice<'a>) -> Result<Self, Self::Error> {
         match slice.data.try_into() {
@@ -1994,7 +1894,7 @@ impl<'a, T: Scalar> TryFrom<ScalarSlice<'a>> for Slice<'a, T> {
     }
 }
 
-impl<'a, T: Scalar> TryFrom<ScalarSliceMut<'a>> for SliceMut<'a, T> {
+impl<'a, T: Element> TryFrom<ScalarSliceMut<'a>> for SliceMut<'a, T> {
     type Error = ScalarSliceMut<'a>;
     fn try_from(slice: ScalarSliceMut<'a>) -> Result<Self, Self::Error> {
         match slice.data.try_into() {
@@ -2004,7 +1904,7 @@ impl<'a, T: Scalar> TryFrom<ScalarSliceMut<'a>> for SliceMut<'a, T> {
     }
 }
 
-impl<T: Scalar> TryFrom<ScalarArcBuffer> for ArcBuffer<T> {
+impl<T: Element> TryFrom<ScalarArcBuffer> for ArcBuffer<T> {
     type Error = ScalarArcBuffer;
     fn try_from(buffer: ScalarArcBuffer) -> Result<Self, Self::Error> {
         match buffer.data.try_into() {
@@ -2014,7 +1914,7 @@ impl<T: Scalar> TryFrom<ScalarArcBuffer> for ArcBuffer<T> {
     }
 }
 
-impl<T: Scalar> From<Buffer<T>> for ArcBuffer<T> {
+impl<T: Element> From<Buffer<T>> for ArcBuffer<T> {
     fn from(buffer: Buffer<T>) -> Self {
         Self {
             data: buffer.data.into(),
@@ -2022,7 +1922,7 @@ impl<T: Scalar> From<Buffer<T>> for ArcBuffer<T> {
     }
 }
 
-impl<'a, T: Scalar> From<Slice<'a, T>> for CowBuffer<'a, T> {
+impl<'a, T: Element> From<Slice<'a, T>> for CowBuffer<'a, T> {
     fn from(slice: Slice<'a, T>) -> Self {
         Self {
             data: slice.data.into(),
@@ -2030,7 +1930,7 @@ impl<'a, T: Scalar> From<Slice<'a, T>> for CowBuffer<'a, T> {
     }
 }
 
-impl<T: Scalar> From<Buffer<T>> for CowBuffer<'_, T> {
+impl<T: Element> From<Buffer<T>> for CowBuffer<'_, T> {
     fn from(buffer: Buffer<T>) -> Self {
         Self {
             data: buffer.data.into(),
@@ -2038,7 +1938,7 @@ impl<T: Scalar> From<Buffer<T>> for CowBuffer<'_, T> {
     }
 }
 
-impl<'a, T: Scalar> TryFrom<ScalarCowBuffer<'a>> for CowBuffer<'a, T> {
+impl<'a, T: Element> TryFrom<ScalarCowBuffer<'a>> for CowBuffer<'a, T> {
     type Error = ScalarCowBuffer<'a>;
     fn try_from(buffer: ScalarCowBuffer<'a>) -> Result<Self, Self::Error> {
         match buffer.data.try_into() {
@@ -2048,13 +1948,13 @@ impl<'a, T: Scalar> TryFrom<ScalarCowBuffer<'a>> for CowBuffer<'a, T> {
     }
 }
 
-impl<T: Scalar, S: DataOwned<Elem = T>> Default for BufferBase<S> {
+impl<T: Element, S: DataOwned<Elem = T>> Default for BufferBase<S> {
     fn default() -> Self {
-        Self::zeros(Device::host(), 0).unwrap()
+        Self::from_vec(Vec::new())
     }
 }
 
-impl<T: Scalar, S: DataOwned<Elem = T>> BufferBase<S> {
+impl<T: Element, S: DataOwned<Elem = T>> BufferBase<S> {
     /// Allocate a buffer.
     ///
     /// # Safety
@@ -2070,6 +1970,19 @@ impl<T: Scalar, S: DataOwned<Elem = T>> BufferBase<S> {
         let data = S::from_buffer(unsafe { BufferRepr::uninit(device, len)? });
         Ok(Self { data })
     }
+    /// Create a buffer from a [`Vec`].
+    pub fn from_vec(vec: Vec<T>) -> Self {
+        let data = S::from_buffer(BufferRepr::from_vec(vec));
+        Self { data }
+    }
+    /// Create a buffer from a [`Buffer`].
+    pub fn from_buffer(buffer: Buffer<T>) -> Self {
+        let data = S::from_buffer(buffer.data);
+        Self { data }
+    }
+}
+
+impl<T: Scalar, S: DataOwned<Elem = T>> BufferBase<S> {
     /** Create a buffer filled with `elem`
 
     # Errors
@@ -2098,19 +2011,9 @@ impl<T: Scalar, S: DataOwned<Elem = T>> BufferBase<S> {
     pub fn ones(device: Device, len: usize) -> Result<Self> {
         Self::from_elem(device, len, T::one())
     }
-    /// Create a buffer from a [`Vec`].
-    pub fn from_vec(vec: Vec<T>) -> Self {
-        let data = S::from_buffer(BufferRepr::from_vec(vec));
-        Self { data }
-    }
-    /// Create a buffer from a [`Buffer`].
-    pub fn from_buffer(buffer: Buffer<T>) -> Self {
-        let data = S::from_buffer(buffer.data);
-        Self { data }
-    }
 }
 
-impl<'a, T: Scalar> Slice<'a, T> {
+impl<'a, T: Element> Slice<'a, T> {
     /// Create a slice from a `&[T]`.
     pub fn from_host_slice(host_slice: &'a [T]) -> Self {
         let data = SliceRepr::from_host_slice(host_slice);
@@ -2118,7 +2021,7 @@ impl<'a, T: Scalar> Slice<'a, T> {
     }
 }
 
-impl<'a, T: Scalar> SliceMut<'a, T> {
+impl<'a, T: Element> SliceMut<'a, T> {
     /// Create a mutable slice from a `&mut [T]`.
     pub fn from_host_slice_mut(host_slice: &'a mut [T]) -> Self {
         let data = SliceMutRepr::from_host_slice_mut(host_slice);
@@ -2126,7 +2029,7 @@ impl<'a, T: Scalar> SliceMut<'a, T> {
     }
 }
 
-impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
+impl<T: Element, S: Data<Elem = T>> BufferBase<S> {
     /// The device.
     pub fn device(&self) -> Device {
         self.data.device()
@@ -2137,7 +2040,7 @@ impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
     }
     /// The length.
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.data.as_slice().len()
     }
     /// Is the buffer empty.
     pub fn is_empty(&self) -> bool {
@@ -2215,7 +2118,8 @@ impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
     - [`OutOfDeviceMemory`]
     - Could not dispatch the kernel. */
     pub fn to_owned(&self) -> Result<Buffer<T>> {
-        self.cast()
+        let data = self.data.as_slice().to_buffer()?;
+        Ok(Buffer { data })
     }
     /** Moves into an arc buffer.
 
@@ -2301,6 +2205,60 @@ impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
     pub fn to_vec(&self) -> Result<Vec<T>> {
         self.data.as_slice().to_vec()
     }
+    /** Reinterpret as a slice with type `Y`.
+
+    See [`bytemuck::cast_slice`]. */
+    pub fn bitcast<Y: Scalar>(&self) -> Result<Slice<Y>, bytemuck::PodCastError> {
+        let data = self.data.as_slice().bitcast()?;
+        Ok(Slice { data })
+    }
+    /** Reinterpret as a mutable slice with type `Y`.
+
+    See [`bytemuck::cast_slice_mut`]. */
+    pub fn bitcast_mut<Y: Scalar>(&mut self) -> Result<SliceMut<Y>, bytemuck::PodCastError>
+    where
+        S: DataMut,
+    {
+        let data = self.data.as_slice_mut().bitcast()?;
+        Ok(SliceMut { data })
+    }
+    /** Copies from src.
+
+    # Errors
+    - `src` is not the same length.
+    - [`DeviceLost`]
+    - The kernel could not be dispatched.
+    */
+    pub fn copy_from_slice(&mut self, src: &Slice<T>) -> Result<()>
+    where
+        S: DataMut,
+    {
+        self.data.as_slice_mut().copy_from_slice(&src.data)
+    }
+    /** A subslice with `range`.
+
+    Returns None if range is out of bounds.
+
+    See [`<[_]>::get()`](https://doc.rust-lang.org/std/primitive.slice.html#method.get). */
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Option<Slice<T>> {
+        let data = self.data.as_slice().slice(range)?;
+        Some(Slice { data })
+    }
+    /** A mutable subslice with `range`.
+
+    Returns None if range is out of bounds.
+
+    See [`<[_]>::get_mut()`](https://doc.rust-lang.org/std/primitive.slice.html#method.get_mut). */
+    pub fn slice_mut(&mut self, range: impl RangeBounds<usize>) -> Option<SliceMut<T>>
+    where
+        S: DataMut,
+    {
+        let data = self.data.as_slice_mut().slice(range)?;
+        Some(SliceMut { data })
+    }
+}
+
+impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
     /** Fills with `elem`.
 
     # Errors
@@ -2338,6 +2296,18 @@ impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
                 y
             }
             let device = self.device();
+            if T::SCALAR_TYPE.is_complex() {
+                // filled as vectors, so that INT64 is not required
+                return macro_wrap!(paste! { match elem.scalar_elem() {
+                    macro_for!($C in [c32, c64] {
+                        ScalarElem::[<$C:upper>](x) => {
+                            let y = SliceMut::<$C>::try_from(self.as_scalar_slice_mut()).ok().unwrap();
+                            kernels::[<fill_ $C>]::builder()?.build(device)?.dispatch(x, y)
+                        }
+                    })
+                    _ => unreachable!(),
+                }});
+            }
             let features = device.info().unwrap().features();
             if features.contains(Features::INT64) {
                 if let Ok(y) = self.bitcast_mut::<u64>() {
@@ -2409,57 +2379,6 @@ impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
             self.cast().map(Into::into)
         }
     }
-    /** Reinterpret as a slice with type `Y`.
-
-    See [`bytemuck::cast_slice`]. */
-    pub fn bitcast<Y: Scalar>(&self) -> Result<Slice<Y>, bytemuck::PodCastError> {
-        let data = self.data.as_slice().bitcast()?;
-        Ok(Slice { data })
-    }
-    /** Reinterpret as a mutable slice with type `Y`.
-
-    See [`bytemuck::cast_slice_mut`]. */
-    pub fn bitcast_mut<Y: Scalar>(&mut self) -> Result<SliceMut<Y>, bytemuck::PodCastError>
-    where
-        S: DataMut,
-    {
-        let data = self.data.as_slice_mut().bitcast()?;
-        Ok(SliceMut { data })
-    }
-    /** Copies from src.
-
-    # Errors
-    - `src` is not the same length.
-    - [`DeviceLost`]
-    - The kernel could not be dispatched.
-    */
-    pub fn copy_from_slice(&mut self, src: &Slice<T>) -> Result<()>
-    where
-        S: DataMut,
-    {
-        self.data.as_slice_mut().copy_from_slice(&src.data)
-    }
-    /** A subslice with `range`.
-
-    Returns None if range is out of bounds.
-
-    See [`<[_]>::get()`](https://doc.rust-lang.org/std/primitive.slice.html#method.get). */
-    pub fn slice(&self, range: impl RangeBounds<usize>) -> Option<Slice<T>> {
-        let data = self.data.as_slice().slice(range)?;
-        Some(Slice { data })
-    }
-    /** A mutable subslice with `range`.
-
-    Returns None if range is out of bounds.
-
-    See [`<[_]>::get_mut()`](https://doc.rust-lang.org/std/primitive.slice.html#method.get_mut). */
-    pub fn slice_mut(&mut self, range: impl RangeBounds<usize>) -> Option<SliceMut<T>>
-    where
-        S: DataMut,
-    {
-        let data = self.data.as_slice_mut().slice(range)?;
-        Some(SliceMut { data })
-    }
 }
 
 impl<T: Scalar> Slice<'_, T> {
@@ -2507,10 +2426,10 @@ impl<T: Scalar> Slice<'_, T> {
 
 #[cfg(feature = "device")]
 fn device_scalar_buffer_cast_impl(x: ScalarSlice, y: ScalarSliceMut) -> Result<()> {
-    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
         let x = match Slice::<$X>::try_from(x) {
             Ok(x) => {
-                macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+                macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
                     let y = match SliceMut::<$Y>::try_from(y) {
                         Ok(y) => {
                             let builder = paste! {
@@ -2557,18 +2476,8 @@ impl<'de, T: Scalar, S: DataOwned<Elem = T>> Deserialize<'de> for BufferBase<S>
     where
         D: Deserializer<'de>,
     {
-        use serde::de::Error;
-        let scalar_buffer = ScalarBufferRepr::deserialize(deserializer)?;
-        let buffer = BufferRepr::try_from(scalar_buffer).map_err(|e| {
-            D::Error::custom(format!(
-                "Expected {:?}, found {:?}!",
-                T::SCALAR_TYPE,
-                e.scalar_type()
-            ))
-        })?;
-        Ok(Self {
-            data: S::from_buffer(buffer),
-        })
+        use serde::de::DeserializeSeed;
+        BufferSeed::new(Device::host()).deserialize(deserializer)
     }
 }
 
@@ -2583,11 +2492,11 @@ mod kernels {
     #[cfg(target_arch = "spirv")]
     use krnl_core::{
         half::{bf16, f16},
-        scalar::Scalar,
+        scalar::{c32, c64, f8e4m3, f8e5m2, Scalar},
     };
     use paste::paste;
 
-    macro_for!($T in [u8, u16, u32, u64]  {
+    macro_for!($T in [u8, u16, u32, u64, c32, c64]  {
        paste! {
            #[kernel]
            pub fn [<fill_ $T>](x: $T, #[item] y: &mut $T) {
@@ -2596,8 +2505,8 @@ mod kernels {
        }
     });
 
-    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
-        macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
+        macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
             paste! {
                 #[kernel]
                 pub fn [<cast_ $X _ $Y>](#[item] x: $X, #[item] y: &mut $Y) {
@@ -2675,4 +2584,46 @@ mod tests {
             .unwrap();
         assert_eq!(x_vec, y_vec);
     }
+
+    #[test]
+    fn buffer_serde_bincode2_len() {
+        let x_vec: Vec<f16> = (0..11).map(|x| f16::from_f32(x as f32)).collect();
+        let bytes = bincode2::serialize(&Buffer::from_vec(x_vec.clone())).unwrap();
+        let y_vec = bincode2::deserialize::<Buffer<f16>>(&bytes)
+            .unwrap()
+            .into_vec()
+            .unwrap();
+        assert_eq!(x_vec, y_vec);
+    }
+
+    #[test]
+    fn buffer_serde_compressed() {
+        let x_vec: Vec<u8> = (0..1000).map(|x| (x % 7) as u8).collect();
+        let x = ScalarBuffer::from(Buffer::from_vec(x_vec.clone()));
+        let bytes = bincode2::serialize(&Compressed(&x)).unwrap();
+        assert!(bytes.len() < x_vec.len());
+        let Compressed(y) = bincode2::deserialize::<Compressed<ScalarBuffer>>(&bytes).unwrap();
+        let y_vec = Buffer::<u8>::try_from(y).unwrap().into_vec().unwrap();
+        assert_eq!(x_vec, y_vec);
+        let string = serde_json::to_string(&Compressed(&x)).unwrap();
+        let Compressed(y) = serde_json::from_str::<Compressed<Buffer<u8>>>(&string).unwrap();
+        assert_eq!(x_vec, y.into_vec().unwrap());
+    }
+
+    #[test]
+    fn buffer_serde_seed() {
+        use serde::de::DeserializeSeed;
+
+        let x_vec = vec![1u64, 2, 3];
+        let string = serde_json::to_string(&Buffer::from_vec(x_vec.clone())).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&string);
+        let y = BufferSeed::<Buffer<u64>>::new(Device::host())
+            .deserialize(&mut deserializer)
+            .unwrap();
+        assert_eq!(x_vec, y.into_vec().unwrap());
+        let mut deserializer = serde_json::Deserializer::from_str(&string);
+        assert!(BufferSeed::<Buffer<u32>>::new(Device::host())
+            .deserialize(&mut deserializer)
+            .is_err());
+    }
 }
@@ -0,0 +1,130 @@
+#[cfg(doc)]
+use super::error::{DeviceBufferTooLarge, DeviceLost, OutOfDeviceMemory};
+use super::{Buffer, BufferBase, Data, DataMut, Slice, SliceMut};
+use crate::{device::Device, scalar::Scalar};
+use anyhow::{bail, format_err, Error, Result};
+use ndarray::{Array, ArrayBase, ArrayView, ArrayViewMut, Dimension, ShapeBuilder};
+
+fn check_shape<D: Dimension>(dim: &D, len: usize) -> Result<()> {
+    if dim.size_checked() != Some(len) {
+        bail!("Expected shape with len {len}, found {:?}!", dim.slice());
+    }
+    Ok(())
+}
+
+impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
+    /** Borrows as an array view with `shape`.
+
+    The shape may be in standard (C) or Fortran order, ie `shape.f()`.
+
+    # Errors
+    - The buffer is not on the host.
+    - The product of the shape is not the length. */
+    pub fn as_array<Sh: ShapeBuilder>(&self, shape: Sh) -> Result<ArrayView<T, Sh::Dim>> {
+        let shape = shape.into_shape();
+        check_shape(shape.raw_dim(), self.len())?;
+        let slice = self
+            .as_host_slice()
+            .ok_or_else(|| format_err!("Expected host buffer, found {:?}!", self.device()))?;
+        Ok(ArrayView::from_shape(shape, slice)?)
+    }
+    /** Borrows as a mutable array view with `shape`.
+
+    See [`.as_array()`](BufferBase::as_array).
+
+    # Errors
+    - The buffer is not on the host.
+    - The product of the shape is not the length. */
+    pub fn as_array_mut<Sh: ShapeBuilder>(&mut self, shape: Sh) -> Result<ArrayViewMut<T, Sh::Dim>>
+    where
+        S: DataMut,
+    {
+        let shape = shape.into_shape();
+        check_shape(shape.raw_dim(), self.len())?;
+        let device = self.device();
+        let slice = self
+            .as_host_slice_mut()
+            .ok_or_else(|| format_err!("Expected host buffer, found {device:?}!"))?;
+        Ok(ArrayViewMut::from_shape(shape, slice)?)
+    }
+    /** Copies to an array on the host with `shape`.
+
+    The shape may be in standard (C) or Fortran order, ie `shape.f()`.
+
+    # Errors
+    - The product of the shape is not the length.
+    - [`DeviceLost`] */
+    pub fn to_array<Sh: ShapeBuilder>(&self, shape: Sh) -> Result<Array<T, Sh::Dim>> {
+        let shape = shape.into_shape();
+        check_shape(shape.raw_dim(), self.len())?;
+        Ok(Array::from_shape_vec(shape, self.to_vec()?)?)
+    }
+}
+
+impl<T: Scalar> Buffer<T> {
+    /** Copies `array` to `device`.
+
+    The data is copied in standard (C) order, so arrays with other layouts are copied to
+    standard layout on the host first.
+
+    # Errors
+    - [`DeviceLost`]
+    - [`DeviceBufferTooLarge`]
+    - [`OutOfDeviceMemory`] */
+    pub fn from_array<S2, D>(device: Device, array: &ArrayBase<S2, D>) -> Result<Self>
+    where
+        S2: ndarray::Data<Elem = T>,
+        D: Dimension,
+    {
+        let array = array.as_standard_layout();
+        Slice::from(array.as_slice().unwrap()).to_device(device)
+    }
+}
+
+impl<T: Scalar, D: Dimension> From<Array<T, D>> for Buffer<T> {
+    fn from(array: Array<T, D>) -> Self {
+        if array.is_standard_layout() {
+            let len = array.len();
+            let ptr = array.as_ptr();
+            let mut vec = array.into_raw_vec();
+            if len == 0 {
+                vec.clear();
+            } else {
+                // The array may start at an offset into its data, and may not span all of it.
+                let offset = unsafe { ptr.offset_from(vec.as_ptr()) } as usize;
+                vec.truncate(offset + len);
+                vec.drain(..offset);
+            }
+            Self::from(vec)
+        } else {
+            Self::from(array.iter().copied().collect::<Vec<_>>())
+        }
+    }
+}
+
+impl<'a, T: Scalar, D: Dimension> TryFrom<ArrayView<'a, T, D>> for Slice<'a, T> {
+    type Error = Error;
+    fn try_from(array: ArrayView<'a, T, D>) -> Result<Self> {
+        if let Some(slice) = array.to_slice() {
+            Ok(Self::from(slice))
+        } else {
+            bail!(
+                "Expected array in standard layout, found strides {:?}!",
+                array.strides()
+            );
+        }
+    }
+}
+
+impl<'a, T: Scalar, D: Dimension> TryFrom<ArrayViewMut<'a, T, D>> for SliceMut<'a, T> {
+    type Error = Error;
+    fn try_from(array: ArrayViewMut<'a, T, D>) -> Result<Self> {
+        if !array.is_standard_layout() {
+            bail!(
+                "Expected array in standard layout, found strides {:?}!",
+                array.strides()
+            );
+        }
+        Ok(Self::from(array.into_slice().unwrap()))
+    }
+}
@@ -0,0 +1,457 @@
+#[cfg(doc)]
+use super::error::DeviceLost;
+use super::{Buffer, BufferBase, Data, DataMut, Slice, SliceMut};
+#[cfg(doc)]
+use crate::device::Features;
+#[cfg(feature = "device")]
+use crate::macros::module;
+use crate::scalar::{Scalar, ScalarType};
+use anyhow::{bail, Result};
+use dry::macro_for;
+use half::{bf16, f16};
+#[cfg(feature = "device")]
+use paste::paste;
+
+impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
+    /** Adds `rhs` element-wise.
+
+    Integers wrap on overflow.
+
+    # Errors
+    - `rhs` is not on the same device or does not have the same length.
+    - [`DeviceLost`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn add(&self, rhs: &Slice<T>) -> Result<Buffer<T>> {
+        self.as_slice().binary(rhs, BinaryOp::Add)
+    }
+    /** Subtracts `rhs` element-wise.
+
+    See [`.add()`](BufferBase::add). */
+    pub fn sub(&self, rhs: &Slice<T>) -> Result<Buffer<T>> {
+        self.as_slice().binary(rhs, BinaryOp::Sub)
+    }
+    /** Multiplies by `rhs` element-wise.
+
+    See [`.add()`](BufferBase::add). */
+    pub fn mul(&self, rhs: &Slice<T>) -> Result<Buffer<T>> {
+        self.as_slice().binary(rhs, BinaryOp::Mul)
+    }
+    /** Divides by `rhs` element-wise.
+
+    Integer division by zero panics on the host, and is unspecified on devices.
+
+    See [`.add()`](BufferBase::add). */
+    pub fn div(&self, rhs: &Slice<T>) -> Result<Buffer<T>> {
+        self.as_slice().binary(rhs, BinaryOp::Div)
+    }
+    /** Computes `self = alpha * x + self`.
+
+    See [`.add()`](BufferBase::add). */
+    pub fn axpy(&mut self, alpha: T, x: &Slice<T>) -> Result<()>
+    where
+        S: DataMut,
+    {
+        self.as_slice_mut().axpy_impl(alpha, x)
+    }
+    /** Multiplies by `alpha`.
+
+    # Errors
+    - [`DeviceLost`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn scale(&self, alpha: T) -> Result<Buffer<T>> {
+        self.as_slice().unary(UnaryOp::Scale, alpha, T::zero())
+    }
+    /** The absolute value.
+
+    Unsigned integers are copied, signed integers wrap on overflow.
+
+    See [`.scale()`](BufferBase::scale). */
+    pub fn abs(&self) -> Result<Buffer<T>> {
+        self.as_slice().unary(UnaryOp::Abs, T::zero(), T::zero())
+    }
+    /** Rectified linear unit, ie `max(x, 0)`.
+
+    See [`.scale()`](BufferBase::scale). */
+    pub fn relu(&self) -> Result<Buffer<T>> {
+        self.as_slice().unary(UnaryOp::Relu, T::zero(), T::zero())
+    }
+    /** Clamps to the range `min ..= max`.
+
+    See [`.scale()`](BufferBase::scale). */
+    pub fn clamp(&self, min: T, max: T) -> Result<Buffer<T>> {
+        self.as_slice().unary(UnaryOp::Clamp, min, max)
+    }
+    /** The exponential, ie `e^x`.
+
+    [`f16`] and [`bf16`] are computed as [`f32`].
+
+    # Errors
+    - `T` is not a float.
+    - See [`.scale()`](BufferBase::scale). */
+    pub fn exp(&self) -> Result<Buffer<T>> {
+        self.as_slice().unary(UnaryOp::Exp, T::zero(), T::zero())
+    }
+    /** The natural logarithm.
+
+    See [`.exp()`](BufferBase::exp). */
+    pub fn log(&self) -> Result<Buffer<T>> {
+        self.as_slice().unary(UnaryOp::Log, T::zero(), T::zero())
+    }
+}
+
+// Must match the ops in kernels.
+#[derive(Clone, Copy, Debug)]
+#[repr(u32)]
+enum BinaryOp {
+    Add = 1,
+    Sub = 2,
+    Mul = 3,
+    Div = 4,
+}
+
+// Must match the ops in kernels.
+#[derive(Clone, Copy, Debug)]
+#[repr(u32)]
+enum UnaryOp {
+    Abs = 1,
+    Relu = 2,
+    Exp = 3,
+    Log = 4,
+    Scale = 5,
+    Clamp = 6,
+}
+
+fn is_float(scalar_type: ScalarType) -> bool {
+    use ScalarType::*;
+    matches!(scalar_type, F16 | BF16 | F32 | F64)
+}
+
+fn check_binary_args<T: Scalar>(a: &Slice<T>, b: &Slice<T>) -> Result<()> {
+    if a.device() != b.device() {
+        bail!("Expected rhs on {:?}, found {:?}!", a.device(), b.device());
+    }
+    if a.len() != b.len() {
+        bail!("Expected rhs with len {}, found {}!", a.len(), b.len());
+    }
+    Ok(())
+}
+
+impl<T: Scalar> Slice<'_, T> {
+    fn binary(&self, rhs: &Slice<T>, op: BinaryOp) -> Result<Buffer<T>> {
+        check_binary_args(self, rhs)?;
+        let mut output = unsafe { Buffer::uninit(self.device(), self.len())? };
+        if output.is_empty() {
+            return Ok(output);
+        }
+        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                {
+                    let a = Slice::<$X>::try_from(self.as_scalar_slice()).ok().unwrap();
+                    let b = Slice::<$X>::try_from(rhs.as_scalar_slice()).ok().unwrap();
+                    let mut y = SliceMut::<$X>::try_from(output.as_scalar_slice_mut()).ok().unwrap();
+                    if let Some((a, b)) = a.as_host_slice().zip(b.as_host_slice()) {
+                        for ((a, b), y) in a.iter().zip(b).zip(y.as_host_slice_mut().unwrap()) {
+                            *y = a.binary(*b, op);
+                        }
+                    } else {
+                        #[cfg(feature = "device")]
+                        {
+                            let builder = paste! {
+                                kernels::[<binary_ $X>]::builder()?
+                            };
+                            builder
+                                .specialize(op as u32)
+                                .build(y.device())?
+                                .dispatch(a, b, y)?;
+                        }
+                        #[cfg(not(feature = "device"))]
+                        {
+                            unreachable!()
+                        }
+                    }
+                }
+                return Ok(output);
+            }
+        });
+        bail!("{op:?} is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+    fn unary(&self, op: UnaryOp, alpha: T, beta: T) -> Result<Buffer<T>> {
+        if matches!(op, UnaryOp::Exp | UnaryOp::Log) && !is_float(T::SCALAR_TYPE) {
+            bail!("{op:?} is not implemented for {:?}!", T::SCALAR_TYPE);
+        }
+        let mut output = unsafe { Buffer::uninit(self.device(), self.len())? };
+        if output.is_empty() {
+            return Ok(output);
+        }
+        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                {
+                    let x = Slice::<$X>::try_from(self.as_scalar_slice()).ok().unwrap();
+                    let mut y = SliceMut::<$X>::try_from(output.as_scalar_slice_mut()).ok().unwrap();
+                    let alpha = alpha.cast::<$X>();
+                    let beta = beta.cast::<$X>();
+                    if let Some(x) = x.as_host_slice() {
+                        for (x, y) in x.iter().zip(y.as_host_slice_mut().unwrap()) {
+                            *y = x.unary(op, alpha, beta);
+                        }
+                    } else {
+                        #[cfg(feature = "device")]
+                        {
+                            let builder = paste! {
+                                kernels::[<unary_ $X>]::builder()?
+                            };
+                            builder
+                                .specialize(op as u32)
+                                .build(y.device())?
+                                .dispatch(alpha, beta, x, y)?;
+                        }
+                        #[cfg(not(feature = "device"))]
+                        {
+                            unreachable!()
+                        }
+                    }
+                }
+                return Ok(output);
+            }
+        });
+        bail!("{op:?} is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+}
+
+impl<T: Scalar> SliceMut<'_, T> {
+    fn axpy_impl(&mut self, alpha: T, x: &Slice<T>) -> Result<()> {
+        check_binary_args(x, &self.as_slice())?;
+        if self.is_empty() {
+            return Ok(());
+        }
+        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                let x = Slice::<$X>::try_from(x.as_scalar_slice()).ok().unwrap();
+                let mut y = SliceMut::<$X>::try_from(self.as_scalar_slice_mut()).ok().unwrap();
+                let alpha = alpha.cast::<$X>();
+                if let Some(x) = x.as_host_slice() {
+                    for (x, y) in x.iter().zip(y.as_host_slice_mut().unwrap()) {
+                        *y = alpha.binary(*x, BinaryOp::Mul).binary(*y, BinaryOp::Add);
+                    }
+                    return Ok(());
+                }
+                #[cfg(feature = "device")]
+                {
+                    let builder = paste! {
+                        kernels::[<axpy_ $X>]::builder()?
+                    };
+                    return builder.build(y.device())?.dispatch(alpha, x, y);
+                }
+                #[cfg(not(feature = "device"))]
+                {
+                    unreachable!()
+                }
+            }
+        });
+        bail!("Axpy is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+}
+
+trait Elementwise: Scalar {
+    fn binary(self, rhs: Self, op: BinaryOp) -> Self;
+    fn unary(self, op: UnaryOp, alpha: Self, beta: Self) -> Self;
+}
+
+macro_for!($X in [u8, u16, u32, u64, i8, i16, i32, i64] {
+    impl Elementwise for $X {
+        fn binary(self, rhs: Self, op: BinaryOp) -> Self {
+            match op {
+                BinaryOp::Add => self.wrapping_add(rhs),
+                BinaryOp::Sub => self.wrapping_sub(rhs),
+                BinaryOp::Mul => self.wrapping_mul(rhs),
+                BinaryOp::Div => self.wrapping_div(rhs),
+            }
+        }
+        #[allow(unused_comparisons)]
+        fn unary(self, op: UnaryOp, alpha: Self, beta: Self) -> Self {
+            match op {
+                UnaryOp::Abs => if self < 0 { (0 as $X).wrapping_sub(self) } else { self },
+                UnaryOp::Relu => if self > 0 { self } else { 0 },
+                UnaryOp::Exp | UnaryOp::Log => unreachable!(),
+                UnaryOp::Scale => alpha.wrapping_mul(self),
+                UnaryOp::Clamp => if self < alpha { alpha } else if self > beta { beta } else { self },
+            }
+        }
+    }
+});
+
+macro_for!($X in [f32, f64] {
+    impl Elementwise for $X {
+        fn binary(self, rhs: Self, op: BinaryOp) -> Self {
+            match op {
+                BinaryOp::Add => self + rhs,
+                BinaryOp::Sub => self - rhs,
+                BinaryOp::Mul => self * rhs,
+                BinaryOp::Div => self / rhs,
+            }
+        }
+        fn unary(self, op: UnaryOp, alpha: Self, beta: Self) -> Self {
+            match op {
+                UnaryOp::Abs => self.abs(),
+                UnaryOp::Relu => if self > 0. { self } else { 0. },
+                UnaryOp::Exp => self.exp(),
+                UnaryOp::Log => self.ln(),
+                UnaryOp::Scale => alpha * self,
+                UnaryOp::Clamp => if self < alpha { alpha } else if self > beta { beta } else { self },
+            }
+        }
+    }
+});
+
+macro_for!($X in [f16, bf16] {
+    impl Elementwise for $X {
+        fn binary(self, rhs: Self, op: BinaryOp) -> Self {
+            $X::from_f32(self.to_f32().binary(rhs.to_f32(), op))
+        }
+        fn unary(self, op: UnaryOp, alpha: Self, beta: Self) -> Self {
+            $X::from_f32(self.to_f32().unary(op, alpha.to_f32(), beta.to_f32()))
+        }
+    }
+});
+
+#[cfg(feature = "device")]
+#[module]
+#[krnl(crate=crate)]
+mod kernels {
+    use dry::macro_for;
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl_core;
+    use krnl_core::macros::kernel;
+    #[cfg(target_arch = "spirv")]
+    use krnl_core::{
+        half::{bf16, f16},
+        num_traits::Float,
+        scalar::Scalar,
+    };
+    use paste::paste;
+
+    // Must match BinaryOp.
+    #[cfg(target_arch = "spirv")]
+    const ADD: u32 = 1;
+    #[cfg(target_arch = "spirv")]
+    const SUB: u32 = 2;
+    #[cfg(target_arch = "spirv")]
+    const MUL: u32 = 3;
+
+    // Must match UnaryOp.
+    #[cfg(target_arch = "spirv")]
+    const ABS: u32 = 1;
+    #[cfg(target_arch = "spirv")]
+    const RELU: u32 = 2;
+    #[cfg(target_arch = "spirv")]
+    const EXP: u32 = 3;
+    #[cfg(target_arch = "spirv")]
+    const LOG: u32 = 4;
+    #[cfg(target_arch = "spirv")]
+    const SCALE: u32 = 5;
+
+    #[cfg(target_arch = "spirv")]
+    trait Elementwise: Scalar {
+        #[inline]
+        fn binary(self, rhs: Self, op: u32) -> Self {
+            if op == ADD {
+                self + rhs
+            } else if op == SUB {
+                self - rhs
+            } else if op == MUL {
+                self * rhs
+            } else {
+                self / rhs
+            }
+        }
+        fn unary(self, op: u32, alpha: Self, beta: Self) -> Self;
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn clamp<T: Scalar>(x: T, min: T, max: T) -> T {
+        if x < min {
+            min
+        } else if x > max {
+            max
+        } else {
+            x
+        }
+    }
+
+    macro_for!($X in [u8, u16, u32, u64, i8, i16, i32, i64] {
+        #[cfg(target_arch = "spirv")]
+        impl Elementwise for $X {
+            #[allow(unused_comparisons)]
+            #[inline]
+            fn unary(self, op: u32, alpha: Self, beta: Self) -> Self {
+                if op == ABS {
+                    if self < 0 { (0 as $X).wrapping_sub(self) } else { self }
+                } else if op == RELU {
+                    if self > 0 { self } else { 0 }
+                } else if op == SCALE {
+                    alpha * self
+                } else {
+                    clamp(self, alpha, beta)
+                }
+            }
+        }
+    });
+
+    macro_for!($X in [f32, f64] {
+        #[cfg(target_arch = "spirv")]
+        impl Elementwise for $X {
+            #[inline]
+            fn unary(self, op: u32, alpha: Self, beta: Self) -> Self {
+                if op == ABS {
+                    self.abs()
+                } else if op == RELU {
+                    if self > 0. { self } else { 0. }
+                } else if op == EXP {
+                    self.exp()
+                } else if op == LOG {
+                    self.ln()
+                } else if op == SCALE {
+                    alpha * self
+                } else {
+                    clamp(self, alpha, beta)
+                }
+            }
+        }
+    });
+
+    macro_for!($X in [f16, bf16] {
+        #[cfg(target_arch = "spirv")]
+        impl Elementwise for $X {
+            #[inline]
+            fn binary(self, rhs: Self, op: u32) -> Self {
+                self.to_f32().binary(rhs.to_f32(), op).cast()
+            }
+            #[inline]
+            fn unary(self, op: u32, alpha: Self, beta: Self) -> Self {
+                self.to_f32().unary(op, alpha.to_f32(), beta.to_f32()).cast()
+            }
+        }
+    });
+
+    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        paste! {
+            #[kernel]
+            pub fn [<binary_ $X>]<const OP: u32>(#[item] a: $X, #[item] b: $X, #[item] y: &mut $X) {
+                *y = a.binary(b, OP);
+            }
+
+            #[kernel]
+            pub fn [<unary_ $X>]<const OP: u32>(alpha: $X, beta: $X, #[item] x: $X, #[item] y: &mut $X) {
+                *y = x.unary(OP, alpha, beta);
+            }
+
+            #[kernel]
+            pub fn [<axpy_ $X>](alpha: $X, #[item] x: $X, #[item] y: &mut $X) {
+                *y = alpha.binary(x, MUL).binary(*y, ADD);
+            }
+        }
+    });
+}
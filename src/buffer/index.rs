@@ -0,0 +1,508 @@
+#[cfg(doc)]
+use super::error::DeviceLost;
+use super::{Buffer, BufferBase, Data, DataMut, Sealed, Slice, SliceMut};
+#[cfg(all(doc, not(feature = "device")))]
+use crate::device::Features;
+use crate::scalar::Scalar;
+#[cfg(feature = "device")]
+use crate::{device::Features, macros::module, scalar::ScalarType};
+use anyhow::{bail, Result};
+use dry::macro_for;
+use half::{bf16, f16};
+#[cfg(feature = "device")]
+use paste::paste;
+#[cfg(feature = "device")]
+use std::mem::size_of;
+
+/** Index types.
+
+Implemented for [`u32`] and [`u64`]. [`u64`] indices on devices require [`Features::INT64`].
+
+See [`.gather()`](BufferBase::gather). */
+pub trait IndexScalar: Scalar + Sealed {
+    #[doc(hidden)]
+    fn to_index(self) -> Option<usize>;
+}
+
+macro_for!($I in [u32, u64] {
+    impl Sealed for $I {}
+
+    impl IndexScalar for $I {
+        fn to_index(self) -> Option<usize> {
+            usize::try_from(self).ok()
+        }
+    }
+});
+
+impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
+    /** Gathers elements.
+
+    `y[i] = self[indices[i]]`
+
+    # Errors
+    - `indices` is not on the same device.
+    - An index is out of range.
+    - [`DeviceLost`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn gather<I: IndexScalar>(&self, indices: &Slice<I>) -> Result<Buffer<T>> {
+        let x = self.as_slice();
+        check_device(&x, indices)?;
+        let mut output = unsafe { Buffer::uninit(x.device(), indices.len())? };
+        if output.is_empty() {
+            return Ok(output);
+        }
+        if let Some((x, indices)) = x.as_host_slice().zip(indices.as_host_slice()) {
+            let y = output.as_host_slice_mut().unwrap();
+            for (y, index) in y.iter_mut().zip(indices) {
+                *y = x[check_index(*index, x.len())?];
+            }
+            return Ok(output);
+        }
+        #[cfg(not(feature = "device"))]
+        {
+            unreachable!()
+        }
+        #[cfg(feature = "device")]
+        {
+            let device = x.device();
+            let mut error = Buffer::<u32>::zeros(device.clone(), 1)?;
+            macro_for!($W in [u8, u16, u32, u64] {
+                if size_of::<T>() == size_of::<$W>() {
+                    macro_for!($I in [u32, u64] {
+                        if I::SCALAR_TYPE == $I::SCALAR_TYPE {
+                            let builder = paste! {
+                                kernels::[<gather_ $W _ $I>]::builder()?
+                            };
+                            builder.build(device)?.dispatch(
+                                x.bitcast().unwrap(),
+                                Slice::<$I>::try_from(indices.as_scalar_slice()).ok().unwrap(),
+                                output.bitcast_mut().unwrap(),
+                                error.as_slice_mut(),
+                            )?;
+                            check_error(&error, x.len())?;
+                            return Ok(output);
+                        }
+                    });
+                }
+            });
+            bail!("Gather is not implemented for {:?}!", T::SCALAR_TYPE)
+        }
+    }
+    /** Scatters elements.
+
+    `self[indices[i]] = x[i]`
+
+    If an index is repeated, which element is written is unspecified.
+
+    # Errors
+    - `indices` or `x` is not on the same device.
+    - `indices` and `x` do not have the same length.
+    - An index is out of range.
+        - In range elements may still be written on devices.
+    - [`DeviceLost`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn scatter<I: IndexScalar>(&mut self, indices: &Slice<I>, x: &Slice<T>) -> Result<()>
+    where
+        S: DataMut,
+    {
+        self.as_slice_mut().scatter_impl(indices, x, false)
+    }
+    /** Scatters elements, adding them to the output.
+
+    `self[indices[i]] += x[i]`
+
+    Integers wrap on overflow. On devices, uses atomics for [`u32`] and [`i32`], and for [`u64`], [`i64`],
+    and [`f32`] if the device has the required [`Features`]. Otherwise, the elements are sorted by index with
+    [`radix_sort_pairs`](crate::algorithms::radix_sort_pairs) and each run of an index is summed in order.
+
+    See [`.scatter()`](BufferBase::scatter). */
+    pub fn scatter_add<I: IndexScalar>(&mut self, indices: &Slice<I>, x: &Slice<T>) -> Result<()>
+    where
+        S: DataMut,
+    {
+        self.as_slice_mut().scatter_impl(indices, x, true)
+    }
+}
+
+fn check_device<T: Scalar, I: Scalar>(x: &Slice<T>, indices: &Slice<I>) -> Result<()> {
+    if x.device() != indices.device() {
+        bail!(
+            "Expected indices on {:?}, found {:?}!",
+            x.device(),
+            indices.device()
+        );
+    }
+    Ok(())
+}
+
+fn check_index<I: IndexScalar>(index: I, len: usize) -> Result<usize> {
+    match index.to_index() {
+        Some(i) if i < len => Ok(i),
+        _ => bail!("Index {index:?} out of range for len {len}!"),
+    }
+}
+
+#[cfg(feature = "device")]
+fn check_error(error: &Buffer<u32>, len: usize) -> Result<()> {
+    if error.to_vec()?[0] != 0 {
+        bail!("Index out of range for len {len}!");
+    }
+    Ok(())
+}
+
+impl<T: Scalar> SliceMut<'_, T> {
+    fn scatter_impl<I: IndexScalar>(
+        &mut self,
+        indices: &Slice<I>,
+        x: &Slice<T>,
+        add: bool,
+    ) -> Result<()> {
+        let y = self.as_slice();
+        check_device(&y, indices)?;
+        if x.device() != y.device() {
+            bail!("Expected x on {:?}, found {:?}!", y.device(), x.device());
+        }
+        if x.len() != indices.len() {
+            bail!("Expected x with len {}, found {}!", indices.len(), x.len());
+        }
+        if x.is_empty() {
+            return Ok(());
+        }
+        let len = y.len();
+        if let Some((indices, x)) = indices.as_host_slice().zip(x.as_host_slice()) {
+            let indices = indices
+                .iter()
+                .map(|index| check_index(*index, len))
+                .collect::<Result<Vec<_>>>()?;
+            macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+                if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                    let x = Slice::<$X>::try_from(x.as_scalar_slice()).ok().unwrap();
+                    let x = x.as_host_slice().unwrap();
+                    let mut y = SliceMut::<$X>::try_from(self.as_scalar_slice_mut()).ok().unwrap();
+                    let y = y.as_host_slice_mut().unwrap();
+                    for (index, x) in indices.into_iter().zip(x.iter().copied()) {
+                        y[index] = if add { y[index].scatter_add(x) } else { x };
+                    }
+                    return Ok(());
+                }
+            });
+            bail!("Scatter is not implemented for {:?}!", T::SCALAR_TYPE)
+        }
+        #[cfg(not(feature = "device"))]
+        {
+            unreachable!()
+        }
+        #[cfg(feature = "device")]
+        {
+            let device = y.device();
+            let mut error = Buffer::<u32>::zeros(device.clone(), 1)?;
+            if !add {
+                macro_for!($W in [u8, u16, u32, u64] {
+                    if size_of::<T>() == size_of::<$W>() {
+                        macro_for!($I in [u32, u64] {
+                            if I::SCALAR_TYPE == $I::SCALAR_TYPE {
+                                let builder = paste! {
+                                    kernels::[<scatter_ $W _ $I>]::builder()?
+                                };
+                                builder.build(device)?.dispatch(
+                                    Slice::<$I>::try_from(indices.as_scalar_slice()).ok().unwrap(),
+                                    x.bitcast().unwrap(),
+                                    self.bitcast_mut().unwrap(),
+                                    error.as_slice_mut(),
+                                )?;
+                                return check_error(&error, len);
+                            }
+                        });
+                    }
+                });
+                bail!("Scatter is not implemented for {:?}!", T::SCALAR_TYPE)
+            }
+            let features = device.info().unwrap().features();
+            if atomic_add_features(T::SCALAR_TYPE).map_or(false, |x| features.contains(x)) {
+                macro_for!($X in [u32, i32, f32, u64, i64] {
+                    if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                        macro_for!($I in [u32, u64] {
+                            if I::SCALAR_TYPE == $I::SCALAR_TYPE {
+                                let builder = paste! {
+                                    kernels::[<scatter_add_atomic_ $X _ $I>]::builder()?
+                                };
+                                builder.build(device)?.dispatch(
+                                    Slice::<$I>::try_from(indices.as_scalar_slice()).ok().unwrap(),
+                                    Slice::<$X>::try_from(x.as_scalar_slice()).ok().unwrap(),
+                                    SliceMut::<$X>::try_from(self.as_scalar_slice_mut()).ok().unwrap(),
+                                    error.as_slice_mut(),
+                                )?;
+                                return check_error(&error, len);
+                            }
+                        });
+                    }
+                });
+            }
+            // Sorts by index, so that each output is accumulated by one thread.
+            let (indices, x) = crate::algorithms::radix_sort_pairs(indices.clone(), x.clone())?;
+            let n = indices.len();
+            // Chunks of sqrt(n) elements bound the serial work per thread for long runs of an index.
+            let chunk = ((n as f64).sqrt() as usize).max(64);
+            let chunks = n / chunk + usize::from(n % chunk != 0);
+            let chunk = u32::try_from(chunk)?;
+            let global_threads = u32::try_from(chunks)?;
+            macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+                if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                    macro_for!($I in [u32, u64] {
+                        if I::SCALAR_TYPE == $I::SCALAR_TYPE {
+                            let indices = Slice::<$I>::try_from(indices.as_scalar_slice()).ok().unwrap();
+                            let x = Slice::<$X>::try_from(x.as_scalar_slice()).ok().unwrap();
+                            let mut y = SliceMut::<$X>::try_from(self.as_scalar_slice_mut()).ok().unwrap();
+                            let mut leads = unsafe { Buffer::<$X>::uninit(device.clone(), chunks)? };
+                            let mut tails = unsafe { Buffer::<$X>::uninit(device.clone(), chunks)? };
+                            let builder = paste! {
+                                kernels::[<scatter_add_sorted_ $X _ $I>]::builder()?
+                            };
+                            builder
+                                .build(device.clone())?
+                                .with_global_threads(global_threads)
+                                .dispatch(
+                                    chunk,
+                                    indices.clone(),
+                                    x,
+                                    y.as_slice_mut(),
+                                    leads.as_slice_mut(),
+                                    tails.as_slice_mut(),
+                                    error.as_slice_mut(),
+                                )?;
+                            let builder = paste! {
+                                kernels::[<scatter_add_carry_ $X _ $I>]::builder()?
+                            };
+                            builder
+                                .build(device)?
+                                .with_global_threads(global_threads)
+                                .dispatch(
+                                    chunk,
+                                    indices,
+                                    leads.as_slice(),
+                                    tails.as_slice(),
+                                    y,
+                                )?;
+                            return check_error(&error, len);
+                        }
+                    });
+                }
+            });
+            bail!("Scatter is not implemented for {:?}!", T::SCALAR_TYPE)
+        }
+    }
+}
+
+/// Features required for atomic adds, if implemented.
+#[cfg(feature = "device")]
+fn atomic_add_features(scalar_type: ScalarType) -> Option<Features> {
+    use ScalarType::*;
+    match scalar_type {
+        U32 | I32 => Some(Features::empty()),
+        U64 | I64 => Some(Features::INT64 | Features::BUFFER_INT64_ATOMICS),
+        F32 => Some(Features::BUFFER_FLOAT32_ATOMIC_ADD),
+        _ => None,
+    }
+}
+
+trait ScatterAdd: Scalar {
+    fn scatter_add(self, x: Self) -> Self;
+}
+
+macro_for!($X in [u8, i8, u16, i16, u32, i32, u64, i64] {
+    impl ScatterAdd for $X {
+        fn scatter_add(self, x: Self) -> Self {
+            self.wrapping_add(x)
+        }
+    }
+});
+
+macro_for!($X in [f16, bf16, f32, f64] {
+    impl ScatterAdd for $X {
+        fn scatter_add(self, x: Self) -> Self {
+            self + x
+        }
+    }
+});
+
+#[cfg(feature = "device")]
+#[module]
+#[krnl(crate=crate)]
+mod kernels {
+    use dry::macro_for;
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl_core;
+    use krnl_core::macros::kernel;
+    #[cfg(target_arch = "spirv")]
+    use krnl_core::{
+        buffer::UnsafeIndex,
+        half::{bf16, f16},
+        num_traits::Zero,
+    };
+    use paste::paste;
+
+    macro_for!($W in [u8, u16, u32, u64] {
+        macro_for!($I in [u32, u64] {
+            paste! {
+                #[kernel]
+                pub fn [<gather_ $W _ $I>](
+                    #[global] x: Slice<$W>,
+                    #[item] index: $I,
+                    #[item] y: &mut $W,
+                    #[global] error: UnsafeSlice<u32>,
+                ) {
+                    if index < x.len() as $I {
+                        *y = x[index as usize];
+                    } else {
+                        unsafe {
+                            *error.unsafe_index_mut(0) = 1;
+                        }
+                    }
+                }
+
+                #[kernel]
+                pub fn [<scatter_ $W _ $I>](
+                    #[item] index: $I,
+                    #[item] x: $W,
+                    #[global] y: UnsafeSlice<$W>,
+                    #[global] error: UnsafeSlice<u32>,
+                ) {
+                    if index < y.len() as $I {
+                        unsafe {
+                            *y.unsafe_index_mut(index as usize) = x;
+                        }
+                    } else {
+                        unsafe {
+                            *error.unsafe_index_mut(0) = 1;
+                        }
+                    }
+                }
+            }
+        });
+    });
+
+    macro_for!($X in [u32, i32, f32, u64, i64] {
+        macro_for!($I in [u32, u64] {
+            paste! {
+                #[kernel]
+                pub fn [<scatter_add_atomic_ $X _ $I>](
+                    #[item] index: $I,
+                    #[item] x: $X,
+                    #[global] y: UnsafeSlice<$X>,
+                    #[global] error: UnsafeSlice<u32>,
+                ) {
+                    if index < y.len() as $I {
+                        y.atomic_add(index as usize, x);
+                    } else {
+                        unsafe {
+                            *error.unsafe_index_mut(0) = 1;
+                        }
+                    }
+                }
+            }
+        });
+    });
+
+    // Segmented reduction of x sorted by index. Each thread sums the runs of an index within its
+    // chunk. Runs that start before the chunk are stored in leads, and runs that continue after the
+    // chunk are stored in tails, to be added by scatter_add_carry.
+    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        macro_for!($I in [u32, u64] {
+            paste! {
+                #[kernel]
+                pub fn [<scatter_add_sorted_ $X _ $I>](
+                    chunk: u32,
+                    #[global] indices: Slice<$I>,
+                    #[global] x: Slice<$X>,
+                    #[global] y: UnsafeSlice<$X>,
+                    #[global] leads: UnsafeSlice<$X>,
+                    #[global] tails: UnsafeSlice<$X>,
+                    #[global] error: UnsafeSlice<u32>,
+                ) {
+                    let global_id = kernel.global_id();
+                    let n = indices.len();
+                    let start = global_id * chunk as usize;
+                    if start >= n {
+                        return;
+                    }
+                    let end = (start + chunk as usize).min(n);
+                    let len = y.len() as $I;
+                    let mut i = start;
+                    let mut lead = $X::zero();
+                    if start > 0 && indices[start - 1] == indices[start] {
+                        let index = indices[start];
+                        while i < end && indices[i] == index {
+                            lead += x[i];
+                            i += 1;
+                        }
+                    }
+                    unsafe {
+                        *leads.unsafe_index_mut(global_id) = lead;
+                    }
+                    while i < end {
+                        let index = indices[i];
+                        let mut sum = $X::zero();
+                        while i < end && indices[i] == index {
+                            sum += x[i];
+                            i += 1;
+                        }
+                        if index >= len {
+                            unsafe {
+                                *error.unsafe_index_mut(0) = 1;
+                            }
+                        } else if end < n && indices[end] == index {
+                            unsafe {
+                                *tails.unsafe_index_mut(global_id) = sum;
+                            }
+                        } else {
+                            unsafe {
+                                *y.unsafe_index_mut(index as usize) += sum;
+                            }
+                        }
+                    }
+                }
+
+                #[kernel]
+                pub fn [<scatter_add_carry_ $X _ $I>](
+                    chunk: u32,
+                    #[global] indices: Slice<$I>,
+                    #[global] leads: Slice<$X>,
+                    #[global] tails: Slice<$X>,
+                    #[global] y: UnsafeSlice<$X>,
+                ) {
+                    let global_id = kernel.global_id();
+                    let n = indices.len();
+                    let chunk = chunk as usize;
+                    let start = global_id * chunk;
+                    if start >= n {
+                        return;
+                    }
+                    let end = (start + chunk).min(n);
+                    let index = indices[end - 1];
+                    // The run continues after the chunk, and starts within it.
+                    if end == n || indices[end] != index || index >= y.len() as $I {
+                        return;
+                    }
+                    if start > 0 && indices[start - 1] == index {
+                        return;
+                    }
+                    let mut sum = tails[global_id];
+                    let mut next = global_id + 1;
+                    loop {
+                        sum += leads[next];
+                        let end = ((next + 1) * chunk).min(n);
+                        if end == n || indices[end] != index {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    unsafe {
+                        *y.unsafe_index_mut(index as usize) += sum;
+                    }
+                }
+            }
+        });
+    });
+}
@@ -0,0 +1,290 @@
+#[cfg(doc)]
+use super::error::DeviceLost;
+#[cfg(feature = "device")]
+use super::Slice;
+use super::{Buffer, BufferBase, Data};
+#[cfg(doc)]
+use crate::device::Features;
+use crate::scalar::Scalar;
+#[cfg(feature = "device")]
+use crate::{device::Device, macros::module};
+use anyhow::{bail, format_err, Result};
+#[cfg(feature = "device")]
+use dry::macro_for;
+#[cfg(feature = "device")]
+use paste::paste;
+#[cfg(feature = "device")]
+use std::mem::size_of;
+
+impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
+    /** Transposes a row major `rows x cols` matrix.
+
+    See [`.permute()`](BufferBase::permute). */
+    pub fn transpose_2d(&self, rows: usize, cols: usize) -> Result<Buffer<T>> {
+        self.permute(&[rows, cols], &[1, 0])
+    }
+    /** Permutes the axes of a contiguous row major tensor.
+
+    Axis `i` of the output is axis `axes[i]` of `self`, like [`numpy.transpose`](https://numpy.org/doc/stable/reference/generated/numpy.transpose.html).
+    For example, NCHW to NHWC is `permute(&[n, c, h, w], &[0, 2, 3, 1])`.
+
+    Permutations that reduce to (batched) 2-D transposes use tiles in group memory.
+
+    # Errors
+    - The product of `shape` overflows or is not the length.
+    - `axes` is not a permutation of `0..shape.len()`.
+    - [`DeviceLost`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn permute(&self, shape: &[usize], axes: &[usize]) -> Result<Buffer<T>> {
+        let x = self.as_slice();
+        let len = shape
+            .iter()
+            .try_fold(1usize, |len, dim| len.checked_mul(*dim))
+            .ok_or_else(|| format_err!("Shape {shape:?} overflows!"))?;
+        if len != x.len() {
+            bail!("Expected shape with len {}, found {shape:?}!", x.len());
+        }
+        if axes.len() != shape.len() || (0..axes.len()).any(|axis| !axes.contains(&axis)) {
+            bail!(
+                "Expected axes to be a permutation of 0..{}, found {axes:?}!",
+                shape.len()
+            );
+        }
+        let (shape, axes) = collapse_axes(shape, axes);
+        if x.is_empty() || axes.iter().copied().eq(0..axes.len()) {
+            return x.to_owned();
+        }
+        if let Some(x) = x.as_host_slice() {
+            let strides = strides(&shape);
+            let output_shape: Vec<usize> = axes.iter().map(|axis| shape[*axis]).collect();
+            let output_strides: Vec<usize> = axes.iter().map(|axis| strides[*axis]).collect();
+            let mut output = Vec::with_capacity(len);
+            for i in 0..len {
+                let mut index = i;
+                let mut x_index = 0;
+                for (dim, stride) in output_shape.iter().zip(output_strides.iter()).rev() {
+                    x_index += (index % dim) * stride;
+                    index /= dim;
+                }
+                output.push(x[x_index]);
+            }
+            return Ok(output.into());
+        }
+        #[cfg(not(feature = "device"))]
+        {
+            unreachable!()
+        }
+        #[cfg(feature = "device")]
+        {
+            device_permute(x, &shape, &axes)
+        }
+    }
+}
+
+/// Merges axes that stay adjacent and removes axes of size 1.
+fn collapse_axes(shape: &[usize], axes: &[usize]) -> (Vec<usize>, Vec<usize>) {
+    let axes: Vec<usize> = axes
+        .iter()
+        .copied()
+        .filter(|axis| shape[*axis] != 1)
+        .collect();
+    // Groups of input axes, in output order.
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for axis in axes {
+        match groups.last_mut() {
+            Some(group) if *group.last().unwrap() + 1 == axis => group.push(axis),
+            _ => groups.push(vec![axis]),
+        }
+    }
+    let mut input_order: Vec<usize> = (0..groups.len()).collect();
+    input_order.sort_by_key(|group| groups[*group][0]);
+    let new_shape: Vec<usize> = input_order
+        .iter()
+        .map(|group| groups[*group].iter().map(|axis| shape[*axis]).product())
+        .collect();
+    let mut new_axes = vec![0; groups.len()];
+    for (new_axis, group) in input_order.into_iter().enumerate() {
+        new_axes[group] = new_axis;
+    }
+    (new_shape, new_axes)
+}
+
+fn strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1] * shape[i + 1];
+    }
+    strides
+}
+
+#[cfg(feature = "device")]
+fn device_permute<T: Scalar>(x: Slice<T>, shape: &[usize], axes: &[usize]) -> Result<Buffer<T>> {
+    let device = x.device();
+    let mut output = unsafe { Buffer::<T>::uninit(device.clone(), x.len())? };
+    let transpose = match axes {
+        [1, 0] => Some((1, shape[0], shape[1])),
+        [0, 2, 1] => Some((shape[0], shape[1], shape[2])),
+        _ => None,
+    };
+    if let Some((batch, rows, cols)) = transpose {
+        let tile = transpose_tile(&device);
+        let tiles = |x: usize| (x + tile as usize - 1) / tile as usize;
+        let max_groups = device.info().unwrap().max_groups() as usize;
+        let groups = (batch * tiles(rows) * tiles(cols)).min(max_groups) as u32;
+        macro_for!($W in [u8, u16, u32, u64] {
+            if size_of::<T>() == size_of::<$W>() {
+                let builder = paste! {
+                    kernels::[<transpose_ $W>]::builder()?
+                };
+                builder
+                    .specialize(tile)
+                    .with_threads(tile * tile)
+                    .build(device)?
+                    .with_groups(groups)
+                    .dispatch(
+                        batch.try_into()?,
+                        rows.try_into()?,
+                        cols.try_into()?,
+                        x.bitcast().unwrap(),
+                        output.bitcast_mut().unwrap(),
+                    )?;
+                return Ok(output);
+            }
+        });
+        bail!("Permute is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+    let strides = strides(shape);
+    let dims: Vec<u32> = axes
+        .iter()
+        .map(|axis| shape[*axis])
+        .chain(axes.iter().map(|axis| strides[*axis]))
+        .map(u32::try_from)
+        .collect::<Result<_, _>>()?;
+    let dims = Slice::from(dims.as_slice()).to_device(device.clone())?;
+    macro_for!($W in [u8, u16, u32, u64] {
+        if size_of::<T>() == size_of::<$W>() {
+            let builder = paste! {
+                kernels::[<permute_ $W>]::builder()?
+            };
+            builder.build(device)?.dispatch(
+                axes.len().try_into()?,
+                dims.as_slice(),
+                x.bitcast().unwrap(),
+                output.bitcast_mut().unwrap(),
+            )?;
+            return Ok(output);
+        }
+    });
+    bail!("Permute is not implemented for {:?}!", T::SCALAR_TYPE)
+}
+
+/// The largest power of 2 with `tile * tile <= threads`.
+#[cfg(feature = "device")]
+fn transpose_tile(device: &Device) -> u32 {
+    let threads = device.info().unwrap().default_threads();
+    let mut tile = 1;
+    while (tile * 2) * (tile * 2) <= threads {
+        tile *= 2;
+    }
+    tile
+}
+
+#[cfg(feature = "device")]
+#[module]
+#[krnl(crate=crate)]
+mod kernels {
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl_core;
+    use krnl_core::macros::kernel;
+    #[cfg(target_arch = "spirv")]
+    use krnl_core::{
+        buffer::UnsafeIndex,
+        spirv_std::arch::workgroup_memory_barrier_with_group_sync as group_barrier,
+    };
+    use paste::paste;
+
+    macro_rules! impl_transpose {
+        ($($W:ident => $A:ident),* $(,)?) => {
+            $(
+                paste! {
+                    // Each group loads a `TILE x TILE` tile of `x` and stores it transposed, so that both
+                    // reads and writes are contiguous. The tile is padded to avoid bank conflicts.
+                    #[kernel]
+                    pub fn [<transpose_ $W>]<const TILE: u32>(
+                        batch: u32,
+                        rows: u32,
+                        cols: u32,
+                        #[global] x: Slice<$W>,
+                        #[group] x_tile: UnsafeSlice<$A, { (TILE * (TILE + 1)) as usize }>,
+                        #[global] y: UnsafeSlice<$W>,
+                    ) {
+                        let tile = TILE as usize;
+                        let (rows, cols) = (rows as usize, cols as usize);
+                        let tiles_rows = (rows + tile - 1) / tile;
+                        let tiles_cols = (cols + tile - 1) / tile;
+                        let tiles = batch as usize * tiles_rows * tiles_cols;
+                        let thread_id = kernel.thread_id();
+                        let (ty, tx) = (thread_id / tile, thread_id % tile);
+                        let mut tile_id = kernel.group_id();
+                        while tile_id < tiles {
+                            let offset = (tile_id / (tiles_rows * tiles_cols)) * rows * cols;
+                            let tile_row = (tile_id / tiles_cols) % tiles_rows;
+                            let tile_col = tile_id % tiles_cols;
+                            let row = tile_row * tile + ty;
+                            let col = tile_col * tile + tx;
+                            if row < rows && col < cols {
+                                unsafe {
+                                    *x_tile.unsafe_index_mut(ty * (tile + 1) + tx) =
+                                        x[offset + row * cols + col] as $A;
+                                }
+                            }
+                            unsafe {
+                                group_barrier();
+                            }
+                            let row = tile_row * tile + tx;
+                            let col = tile_col * tile + ty;
+                            if row < rows && col < cols {
+                                unsafe {
+                                    *y.unsafe_index_mut(offset + col * rows + row) =
+                                        *x_tile.unsafe_index(tx * (tile + 1) + ty) as $W;
+                                }
+                            }
+                            unsafe {
+                                group_barrier();
+                            }
+                            tile_id += kernel.groups();
+                        }
+                    }
+
+                    #[kernel]
+                    pub fn [<permute_ $W>](
+                        rank: u32,
+                        #[global] dims: Slice<u32>,
+                        #[global] x: Slice<$W>,
+                        #[item] y: &mut $W,
+                    ) {
+                        let rank = rank as usize;
+                        let mut index = kernel.item_id();
+                        let mut x_index = 0;
+                        let mut axis = rank;
+                        while axis > 0 {
+                            axis -= 1;
+                            let dim = dims[axis] as usize;
+                            x_index += (index % dim) * dims[rank + axis] as usize;
+                            index /= dim;
+                        }
+                        *y = x[x_index];
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_transpose! {
+        u8 => u32,
+        u16 => u32,
+        u32 => u32,
+        u64 => u64,
+    }
+}
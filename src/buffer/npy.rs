@@ -0,0 +1,577 @@
+#[cfg(doc)]
+use super::error::{DeviceBufferTooLarge, DeviceLost, OutOfDeviceMemory};
+use super::{
+    Buffer, ScalarBuffer, ScalarBufferBase, ScalarData, ScalarDataOwned, ScalarSlice, Slice,
+    SliceMut, CHUNK_BYTES,
+};
+use crate::{
+    device::Device,
+    scalar::{f8e4m3, f8e5m2, Scalar, ScalarType},
+};
+use anyhow::{bail, format_err, Result};
+use dry::{macro_for, macro_wrap};
+use flate2::{read::DeflateDecoder, Crc};
+use half::{bf16, f16};
+use paste::paste;
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    mem::size_of,
+};
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+impl<S: ScalarDataOwned> ScalarBufferBase<S> {
+    /** Reads a NumPy `.npy` array onto `device`.
+
+    Returns the buffer and its shape. Supports `bool` (as [`u8`]), integer and float dtypes of
+    either byte order, including `float16`. Fortran order arrays are permuted to row major.
+
+    The data is read in chunks and copied directly into device memory.
+
+    # Errors
+    - The header is invalid, or the dtype is not supported.
+    - Reading from `reader` failed.
+    - [`DeviceLost`]
+    - [`DeviceBufferTooLarge`]
+    - [`OutOfDeviceMemory`]
+    - Fortran order requires dispatching a kernel, see [`.permute()`](super::BufferBase::permute). */
+    pub fn read_npy(device: Device, mut reader: impl Read) -> Result<(Self, Vec<usize>)> {
+        let header = NpyHeader::read(&mut reader)?;
+        let buffer: ScalarBuffer = macro_wrap!(paste! {
+            match header.scalar_type {
+                macro_for!($T in [u8, i8, u16, i16, f16, u32, i32, f32, u64, i64, f64] {
+                    ScalarType::[<$T:upper>] => read_npy_data::<$T>(device, &header, &mut reader)?.into(),
+                })
+                _ => unreachable!(),
+            }
+        });
+        Ok((Self::from_scalar_buffer(buffer), header.shape))
+    }
+    /** Reads all arrays in a NumPy `.npz` archive onto `device`.
+
+    Returns the name, buffer and shape of each array, in the order stored. The ".npy" extension
+    is removed from names. Supports both `numpy.savez` and `numpy.savez_compressed`.
+
+    See [`read_npy`](ScalarBufferBase::read_npy).
+
+    # Errors
+    - The archive is invalid, or uses an unsupported compression method.
+    - See [`read_npy`](ScalarBufferBase::read_npy). */
+    pub fn read_npz<R: Read + Seek>(
+        device: Device,
+        mut reader: R,
+    ) -> Result<Vec<(String, Self, Vec<usize>)>> {
+        let entries = read_zip_entries(&mut reader)?;
+        let mut arrays = Vec::with_capacity(entries.len());
+        for entry in entries {
+            reader.seek(SeekFrom::Start(entry.offset))?;
+            let mut local_header = [0; 30];
+            reader.read_exact(&mut local_header)?;
+            if &local_header[..4] != b"PK\x03\x04" {
+                bail!("Expected local file header for {:?}!", entry.name);
+            }
+            let skip = u16_le(&local_header, 26) as i64 + u16_le(&local_header, 28) as i64;
+            reader.seek(SeekFrom::Current(skip))?;
+            let data = (&mut reader).take(entry.compressed_size);
+            let (buffer, shape) = match entry.method {
+                0 => Self::read_npy(device.clone(), data)?,
+                8 => Self::read_npy(device.clone(), DeflateDecoder::new(data))?,
+                method => bail!(
+                    "Unsupported compression method {method} for {:?}!",
+                    entry.name
+                ),
+            };
+            let name = entry
+                .name
+                .strip_suffix(".npy")
+                .unwrap_or(&entry.name)
+                .to_string();
+            arrays.push((name, buffer, shape));
+        }
+        Ok(arrays)
+    }
+}
+
+impl<S: ScalarData> ScalarBufferBase<S> {
+    /** Writes as a NumPy `.npy` array with `shape`.
+
+    Data on a device is copied to the host in chunks.
+
+    # Errors
+    - The product of `shape` is not the length.
+    - [`bf16`] is not supported by NumPy.
+    - Writing to `writer` failed.
+    - [`DeviceLost`] */
+    pub fn write_npy(&self, shape: &[usize], mut writer: impl Write) -> Result<()> {
+        let x = self.as_scalar_slice();
+        writer.write_all(&npy_header(x.scalar_type(), x.len(), shape)?)?;
+        write_scalar_slice(x, false, &mut writer)
+    }
+}
+
+impl ScalarBuffer {
+    /** Writes `arrays` as an uncompressed NumPy `.npz` archive, like `numpy.savez`.
+
+    Each array is stored as "{name}.npy", with its shape.
+
+    See [`write_npy`](ScalarBufferBase::write_npy).
+
+    # Errors
+    - The archive would be larger than 4 GiB.
+    - See [`write_npy`](ScalarBufferBase::write_npy). */
+    pub fn write_npz<'a>(
+        arrays: impl IntoIterator<Item = (&'a str, ScalarSlice<'a>, &'a [usize])>,
+        writer: impl Write,
+    ) -> Result<()> {
+        let mut writer = ZipWriter {
+            writer,
+            position: 0,
+            crc: Crc::new(),
+        };
+        let mut central_directory = Vec::new();
+        let mut entries = 0u16;
+        for (name, x, shape) in arrays {
+            let name = format!("{name}.npy");
+            let header = npy_header(x.scalar_type(), x.len(), shape)?;
+            let size = header.len() as u64 + (x.len() * x.scalar_type().size()) as u64;
+            let offset = writer.position;
+            if offset + 30 + name.len() as u64 + size + 16 > u32::MAX as u64 {
+                bail!("npz archives larger than 4 GiB are not supported!");
+            }
+            let Some(next_entries) = entries.checked_add(1) else {
+                bail!(
+                    "npz archives with more than {} arrays are not supported!",
+                    u16::MAX
+                );
+            };
+            entries = next_entries;
+            let mut local_header = Vec::with_capacity(30 + name.len());
+            local_header.extend_from_slice(b"PK\x03\x04");
+            local_header.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+            local_header.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+            // method, time and date
+            local_header.extend_from_slice(&[0, 0, 0, 0]);
+            local_header.extend_from_slice(&ZIP_DATE.to_le_bytes());
+            // crc and sizes are in the data descriptor
+            local_header.extend_from_slice(&[0; 12]);
+            local_header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            local_header.extend_from_slice(&[0, 0]);
+            local_header.extend_from_slice(name.as_bytes());
+            writer.write_all(&local_header)?;
+            writer.crc.reset();
+            writer.write_all(&header)?;
+            write_scalar_slice(x, false, &mut writer)?;
+            let crc = writer.crc.sum();
+            let mut data_descriptor = Vec::with_capacity(16);
+            data_descriptor.extend_from_slice(b"PK\x07\x08");
+            data_descriptor.extend_from_slice(&crc.to_le_bytes());
+            data_descriptor.extend_from_slice(&(size as u32).to_le_bytes());
+            data_descriptor.extend_from_slice(&(size as u32).to_le_bytes());
+            writer.write_all(&data_descriptor)?;
+            central_directory.extend_from_slice(b"PK\x01\x02");
+            // version made by, version needed
+            central_directory.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+            central_directory.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+            central_directory.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+            central_directory.extend_from_slice(&[0, 0, 0, 0]);
+            central_directory.extend_from_slice(&ZIP_DATE.to_le_bytes());
+            central_directory.extend_from_slice(&crc.to_le_bytes());
+            central_directory.extend_from_slice(&(size as u32).to_le_bytes());
+            central_directory.extend_from_slice(&(size as u32).to_le_bytes());
+            central_directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            // extra, comment, disk, attributes
+            central_directory.extend_from_slice(&[0; 12]);
+            central_directory.extend_from_slice(&(offset as u32).to_le_bytes());
+            central_directory.extend_from_slice(name.as_bytes());
+        }
+        let offset = writer.position;
+        if offset + central_directory.len() as u64 > u32::MAX as u64 {
+            bail!("npz archives larger than 4 GiB are not supported!");
+        }
+        writer.write_all(&central_directory)?;
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(b"PK\x05\x06");
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&entries.to_le_bytes());
+        end.extend_from_slice(&entries.to_le_bytes());
+        end.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+        end.extend_from_slice(&(offset as u32).to_le_bytes());
+        end.extend_from_slice(&[0, 0]);
+        writer.write_all(&end)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct NpyHeader {
+    scalar_type: ScalarType,
+    byte_swap: bool,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+impl NpyHeader {
+    fn read(reader: &mut impl Read) -> Result<Self> {
+        let mut prefix = [0; 8];
+        reader.read_exact(&mut prefix)?;
+        if &prefix[..6] != NPY_MAGIC {
+            bail!("Expected .npy magic string, found {:?}!", &prefix[..6]);
+        }
+        let header_len = match prefix[6] {
+            1 => {
+                let mut len = [0; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_le_bytes(len) as usize
+            }
+            2 | 3 => {
+                let mut len = [0; 4];
+                reader.read_exact(&mut len)?;
+                u32::from_le_bytes(len) as usize
+            }
+            major => bail!("Unsupported .npy version {major}.{}!", prefix[7]),
+        };
+        let mut header = vec![0; header_len];
+        reader.read_exact(&mut header)?;
+        let header = String::from_utf8(header)?.replace('"', "'");
+        let value = |key: &str| -> Result<&str> {
+            let pattern = format!("'{key}':");
+            let start = header
+                .find(&pattern)
+                .ok_or_else(|| format_err!("Expected {key:?} in .npy header {header:?}!"))?;
+            Ok(header[start + pattern.len()..].trim_start())
+        };
+        let descr = value("descr")?;
+        let descr = descr
+            .strip_prefix('\'')
+            .and_then(|descr| descr.split('\'').next())
+            .ok_or_else(|| format_err!("Unsupported dtype {descr:?}!"))?;
+        let (scalar_type, byte_swap) = descr_to_scalar_type(descr)?;
+        let fortran_order = value("fortran_order")?.starts_with("True");
+        let shape = value("shape")?;
+        let shape = shape
+            .strip_prefix('(')
+            .and_then(|shape| shape.split(')').next())
+            .ok_or_else(|| format_err!("Expected shape tuple, found {shape:?}!"))?;
+        let shape = shape
+            .split(',')
+            .map(|dim| dim.trim().trim_end_matches('L'))
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| dim.parse())
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            scalar_type,
+            byte_swap,
+            fortran_order,
+            shape,
+        })
+    }
+}
+
+fn descr_to_scalar_type(descr: &str) -> Result<(ScalarType, bool)> {
+    use ScalarType::*;
+    let byte_swap = match descr.get(..1) {
+        Some("<") => cfg!(target_endian = "big"),
+        Some(">") => cfg!(target_endian = "little"),
+        Some("|" | "=") => false,
+        _ => bail!("Unsupported dtype {descr:?}!"),
+    };
+    let scalar_type = match &descr[1..] {
+        "b1" | "u1" => U8,
+        "i1" => I8,
+        "u2" => U16,
+        "i2" => I16,
+        "f2" => F16,
+        "u4" => U32,
+        "i4" => I32,
+        "f4" => F32,
+        "u8" => U64,
+        "i8" => I64,
+        "f8" => F64,
+        _ => bail!("Unsupported dtype {descr:?}!"),
+    };
+    Ok((scalar_type, byte_swap))
+}
+
+/// The magic string, version, header len and header, padded to 64 bytes.
+fn npy_header(scalar_type: ScalarType, len: usize, shape: &[usize]) -> Result<Vec<u8>> {
+    use ScalarType::*;
+    if shape.iter().product::<usize>() != len {
+        bail!("Expected shape with len {len}, found {shape:?}!");
+    }
+    let kind = match scalar_type {
+        U8 | U16 | U32 | U64 => 'u',
+        I8 | I16 | I32 | I64 => 'i',
+        F16 | F32 | F64 => 'f',
+        _ => bail!("{scalar_type:?} is not supported by NumPy!"),
+    };
+    let order = if scalar_type.size() == 1 {
+        '|'
+    } else if cfg!(target_endian = "little") {
+        '<'
+    } else {
+        '>'
+    };
+    let shape = match shape {
+        [dim] => format!("({dim},)"),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let dict = format!(
+        "{{'descr': '{order}{kind}{}', 'fortran_order': False, 'shape': {shape}, }}",
+        scalar_type.size()
+    );
+    let padded_len = |prefix_len: usize| {
+        let len = prefix_len + dict.len() + 1;
+        dict.len() + 1 + (64 - len % 64) % 64
+    };
+    let mut header = NPY_MAGIC.to_vec();
+    let header_len = if padded_len(10) <= u16::MAX as usize {
+        let header_len = padded_len(10);
+        header.extend_from_slice(&[1, 0]);
+        header.extend_from_slice(&(header_len as u16).to_le_bytes());
+        header_len
+    } else {
+        let header_len = padded_len(12);
+        header.extend_from_slice(&[2, 0]);
+        header.extend_from_slice(&u32::try_from(header_len)?.to_le_bytes());
+        header_len
+    };
+    header.extend_from_slice(dict.as_bytes());
+    header.resize(header.len() + header_len - dict.len() - 1, b' ');
+    header.push(b'\n');
+    Ok(header)
+}
+
+fn read_npy_data<T: Scalar>(
+    device: Device,
+    header: &NpyHeader,
+    reader: &mut impl Read,
+) -> Result<Buffer<T>> {
+    let shape = &header.shape;
+    let len = shape
+        .iter()
+        .try_fold(1usize, |len, dim| len.checked_mul(*dim))
+        .filter(|len| len.checked_mul(size_of::<T>()).is_some())
+        .ok_or_else(|| format_err!("Shape {shape:?} in .npy header overflows!"))?;
+    let output = read_buffer(device, len, header.byte_swap, reader)?;
+    if header.fortran_order && shape.len() > 1 {
+        let reversed_shape: Vec<usize> = shape.iter().rev().copied().collect();
+        let axes: Vec<usize> = (0..shape.len()).rev().collect();
+        return output.permute(&reversed_shape, &axes);
+    }
+    Ok(output)
+}
+
+/// Reads `len` elements, copying to the device in chunks.
+pub(super) fn read_buffer<T: Scalar>(
+    device: Device,
+    len: usize,
+    byte_swap: bool,
+    reader: &mut impl Read,
+) -> Result<Buffer<T>> {
+    let mut output = unsafe { Buffer::<T>::uninit(device, len)? };
+    let read_chunk = |reader: &mut dyn Read, chunk: &mut [T]| -> Result<()> {
+        reader.read_exact(bytemuck::cast_slice_mut(chunk))?;
+        if byte_swap {
+            swap_bytes(chunk);
+        }
+        Ok(())
+    };
+    if let Some(y) = output.as_host_slice_mut() {
+        read_chunk(reader, y)?;
+    } else {
+        let chunk_len = (CHUNK_BYTES / size_of::<T>()).min(len);
+        let mut chunk = vec![T::default(); chunk_len];
+        for offset in (0..len).step_by(chunk_len.max(1)) {
+            let chunk = &mut chunk[..chunk_len.min(len - offset)];
+            read_chunk(reader, chunk)?;
+            output
+                .slice_mut(offset..offset + chunk.len())
+                .unwrap()
+                .copy_from_slice(&Slice::from(&*chunk))?;
+        }
+    }
+    Ok(output)
+}
+
+/// Writes the bytes of `x`, copying from the device in chunks.
+pub(super) fn write_scalar_slice(
+    x: ScalarSlice,
+    byte_swap: bool,
+    writer: &mut impl Write,
+) -> Result<()> {
+    macro_wrap!(paste! {
+        match x.scalar_type() {
+            macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, f8e4m3, f8e5m2] {
+                ScalarType::[<$T:upper>] => {
+                    let x = Slice::<$T>::try_from(x).ok().unwrap();
+                    write_slice(x, byte_swap, writer)
+                }
+            })
+            _ => unreachable!(),
+        }
+    })
+}
+
+fn write_slice<T: Scalar>(x: Slice<T>, byte_swap: bool, writer: &mut impl Write) -> Result<()> {
+    if let Some(x) = x.as_host_slice().filter(|_| !byte_swap) {
+        writer.write_all(bytemuck::cast_slice(x))?;
+        return Ok(());
+    }
+    let chunk_len = (CHUNK_BYTES / size_of::<T>()).min(x.len());
+    let mut chunk = vec![T::default(); chunk_len];
+    for offset in (0..x.len()).step_by(chunk_len.max(1)) {
+        let chunk = &mut chunk[..chunk_len.min(x.len() - offset)];
+        SliceMut::from(&mut *chunk)
+            .copy_from_slice(&x.slice(offset..offset + chunk.len()).unwrap())?;
+        if byte_swap {
+            swap_bytes(chunk);
+        }
+        writer.write_all(bytemuck::cast_slice(chunk))?;
+    }
+    Ok(())
+}
+
+fn swap_bytes<T: Scalar>(x: &mut [T]) {
+    for x in x.iter_mut() {
+        bytemuck::bytes_of_mut(x).reverse();
+    }
+}
+
+/// Version 2.0, no zip64.
+const ZIP_VERSION: u16 = 20;
+/// Data descriptor and utf-8 names.
+const ZIP_FLAGS: u16 = 0x0808;
+/// 1980-01-01
+const ZIP_DATE: u16 = 0x21;
+
+/// Tracks the position and crc of a zip archive.
+struct ZipWriter<W> {
+    writer: W,
+    position: u64,
+    crc: Crc,
+}
+
+impl<W: Write> Write for ZipWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.crc.update(&buf[..n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+struct ZipEntry {
+    name: String,
+    method: u16,
+    compressed_size: u64,
+    offset: u64,
+}
+
+fn u16_le(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_le(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_le(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// Reads the central directory, including zip64 extensions.
+fn read_zip_entries<R: Read + Seek>(reader: &mut R) -> Result<Vec<ZipEntry>> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    // end of central directory, zip64 locator and max comment
+    let tail_len = file_len.min(22 + 20 + u16::MAX as u64);
+    reader.seek(SeekFrom::Start(file_len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    reader.read_exact(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|i| &tail[*i..*i + 4] == b"PK\x05\x06")
+        .ok_or_else(|| format_err!("Expected end of central directory in npz archive!"))?;
+    let mut entries = u16_le(&tail, end + 10) as u64;
+    let mut directory_len = u32_le(&tail, end + 12) as u64;
+    let mut directory_offset = u32_le(&tail, end + 16) as u64;
+    if entries == u16::MAX as u64
+        || directory_len == u32::MAX as u64
+        || directory_offset == u32::MAX as u64
+    {
+        if end < 20 || &tail[end - 20..end - 16] != b"PK\x06\x07" {
+            bail!("Expected zip64 end of central directory locator in npz archive!");
+        }
+        reader.seek(SeekFrom::Start(u64_le(&tail, end - 12)))?;
+        let mut end64 = [0; 56];
+        reader.read_exact(&mut end64)?;
+        if &end64[..4] != b"PK\x06\x06" {
+            bail!("Expected zip64 end of central directory in npz archive!");
+        }
+        entries = u64_le(&end64, 32);
+        directory_len = u64_le(&end64, 40);
+        directory_offset = u64_le(&end64, 48);
+    }
+    reader.seek(SeekFrom::Start(directory_offset))?;
+    let mut directory = vec![0; directory_len.try_into()?];
+    reader.read_exact(&mut directory)?;
+    let mut output = Vec::new();
+    let mut i = 0;
+    while output.len() < entries as usize {
+        if directory.len() < i + 46 || &directory[i..i + 4] != b"PK\x01\x02" {
+            bail!("Expected central directory file header in npz archive!");
+        }
+        let method = u16_le(&directory, i + 10);
+        let mut compressed_size = u32_le(&directory, i + 20) as u64;
+        let uncompressed_size = u32_le(&directory, i + 24);
+        let name_len = u16_le(&directory, i + 28) as usize;
+        let extra_len = u16_le(&directory, i + 30) as usize;
+        let comment_len = u16_le(&directory, i + 32) as usize;
+        let mut offset = u32_le(&directory, i + 42) as u64;
+        let name_start = i + 46;
+        let extra_start = name_start + name_len;
+        i = extra_start + extra_len + comment_len;
+        if directory.len() < i {
+            bail!("Expected central directory file header in npz archive!");
+        }
+        let name = std::str::from_utf8(&directory[name_start..extra_start])?.to_string();
+        let mut extra = &directory[extra_start..extra_start + extra_len];
+        while extra.len() >= 4 {
+            let (id, len) = (u16_le(extra, 0), u16_le(extra, 2) as usize);
+            let field = extra
+                .get(4..4 + len)
+                .ok_or_else(|| format_err!("Invalid extra field for {name:?} in npz archive!"))?;
+            if id == 1 {
+                // zip64 fields are only present if saturated
+                let mut values = field.chunks_exact(8).map(|x| u64_le(x, 0));
+                if uncompressed_size == u32::MAX {
+                    values.next();
+                }
+                if compressed_size == u32::MAX as u64 {
+                    compressed_size = values.next().unwrap_or(compressed_size);
+                }
+                if offset == u32::MAX as u64 {
+                    offset = values.next().unwrap_or(offset);
+                }
+            }
+            extra = &extra[4 + len..];
+        }
+        output.push(ZipEntry {
+            name,
+            method,
+            compressed_size,
+            offset,
+        });
+    }
+    Ok(output)
+}
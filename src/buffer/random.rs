@@ -0,0 +1,525 @@
+#[cfg(doc)]
+use super::error::{DeviceBufferTooLarge, DeviceLost, OutOfDeviceMemory};
+use super::{Buffer, BufferBase, DataOwned, SliceMut};
+#[cfg(doc)]
+use crate::device::Features;
+#[cfg(feature = "device")]
+use crate::macros::module;
+use crate::{
+    device::Device,
+    scalar::{Scalar, ScalarType},
+};
+use anyhow::{bail, Result};
+use dry::macro_for;
+use half::{bf16, f16};
+use krnl_core::random::{
+    affine_f32, affine_f64, index_add, next_down_bf16, next_down_f16, next_down_f32, next_down_f64,
+    normal_f32, normal_f64, random_bits, uniform_f32, uniform_f64,
+};
+#[cfg(feature = "device")]
+use paste::paste;
+use std::cmp::Ordering;
+
+impl<T: Scalar, S: DataOwned<Elem = T>> BufferBase<S> {
+    /** Create a buffer with random values uniformly distributed in [`low`, `high`).
+
+    Values are generated with [Philox4x32-10](krnl_core::random), and element `i` only depends on `seed`
+    and `offset + i`, so `offset` can be used to continue a stream or to split it into chunks. The random bits
+    are identical on the host and on devices.
+
+    Integers are generated with `bits % (high - low)`, which has a slight bias for large ranges. Floats are
+    generated with `low + (high - low) * x`, where `x` is in [0, 1), and values that round to `high` are
+    replaced with the largest value less than `high`. The multiply and add are rounded separately on devices,
+    so values are identical on the host and on devices, except that devices may flush subnormals to zero.
+
+    # Errors
+    - `low` is not less than `high`.
+    - [`DeviceLost`]
+    - [`DeviceBufferTooLarge`]
+    - [`OutOfDeviceMemory`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn random_uniform(
+        device: Device,
+        len: usize,
+        low: T,
+        high: T,
+        seed: u64,
+        offset: u64,
+    ) -> Result<Self> {
+        if low.partial_cmp(&high) != Some(Ordering::Less) {
+            bail!("Expected low < high, found {low:?} and {high:?}!");
+        }
+        random(device, len, Distribution::Uniform(low, high), seed, offset).map(Self::from_buffer)
+    }
+    /** Create a buffer with random values from a normal distribution.
+
+    Only implemented for floats. Values are generated with the Box-Muller transform, computed in 32 bit fixed
+    point with an absolute error of about 2^-29, so that they are identical on the host and on devices. See
+    [`normal_q29`](krnl_core::random::normal_q29).
+
+    See [`random_uniform`](BufferBase::random_uniform).
+
+    # Errors
+    - `T` is not a float.
+    - `std` is negative.
+    - See [`random_uniform`](BufferBase::random_uniform). */
+    pub fn random_normal(
+        device: Device,
+        len: usize,
+        mean: T,
+        std: T,
+        seed: u64,
+        offset: u64,
+    ) -> Result<Self> {
+        if !is_float(T::SCALAR_TYPE) {
+            bail!("random_normal is not implemented for {:?}!", T::SCALAR_TYPE);
+        }
+        if !matches!(
+            std.partial_cmp(&T::zero()),
+            Some(Ordering::Greater | Ordering::Equal)
+        ) {
+            bail!("Expected std >= 0, found {std:?}!");
+        }
+        random(device, len, Distribution::Normal(mean, std), seed, offset).map(Self::from_buffer)
+    }
+    /** Create a buffer of 1's with probability `p`, and 0's otherwise.
+
+    See [`random_uniform`](BufferBase::random_uniform).
+
+    # Errors
+    - `p` is not in [0, 1].
+    - See [`random_uniform`](BufferBase::random_uniform). */
+    pub fn random_bernoulli(
+        device: Device,
+        len: usize,
+        p: f32,
+        seed: u64,
+        offset: u64,
+    ) -> Result<Self> {
+        if !(0. ..=1.).contains(&p) {
+            bail!("Expected p in [0, 1], found {p}!");
+        }
+        random(device, len, Distribution::Bernoulli(p), seed, offset).map(Self::from_buffer)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Distribution<T> {
+    Uniform(T, T),
+    Normal(T, T),
+    Bernoulli(f32),
+}
+
+impl<T: Scalar> Distribution<T> {
+    fn cast<Y: Scalar>(self) -> Distribution<Y> {
+        match self {
+            Self::Uniform(low, high) => Distribution::Uniform(low.cast(), high.cast()),
+            Self::Normal(mean, std) => Distribution::Normal(mean.cast(), std.cast()),
+            Self::Bernoulli(p) => Distribution::Bernoulli(p),
+        }
+    }
+}
+
+fn is_float(scalar_type: ScalarType) -> bool {
+    use ScalarType::*;
+    matches!(scalar_type, F16 | BF16 | F32 | F64)
+}
+
+/// Splits into `[lo, hi]`.
+fn split_u64(x: u64) -> [u32; 2] {
+    [x as u32, (x >> 32) as u32]
+}
+
+fn random<T: Scalar>(
+    device: Device,
+    len: usize,
+    distribution: Distribution<T>,
+    seed: u64,
+    offset: u64,
+) -> Result<Buffer<T>> {
+    let mut output = unsafe { Buffer::uninit(device, len)? };
+    if output.is_empty() {
+        return Ok(output);
+    }
+    if len as u64 > u64::from(u32::MAX) + 1 {
+        bail!("Expected len <= 2^32, found {len}!");
+    }
+    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+            let y = SliceMut::<$X>::try_from(output.as_scalar_slice_mut()).ok().unwrap();
+            <$X as Random>::random(y, distribution.cast(), seed, offset)?;
+            return Ok(output);
+        }
+    });
+    bail!("Random is not implemented for {:?}!", T::SCALAR_TYPE)
+}
+
+trait Random: Scalar {
+    fn random(
+        y: SliceMut<Self>,
+        distribution: Distribution<Self>,
+        seed: u64,
+        offset: u64,
+    ) -> Result<()>;
+}
+
+/// Fills `y` on the host.
+fn host_random<T: Scalar>(y: &mut [T], seed: u64, offset: u64, mut f: impl FnMut([u32; 4]) -> T) {
+    let seed = split_u64(seed);
+    let offset = split_u64(offset);
+    for (i, y) in y.iter_mut().enumerate() {
+        *y = f(random_bits(seed, index_add(offset, i as u32)));
+    }
+}
+
+macro_rules! impl_random_int {
+    ($($X:ident => $U:ident),* $(,)?) => {
+        $(
+            impl Random for $X {
+                #[allow(unused_mut)]
+                fn random(
+                    mut y: SliceMut<Self>,
+                    distribution: Distribution<Self>,
+                    seed: u64,
+                    offset: u64,
+                ) -> Result<()> {
+                    let (low, span) = match distribution {
+                        Distribution::Uniform(low, high) => (low as i64 as $U, (high as i64).wrapping_sub(low as i64) as $U),
+                        Distribution::Bernoulli(p) => return bernoulli(y, p, seed, offset),
+                        Distribution::Normal(..) => unreachable!(),
+                    };
+                    if let Some(y) = y.as_host_slice_mut() {
+                        host_random(y, seed, offset, |bits| {
+                            low.wrapping_add(<$U as RandomBits>::random_bits(bits) % span) as $X
+                        });
+                        return Ok(());
+                    }
+                    #[cfg(feature = "device")]
+                    {
+                        let [seed_lo, seed_hi] = split_u64(seed);
+                        let [offset_lo, offset_hi] = split_u64(offset);
+                        let builder = paste! {
+                            kernels::[<random_uniform_ $X>]::builder()?
+                        };
+                        builder
+                            .build(y.device())?
+                            .dispatch(seed_lo, seed_hi, offset_lo, offset_hi, low, span, y)
+                    }
+                    #[cfg(not(feature = "device"))]
+                    {
+                        unreachable!()
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_random_int! {
+    u8 => u32,
+    i8 => u32,
+    u16 => u32,
+    i16 => u32,
+    u32 => u32,
+    i32 => u32,
+    u64 => u64,
+    i64 => u64,
+}
+
+trait RandomBits {
+    fn random_bits(bits: [u32; 4]) -> Self;
+}
+
+impl RandomBits for u32 {
+    fn random_bits(bits: [u32; 4]) -> Self {
+        bits[0]
+    }
+}
+
+impl RandomBits for u64 {
+    fn random_bits(bits: [u32; 4]) -> Self {
+        (u64::from(bits[1]) << 32) | u64::from(bits[0])
+    }
+}
+
+macro_rules! impl_random_float {
+    ($($X:ident => $A:ident: $next_down:ident),* $(,)?) => {
+        $(
+            impl Random for $X {
+                #[allow(unused_mut)]
+                fn random(
+                    mut y: SliceMut<Self>,
+                    distribution: Distribution<Self>,
+                    seed: u64,
+                    offset: u64,
+                ) -> Result<()> {
+                    // uniform values in [high, inf) are replaced with below
+                    let (alpha, beta, high, below, normal) = match distribution {
+                        Distribution::Uniform(low, high) => {
+                            let low = low.cast::<$A>();
+                            let below = $next_down(high).cast::<$A>();
+                            let high = high.cast::<$A>();
+                            (low, high - low, high, below, false)
+                        }
+                        Distribution::Normal(mean, std) => {
+                            (mean.cast::<$A>(), std.cast::<$A>(), 0., 0., true)
+                        }
+                        Distribution::Bernoulli(p) => return bernoulli(y, p, seed, offset),
+                    };
+                    if let Some(y) = y.as_host_slice_mut() {
+                        host_random(y, seed, offset, |bits| {
+                            if normal {
+                                <$A as RandomFloat>::affine(alpha, beta, <$A as RandomFloat>::normal(bits)).cast()
+                            } else {
+                                let y = <$A as RandomFloat>::affine(alpha, beta, <$A as RandomFloat>::uniform(bits)).cast::<Self>();
+                                if y.cast::<$A>() >= high {
+                                    below.cast()
+                                } else {
+                                    y
+                                }
+                            }
+                        });
+                        return Ok(());
+                    }
+                    #[cfg(feature = "device")]
+                    {
+                        let [seed_lo, seed_hi] = split_u64(seed);
+                        let [offset_lo, offset_hi] = split_u64(offset);
+                        let builder = paste! {
+                            kernels::[<random_ $X>]::builder()?
+                        };
+                        builder
+                            .specialize(normal as u32)
+                            .build(y.device())?
+                            .dispatch(seed_lo, seed_hi, offset_lo, offset_hi, alpha, beta, high, below, y)
+                    }
+                    #[cfg(not(feature = "device"))]
+                    {
+                        unreachable!()
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_random_float! {
+    f16 => f32: next_down_f16,
+    bf16 => f32: next_down_bf16,
+    f32 => f32: next_down_f32,
+    f64 => f64: next_down_f64,
+}
+
+trait RandomFloat {
+    fn uniform(bits: [u32; 4]) -> Self;
+    fn normal(bits: [u32; 4]) -> Self;
+    fn affine(alpha: Self, beta: Self, x: Self) -> Self;
+}
+
+impl RandomFloat for f32 {
+    fn uniform(bits: [u32; 4]) -> Self {
+        uniform_f32(bits[0])
+    }
+    fn normal(bits: [u32; 4]) -> Self {
+        normal_f32(bits[0], bits[1])
+    }
+    fn affine(alpha: Self, beta: Self, x: Self) -> Self {
+        affine_f32(alpha, beta, x)
+    }
+}
+
+impl RandomFloat for f64 {
+    fn uniform(bits: [u32; 4]) -> Self {
+        uniform_f64(bits[0], bits[1])
+    }
+    fn normal(bits: [u32; 4]) -> Self {
+        normal_f64(bits[0], bits[1])
+    }
+    fn affine(alpha: Self, beta: Self, x: Self) -> Self {
+        affine_f64(alpha, beta, x)
+    }
+}
+
+fn bernoulli<T: Scalar>(mut y: SliceMut<T>, p: f32, seed: u64, offset: u64) -> Result<()> {
+    if let Some(y) = y.as_host_slice_mut() {
+        host_random(y, seed, offset, |bits| {
+            u32::from(uniform_f32(bits[0]) < p).cast()
+        });
+        return Ok(());
+    }
+    #[cfg(feature = "device")]
+    {
+        let [seed_lo, seed_hi] = split_u64(seed);
+        let [offset_lo, offset_hi] = split_u64(offset);
+        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                let y = SliceMut::<$X>::try_from(y.as_scalar_slice_mut()).ok().unwrap();
+                let builder = paste! {
+                    kernels::[<random_bernoulli_ $X>]::builder()?
+                };
+                return builder
+                    .build(y.device())?
+                    .dispatch(seed_lo, seed_hi, offset_lo, offset_hi, p, y);
+            }
+        });
+        bail!("Bernoulli is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+    #[cfg(not(feature = "device"))]
+    {
+        unreachable!()
+    }
+}
+
+#[cfg(feature = "device")]
+#[module]
+#[krnl(crate=crate)]
+mod kernels {
+    use dry::macro_for;
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl_core;
+    use krnl_core::macros::kernel;
+    #[cfg(target_arch = "spirv")]
+    use krnl_core::{
+        half::{bf16, f16},
+        kernel::ItemKernel,
+        random::{
+            affine_f32, affine_f64, index_add, normal_f32, normal_f64, random_bits, uniform_f32,
+            uniform_f64,
+        },
+        scalar::Scalar,
+    };
+    use paste::paste;
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn item_bits(kernel: &ItemKernel, seed: [u32; 2], offset: [u32; 2]) -> [u32; 4] {
+        random_bits(seed, index_add(offset, kernel.item_id() as u32))
+    }
+
+    macro_rules! impl_random_int {
+        ($($X:ident => $U:ident),* $(,)?) => {
+            $(
+                paste! {
+                    #[kernel]
+                    pub fn [<random_uniform_ $X>](
+                        seed_lo: u32,
+                        seed_hi: u32,
+                        offset_lo: u32,
+                        offset_hi: u32,
+                        low: $U,
+                        span: $U,
+                        #[item] y: &mut $X,
+                    ) {
+                        let bits = item_bits(&kernel, [seed_lo, seed_hi], [offset_lo, offset_hi]);
+                        *y = low.wrapping_add([<random_bits_ $U>](bits) % span) as $X;
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_random_int! {
+        u8 => u32,
+        i8 => u32,
+        u16 => u32,
+        i16 => u32,
+        u32 => u32,
+        i32 => u32,
+        u64 => u64,
+        i64 => u64,
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn random_bits_u32(bits: [u32; 4]) -> u32 {
+        bits[0]
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn random_bits_u64(bits: [u32; 4]) -> u64 {
+        ((bits[1] as u64) << 32) | bits[0] as u64
+    }
+
+    macro_rules! impl_random_float {
+        ($($X:ident => $A:ident),* $(,)?) => {
+            $(
+                paste! {
+                    #[kernel]
+                    pub fn [<random_ $X>]<const NORMAL: u32>(
+                        seed_lo: u32,
+                        seed_hi: u32,
+                        offset_lo: u32,
+                        offset_hi: u32,
+                        alpha: $A,
+                        beta: $A,
+                        high: $A,
+                        below: $A,
+                        #[item] y: &mut $X,
+                    ) {
+                        let bits = item_bits(&kernel, [seed_lo, seed_hi], [offset_lo, offset_hi]);
+                        *y = if NORMAL == 1 {
+                            [<affine_ $A>](alpha, beta, [<normal_ $A _bits>](bits)).cast()
+                        } else {
+                            let y = [<affine_ $A>](alpha, beta, [<uniform_ $A _bits>](bits)).cast::<$X>();
+                            if y.cast::<$A>() >= high {
+                                below.cast()
+                            } else {
+                                y
+                            }
+                        };
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_random_float! {
+        f16 => f32,
+        bf16 => f32,
+        f32 => f32,
+        f64 => f64,
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn uniform_f32_bits(bits: [u32; 4]) -> f32 {
+        uniform_f32(bits[0])
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn normal_f32_bits(bits: [u32; 4]) -> f32 {
+        normal_f32(bits[0], bits[1])
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn uniform_f64_bits(bits: [u32; 4]) -> f64 {
+        uniform_f64(bits[0], bits[1])
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn normal_f64_bits(bits: [u32; 4]) -> f64 {
+        normal_f64(bits[0], bits[1])
+    }
+
+    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        paste! {
+            #[kernel]
+            pub fn [<random_bernoulli_ $X>](
+                seed_lo: u32,
+                seed_hi: u32,
+                offset_lo: u32,
+                offset_hi: u32,
+                p: f32,
+                #[item] y: &mut $X,
+            ) {
+                let bits = item_bits(&kernel, [seed_lo, seed_hi], [offset_lo, offset_hi]);
+                *y = ((uniform_f32(bits[0]) < p) as u32).cast();
+            }
+        }
+    });
+}
@@ -0,0 +1,803 @@
+#[cfg(doc)]
+use super::error::DeviceLost;
+#[cfg(feature = "device")]
+use super::Buffer;
+use super::{BufferBase, Data, Slice};
+#[cfg(all(not(feature = "device"), doc))]
+use crate::device::Features;
+use crate::scalar::{Scalar, ScalarElem};
+#[cfg(feature = "device")]
+use crate::{
+    device::{Device, Features},
+    macros::module,
+    scalar::ScalarType,
+};
+use anyhow::{bail, Result};
+use dry::macro_for;
+use half::{bf16, f16};
+#[cfg(feature = "device")]
+use paste::paste;
+
+impl<T: Scalar, S: Data<Elem = T>> BufferBase<S> {
+    /** Sums the elements.
+
+    Integers wrap on overflow. [`f16`] and [`bf16`] are accumulated as [`f32`].
+
+    Returns 0 if the buffer is empty.
+
+    # Errors
+    - [`DeviceLost`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn sum(&self) -> Result<T> {
+        Ok(self.as_slice().reduce(ReduceOp::Sum)?.cast())
+    }
+    /** The minimum element.
+
+    The result is unspecified if the buffer contains NaN.
+
+    # Errors
+    - The buffer is empty.
+    - See [`.sum()`](BufferBase::sum). */
+    pub fn min(&self) -> Result<T> {
+        if self.is_empty() {
+            bail!("Can not compute the min of an empty buffer!");
+        }
+        Ok(self.as_slice().reduce(ReduceOp::Min)?.cast())
+    }
+    /** The maximum element.
+
+    See [`.min()`](BufferBase::min). */
+    pub fn max(&self) -> Result<T> {
+        if self.is_empty() {
+            bail!("Can not compute the max of an empty buffer!");
+        }
+        Ok(self.as_slice().reduce(ReduceOp::Max)?.cast())
+    }
+    /** The index of the minimum element.
+
+    If there are several minimums, the first index is returned.
+
+    See [`.min()`](BufferBase::min). */
+    pub fn argmin(&self) -> Result<usize> {
+        if self.is_empty() {
+            bail!("Can not compute the argmin of an empty buffer!");
+        }
+        self.as_slice().arg_reduce(ReduceOp::Min)
+    }
+    /** The index of the maximum element.
+
+    If there are several maximums, the first index is returned.
+
+    See [`.min()`](BufferBase::min). */
+    pub fn argmax(&self) -> Result<usize> {
+        if self.is_empty() {
+            bail!("Can not compute the argmax of an empty buffer!");
+        }
+        self.as_slice().arg_reduce(ReduceOp::Max)
+    }
+    /** The mean of the elements.
+
+    The sum is computed as in [`.sum()`](BufferBase::sum), then divided by the length
+    as [`f64`]. Integer means are truncated.
+
+    # Errors
+    - The buffer is empty.
+    - See [`.sum()`](BufferBase::sum). */
+    pub fn mean(&self) -> Result<T> {
+        if self.is_empty() {
+            bail!("Can not compute the mean of an empty buffer!");
+        }
+        let sum = self.as_slice().reduce(ReduceOp::Sum)?.cast::<f64>();
+        Ok((sum / self.len() as f64).cast())
+    }
+}
+
+// Must match the ops in kernels.
+#[derive(Clone, Copy, Debug)]
+#[repr(u32)]
+enum ReduceOp {
+    Sum = 1,
+    Min = 2,
+    Max = 3,
+}
+
+impl<T: Scalar> Slice<'_, T> {
+    fn reduce(&self, op: ReduceOp) -> Result<ScalarElem> {
+        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            if let Ok(x) = Slice::<$X>::try_from(self.as_scalar_slice()) {
+                return <$X as Reduce>::reduce(x, op).map(Into::into);
+            }
+        });
+        bail!("{op:?} is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+    fn arg_reduce(&self, op: ReduceOp) -> Result<usize> {
+        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            if let Ok(x) = Slice::<$X>::try_from(self.as_scalar_slice()) {
+                return <$X as Reduce>::arg_reduce(x, op);
+            }
+        });
+        bail!("{op:?} is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+}
+
+trait Accumulator: Scalar {
+    fn identity(op: ReduceOp) -> Self;
+    fn reduce(self, x: Self, op: ReduceOp) -> Self;
+}
+
+macro_for!($A in [u32, i32, u64, i64] {
+    impl Accumulator for $A {
+        fn identity(op: ReduceOp) -> Self {
+            match op {
+                ReduceOp::Sum => 0,
+                ReduceOp::Min => $A::MAX,
+                ReduceOp::Max => $A::MIN,
+            }
+        }
+        fn reduce(self, x: Self, op: ReduceOp) -> Self {
+            match op {
+                ReduceOp::Sum => self.wrapping_add(x),
+                ReduceOp::Min => self.min(x),
+                ReduceOp::Max => self.max(x),
+            }
+        }
+    }
+});
+
+macro_for!($A in [f32, f64] {
+    impl Accumulator for $A {
+        fn identity(op: ReduceOp) -> Self {
+            match op {
+                ReduceOp::Sum => 0.,
+                ReduceOp::Min => $A::INFINITY,
+                ReduceOp::Max => $A::NEG_INFINITY,
+            }
+        }
+        fn reduce(self, x: Self, op: ReduceOp) -> Self {
+            match op {
+                ReduceOp::Sum => self + x,
+                ReduceOp::Min => if x < self { x } else { self },
+                ReduceOp::Max => if x > self { x } else { self },
+            }
+        }
+    }
+});
+
+trait Reduce: Scalar {
+    type Acc: Accumulator;
+    fn reduce(x: Slice<Self>, op: ReduceOp) -> Result<Self::Acc> {
+        if let Some(x) = x.as_host_slice() {
+            return Ok(x
+                .iter()
+                .fold(Self::Acc::identity(op), |acc, x| acc.reduce(x.cast(), op)));
+        }
+        #[cfg(feature = "device")]
+        {
+            Self::device_reduce(x, op)
+        }
+        #[cfg(not(feature = "device"))]
+        {
+            unreachable!()
+        }
+    }
+    fn arg_reduce(x: Slice<Self>, op: ReduceOp) -> Result<usize> {
+        if let Some(x) = x.as_host_slice() {
+            let mut output = (Self::Acc::identity(op), 0);
+            for (i, x) in x.iter().enumerate() {
+                let x = x.cast::<Self::Acc>();
+                let replace = match op {
+                    ReduceOp::Min => x < output.0,
+                    ReduceOp::Max => x > output.0,
+                    ReduceOp::Sum => unreachable!(),
+                };
+                if i == 0 || replace {
+                    output = (x, i);
+                }
+            }
+            return Ok(output.1);
+        }
+        #[cfg(feature = "device")]
+        {
+            Self::device_arg_reduce(x, op)
+        }
+        #[cfg(not(feature = "device"))]
+        {
+            unreachable!()
+        }
+    }
+    #[cfg(feature = "device")]
+    fn device_reduce(x: Slice<Self>, op: ReduceOp) -> Result<Self::Acc>;
+    #[cfg(feature = "device")]
+    fn device_arg_reduce(x: Slice<Self>, op: ReduceOp) -> Result<usize>;
+}
+
+/// Threads, groups, and whether to use subgroup operations.
+#[cfg(feature = "device")]
+fn reduce_dims<A: Scalar>(device: &Device, len: usize) -> (u32, u32, bool) {
+    let info = device.info().unwrap();
+    let threads = info.default_threads();
+    // Limit groups to threads so that the partials can be reduced by a single group.
+    let groups =
+        ((len + threads as usize - 1) / threads as usize).clamp(1, threads as usize) as u32;
+    let mut subgroup_features = Features::SUBGROUP_BASIC | Features::SUBGROUP_ARITHMETIC;
+    if matches!(A::SCALAR_TYPE, ScalarType::U64 | ScalarType::I64) {
+        subgroup_features |= Features::SUBGROUP_EXTENDED_TYPES;
+    }
+    let subgroup = info.features().contains(subgroup_features);
+    (threads, groups, subgroup)
+}
+
+#[cfg(feature = "device")]
+macro_rules! dispatch_reduce {
+    ($subgroup:expr, $kernel:ident, $kernel_subgroup:ident, $device:expr, $threads:expr, $groups:expr, $op:expr, $($arg:expr),* $(,)?) => {
+        if $subgroup {
+            kernels::$kernel_subgroup::builder()?
+                .specialize($threads, $op as u32)
+                .with_threads($threads)
+                .build($device)?
+                .with_groups($groups)
+                .dispatch($($arg),*)
+        } else {
+            kernels::$kernel::builder()?
+                .specialize($threads, $op as u32)
+                .with_threads($threads)
+                .build($device)?
+                .with_groups($groups)
+                .dispatch($($arg),*)
+        }
+    };
+}
+
+macro_rules! impl_reduce {
+    ($($X:ident => $A:ident),* $(,)?) => {
+        $(
+            impl Reduce for $X {
+                type Acc = $A;
+                #[cfg(feature = "device")]
+                fn device_reduce(x: Slice<Self>, op: ReduceOp) -> Result<$A> {
+                    if x.is_empty() {
+                        return Ok($A::identity(op));
+                    }
+                    let device = x.device();
+                    let (threads, groups, subgroup) = reduce_dims::<$A>(&device, x.len());
+                    let mut y = unsafe { Buffer::<$A>::uninit(device.clone(), groups as usize)? };
+                    paste! {
+                        dispatch_reduce!(
+                            subgroup,
+                            [<reduce_ $X>],
+                            [<reduce_subgroup_ $X>],
+                            device.clone(),
+                            threads,
+                            groups,
+                            op,
+                            x,
+                            y.as_slice_mut(),
+                        )?;
+                    }
+                    if groups > 1 {
+                        let mut output = unsafe { Buffer::<$A>::uninit(device.clone(), 1)? };
+                        paste! {
+                            dispatch_reduce!(
+                                subgroup,
+                                [<reduce_ $A>],
+                                [<reduce_subgroup_ $A>],
+                                device,
+                                threads,
+                                1,
+                                op,
+                                y.as_slice(),
+                                output.as_slice_mut(),
+                            )?;
+                        }
+                        y = output;
+                    }
+                    Ok(y.into_vec()?[0])
+                }
+                #[cfg(feature = "device")]
+                fn device_arg_reduce(x: Slice<Self>, op: ReduceOp) -> Result<usize> {
+                    let device = x.device();
+                    let (threads, groups, subgroup) = reduce_dims::<$A>(&device, x.len());
+                    let mut y = unsafe { Buffer::<$A>::uninit(device.clone(), groups as usize)? };
+                    let mut y_index = unsafe { Buffer::<u32>::uninit(device.clone(), groups as usize)? };
+                    paste! {
+                        dispatch_reduce!(
+                            subgroup,
+                            [<arg_reduce_ $X>],
+                            [<arg_reduce_subgroup_ $X>],
+                            device.clone(),
+                            threads,
+                            groups,
+                            op,
+                            x,
+                            y.as_slice_mut(),
+                            y_index.as_slice_mut(),
+                        )?;
+                    }
+                    if groups > 1 {
+                        let mut output = unsafe { Buffer::<$A>::uninit(device.clone(), 1)? };
+                        let mut output_index = unsafe { Buffer::<u32>::uninit(device.clone(), 1)? };
+                        paste! {
+                            dispatch_reduce!(
+                                subgroup,
+                                [<arg_reduce_partials_ $A>],
+                                [<arg_reduce_partials_subgroup_ $A>],
+                                device,
+                                threads,
+                                1,
+                                op,
+                                y.as_slice(),
+                                y_index.as_slice(),
+                                output.as_slice_mut(),
+                                output_index.as_slice_mut(),
+                            )?;
+                        }
+                        y_index = output_index;
+                    }
+                    Ok(y_index.into_vec()?[0] as usize)
+                }
+            }
+        )*
+    };
+}
+
+impl_reduce! {
+    u8 => u32,
+    i8 => i32,
+    u16 => u32,
+    i16 => i32,
+    f16 => f32,
+    bf16 => f32,
+    u32 => u32,
+    i32 => i32,
+    f32 => f32,
+    u64 => u64,
+    i64 => i64,
+    f64 => f64,
+}
+
+#[cfg(feature = "device")]
+#[module]
+#[krnl(crate=crate)]
+mod kernels {
+    #[cfg(target_arch = "spirv")]
+    use core::arch::asm;
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl_core;
+    use krnl_core::macros::kernel;
+    #[cfg(target_arch = "spirv")]
+    use krnl_core::{
+        buffer::{Slice, UnsafeIndex, UnsafeSlice},
+        half::{bf16, f16},
+        kernel::Kernel,
+        scalar::Scalar,
+        spirv_std::arch::workgroup_memory_barrier_with_group_sync as group_barrier,
+    };
+    use paste::paste;
+
+    // Must match ReduceOp.
+    #[cfg(target_arch = "spirv")]
+    const MIN: u32 = 2;
+    #[cfg(target_arch = "spirv")]
+    const MAX: u32 = 3;
+
+    #[cfg(target_arch = "spirv")]
+    trait Accumulator: Scalar {
+        fn identity(op: u32) -> Self;
+        fn reduce(self, x: Self, op: u32) -> Self;
+        unsafe fn subgroup_reduce(self, op: u32) -> Self;
+        /// Selects the lesser index if the values are equal.
+        #[inline]
+        fn arg_reduce(self, index: u32, x: Self, x_index: u32, op: u32) -> (Self, u32) {
+            let replace = if op == MIN { x < self } else { x > self };
+            if replace || (x == self && x_index < index) {
+                (x, x_index)
+            } else {
+                (self, index)
+            }
+        }
+    }
+
+    macro_rules! impl_accumulator {
+        ($($A:ident($add:literal, $min:literal, $max:literal, $lowest:expr, $highest:expr)),* $(,)?) => {
+            $(
+                #[cfg(target_arch = "spirv")]
+                impl Accumulator for $A {
+                    #[inline]
+                    fn identity(op: u32) -> Self {
+                        if op == MIN {
+                            $highest
+                        } else if op == MAX {
+                            $lowest
+                        } else {
+                            0 as $A
+                        }
+                    }
+                    #[inline]
+                    fn reduce(self, x: Self, op: u32) -> Self {
+                        if op == MIN {
+                            if x < self { x } else { self }
+                        } else if op == MAX {
+                            if x > self { x } else { self }
+                        } else {
+                            self + x
+                        }
+                    }
+                    #[inline]
+                    unsafe fn subgroup_reduce(self, op: u32) -> Self {
+                        let mut y = Self::default();
+                        if op == MIN {
+                            unsafe {
+                                asm! {
+                                    "%u32 = OpTypeInt 32 0",
+                                    "%subgroup = OpConstant %u32 3",
+                                    concat!("%y = ", $min, " _ %subgroup Reduce {x}"),
+                                    "OpStore {y} %y",
+                                    x = in(reg) self,
+                                    y = in(reg) &mut y,
+                                }
+                            }
+                        } else if op == MAX {
+                            unsafe {
+                                asm! {
+                                    "%u32 = OpTypeInt 32 0",
+                                    "%subgroup = OpConstant %u32 3",
+                                    concat!("%y = ", $max, " _ %subgroup Reduce {x}"),
+                                    "OpStore {y} %y",
+                                    x = in(reg) self,
+                                    y = in(reg) &mut y,
+                                }
+                            }
+                        } else {
+                            unsafe {
+                                asm! {
+                                    "%u32 = OpTypeInt 32 0",
+                                    "%subgroup = OpConstant %u32 3",
+                                    concat!("%y = ", $add, " _ %subgroup Reduce {x}"),
+                                    "OpStore {y} %y",
+                                    x = in(reg) self,
+                                    y = in(reg) &mut y,
+                                }
+                            }
+                        }
+                        y
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_accumulator! {
+        u32("OpGroupNonUniformIAdd", "OpGroupNonUniformUMin", "OpGroupNonUniformUMax", u32::MIN, u32::MAX),
+        i32("OpGroupNonUniformIAdd", "OpGroupNonUniformSMin", "OpGroupNonUniformSMax", i32::MIN, i32::MAX),
+        f32("OpGroupNonUniformFAdd", "OpGroupNonUniformFMin", "OpGroupNonUniformFMax", f32::NEG_INFINITY, f32::INFINITY),
+        u64("OpGroupNonUniformIAdd", "OpGroupNonUniformUMin", "OpGroupNonUniformUMax", u64::MIN, u64::MAX),
+        i64("OpGroupNonUniformIAdd", "OpGroupNonUniformSMin", "OpGroupNonUniformSMax", i64::MIN, i64::MAX),
+        f64("OpGroupNonUniformFAdd", "OpGroupNonUniformFMin", "OpGroupNonUniformFMax", f64::NEG_INFINITY, f64::INFINITY),
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn global_reduce<X: Scalar, A: Accumulator>(kernel: &Kernel, x: Slice<X>, op: u32) -> A {
+        let mut acc = A::identity(op);
+        let mut index = kernel.global_id();
+        while index < x.len() {
+            acc = acc.reduce(x[index].cast(), op);
+            index += kernel.global_threads();
+        }
+        acc
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn global_arg_reduce<X: Scalar, A: Accumulator>(
+        kernel: &Kernel,
+        x: Slice<X>,
+        op: u32,
+    ) -> (A, u32) {
+        let mut acc = (A::identity(op), u32::MAX);
+        let mut index = kernel.global_id();
+        while index < x.len() {
+            acc = acc.0.arg_reduce(acc.1, x[index].cast(), index as u32, op);
+            index += kernel.global_threads();
+        }
+        acc
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn global_arg_reduce_partials<A: Accumulator>(
+        kernel: &Kernel,
+        x: Slice<A>,
+        x_index: Slice<u32>,
+        op: u32,
+    ) -> (A, u32) {
+        let mut acc = (A::identity(op), u32::MAX);
+        let mut index = kernel.global_id();
+        while index < x.len() {
+            acc = acc.0.arg_reduce(acc.1, x[index], x_index[index], op);
+            index += kernel.global_threads();
+        }
+        acc
+    }
+
+    /// Largest power of 2 less than `threads`, or 1.
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn tree_stride(threads: usize) -> usize {
+        let mut stride = 1;
+        while stride * 2 < threads {
+            stride *= 2;
+        }
+        stride
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    unsafe fn group_reduce<A: Accumulator>(
+        kernel: &Kernel,
+        acc: A,
+        y_group: UnsafeSlice<A>,
+        op: u32,
+    ) -> A {
+        let thread_id = kernel.thread_id();
+        let threads = kernel.threads();
+        unsafe {
+            *y_group.unsafe_index_mut(thread_id) = acc;
+            group_barrier();
+        }
+        let mut stride = tree_stride(threads);
+        while stride > 0 {
+            if thread_id < stride && thread_id + stride < threads {
+                unsafe {
+                    let x = *y_group.unsafe_index(thread_id + stride);
+                    let y = y_group.unsafe_index_mut(thread_id);
+                    *y = y.reduce(x, op);
+                }
+            }
+            unsafe {
+                group_barrier();
+            }
+            stride /= 2;
+        }
+        unsafe { *y_group.unsafe_index(0) }
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    unsafe fn group_arg_reduce<A: Accumulator>(
+        kernel: &Kernel,
+        acc: (A, u32),
+        y_group: UnsafeSlice<A>,
+        y_index_group: UnsafeSlice<u32>,
+        op: u32,
+    ) -> (A, u32) {
+        let thread_id = kernel.thread_id();
+        let threads = kernel.threads();
+        unsafe {
+            *y_group.unsafe_index_mut(thread_id) = acc.0;
+            *y_index_group.unsafe_index_mut(thread_id) = acc.1;
+            group_barrier();
+        }
+        let mut stride = tree_stride(threads);
+        while stride > 0 {
+            if thread_id < stride && thread_id + stride < threads {
+                unsafe {
+                    let x = *y_group.unsafe_index(thread_id + stride);
+                    let x_index = *y_index_group.unsafe_index(thread_id + stride);
+                    let y = y_group.unsafe_index_mut(thread_id);
+                    let y_index = y_index_group.unsafe_index_mut(thread_id);
+                    (*y, *y_index) = y.arg_reduce(*y_index, x, x_index, op);
+                }
+            }
+            unsafe {
+                group_barrier();
+            }
+            stride /= 2;
+        }
+        unsafe { (*y_group.unsafe_index(0), *y_index_group.unsafe_index(0)) }
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    unsafe fn subgroup_group_reduce<A: Accumulator>(
+        kernel: &Kernel,
+        acc: A,
+        y_group: UnsafeSlice<A>,
+        op: u32,
+    ) -> A {
+        let acc = unsafe { acc.subgroup_reduce(op) };
+        if kernel.subgroup_thread_id() == 0 {
+            unsafe {
+                *y_group.unsafe_index_mut(kernel.subgroup_id()) = acc;
+            }
+        }
+        unsafe {
+            group_barrier();
+        }
+        let mut acc = A::identity(op);
+        if kernel.thread_id() == 0 {
+            let mut index = 0;
+            while index < kernel.subgroups() {
+                acc = acc.reduce(unsafe { *y_group.unsafe_index(index) }, op);
+                index += 1;
+            }
+        }
+        acc
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    unsafe fn subgroup_group_arg_reduce<A: Accumulator>(
+        kernel: &Kernel,
+        acc: (A, u32),
+        y_group: UnsafeSlice<A>,
+        y_index_group: UnsafeSlice<u32>,
+        op: u32,
+    ) -> (A, u32) {
+        let value = unsafe { acc.0.subgroup_reduce(op) };
+        let index = if acc.0 == value { acc.1 } else { u32::MAX };
+        let index = unsafe { index.subgroup_reduce(MIN) };
+        if kernel.subgroup_thread_id() == 0 {
+            unsafe {
+                *y_group.unsafe_index_mut(kernel.subgroup_id()) = value;
+                *y_index_group.unsafe_index_mut(kernel.subgroup_id()) = index;
+            }
+        }
+        unsafe {
+            group_barrier();
+        }
+        let mut acc = (A::identity(op), u32::MAX);
+        if kernel.thread_id() == 0 {
+            let mut index = 0;
+            while index < kernel.subgroups() {
+                let (x, x_index) = unsafe {
+                    (
+                        *y_group.unsafe_index(index),
+                        *y_index_group.unsafe_index(index),
+                    )
+                };
+                acc = acc.0.arg_reduce(acc.1, x, x_index, op);
+                index += 1;
+            }
+        }
+        acc
+    }
+
+    macro_rules! impl_reduce {
+        ($($X:ident => $A:ident),* $(,)?) => {
+            $(
+                paste! {
+                    #[kernel]
+                    pub fn [<reduce_ $X>]<const THREADS: u32, const OP: u32>(
+                        #[global] x: Slice<$X>,
+                        #[group] y_group: UnsafeSlice<$A, { THREADS as usize }>,
+                        #[global] y: UnsafeSlice<$A>,
+                    ) {
+                        let acc = global_reduce::<$X, $A>(&kernel, x, OP);
+                        let acc = unsafe { group_reduce(&kernel, acc, y_group, OP) };
+                        if kernel.thread_id() == 0 {
+                            unsafe {
+                                *y.unsafe_index_mut(kernel.group_id()) = acc;
+                            }
+                        }
+                    }
+
+                    #[kernel]
+                    pub fn [<reduce_subgroup_ $X>]<const THREADS: u32, const OP: u32>(
+                        #[global] x: Slice<$X>,
+                        #[group] y_group: UnsafeSlice<$A, { THREADS as usize }>,
+                        #[global] y: UnsafeSlice<$A>,
+                    ) {
+                        let acc = global_reduce::<$X, $A>(&kernel, x, OP);
+                        let acc = unsafe { subgroup_group_reduce(&kernel, acc, y_group, OP) };
+                        if kernel.thread_id() == 0 {
+                            unsafe {
+                                *y.unsafe_index_mut(kernel.group_id()) = acc;
+                            }
+                        }
+                    }
+
+                    #[kernel]
+                    pub fn [<arg_reduce_ $X>]<const THREADS: u32, const OP: u32>(
+                        #[global] x: Slice<$X>,
+                        #[group] y_group: UnsafeSlice<$A, { THREADS as usize }>,
+                        #[group] y_index_group: UnsafeSlice<u32, { THREADS as usize }>,
+                        #[global] y: UnsafeSlice<$A>,
+                        #[global] y_index: UnsafeSlice<u32>,
+                    ) {
+                        let acc = global_arg_reduce::<$X, $A>(&kernel, x, OP);
+                        let acc = unsafe { group_arg_reduce(&kernel, acc, y_group, y_index_group, OP) };
+                        if kernel.thread_id() == 0 {
+                            unsafe {
+                                *y.unsafe_index_mut(kernel.group_id()) = acc.0;
+                                *y_index.unsafe_index_mut(kernel.group_id()) = acc.1;
+                            }
+                        }
+                    }
+
+                    #[kernel]
+                    pub fn [<arg_reduce_subgroup_ $X>]<const THREADS: u32, const OP: u32>(
+                        #[global] x: Slice<$X>,
+                        #[group] y_group: UnsafeSlice<$A, { THREADS as usize }>,
+                        #[group] y_index_group: UnsafeSlice<u32, { THREADS as usize }>,
+                        #[global] y: UnsafeSlice<$A>,
+                        #[global] y_index: UnsafeSlice<u32>,
+                    ) {
+                        let acc = global_arg_reduce::<$X, $A>(&kernel, x, OP);
+                        let acc = unsafe { subgroup_group_arg_reduce(&kernel, acc, y_group, y_index_group, OP) };
+                        if kernel.thread_id() == 0 {
+                            unsafe {
+                                *y.unsafe_index_mut(kernel.group_id()) = acc.0;
+                                *y_index.unsafe_index_mut(kernel.group_id()) = acc.1;
+                            }
+                        }
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_reduce! {
+        u8 => u32,
+        i8 => i32,
+        u16 => u32,
+        i16 => i32,
+        f16 => f32,
+        bf16 => f32,
+        u32 => u32,
+        i32 => i32,
+        f32 => f32,
+        u64 => u64,
+        i64 => i64,
+        f64 => f64,
+    }
+
+    macro_rules! impl_arg_reduce_partials {
+        ($($A:ident),* $(,)?) => {
+            $(
+                paste! {
+                    #[kernel]
+                    pub fn [<arg_reduce_partials_ $A>]<const THREADS: u32, const OP: u32>(
+                        #[global] x: Slice<$A>,
+                        #[global] x_index: Slice<u32>,
+                        #[group] y_group: UnsafeSlice<$A, { THREADS as usize }>,
+                        #[group] y_index_group: UnsafeSlice<u32, { THREADS as usize }>,
+                        #[global] y: UnsafeSlice<$A>,
+                        #[global] y_index: UnsafeSlice<u32>,
+                    ) {
+                        let acc = global_arg_reduce_partials(&kernel, x, x_index, OP);
+                        let acc = unsafe { group_arg_reduce(&kernel, acc, y_group, y_index_group, OP) };
+                        if kernel.thread_id() == 0 {
+                            unsafe {
+                                *y.unsafe_index_mut(kernel.group_id()) = acc.0;
+                                *y_index.unsafe_index_mut(kernel.group_id()) = acc.1;
+                            }
+                        }
+                    }
+
+                    #[kernel]
+                    pub fn [<arg_reduce_partials_subgroup_ $A>]<const THREADS: u32, const OP: u32>(
+                        #[global] x: Slice<$A>,
+                        #[global] x_index: Slice<u32>,
+                        #[group] y_group: UnsafeSlice<$A, { THREADS as usize }>,
+                        #[group] y_index_group: UnsafeSlice<u32, { THREADS as usize }>,
+                        #[global] y: UnsafeSlice<$A>,
+                        #[global] y_index: UnsafeSlice<u32>,
+                    ) {
+                        let acc = global_arg_reduce_partials(&kernel, x, x_index, OP);
+                        let acc = unsafe { subgroup_group_arg_reduce(&kernel, acc, y_group, y_index_group, OP) };
+                        if kernel.thread_id() == 0 {
+                            unsafe {
+                                *y.unsafe_index_mut(kernel.group_id()) = acc.0;
+                                *y_index.unsafe_index_mut(kernel.group_id()) = acc.1;
+                            }
+                        }
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_arg_reduce_partials!(u32, i32, f32, u64, i64, f64);
+}
@@ -0,0 +1,216 @@
+#[cfg(doc)]
+use super::error::{DeviceBufferTooLarge, DeviceLost, OutOfDeviceMemory};
+#[cfg(doc)]
+use super::ScalarArcBuffer;
+use super::{
+    npy::{read_buffer, write_scalar_slice},
+    ScalarBuffer, ScalarBufferBase, ScalarDataOwned, ScalarSlice,
+};
+use crate::{
+    device::Device,
+    scalar::{f8e4m3, f8e5m2, ScalarType},
+};
+use anyhow::{bail, format_err, Result};
+use dry::{macro_for, macro_wrap};
+use half::{bf16, f16};
+use memmap2::Mmap;
+use paste::paste;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs::File, io::Write, path::Path};
+
+const METADATA: &str = "__metadata__";
+
+#[derive(Serialize, Deserialize)]
+struct TensorInfo {
+    dtype: String,
+    shape: Vec<usize>,
+    data_offsets: [usize; 2],
+}
+
+impl<S: ScalarDataOwned> ScalarBufferBase<S> {
+    /** Loads the tensors in a `.safetensors` file onto `device`.
+
+    The file is memory mapped, so tensors are copied into device memory without reading the
+    whole file. The file must not be modified while loading.
+
+    Use [`ScalarArcBuffer`] to share the buffers cheaply.
+
+    See [`read_safetensors`](ScalarBufferBase::read_safetensors).
+
+    # Errors
+    - The file could not be opened or mapped.
+    - See [`read_safetensors`](ScalarBufferBase::read_safetensors). */
+    pub fn load_safetensors(
+        device: Device,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<(String, Self, Vec<usize>)>> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::read_safetensors(device, &mmap)
+    }
+    /** Reads the tensors in safetensors `bytes` onto `device`.
+
+    Returns the name, buffer and shape of each tensor, in the order stored. `BOOL` is read as
+    [`u8`], `F8_E4M3` and `F8_E5M2` as [`f8e4m3`] and [`f8e5m2`].
+
+    # Errors
+    - The header is invalid, or a dtype is not supported.
+    - The size of a tensor overflows.
+    - [`DeviceLost`]
+    - [`DeviceBufferTooLarge`]
+    - [`OutOfDeviceMemory`] */
+    pub fn read_safetensors(
+        device: Device,
+        bytes: &[u8],
+    ) -> Result<Vec<(String, Self, Vec<usize>)>> {
+        let header_len: usize = bytes
+            .get(..8)
+            .map(|len| u64::from_le_bytes(len.try_into().unwrap()))
+            .ok_or_else(|| format_err!("Expected safetensors header len!"))?
+            .try_into()?;
+        let header = 8usize
+            .checked_add(header_len)
+            .and_then(|end| bytes.get(8..end))
+            .ok_or_else(|| format_err!("Expected safetensors header with len {header_len}!"))?;
+        let data = &bytes[8 + header_len..];
+        let header: BTreeMap<String, serde_json::Value> = serde_json::from_slice(header)?;
+        let mut tensors = header
+            .into_iter()
+            .filter(|(name, _)| name != METADATA)
+            .map(|(name, info)| Ok((name, serde_json::from_value::<TensorInfo>(info)?)))
+            .collect::<Result<Vec<_>>>()?;
+        tensors.sort_by_key(|(_, info)| info.data_offsets);
+        tensors
+            .into_iter()
+            .map(|(name, info)| {
+                let scalar_type = dtype_to_scalar_type(&info.dtype)?;
+                let len = info
+                    .shape
+                    .iter()
+                    .try_fold(1usize, |len, dim| len.checked_mul(*dim))
+                    .ok_or_else(|| {
+                        format_err!("Shape {:?} for {name:?} overflows!", info.shape)
+                    })?;
+                let size = len.checked_mul(scalar_type.size()).ok_or_else(|| {
+                    format_err!(
+                        "Shape {:?} for {name:?} with dtype {} overflows!",
+                        info.shape,
+                        info.dtype
+                    )
+                })?;
+                let [start, end] = info.data_offsets;
+                if start > end || end - start != size || end > data.len() {
+                    bail!(
+                        "Invalid data_offsets {:?} for {name:?} with dtype {} and shape {:?}!",
+                        info.data_offsets,
+                        info.dtype,
+                        info.shape
+                    );
+                }
+                let reader = &mut &data[start..end];
+                let byte_swap = cfg!(target_endian = "big");
+                let buffer: ScalarBuffer = macro_wrap!(paste! {
+                    match scalar_type {
+                        macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, f8e4m3, f8e5m2] {
+                            ScalarType::[<$T:upper>] => {
+                                read_buffer::<$T>(device.clone(), len, byte_swap, reader)?.into()
+                            }
+                        })
+                        _ => unreachable!(),
+                    }
+                });
+                Ok((name, Self::from_scalar_buffer(buffer), info.shape))
+            })
+            .collect()
+    }
+}
+
+impl ScalarBuffer {
+    /** Writes `tensors` in the safetensors format.
+
+    Each tensor is stored with its name and shape, in order. Data on a device is copied to the
+    host in chunks.
+
+    # Errors
+    - A name is duplicated, or is "__metadata__".
+    - A tensor is complex.
+    - The product of a shape is not the length.
+    - Writing to `writer` failed.
+    - [`DeviceLost`] */
+    pub fn write_safetensors<'a>(
+        tensors: impl IntoIterator<Item = (&'a str, ScalarSlice<'a>, &'a [usize])>,
+        mut writer: impl Write,
+    ) -> Result<()> {
+        let tensors: Vec<_> = tensors.into_iter().collect();
+        let mut header = serde_json::Map::new();
+        let mut offset = 0;
+        for (name, x, shape) in tensors.iter() {
+            if shape
+                .iter()
+                .try_fold(1usize, |len, dim| len.checked_mul(*dim))
+                != Some(x.len())
+            {
+                bail!(
+                    "Expected shape with len {} for {name:?}, found {shape:?}!",
+                    x.len()
+                );
+            }
+            let end = offset + x.len() * x.scalar_type().size();
+            let info = TensorInfo {
+                dtype: scalar_type_to_dtype(x.scalar_type())?.to_string(),
+                shape: shape.to_vec(),
+                data_offsets: [offset, end],
+            };
+            if *name == METADATA
+                || header
+                    .insert(name.to_string(), serde_json::to_value(info)?)
+                    .is_some()
+            {
+                bail!("Invalid or duplicate tensor name {name:?}!");
+            }
+            offset = end;
+        }
+        let mut header = serde_json::to_vec(&header)?;
+        // pad so that the data is aligned
+        header.resize(header.len() + (8 - header.len() % 8) % 8, b' ');
+        writer.write_all(&(header.len() as u64).to_le_bytes())?;
+        writer.write_all(&header)?;
+        let byte_swap = cfg!(target_endian = "big");
+        for (_, x, _) in tensors {
+            write_scalar_slice(x, byte_swap, &mut writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn dtype_to_scalar_type(dtype: &str) -> Result<ScalarType> {
+    use ScalarType::*;
+    Ok(match dtype {
+        "BOOL" | "U8" => U8,
+        "I8" => I8,
+        "U16" => U16,
+        "I16" => I16,
+        "F16" => F16,
+        "BF16" => BF16,
+        "U32" => U32,
+        "I32" => I32,
+        "F32" => F32,
+        "U64" => U64,
+        "I64" => I64,
+        "F64" => F64,
+        "F8_E4M3" => F8E4M3,
+        "F8_E5M2" => F8E5M2,
+        _ => bail!("Unsupported dtype {dtype:?}!"),
+    })
+}
+
+fn scalar_type_to_dtype(scalar_type: ScalarType) -> Result<&'static str> {
+    use ScalarType::*;
+    Ok(match scalar_type {
+        F8E4M3 => "F8_E4M3",
+        F8E5M2 => "F8_E5M2",
+        C32 | C64 => bail!("{scalar_type:?} is not supported by safetensors!"),
+        _ => scalar_type.as_str(),
+    })
+}
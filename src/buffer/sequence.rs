@@ -0,0 +1,220 @@
+#[cfg(doc)]
+use super::error::{DeviceBufferTooLarge, DeviceLost, OutOfDeviceMemory};
+use super::{Buffer, BufferBase, DataOwned, SliceMut};
+#[cfg(doc)]
+use crate::device::Features;
+#[cfg(feature = "device")]
+use crate::macros::module;
+use crate::{
+    device::Device,
+    scalar::{Scalar, ScalarType},
+};
+use anyhow::{bail, Result};
+use dry::macro_for;
+use half::{bf16, f16};
+#[cfg(feature = "device")]
+use paste::paste;
+
+impl<T: Scalar, S: DataOwned<Elem = T>> BufferBase<S> {
+    /** Create a buffer with `y[i] = start + step * i`.
+
+    Integers wrap on overflow. [`f16`] and [`bf16`] are computed as [`f32`].
+
+    # Errors
+    - [`DeviceLost`]
+    - [`DeviceBufferTooLarge`]
+    - [`OutOfDeviceMemory`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn arange(device: Device, start: T, step: T, len: usize) -> Result<Self> {
+        let mut output = unsafe { Buffer::uninit(device, len)? };
+        output.as_slice_mut().arange_impl(start, step)?;
+        Ok(Self::from_buffer(output))
+    }
+    /** Create a buffer of `len` evenly spaced values from `start` to `end`, inclusive.
+
+    Only implemented for floats. The first value is exactly `start`, and the last is exactly `end`.
+
+    See [`arange`](BufferBase::arange).
+
+    # Errors
+    - `T` is not a float.
+    - See [`arange`](BufferBase::arange). */
+    pub fn linspace(device: Device, start: T, end: T, len: usize) -> Result<Self> {
+        use ScalarType::*;
+        if !matches!(T::SCALAR_TYPE, F16 | BF16 | F32 | F64) {
+            bail!("linspace is not implemented for {:?}!", T::SCALAR_TYPE);
+        }
+        let mut output = unsafe { Buffer::uninit(device, len)? };
+        if len == 0 {
+            return Ok(Self::from_buffer(output));
+        } else if len == 1 {
+            output.fill(start)?;
+            return Ok(Self::from_buffer(output));
+        }
+        let step = (end.cast::<f64>() - start.cast::<f64>()) / (len - 1) as f64;
+        output
+            .as_slice_mut()
+            .arange_impl(start.cast::<f64>(), step)?;
+        output.slice_mut(len - 1..).unwrap().fill(end)?;
+        Ok(Self::from_buffer(output))
+    }
+}
+
+impl<T: Scalar> SliceMut<'_, T> {
+    /// Computes in the accumulator type of `T`, after casting `start` and `step`.
+    fn arange_impl<X: Scalar>(&mut self, start: X, step: X) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        if self.len() as u64 > u64::from(u32::MAX) + 1 {
+            bail!("Expected len <= 2^32, found {}!", self.len());
+        }
+        macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+            if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                let y = SliceMut::<$X>::try_from(self.as_scalar_slice_mut()).ok().unwrap();
+                return <$X as Arange>::arange(y, start.cast(), step.cast());
+            }
+        });
+        bail!("Arange is not implemented for {:?}!", T::SCALAR_TYPE)
+    }
+}
+
+trait Arange: Scalar {
+    type Acc: Accumulator;
+    fn arange(y: SliceMut<Self>, start: Self::Acc, step: Self::Acc) -> Result<()>;
+}
+
+macro_rules! impl_arange {
+    ($($X:ident => $A:ident),* $(,)?) => {
+        $(
+            impl Arange for $X {
+                type Acc = $A;
+                #[allow(unused_mut)]
+                fn arange(mut y: SliceMut<Self>, start: $A, step: $A) -> Result<()> {
+                    if let Some(y) = y.as_host_slice_mut() {
+                        for (i, y) in y.iter_mut().enumerate() {
+                            *y = start.arange(step, i as u32).cast();
+                        }
+                        return Ok(());
+                    }
+                    #[cfg(feature = "device")]
+                    {
+                        let builder = paste! {
+                            kernels::[<arange_ $X>]::builder()?
+                        };
+                        builder.build(y.device())?.dispatch(start, step, y)
+                    }
+                    #[cfg(not(feature = "device"))]
+                    {
+                        unreachable!()
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_arange! {
+    u8 => u32,
+    i8 => u32,
+    u16 => u32,
+    i16 => u32,
+    u32 => u32,
+    i32 => u32,
+    u64 => u64,
+    i64 => u64,
+    f16 => f32,
+    bf16 => f32,
+    f32 => f32,
+    f64 => f64,
+}
+
+trait Accumulator: Scalar {
+    /// `self + step * i`
+    fn arange(self, step: Self, i: u32) -> Self;
+}
+
+macro_for!($A in [u32, u64] {
+    impl Accumulator for $A {
+        fn arange(self, step: Self, i: u32) -> Self {
+            self.wrapping_add(step.wrapping_mul($A::from(i)))
+        }
+    }
+});
+
+macro_for!($A in [f32, f64] {
+    impl Accumulator for $A {
+        fn arange(self, step: Self, i: u32) -> Self {
+            self + step * i as $A
+        }
+    }
+});
+
+#[cfg(feature = "device")]
+#[module]
+#[krnl(crate=crate)]
+mod kernels {
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl_core;
+    use krnl_core::macros::kernel;
+    #[cfg(target_arch = "spirv")]
+    use krnl_core::{
+        half::{bf16, f16},
+        scalar::Scalar,
+    };
+    use paste::paste;
+
+    macro_rules! impl_arange {
+        ($($X:ident => $A:ident),* $(,)?) => {
+            $(
+                paste! {
+                    #[kernel]
+                    pub fn [<arange_ $X>](start: $A, step: $A, #[item] y: &mut $X) {
+                        let i = kernel.item_id() as u32;
+                        *y = [<arange_ $A>](start, step, i).cast();
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_arange! {
+        u8 => u32,
+        i8 => u32,
+        u16 => u32,
+        i16 => u32,
+        u32 => u32,
+        i32 => u32,
+        u64 => u64,
+        i64 => u64,
+        f16 => f32,
+        bf16 => f32,
+        f32 => f32,
+        f64 => f64,
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn arange_u32(start: u32, step: u32, i: u32) -> u32 {
+        start.wrapping_add(step.wrapping_mul(i))
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn arange_u64(start: u64, step: u64, i: u32) -> u64 {
+        start.wrapping_add(step.wrapping_mul(i as u64))
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn arange_f32(start: f32, step: f32, i: u32) -> f32 {
+        start + step * i as f32
+    }
+
+    #[cfg(target_arch = "spirv")]
+    #[inline]
+    fn arange_f64(start: f64, step: f64, i: u32) -> f64 {
+        start + step * i as f64
+    }
+}
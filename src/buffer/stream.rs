@@ -0,0 +1,507 @@
+use super::{
+    Buffer, BufferBase, BufferRepr, Data, DataOwned, ScalarBuffer, ScalarBufferBase,
+    ScalarBufferRepr, ScalarData, ScalarDataOwned, ScalarSlice, ScalarSliceMut, Slice, SliceMut,
+    CHUNK_BYTES,
+};
+use crate::{
+    device::Device,
+    scalar::{c32, c64, f8e4m3, f8e5m2, Scalar, ScalarType},
+};
+use dry::{macro_for, macro_wrap};
+use flate2::{
+    write::{DeflateDecoder, DeflateEncoder},
+    Compression,
+};
+use half::{bf16, f16};
+use paste::paste;
+use serde::{
+    de::{self, DeserializeSeed, Deserializer, SeqAccess, Visitor},
+    ser::{self, SerializeSeq, SerializeTupleStruct, Serializer},
+    Deserialize, Serialize,
+};
+use serde_bytes::{ByteBuf, Bytes};
+use std::{
+    fmt,
+    io::{self, Write},
+    marker::PhantomData,
+};
+
+/** Serializes a buffer compressed with [deflate](flate2).
+
+The data is copied from the device and compressed in fixed size chunks, and decompressed and
+copied to the device in chunks when deserialized.
+
+```
+# use krnl::{anyhow::Result, buffer::{Buffer, Compressed}};
+# fn main() -> Result<()> {
+let x = Buffer::from(vec![1f32; 1000]);
+let bytes = bincode2::serialize(&Compressed(&x))?;
+let Compressed(y) = bincode2::deserialize::<Compressed<Buffer<f32>>>(&bytes)?;
+assert_eq!(x.as_host_slice(), y.as_host_slice());
+# Ok(())
+# }
+```
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Compressed<B>(pub B);
+
+/** Deserializes a buffer onto a device.
+
+The data is copied to the device in chunks as it is deserialized, so that the buffer is never
+entirely on the host. The scalar type is checked before the data, and the buffer is allocated
+before reading the data, so that a buffer too large for the device fails early. Also supports
+[`Compressed`] buffers.
+
+```no_run
+# use krnl::{anyhow::Result, buffer::{Buffer, BufferSeed}, device::Device};
+# use serde::de::DeserializeSeed;
+# fn main() -> Result<()> {
+# let device = Device::builder().build()?;
+# let bytes = Vec::new();
+let mut deserializer = serde_json::Deserializer::from_slice(&bytes);
+let x = BufferSeed::<Buffer<f32>>::new(device).deserialize(&mut deserializer)?;
+# Ok(())
+# }
+```
+*/
+pub struct BufferSeed<B> {
+    device: Device,
+    _m: PhantomData<B>,
+}
+
+impl<B> BufferSeed<B> {
+    /// Creates a seed for `device`.
+    pub fn new(device: Device) -> Self {
+        Self {
+            device,
+            _m: PhantomData,
+        }
+    }
+}
+
+/// Calls `f` with the bytes of `slice`, copied to the host in chunks.
+fn for_each_chunk<E: ser::Error>(
+    slice: ScalarSlice,
+    mut f: impl FnMut(&[u8]) -> Result<(), E>,
+) -> Result<(), E> {
+    let bytes = Slice::<u8>::try_from(slice.bitcast(ScalarType::U8).unwrap())
+        .ok()
+        .unwrap();
+    if let Some(bytes) = bytes.as_host_slice() {
+        return f(bytes);
+    }
+    let mut chunk = vec![0; CHUNK_BYTES.min(bytes.len())];
+    for offset in (0..bytes.len()).step_by(CHUNK_BYTES) {
+        let chunk = &mut chunk[..CHUNK_BYTES.min(bytes.len() - offset)];
+        SliceMut::from(&mut *chunk)
+            .copy_from_slice(&bytes.slice(offset..offset + chunk.len()).unwrap())
+            .map_err(E::custom)?;
+        f(chunk)?;
+    }
+    Ok(())
+}
+
+pub(super) fn serialize_scalar_slice<S: Serializer>(
+    slice: ScalarSlice,
+    compressed: bool,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let name = if compressed {
+        "CompressedBuffer"
+    } else {
+        "Buffer"
+    };
+    let mut tuple_struct = serializer.serialize_tuple_struct(name, 3)?;
+    tuple_struct.serialize_field(&slice.scalar_type())?;
+    tuple_struct.serialize_field(&slice.len())?;
+    tuple_struct.serialize_field(&BufferData { slice, compressed })?;
+    tuple_struct.end()
+}
+
+struct BufferData<'a> {
+    slice: ScalarSlice<'a>,
+    compressed: bool,
+}
+
+impl Serialize for BufferData<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use ser::Error;
+
+        let len = self.slice.len() * self.slice.scalar_type().size();
+        if self.compressed {
+            // Chunks are compressed independently, so the number of chunks is known.
+            let chunks = len / CHUNK_BYTES + (len % CHUNK_BYTES != 0) as usize;
+            let mut seq = serializer.serialize_seq(Some(chunks))?;
+            for_each_chunk(self.slice.clone(), |bytes| {
+                for chunk in bytes.chunks(CHUNK_BYTES) {
+                    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                    encoder.write_all(chunk).map_err(S::Error::custom)?;
+                    let chunk = encoder.finish().map_err(S::Error::custom)?;
+                    seq.serialize_element(Bytes::new(&chunk))?;
+                }
+                Ok(())
+            })?;
+            seq.end()
+        } else {
+            let items = len / 8 + (len % 8 != 0) as usize;
+            let mut seq = serializer.serialize_seq(Some(items))?;
+            for_each_chunk(self.slice.clone(), |bytes| {
+                for chunk in bytes.chunks(8) {
+                    let mut item = 0u64;
+                    bytemuck::bytes_of_mut(&mut item)[..chunk.len()].copy_from_slice(chunk);
+                    seq.serialize_element(&item.to_be())?;
+                }
+                Ok(())
+            })?;
+            seq.end()
+        }
+    }
+}
+
+/// Deserializes a buffer, checking the scalar type before reading the data if provided.
+pub(super) fn deserialize_scalar_buffer<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    device: Device,
+    scalar_type: Option<ScalarType>,
+    compressed: bool,
+) -> Result<ScalarBufferRepr, D::Error> {
+    let name = if compressed {
+        "CompressedBuffer"
+    } else {
+        "Buffer"
+    };
+    let visitor = BufferVisitor {
+        device,
+        scalar_type,
+        compressed,
+    };
+    Ok(deserializer
+        .deserialize_tuple_struct(name, 3, visitor)?
+        .data)
+}
+
+fn deserialize_buffer<'de, T: Scalar, S: DataOwned<Elem = T>, D: Deserializer<'de>>(
+    deserializer: D,
+    device: Device,
+    compressed: bool,
+) -> Result<BufferBase<S>, D::Error> {
+    let scalar_buffer =
+        deserialize_scalar_buffer(deserializer, device, Some(T::SCALAR_TYPE), compressed)?;
+    let buffer = BufferRepr::try_from(scalar_buffer).ok().unwrap();
+    Ok(BufferBase {
+        data: S::from_buffer(buffer),
+    })
+}
+
+struct BufferVisitor {
+    device: Device,
+    scalar_type: Option<ScalarType>,
+    compressed: bool,
+}
+
+impl<'de> Visitor<'de> for BufferVisitor {
+    type Value = ScalarBuffer;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.compressed {
+            write!(formatter, "struct CompressedBuffer")
+        } else {
+            write!(formatter, "struct Buffer")
+        }
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        use de::Error;
+
+        let scalar_type = if let Some(scalar_type) = seq.next_element::<ScalarType>()? {
+            scalar_type
+        } else {
+            return Err(A::Error::custom("expected ScalarType"));
+        };
+        if let Some(expected) = self.scalar_type {
+            if scalar_type != expected {
+                return Err(A::Error::custom(format!(
+                    "Expected {expected:?}, found {scalar_type:?}!"
+                )));
+            }
+        }
+        let len = if let Some(len) = seq.next_element::<usize>()? {
+            len
+        } else {
+            return Err(A::Error::custom("expected usize"));
+        };
+        if len.checked_mul(scalar_type.size()).is_none() {
+            return Err(A::Error::custom(format!(
+                "{scalar_type:?} buffer with len {len} is too large"
+            )));
+        }
+        let writer = BufferWriter::new(self.device, len, scalar_type).map_err(A::Error::custom)?;
+        let visitor = BufferDataVisitor {
+            writer,
+            compressed: self.compressed,
+        };
+        if let Some(buffer) = seq.next_element_seed(visitor)? {
+            Ok(buffer)
+        } else {
+            Err(A::Error::custom("expected sequence"))
+        }
+    }
+}
+
+struct BufferDataVisitor {
+    writer: BufferWriter,
+    compressed: bool,
+}
+
+impl<'de> Visitor<'de> for BufferDataVisitor {
+    type Value = ScalarBuffer;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "buffer data")
+    }
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        use de::Error;
+
+        let writer = &mut self.writer;
+        if self.compressed {
+            while let Some(chunk) = seq.next_element::<ByteBuf>()? {
+                let mut decoder = DeflateDecoder::new(&mut *writer);
+                decoder.write_all(&chunk).map_err(A::Error::custom)?;
+                decoder.finish().map_err(A::Error::custom)?;
+            }
+        } else {
+            while let Some(item) = seq.next_element::<u64>()? {
+                if writer.remaining() == 0 {
+                    return Err(A::Error::custom("too many items"));
+                }
+                let bytes = u64::from_be(item).to_ne_bytes();
+                let len = writer.remaining().min(bytes.len());
+                writer.write_all(&bytes[..len]).map_err(A::Error::custom)?;
+            }
+        }
+        self.writer.finish().map_err(A::Error::custom)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for BufferDataVisitor {
+    type Value = ScalarBuffer;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+/** Writes bytes into a buffer, copying to the device in chunks.
+
+The buffer is allocated up front, so that a len too large for the device fails before any data is
+read, and each chunk is copied straight into it. */
+struct BufferWriter {
+    buffer: ScalarBuffer,
+    len: usize,
+    offset: usize,
+    chunk: Vec<u8>,
+}
+
+impl BufferWriter {
+    fn new(device: Device, len: usize, scalar_type: ScalarType) -> anyhow::Result<Self> {
+        let buffer = if device.is_host() {
+            // Returns an error instead of aborting if the allocation fails.
+            macro_wrap!(paste! {
+                match scalar_type {
+                    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
+                        ScalarType::[<$T:upper>] => {
+                            let mut vec = Vec::<$T>::new();
+                            vec.try_reserve_exact(len)?;
+                            #[allow(clippy::uninit_vec)]
+                            unsafe {
+                                vec.set_len(len);
+                            }
+                            Buffer::from(vec).into()
+                        }
+                    })
+                    _ => unreachable!(),
+                }
+            })
+        } else {
+            unsafe { ScalarBuffer::uninit(device, len, scalar_type)? }
+        };
+        Ok(Self {
+            buffer,
+            len: len * scalar_type.size(),
+            offset: 0,
+            chunk: Vec::new(),
+        })
+    }
+    fn remaining(&self) -> usize {
+        self.len - self.offset - self.chunk.len()
+    }
+    fn upload(&mut self) -> io::Result<()> {
+        let offset = self.offset;
+        let len = self.chunk.len();
+        let mut bytes = SliceMut::<u8>::try_from(ScalarSliceMut {
+            data: self
+                .buffer
+                .as_scalar_slice_mut()
+                .data
+                .bitcast_mut(ScalarType::U8)
+                .unwrap(),
+        })
+        .ok()
+        .unwrap();
+        bytes
+            .slice_mut(offset..offset + len)
+            .unwrap()
+            .copy_from_slice(&Slice::from(self.chunk.as_slice()))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.offset += len;
+        self.chunk.clear();
+        Ok(())
+    }
+    fn finish(mut self) -> anyhow::Result<ScalarBuffer> {
+        if !self.chunk.is_empty() {
+            self.upload()?;
+        }
+        if self.offset != self.len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("expected {} bytes, found {}", self.len, self.offset),
+            )
+            .into());
+        }
+        Ok(self.buffer)
+    }
+}
+
+impl Write for BufferWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf
+            .len()
+            .min(self.remaining())
+            .min(CHUNK_BYTES - self.chunk.len());
+        if len == 0 && !buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected {} bytes", self.len),
+            ));
+        }
+        if self.chunk.capacity() == 0 {
+            self.chunk.reserve_exact(CHUNK_BYTES.min(self.remaining()));
+        }
+        self.chunk.extend_from_slice(&buf[..len]);
+        if self.chunk.len() == CHUNK_BYTES || self.remaining() == 0 {
+            self.upload()?;
+        }
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: ScalarData> Serialize for Compressed<ScalarBufferBase<S>> {
+    fn serialize<S2>(&self, serializer: S2) -> Result<S2::Ok, S2::Error>
+    where
+        S2: Serializer,
+    {
+        serialize_scalar_slice(self.0.as_scalar_slice(), true, serializer)
+    }
+}
+
+impl<S: Data> Serialize for Compressed<BufferBase<S>> {
+    fn serialize<S2>(&self, serializer: S2) -> Result<S2::Ok, S2::Error>
+    where
+        S2: Serializer,
+    {
+        serialize_scalar_slice(self.0.as_scalar_slice(), true, serializer)
+    }
+}
+
+impl<S: ScalarData> Serialize for Compressed<&ScalarBufferBase<S>> {
+    fn serialize<S2>(&self, serializer: S2) -> Result<S2::Ok, S2::Error>
+    where
+        S2: Serializer,
+    {
+        serialize_scalar_slice(self.0.as_scalar_slice(), true, serializer)
+    }
+}
+
+impl<S: Data> Serialize for Compressed<&BufferBase<S>> {
+    fn serialize<S2>(&self, serializer: S2) -> Result<S2::Ok, S2::Error>
+    where
+        S2: Serializer,
+    {
+        serialize_scalar_slice(self.0.as_scalar_slice(), true, serializer)
+    }
+}
+
+impl<'de, S: ScalarDataOwned> Deserialize<'de> for Compressed<ScalarBufferBase<S>> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        BufferSeed::new(Device::host()).deserialize(deserializer)
+    }
+}
+
+impl<'de, T: Scalar, S: DataOwned<Elem = T>> Deserialize<'de> for Compressed<BufferBase<S>> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        BufferSeed::new(Device::host()).deserialize(deserializer)
+    }
+}
+
+impl<'de, S: ScalarDataOwned> DeserializeSeed<'de> for BufferSeed<ScalarBufferBase<S>> {
+    type Value = ScalarBufferBase<S>;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = deserialize_scalar_buffer(deserializer, self.device, None, false)?;
+        Ok(ScalarBufferBase::from_scalar_buffer(ScalarBuffer { data }))
+    }
+}
+
+impl<'de, T: Scalar, S: DataOwned<Elem = T>> DeserializeSeed<'de> for BufferSeed<BufferBase<S>> {
+    type Value = BufferBase<S>;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_buffer(deserializer, self.device, false)
+    }
+}
+
+impl<'de, S: ScalarDataOwned> DeserializeSeed<'de> for BufferSeed<Compressed<ScalarBufferBase<S>>> {
+    type Value = Compressed<ScalarBufferBase<S>>;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = deserialize_scalar_buffer(deserializer, self.device, None, true)?;
+        Ok(Compressed(ScalarBufferBase::from_scalar_buffer(
+            ScalarBuffer { data },
+        )))
+    }
+}
+
+impl<'de, T: Scalar, S: DataOwned<Elem = T>> DeserializeSeed<'de>
+    for BufferSeed<Compressed<BufferBase<S>>>
+{
+    type Value = Compressed<BufferBase<S>>;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_buffer(deserializer, self.device, true).map(Compressed)
+    }
+}
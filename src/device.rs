@@ -157,6 +157,8 @@ trait DeviceEngineBuffer: Sized {
     fn engine(&self) -> &Arc<Self::Engine>;
     fn offset(&self) -> usize;
     fn len(&self) -> usize;
+    // the length in bytes bound to kernels, including padding
+    fn bound_len(&self) -> usize;
     fn slice(self: &Arc<Self>, range: Range<usize>) -> Option<Arc<Self>>;
 }
 
@@ -171,7 +173,7 @@ trait DeviceEngineKernel: Sized {
     ) -> Result<Arc<Self>>;
     unsafe fn dispatch(
         &self,
-        groups: u32,
+        groups: [u32; 3],
         buffers: &[Arc<Self::DeviceBuffer>],
         push_consts: Vec<u8>,
         debug_printf_panic: Option<Arc<AtomicBool>>,
@@ -398,6 +400,9 @@ impl DeviceBuffer {
     pub(crate) fn len(&self) -> usize {
         self.inner.len()
     }
+    pub(crate) fn bound_len(&self) -> usize {
+        self.inner.bound_len()
+    }
     pub(crate) fn device(&self) -> RawDevice {
         RawDevice {
             engine: self.inner.engine().clone(),
@@ -470,6 +475,14 @@ impl Features {
     ///
     /// StoragePushConstant16 capability.
     pub const PUSH_CONSTANT16: Self = Self::new(1 << 11);
+    /// 64 bit atomics on buffers.
+    ///
+    /// Int64Atomics capability, shaderBufferInt64Atomics.
+    pub const BUFFER_INT64_ATOMICS: Self = Self::new(1 << 12);
+    /// 64 bit atomics on group buffers.
+    ///
+    /// Int64Atomics capability, shaderSharedInt64Atomics.
+    pub const GROUP_INT64_ATOMICS: Self = Self::new(1 << 13);
     /// Subgroup operations.
     ///
     /// GroupNonUniform capability.
@@ -507,6 +520,34 @@ impl Features {
     ///
     /// GroupNonUniformQuad capability.
     pub const SUBGROUP_QUAD: Self = Self::new(1 << 23);
+    /// Subgroup operations on 8, 16 and 64 bit integers and 16 bit floats.
+    ///
+    /// shaderSubgroupExtendedTypes.
+    pub const SUBGROUP_EXTENDED_TYPES: Self = Self::new(1 << 30);
+    /// f32 atomic exchange on buffers.
+    ///
+    /// shaderBufferFloat32Atomics.
+    pub const BUFFER_FLOAT32_ATOMICS: Self = Self::new(1 << 24);
+    /// f32 atomic exchange on group buffers.
+    ///
+    /// shaderSharedFloat32Atomics.
+    pub const GROUP_FLOAT32_ATOMICS: Self = Self::new(1 << 25);
+    /// f32 atomic add on buffers.
+    ///
+    /// AtomicFloat32AddEXT capability, shaderBufferFloat32AtomicAdd.
+    pub const BUFFER_FLOAT32_ATOMIC_ADD: Self = Self::new(1 << 26);
+    /// f32 atomic add on group buffers.
+    ///
+    /// AtomicFloat32AddEXT capability, shaderSharedFloat32AtomicAdd.
+    pub const GROUP_FLOAT32_ATOMIC_ADD: Self = Self::new(1 << 27);
+    /// f32 atomic min and max on buffers.
+    ///
+    /// AtomicFloat32MinMaxEXT capability, shaderBufferFloat32AtomicMinMax.
+    pub const BUFFER_FLOAT32_ATOMIC_MIN_MAX: Self = Self::new(1 << 28);
+    /// f32 atomic min and max on group buffers.
+    ///
+    /// AtomicFloat32MinMaxEXT capability, shaderSharedFloat32AtomicMinMax.
+    pub const GROUP_FLOAT32_ATOMIC_MIN_MAX: Self = Self::new(1 << 29);
 
     #[inline]
     const fn new(bits: u32) -> Self {
@@ -530,6 +571,8 @@ impl Features {
             .union(Self::BUFFER16)
             .union(Self::PUSH_CONSTANT8)
             .union(Self::PUSH_CONSTANT16)
+            .union(Self::BUFFER_INT64_ATOMICS)
+            .union(Self::GROUP_INT64_ATOMICS)
             .union(Self::SUBGROUP_BASIC)
             .union(Self::SUBGROUP_VOTE)
             .union(Self::SUBGROUP_ARITHMETIC)
@@ -538,6 +581,13 @@ impl Features {
             .union(Self::SUBGROUP_SHUFFLE_RELATIVE)
             .union(Self::SUBGROUP_CLUSTERED)
             .union(Self::SUBGROUP_QUAD)
+            .union(Self::SUBGROUP_EXTENDED_TYPES)
+            .union(Self::BUFFER_FLOAT32_ATOMICS)
+            .union(Self::GROUP_FLOAT32_ATOMICS)
+            .union(Self::BUFFER_FLOAT32_ATOMIC_ADD)
+            .union(Self::GROUP_FLOAT32_ATOMIC_ADD)
+            .union(Self::BUFFER_FLOAT32_ATOMIC_MIN_MAX)
+            .union(Self::GROUP_FLOAT32_ATOMIC_MIN_MAX)
     }
     /// Contains all features of `other`.
     #[inline]
@@ -549,6 +599,11 @@ impl Features {
     pub const fn union(self, other: Self) -> Self {
         Self::new(self.bits | other.bits)
     }
+    /// Contains any features of `other`.
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        (self.bits & other.bits) != 0
+    }
     fn name_iter(&self) -> impl Iterator<Item = &str> {
         macro_rules! features {
             ($($f:ident),*) => {
@@ -570,6 +625,8 @@ impl Features {
             BUFFER16,
             PUSH_CONSTANT8,
             PUSH_CONSTANT16,
+            BUFFER_INT64_ATOMICS,
+            GROUP_INT64_ATOMICS,
             SUBGROUP_BASIC,
             SUBGROUP_VOTE,
             SUBGROUP_ARITHMETIC,
@@ -577,7 +634,14 @@ impl Features {
             SUBGROUP_SHUFFLE,
             SUBGROUP_SHUFFLE_RELATIVE,
             SUBGROUP_CLUSTERED,
-            SUBGROUP_QUAD
+            SUBGROUP_QUAD,
+            SUBGROUP_EXTENDED_TYPES,
+            BUFFER_FLOAT32_ATOMICS,
+            GROUP_FLOAT32_ATOMICS,
+            BUFFER_FLOAT32_ATOMIC_ADD,
+            GROUP_FLOAT32_ATOMIC_ADD,
+            BUFFER_FLOAT32_ATOMIC_MIN_MAX,
+            GROUP_FLOAT32_ATOMIC_MIN_MAX
         )
         .into_iter()
         .filter_map(|(name, features)| {
@@ -799,8 +863,9 @@ pub struct DeviceInfo {
     name: String,
     device_id: u32,
     vendor_id: u32,
-    max_groups: u32,
+    max_groups_xyz: [u32; 3],
     max_threads: u32,
+    max_threads_xyz: [u32; 3],
     min_subgroup_threads: u32,
     max_subgroup_threads: u32,
     features: Features,
@@ -809,13 +874,25 @@ pub struct DeviceInfo {
 
 impl DeviceInfo {
     /// Max groups per kernel dispatch.
+    ///
+    /// Equivalent to `max_groups_xyz()[0]`.
     pub fn max_groups(&self) -> u32 {
-        self.max_groups
+        self.max_groups_xyz[0]
+    }
+    /// Max groups per kernel dispatch in each dimension.
+    pub fn max_groups_xyz(&self) -> [u32; 3] {
+        self.max_groups_xyz
     }
     /// Max threads per group.
+    ///
+    /// For multi-dimensional threads, this is the max of the product of each dimension.
     pub fn max_threads(&self) -> u32 {
         self.max_threads
     }
+    /// Max threads per group in each dimension.
+    pub fn max_threads_xyz(&self) -> [u32; 3] {
+        self.max_threads_xyz
+    }
     /// Min threads per subgroup.
     ///
     /// Power of 2 between 1 and 128.
@@ -834,7 +911,7 @@ impl DeviceInfo {
     }
     /// Default threads.
     pub fn default_threads(&self) -> u32 {
-        256.min(self.max_threads)
+        256.min(self.max_threads).min(self.max_threads_xyz[0])
     }
     #[allow(dead_code)]
     pub(crate) fn debug_printf(&self) -> bool {
@@ -861,7 +938,7 @@ impl RawKernel {
     }
     pub(crate) unsafe fn dispatch(
         &self,
-        groups: u32,
+        groups: [u32; 3],
         buffers: &[DeviceBuffer],
         push_consts: Vec<u8>,
         debug_printf_panic: Option<Arc<AtomicBool>>,
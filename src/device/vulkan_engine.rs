@@ -84,7 +84,7 @@ impl Engine {
         &self,
         kernel_desc: &Arc<KernelDesc>,
         pipeline: &Arc<ComputePipeline>,
-        groups: u32,
+        groups: [u32; 3],
         buffers: &[Arc<DeviceBuffer>],
         push_consts: &[u8],
         debug_printf_panic: Option<Arc<AtomicBool>>,
@@ -208,6 +208,16 @@ impl DeviceEngine for Engine {
         let optimal_device_extensions = vulkano::device::DeviceExtensions {
             khr_vulkan_memory_model: true,
             ext_subgroup_size_control: true,
+            ext_shader_atomic_float: optimal_features.intersects(
+                Features::BUFFER_FLOAT32_ATOMICS
+                    .union(Features::GROUP_FLOAT32_ATOMICS)
+                    .union(Features::BUFFER_FLOAT32_ATOMIC_ADD)
+                    .union(Features::GROUP_FLOAT32_ATOMIC_ADD),
+            ),
+            ext_shader_atomic_float2: optimal_features.intersects(
+                Features::BUFFER_FLOAT32_ATOMIC_MIN_MAX
+                    .union(Features::GROUP_FLOAT32_ATOMIC_MIN_MAX),
+            ),
             ..vulkano::device::DeviceExtensions::empty()
         };
         let device_extensions = physical_device
@@ -222,10 +232,26 @@ impl DeviceEngine for Engine {
             shader_int64: optimal_features.contains(Features::INT64),
             shader_float16: optimal_features.contains(Features::FLOAT16),
             shader_float64: optimal_features.contains(Features::FLOAT64),
+            shader_subgroup_extended_types: optimal_features
+                .contains(Features::SUBGROUP_EXTENDED_TYPES),
             storage_buffer8_bit_access: optimal_features.contains(Features::BUFFER8),
             storage_buffer16_bit_access: optimal_features.contains(Features::BUFFER16),
             storage_push_constant8: optimal_features.contains(Features::PUSH_CONSTANT8),
             storage_push_constant16: optimal_features.contains(Features::PUSH_CONSTANT16),
+            shader_buffer_int64_atomics: optimal_features.contains(Features::BUFFER_INT64_ATOMICS),
+            shader_shared_int64_atomics: optimal_features.contains(Features::GROUP_INT64_ATOMICS),
+            shader_buffer_float32_atomics: optimal_features
+                .contains(Features::BUFFER_FLOAT32_ATOMICS),
+            shader_shared_float32_atomics: optimal_features
+                .contains(Features::GROUP_FLOAT32_ATOMICS),
+            shader_buffer_float32_atomic_add: optimal_features
+                .contains(Features::BUFFER_FLOAT32_ATOMIC_ADD),
+            shader_shared_float32_atomic_add: optimal_features
+                .contains(Features::GROUP_FLOAT32_ATOMIC_ADD),
+            shader_buffer_float32_atomic_min_max: optimal_features
+                .contains(Features::BUFFER_FLOAT32_ATOMIC_MIN_MAX),
+            shader_shared_float32_atomic_min_max: optimal_features
+                .contains(Features::GROUP_FLOAT32_ATOMIC_MIN_MAX),
             ..vulkano::device::Features::empty()
         };
         let device_features = physical_device
@@ -336,6 +362,30 @@ impl DeviceEngine for Engine {
         if device_features.storage_push_constant16 {
             features = features.union(Features::PUSH_CONSTANT16);
         }
+        if device_features.shader_buffer_int64_atomics {
+            features = features.union(Features::BUFFER_INT64_ATOMICS);
+        }
+        if device_features.shader_shared_int64_atomics {
+            features = features.union(Features::GROUP_INT64_ATOMICS);
+        }
+        if device_features.shader_buffer_float32_atomics {
+            features = features.union(Features::BUFFER_FLOAT32_ATOMICS);
+        }
+        if device_features.shader_shared_float32_atomics {
+            features = features.union(Features::GROUP_FLOAT32_ATOMICS);
+        }
+        if device_features.shader_buffer_float32_atomic_add {
+            features = features.union(Features::BUFFER_FLOAT32_ATOMIC_ADD);
+        }
+        if device_features.shader_shared_float32_atomic_add {
+            features = features.union(Features::GROUP_FLOAT32_ATOMIC_ADD);
+        }
+        if device_features.shader_buffer_float32_atomic_min_max {
+            features = features.union(Features::BUFFER_FLOAT32_ATOMIC_MIN_MAX);
+        }
+        if device_features.shader_shared_float32_atomic_min_max {
+            features = features.union(Features::GROUP_FLOAT32_ATOMIC_MIN_MAX);
+        }
         if let Some(subgroup_features) = properties.subgroup_supported_operations {
             use vulkano::device::physical::SubgroupFeatures;
 
@@ -363,14 +413,18 @@ impl DeviceEngine for Engine {
             if subgroup_features.contains(SubgroupFeatures::QUAD) {
                 features = features.union(Features::SUBGROUP_QUAD);
             }
+            if device_features.shader_subgroup_extended_types {
+                features = features.union(Features::SUBGROUP_EXTENDED_TYPES);
+            }
         }
         let info = Arc::new(DeviceInfo {
             index,
             name,
             device_id: properties.device_id,
             vendor_id: properties.vendor_id,
-            max_groups: properties.max_compute_work_group_count[0],
-            max_threads: properties.max_compute_work_group_size[0],
+            max_groups_xyz: properties.max_compute_work_group_count,
+            max_threads: properties.max_compute_work_group_invocations,
+            max_threads_xyz: properties.max_compute_work_group_size,
             min_subgroup_threads,
             max_subgroup_threads,
             features,
@@ -538,7 +592,7 @@ impl FrameOuter {
         kernel_desc: &Arc<KernelDesc>,
         epoch: &AtomicU64,
         pipeline: &Arc<ComputePipeline>,
-        groups: u32,
+        groups: [u32; 3],
         buffers: &[Arc<DeviceBuffer>],
         push_consts: &[u8],
         debug_printf_panic: Option<Arc<AtomicBool>>,
@@ -670,7 +724,7 @@ impl Frame {
         &mut self,
         kernel_desc: &Arc<KernelDesc>,
         pipeline: &Arc<ComputePipeline>,
-        groups: u32,
+        groups: [u32; 3],
         buffers: &[Arc<DeviceBuffer>],
         push_consts: &[u8],
         debug_printf_panic: Option<Arc<AtomicBool>>,
@@ -719,7 +773,7 @@ impl Frame {
             }
         }
         unsafe {
-            builder.dispatch([groups, 1, 1]);
+            builder.dispatch(groups);
         }
         self.buffers
             .extend(buffers.iter().map(|x| x.inner.as_ref().unwrap().clone()));
@@ -1179,6 +1233,11 @@ impl DeviceEngineBuffer for DeviceBuffer {
     fn len(&self) -> usize {
         self.len
     }
+    fn bound_len(&self) -> usize {
+        self.inner
+            .as_ref()
+            .map_or(0, |inner| inner.size().try_into().unwrap())
+    }
     fn slice(self: &Arc<Self>, range: Range<usize>) -> Option<Arc<Self>> {
         let Range { start, end } = range;
         if start > self.len {
@@ -1343,7 +1402,7 @@ impl DeviceEngineKernel for Kernel {
     }
     unsafe fn dispatch(
         &self,
-        groups: u32,
+        groups: [u32; 3],
         buffers: &[Arc<Self::DeviceBuffer>],
         push_consts: Vec<u8>,
         debug_printf_panic: Option<Arc<AtomicBool>>,
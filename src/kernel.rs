@@ -197,6 +197,47 @@ mod baz {
 # fn main() {}
 ```
 
+## Reflection
+Modules have a `kernel_infos()` function that returns a [`KernelInfo`] for each kernel in the module
+and its submodules. These describe the required [features](#features), safety,
+[specialization](#specialization) constants, slices and push constants of the kernels.
+The kernels are read from the [krnlc](#krnlc) cache, so the list is empty if the module hasn't been compiled.
+```no_run
+use krnl::{macros::module, device::Device};
+
+#[module]
+# #[krnl(no_build)]
+mod kernels {
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl::krnl_core;
+    use krnl_core::macros::kernel;
+
+    #[kernel]
+    pub fn saxpy(alpha: f32, #[item] x: f32, #[item] y: &mut f32) {
+        *y += alpha * x;
+    }
+}
+
+fn supported_kernels(device: &Device) -> Vec<&'static str> {
+    let features = if let Some(info) = device.info() {
+        info.features()
+    } else {
+        return Vec::new();
+    };
+    kernels::kernel_infos()
+        .iter()
+        .filter(|kernel| {
+            features.contains(kernel.features())
+                || kernel
+                    .emulated_features()
+                    .map_or(false, |emulated| features.contains(emulated))
+        })
+        .map(|kernel| kernel.name())
+        .collect()
+}
+# fn main() {}
+```
+
 # Kernels
 The `kernel` macro declares a function that executes on the device, dispatched from the host.
 ```no_run
@@ -282,6 +323,57 @@ It may range between [min_subgroup_threads](crate::device::DeviceInfo::min_subgr
 `min_subgroup_threads` and `max_subgroup_threads`, each subgroup in a group will have `subgroup_threads`
 threads, unless `threads` per group is not an exact multiple, where the last subgroup will have the remainder of threads.
 
+Threads in a subgroup can exchange values with [subgroup operations](krnl_core::subgroup), like
+[`reduce_add`](krnl_core::subgroup::reduce_add) and [`shuffle`](krnl_core::subgroup::shuffle), which
+declare the required subgroup [features](#features).
+```no_run
+# #[krnl::macros::module] #[krnl(no_build)] mod kernels {
+# use krnl::macros::kernel;
+#[kernel]
+fn subgroup_sum(#[global] x: Slice<f32>, #[global] y: UnsafeSlice<f32>) {
+    use krnl_core::subgroup;
+
+    let global_id = kernel.global_id();
+    let value = if global_id < x.len() { x[global_id] } else { 0. };
+    let sum = subgroup::reduce_add(value);
+    if kernel.subgroup_thread_id() == 0 {
+        y.atomic_add(0, sum);
+    }
+}
+# }
+```
+
+Groups and threads may have up to 3 dimensions, declared via `#[kernel(threads(..))]`. The number of
+threads in each dimension is the default for the [builder](#KernelBuilder), and `.with_threads(..)`,
+`.with_global_threads(..)`, and `.with_groups(..)` take an array with one value per dimension.
+Methods like [`global_id_xyz()`](krnl_core::kernel::Kernel::global_id_xyz) return the id in each dimension,
+while [`global_id()`](krnl_core::kernel::Kernel::global_id) and others return linearized values.
+[Item kernels](#items) are 1 dimensional.
+```no_run
+# #[krnl::macros::module] #[krnl(no_build)] mod kernels {
+# use krnl::{macros::kernel, buffer::{Slice, SliceMut}, anyhow::Result};
+#[kernel(threads(16, 16))]
+fn transpose(rows: u32, cols: u32, #[global] x: Slice<f32>, #[global] y: UnsafeSlice<f32>) {
+    use krnl_core::buffer::UnsafeIndex;
+
+    let [col, row, _] = kernel.global_id_xyz();
+    let (rows, cols) = (rows as usize, cols as usize);
+    if row < rows && col < cols {
+        unsafe {
+            *y.unsafe_index_mut(col * rows + row) = x[row * cols + col];
+        }
+    }
+}
+
+# fn foo(rows: u32, cols: u32, x: Slice<f32>, y: SliceMut<f32>) -> Result<()> {
+transpose::builder()?
+    .build(x.device())?
+    .with_global_threads([cols, rows])
+    .dispatch(rows, cols, x, y)
+# }
+# }
+```
+
 # Global Buffers
 Visible to all threads. [Slice](krnl_core::buffer::Slice) binds to [Slice](crate::buffer::Slice), [UnsafeSlice](krnl_core::buffer::UnsafeSlice) binds
 to [SliceMut](crate::buffer::SliceMut), provided to [`.dispatch(..)`](#dispatch).
@@ -289,6 +381,36 @@ to [SliceMut](crate::buffer::SliceMut), provided to [`.dispatch(..)`](#dispatch)
 For best performance, consecutive threads should access consecutive elements, allowing loads and stores to be coalesced
 into fewer memory transactions.
 
+Global buffers may also contain user defined [elements](crate::buffer::Element), ie `Slice<Particle>`. The element
+type must be imported with `use`.
+```no_run
+# #[krnl::macros::module] #[krnl(no_build)] mod kernels {
+# #[cfg(not(target_arch = "spirv"))]
+# use krnl::krnl_core;
+# use krnl::macros::kernel;
+use krnl_core::macros::Element;
+
+#[derive(Clone, Copy, Element)]
+#[repr(C)]
+pub struct Particle {
+    position: [f32; 3],
+    mass: f32,
+}
+
+#[kernel]
+fn update(dt: f32, #[global] x: UnsafeSlice<Particle>) {
+    use krnl_core::buffer::UnsafeIndex;
+
+    let global_id = kernel.global_id();
+    if global_id < x.len() {
+        unsafe {
+            x.unsafe_index_mut(global_id).position[1] -= 9.8 * dt;
+        }
+    }
+}
+# }
+```
+
 # Group Buffers
 Shared with all threads in the group, initialized with zeros. Can be used to minimize accesses
 to [global buffers](#global-buffers).
@@ -296,36 +418,32 @@ to [global buffers](#global-buffers).
 The maximum amount of memory that can be used for group buffers depends on the device. Kernels
 exceeding this will fail to [build](#kernel-builder).
 
-Barriers should be used as necessary to synchronize access.
+[`GroupSlice`](krnl_core::buffer::GroupSlice) provides safe access in phases separated by barriers.
+Each thread writes to its own slots, then after a barrier, any thread can read any element. The
+barrier must be reached by all threads in the group, krnlc warns if it may be called in
+non-uniform control flow, ie within a branch on the thread id.
 ```no_run
 # #[krnl::macros::module] #[krnl(no_build)] mod kernels {
 # use krnl::macros::kernel;
-#[kernel]
+#[kernel(threads(64))]
 fn group_sum(
     #[global] x: Slice<f32>,
-    #[group] x_group: UnsafeSlice<f32, 64>,
+    #[group] x_group: GroupSlice<f32, 64>,
     #[global] y: UnsafeSlice<f32>,
 ) {
-    use krnl_core::{
-        buffer::UnsafeIndex,
-        spirv_std::arch::workgroup_memory_barrier_with_group_sync as group_barrier
-    };
+    use krnl_core::buffer::UnsafeIndex;
 
     let global_id = kernel.global_id();
     let group_id = kernel.group_id();
     let thread_id = kernel.thread_id();
-    unsafe {
-        *x_group.unsafe_index_mut(thread_id) = x[global_id];
-        // Barriers are used to synchronize access to group memory.
-        // This call must be reached by all active threads in the group!
-        group_barrier();
-    }
+    x_group.write(if global_id < x.len() { x[global_id] } else { 0. });
+    // Barriers are used to synchronize access to group memory.
+    // This call must be reached by all threads in the group!
+    let x_group = x_group.barrier();
     if thread_id == 0 {
         let mut acc = 0f32;
-        for i in 0 .. 64 {
-            unsafe {
-                acc += *x_group.unsafe_index(i);
-            }
+        for i in 0 .. x_group.len() {
+            acc += x_group[i];
         }
         unsafe {
             *y.unsafe_index_mut(group_id) = acc;
@@ -335,6 +453,26 @@ fn group_sum(
 # }
 ```
 
+Group buffers may also be declared as [`UnsafeSlice`](krnl_core::buffer::UnsafeSlice), where barriers,
+like [`workgroup_memory_barrier_with_group_sync`](krnl_core::spirv_std::arch::workgroup_memory_barrier_with_group_sync),
+should be used as necessary to synchronize access.
+
+# Atomics
+[`UnsafeSlice`](krnl_core::buffer::UnsafeSlice)s of `u32`, `i32`, `u64`, `i64` and `f32`, including
+group buffers, have atomic methods like `.atomic_add(..)` and `.compare_exchange(..)`. These are
+relaxed and do not synchronize other accesses. 64 bit and `f32` atomics require
+[features](#features) like [`BUFFER_INT64_ATOMICS`](device::Features::BUFFER_INT64_ATOMICS) and
+[`BUFFER_FLOAT32_ATOMIC_ADD`](device::Features::BUFFER_FLOAT32_ATOMIC_ADD).
+```no_run
+# #[krnl::macros::module] #[krnl(no_build)] mod kernels {
+# use krnl::macros::kernel;
+#[kernel]
+fn histogram(#[item] x: u8, #[global] counts: UnsafeSlice<u32>) {
+    counts.atomic_add(x as usize, 1);
+}
+# }
+```
+
 # KernelBuilder
 A [kernel declaration](#kernels) is expanded to a `mod` with a custom KernelBuilder and Kernel.
 
@@ -398,6 +536,13 @@ pub mod saxpy {
         /// - [`DeviceLost`].
         /// - The kernel could not be queued.
         pub fn dispatch(&self, alpha: f32, x: Slice<f32>, y: SliceMut<f32>) -> Result<()>;
+        /// Dispatches the kernel with dynamically typed arguments.
+        ///
+        /// # Errors
+        /// - The number, scalar types or mutability of the arguments don't match the kernel.
+        /// - [`DeviceLost`].
+        /// - The kernel could not be queued.
+        pub fn dispatch_dyn(&self, slices: &[KernelSliceArg], push_consts: &[ScalarElem]) -> Result<()>;
     }
 }
 # fn main() {}
@@ -410,7 +555,9 @@ kernel wasn't compiled with [no_build](#attributes). The builder is cached so
 that subsequent calls are trivial.
 
 The number of threads per group can be set via `.with_threads(..)`. It will default to
-[`DeviceInfo::default_threads()`](crate::device::DeviceInfo::default_threads) if not provided.
+`#[kernel(threads(..))]`, or [`DeviceInfo::default_threads()`](crate::device::DeviceInfo::default_threads)
+if not provided. Kernels with [multi-dimensional](#groups-subgroups-and-threads) threads take an array
+instead of a `u32`.
 
 Building a kernel is an expensive operation, so it is cached within [Device](crate::device::Device). Subsequent
 calls to `.build(..)` with identical builders (threads and [spec constants](#specialization)) may avoid recompiling.
@@ -420,6 +567,19 @@ Kernels implicitly declare [`Features`](device::Features) based on types and or
 If the [device](device::Device) does not support these features, `.build(..)` will return an
 error.
 
+Kernels using 8 or 16 bit arithmetic, ie `u8`, `f16` or `bf16`, are also compiled as an emulated variant
+that computes in 32 bits, only loading and storing narrow types. If the device does not support
+[`INT8`](device::Features::INT8), [`INT16`](device::Features::INT16) or [`FLOAT16`](device::Features::FLOAT16),
+`.build(..)` will select the emulated variant, which only requires [`BUFFER8`](device::Features::BUFFER8),
+[`BUFFER16`](device::Features::BUFFER16) and or push constant features for the narrow types. Emulated
+floating point results may differ due to rounding.
+
+Kernels using `u64` or `i64` are similarly emulated with pairs of 32 bit integers, not requiring
+[`INT64`](device::Features::INT64). Addition, subtraction, multiplication, comparisons, shifts, bitwise
+ops and integer casts are supported. Kernels that use 64 bit division, atomics, subgroup arithmetic, float
+conversions or 64 bit spec constants are not emulated. krnlc will emit a warning if an emulated
+variant fails to validate.
+
 See [`DeviceInfo::features()`](device::DeviceInfo::features).
 
 # Specialization
@@ -464,11 +624,34 @@ binary::builder()?
 # Dispatch
 Once [built](#KernelBuilder), the [groups](#groups-subgroups-and-threads) to dispatch may be set via `.with_groups(..)`,
 or `.with_global_threads(..)` which rounds up to the next multiple of threads. [Item kernels](#items)
-infer the global_threads based on the number of items.
+infer the global_threads based on the number of items. The groups in each dimension are limited by
+[`DeviceInfo::max_groups_xyz()`](crate::device::DeviceInfo::max_groups_xyz), so
+[multi-dimensional](#groups-subgroups-and-threads) kernels can dispatch more groups than 1 dimensional kernels.
+Item kernels are dispatched with at most `max_groups_xyz[0]` groups, and each thread loops over items with a
+stride of global_threads, so all items are processed. The total global threads, ie groups times threads in
+every dimension, can not exceed `u32::MAX`.
 
 The `.dispatch(..)` method blocks until the kernel is queued. One kernel can be queued
 while another is executing.
 
+Arguments can also be chosen at runtime with `.dispatch_dyn(..)`, which takes [`KernelSliceArg`]s and
+[`ScalarElem`](crate::scalar::ScalarElem)s. Slices are in declaration order, and push constants are ordered
+by size, largest first, and then in declaration order. The arguments are checked against the kernel, returning
+an error if they don't match.
+```no_run
+# use krnl::{macros::module, anyhow::Result, device::Device, buffer::{ScalarSlice, ScalarSliceMut}, scalar::ScalarElem};
+# #[module] #[krnl(no_build)] mod kernels {
+# use krnl::macros::kernel;
+# #[kernel] pub fn saxpy(alpha: f32, #[item] x: f32, #[item] y: &mut f32) { *y += alpha * x; }
+# }
+# use kernels::saxpy;
+fn saxpy_dyn(alpha: ScalarElem, x: ScalarSlice, y: ScalarSliceMut, device: Device) -> Result<()> {
+    saxpy::builder()?
+        .build(device)?
+        .dispatch_dyn(&[x.into(), y.into()], &[alpha])
+}
+```
+
 When a kernel begins executing, the device will begin processing one or more groups
 in parallel, untill all groups have finished.
 
@@ -484,6 +667,55 @@ to and from devices. [`Device::wait()`](crate::device::Device::wait) can be used
 ## Asm
 The [`asm!`](core::arch::asm) macro can be used with the spirv arch, see [inline-asm](https://github.com/EmbarkStudios/rust-gpu/blob/v0.9.0/docs/src/inline-asm.md).
 
+## External Shaders
+Compute shaders compiled to SPIR-V by other tools, ie GLSL with glslc or HLSL with dxc, can be loaded at runtime
+with [`KernelBuilder::from_spirv`]. The arguments are declared with [`SpirvArg`]s and checked against the shader.
+Buffers are padded, so shaders should read the offset and len of each slice from push constants rather than use
+the length of the runtime array.
+```no_run
+# use krnl::{anyhow::Result, buffer::Buffer, device::Device, scalar::{ScalarElem, ScalarType}};
+use krnl::kernel::{KernelBuilder, SpirvArg};
+# fn foo(device: Device, spirv: &[u8], x: Buffer<f32>, mut y: Buffer<f32>) -> Result<()> {
+/*
+#version 450
+layout(local_size_x = 64) in;
+layout(binding = 0) readonly buffer X { float x[]; };
+layout(binding = 1) buffer Y { float y[]; };
+// the offset and len of each slice follow the push constants
+layout(push_constant) uniform Push {
+    float alpha;
+    uint x_offset;
+    uint x_len;
+    uint y_offset;
+    uint y_len;
+};
+
+void main() {
+    uint i = gl_GlobalInvocationID.x;
+    if (i < y_len) {
+        y[y_offset + i] += alpha * x[x_offset + i];
+    }
+}
+*/
+let kernel = KernelBuilder::from_spirv(
+    "saxpy",
+    spirv,
+    &[
+        SpirvArg::Slice { name: "x", scalar_type: ScalarType::F32, mutable: false },
+        SpirvArg::Slice { name: "y", scalar_type: ScalarType::F32, mutable: true },
+        SpirvArg::Push { name: "alpha", scalar_type: ScalarType::F32 },
+    ],
+)?
+.build(device)?;
+unsafe {
+    kernel
+        .with_global_threads([y.len() as u32, 1, 1])
+        .dispatch(&[x.as_slice().into(), y.as_slice_mut().into()], &[ScalarElem::F32(2.)])?;
+}
+# Ok(())
+# }
+```
+
 # DebugPrintf
 [debug_printf!](krnl_core::spirv_std::macros::debug_printfln) and [debug_printfln!](krnl_core::spirv_std::macros::debug_printfln)
 will print formatted output to stderr.
@@ -547,7 +779,10 @@ Note: The validation layer can be configured to redirect messages to stdout. Thi
 and returning an error in case of a panic.
 */
 
+#[cfg(feature = "device")]
+use crate::device::DeviceBuffer;
 use crate::{
+    buffer::{Element, ElementLayout, ScalarSlice, ScalarSliceMut, Slice, SliceMut},
     device::{Device, DeviceInner, Features},
     scalar::{ScalarElem, ScalarType},
 };
@@ -556,7 +791,11 @@ use anyhow::{bail, Result};
 use dry::macro_wrap;
 #[cfg(feature = "device")]
 use rspirv::{binary::Assemble, dr::Operand};
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
 #[cfg(feature = "device")]
 use std::{
     collections::HashMap,
@@ -564,16 +803,422 @@ use std::{
     sync::atomic::{AtomicBool, Ordering},
 };
 
+#[cfg(feature = "device")]
+mod spirv;
+
+/// An argument of a kernel loaded with [`KernelBuilder::from_spirv`].
+#[derive(Clone, Copy, Debug)]
+pub enum SpirvArg {
+    /// A storage buffer.
+    Slice {
+        /// The name of the slice.
+        name: &'static str,
+        /// The scalar type of the elements.
+        scalar_type: ScalarType,
+        /// Whether the kernel writes to the slice.
+        mutable: bool,
+    },
+    /// A push constant.
+    Push {
+        /// The name of the push constant.
+        name: &'static str,
+        /// The scalar type.
+        scalar_type: ScalarType,
+    },
+}
+
+/** Builder for creating a [`Kernel`] at runtime.
+
+Kernels declared with `#[kernel]` have their own typed builders, see [KernelBuilder](crate::kernel#kernelbuilder). */
+#[derive(Clone)]
+pub struct KernelBuilder {
+    inner: __private::KernelBuilder,
+}
+
+impl KernelBuilder {
+    /** Loads a compute shader from SPIR-V.
+
+    `name` is used for errors and debugging. The shader must have a GLCompute entry point `main`,
+    with a constant local size. `args` declare the slices and push constants of the shader:
+    - Slices are storage buffers in descriptor set 0, bound in the order of `args`. Each buffer
+    is a block with a single runtime array of the scalar type. Slices that are not mutable must
+    be readonly.
+    - Push constants are the members of the push constant block, in the order of `args`, aligned
+    to their size. Optionally, they may be followed by a `u32` offset and length in elements of
+    each slice, aligned to 4 bytes. Otherwise, the shader sees entire buffers, which are padded to
+    a multiple of 256 bytes, so slices must not be offset and their size in bytes must be a
+    multiple of 256.
+
+    bf16 and fp8 slices and push constants are declared with integers of the same width. The
+    signedness of integers is not checked.
+
+    Required [`Features`] are inferred from the capabilities of the shader.
+
+    # Errors
+    - The SPIR-V could not be loaded.
+    - The shader does not have an entry point `main` with a constant local size.
+    - The descriptors or push constants of the shader don't match `args`.
+    */
+    #[cfg(feature = "device")]
+    pub fn from_spirv(
+        name: impl Into<Cow<'static, str>>,
+        spirv: &[u8],
+        args: &[SpirvArg],
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: __private::KernelBuilder::from_spirv(name.into(), spirv, args)?,
+        })
+    }
+    /// Required features.
+    pub fn features(&self) -> Features {
+        self.inner.features()
+    }
+    /** Builds the kernel for `device`.
+
+    The kernel is cached, so subsequent calls to `.build()` with identical builders may avoid
+    recompiling.
+
+    # Errors
+    - `device` is the host.
+    - `device` doesn't have required features.
+    - The kernel is not supported on `device`.
+    - [`DeviceLost`](crate::device::error::DeviceLost). */
+    pub fn build(&self, device: Device) -> Result<Kernel> {
+        Ok(Kernel {
+            inner: self.inner.build(device)?,
+            groups: false,
+        })
+    }
+}
+
+/** A kernel created at runtime.
+
+See [`KernelBuilder::from_spirv`]. */
+#[derive(Clone)]
+pub struct Kernel {
+    inner: __private::Kernel,
+    groups: bool,
+}
+
+impl Kernel {
+    /// Threads per group.
+    pub fn threads(&self) -> [u32; 3] {
+        self.inner.threads()
+    }
+    /// Global threads to dispatch.
+    ///
+    /// Implicitly declares groups by rounding up to the next multiple of threads.
+    pub fn with_global_threads(self, global_threads: [u32; 3]) -> Self {
+        Self {
+            inner: self.inner.with_global_threads(global_threads),
+            groups: true,
+        }
+    }
+    /// Groups to dispatch.
+    pub fn with_groups(self, groups: [u32; 3]) -> Self {
+        Self {
+            inner: self.inner.with_groups(groups),
+            groups: true,
+        }
+    }
+    /// Required features.
+    pub fn features(&self) -> Features {
+        self.inner.features()
+    }
+    /** Dispatches the kernel.
+
+    - Waits for immutable access to slice arguments.
+    - Waits for mutable access to mutable slice arguments.
+    - Blocks until the kernel is queued.
+
+    # Safety
+    The shader is not checked, it may access slices out of bounds or race.
+
+    # Errors
+    - Groups were not provided.
+    - The arguments don't match the kernel.
+    - A slice is empty, not on the kernel's device, or not aligned to its element size.
+    - A slice does not span its entire buffer when the kernel does not read slice offsets.
+    - [`DeviceLost`](crate::device::error::DeviceLost).
+    - The kernel could not be queued. */
+    pub unsafe fn dispatch(
+        &self,
+        slices: &[KernelSliceArg],
+        push_consts: &[ScalarElem],
+    ) -> Result<()> {
+        if !self.groups {
+            bail!("Kernel `{}` groups not provided!", self.inner.name());
+        }
+        unsafe { self.inner.dispatch(slices, push_consts) }
+    }
+}
+
+/** A slice argument of a [`Kernel`].
+
+Converted from [`Slice`]s and [`SliceMut`]s, which carry the [layout](ElementLayout) of user defined
+[elements](Element), or from [`ScalarSlice`]s and [`ScalarSliceMut`]s of scalars. */
+pub struct KernelSliceArg<'a> {
+    slice: KernelSlice<'a>,
+    element: Option<ElementLayout>,
+}
+
+enum KernelSlice<'a> {
+    Slice(ScalarSlice<'a>),
+    SliceMut(ScalarSliceMut<'a>),
+}
+
+// Scalars have no element layout, like slices of kernels.
+fn element_layout<T: Element>() -> Option<ElementLayout> {
+    let layout = T::LAYOUT;
+    if layout == ElementLayout::scalar(layout.scalar_type) {
+        None
+    } else {
+        Some(layout)
+    }
+}
+
+#[cfg(feature = "device")]
+impl KernelSliceArg<'_> {
+    fn scalar_type(&self) -> ScalarType {
+        match &self.slice {
+            KernelSlice::Slice(x) => x.scalar_type(),
+            KernelSlice::SliceMut(x) => x.scalar_type(),
+        }
+    }
+    fn mutable(&self) -> bool {
+        match &self.slice {
+            KernelSlice::Slice(_) => false,
+            KernelSlice::SliceMut(_) => true,
+        }
+    }
+    fn device_buffer(&self) -> Option<&DeviceBuffer> {
+        match &self.slice {
+            KernelSlice::Slice(x) => x.device_buffer(),
+            KernelSlice::SliceMut(x) => x.device_buffer_mut(),
+        }
+    }
+    fn len(&self) -> usize {
+        match &self.slice {
+            KernelSlice::Slice(x) => x.len(),
+            KernelSlice::SliceMut(x) => x.len(),
+        }
+    }
+}
+
+impl<'a, T: Element> From<Slice<'a, T>> for KernelSliceArg<'a> {
+    fn from(slice: Slice<'a, T>) -> Self {
+        Self {
+            slice: KernelSlice::Slice(slice.into()),
+            element: element_layout::<T>(),
+        }
+    }
+}
+
+impl<'a, T: Element> From<SliceMut<'a, T>> for KernelSliceArg<'a> {
+    fn from(slice: SliceMut<'a, T>) -> Self {
+        Self {
+            slice: KernelSlice::SliceMut(slice.into()),
+            element: element_layout::<T>(),
+        }
+    }
+}
+
+impl<'a> From<ScalarSlice<'a>> for KernelSliceArg<'a> {
+    fn from(slice: ScalarSlice<'a>) -> Self {
+        Self {
+            slice: KernelSlice::Slice(slice),
+            element: None,
+        }
+    }
+}
+
+impl<'a> From<ScalarSliceMut<'a>> for KernelSliceArg<'a> {
+    fn from(slice: ScalarSliceMut<'a>) -> Self {
+        Self {
+            slice: KernelSlice::SliceMut(slice),
+            element: None,
+        }
+    }
+}
+
+/** Descriptor of a kernel compiled with [krnlc](#krnlc).
+
+See [Reflection](crate::kernel#reflection). */
+#[derive(Clone, Copy)]
+pub struct KernelInfo {
+    desc: __private::KernelDesc,
+}
+
+impl KernelInfo {
+    /// The path of the kernel, ie `my_crate::kernels::foo`.
+    pub fn name(&self) -> &'static str {
+        self.desc.name()
+    }
+    /// Required features.
+    pub fn features(&self) -> Features {
+        self.desc.features()
+    }
+    /// Required features of the variant with 8, 16 and 64 bit arithmetic computed in 32 bits.
+    ///
+    /// The kernel can be built for devices that have either [`.features()`](Self::features)
+    /// or these features.
+    pub fn emulated_features(&self) -> Option<Features> {
+        self.desc.emulated_features()
+    }
+    /// Whether the kernel is safe to dispatch.
+    pub fn safe(&self) -> bool {
+        self.desc.safe()
+    }
+    /// Specialization constants, in declaration order.
+    pub fn spec_consts(&self) -> &'static [SpecInfo] {
+        SpecInfo::wrap_slice(self.desc.spec_descs())
+    }
+    /// Slices, in declaration order.
+    pub fn slices(&self) -> &'static [SliceInfo] {
+        SliceInfo::wrap_slice(self.desc.slice_descs())
+    }
+    /// Push constants, in dispatch order.
+    ///
+    /// Push constants are ordered by the size of their scalar type, largest first, and then in
+    /// declaration order.
+    pub fn push_consts(&self) -> &'static [PushInfo] {
+        PushInfo::wrap_slice(self.desc.push_descs())
+    }
+}
+
+impl Debug for KernelInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("KernelInfo")
+            .field("name", &self.name())
+            .field("features", &self.features())
+            .field("emulated_features", &self.emulated_features())
+            .field("safe", &self.safe())
+            .field("spec_consts", &self.spec_consts())
+            .field("slices", &self.slices())
+            .field("push_consts", &self.push_consts())
+            .finish()
+    }
+}
+
+macro_rules! impl_wrap_slice {
+    ($($t:ident($d:ident)),* $(,)?) => {
+        $(
+            impl $t {
+                fn wrap_slice(descs: &'static [__private::$d]) -> &'static [Self] {
+                    // Safety: Self is a transparent wrapper.
+                    unsafe { std::slice::from_raw_parts(descs.as_ptr().cast(), descs.len()) }
+                }
+            }
+        )*
+    };
+}
+
+impl_wrap_slice!(SpecInfo(SpecDesc), SliceInfo(SliceDesc), PushInfo(PushDesc));
+
+/// Descriptor of a specialization constant.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct SpecInfo(__private::SpecDesc);
+
+impl SpecInfo {
+    /// The name of the constant.
+    pub fn name(&self) -> &'static str {
+        self.0.name
+    }
+    /// The scalar type.
+    pub fn scalar_type(&self) -> ScalarType {
+        self.0.scalar_type
+    }
+}
+
+impl Debug for SpecInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("SpecInfo")
+            .field("name", &self.name())
+            .field("scalar_type", &self.scalar_type())
+            .finish()
+    }
+}
+
+/// Descriptor of a slice argument.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct SliceInfo(__private::SliceDesc);
+
+impl SliceInfo {
+    /// The name of the slice.
+    pub fn name(&self) -> &'static str {
+        self.0.name
+    }
+    /// The scalar type of the elements.
+    pub fn scalar_type(&self) -> ScalarType {
+        self.0.scalar_type
+    }
+    /// Whether the kernel writes to the slice.
+    pub fn mutable(&self) -> bool {
+        self.0.mutable
+    }
+    /// Whether the slice is an item argument.
+    pub fn item(&self) -> bool {
+        self.0.item
+    }
+    /// The layout of non-scalar elements.
+    pub fn element(&self) -> Option<ElementLayout> {
+        self.0.element
+    }
+}
+
+impl Debug for SliceInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("SliceInfo")
+            .field("name", &self.name())
+            .field("scalar_type", &self.scalar_type())
+            .field("mutable", &self.mutable())
+            .field("item", &self.item())
+            .field("element", &self.element())
+            .finish()
+    }
+}
+
+/// Descriptor of a push constant.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct PushInfo(__private::PushDesc);
+
+impl PushInfo {
+    /// The name of the push constant.
+    pub fn name(&self) -> &'static str {
+        self.0.name
+    }
+    /// The scalar type.
+    pub fn scalar_type(&self) -> ScalarType {
+        self.0.scalar_type
+    }
+}
+
+impl Debug for PushInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("PushInfo")
+            .field("name", &self.name())
+            .field("scalar_type", &self.scalar_type())
+            .finish()
+    }
+}
+
 #[cfg_attr(not(feature = "device"), allow(dead_code))]
 #[derive(Clone, Debug)]
 pub(crate) struct KernelDesc {
     pub(crate) name: Cow<'static, str>,
     pub(crate) spirv: Vec<u32>,
     features: Features,
-    pub(crate) threads: u32,
+    pub(crate) threads: [u32; 3],
+    // threads are specialization constants following spec_descs
+    spec_threads: bool,
     spec_descs: &'static [SpecDesc],
-    pub(crate) slice_descs: &'static [SliceDesc],
-    push_descs: &'static [PushDesc],
+    pub(crate) slice_descs: Cow<'static, [SliceDesc]>,
+    push_descs: Cow<'static, [PushDesc]>,
+    // the kernel reads the offset of each slice from push constants
+    slice_offsets: bool,
 }
 
 #[cfg(feature = "device")]
@@ -594,14 +1239,18 @@ impl KernelDesc {
     }
     fn specialize(
         &self,
-        threads: u32,
+        threads: [u32; 3],
         spec_consts: &[ScalarElem],
         debug_printf: bool,
     ) -> Result<Self> {
         use rspirv::spirv::{Decoration, Op};
         let mut module = rspirv::dr::load_words(&self.spirv).unwrap();
         let mut spec_ids = HashMap::<u32, u32>::with_capacity(spec_consts.len());
-        let mut spec_string = format!("threads={threads}");
+        let mut spec_string = match threads {
+            [x, 1, 1] => format!("threads={x}"),
+            [x, y, 1] => format!("threads={x}x{y}"),
+            [x, y, z] => format!("threads={x}x{y}x{z}"),
+        };
         use std::fmt::Write;
         for (desc, spec) in self.spec_descs.iter().zip(spec_consts) {
             if !spec_string.is_empty() {
@@ -609,7 +1258,7 @@ impl KernelDesc {
             }
             let n = desc.name;
             macro_wrap!(match spec {
-                macro_for!($T in [U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64] {
+                macro_for!($T in [U8, I8, U16, I16, F16, BF16, U32, I32, F32, U64, I64, F64, F8E4M3, F8E5M2] {
                     ScalarElem::$T(x) => write!(&mut spec_string, "{n}={x}").unwrap(),
                 })
                 _ => unreachable!("{spec:?}"),
@@ -635,7 +1284,11 @@ impl KernelDesc {
                     if let Some(spec_id) = spec_ids.get(&result_id).copied().map(|x| x as usize) {
                         let value = if let Some(value) = spec_consts.get(spec_id).copied() {
                             value
-                        } else if spec_id == spec_consts.len() {
+                        } else if !self.spec_threads {
+                            continue;
+                        } else if let Some(threads) =
+                            threads.get(spec_id - spec_consts.len()).copied()
+                        {
                             ScalarElem::U32(threads)
                         } else {
                             unreachable!("{inst:?}")
@@ -710,10 +1363,23 @@ fn strip_debug_printf(module: &mut rspirv::dr::Module) {
     }
 }
 
+#[cfg_attr(not(feature = "device"), allow(dead_code))]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum KernelId {
+    // address of the name of a kernel compiled with krnlc
+    Static(usize),
+    // SPIR-V loaded at runtime
+    Spirv {
+        name: Cow<'static, str>,
+        spirv: Arc<[u32]>,
+    },
+}
+
 #[cfg(feature = "device")]
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub(crate) struct KernelKey {
-    id: usize,
+    id: KernelId,
+    emulated: bool,
     spec_bytes: Vec<u8>,
 }
 
@@ -724,28 +1390,33 @@ pub mod __private {
 
     use super::*;
     #[cfg(feature = "device")]
-    use crate::device::{DeviceBuffer, RawKernel};
-    use crate::{
-        buffer::{ScalarSlice, ScalarSliceMut, Slice, SliceMut},
-        scalar::Scalar,
-    };
+    use crate::device::RawKernel;
 
     #[derive(Clone, Copy)]
     pub struct KernelDesc {
         name: &'static str,
         spirv: &'static [u8],
         features: Features,
+        emulated: Option<EmulatedDesc>,
         safe: bool,
         spec_descs: &'static [SpecDesc],
         slice_descs: &'static [SliceDesc],
         push_descs: &'static [PushDesc],
     }
 
+    /// Variant of a kernel with 8, 16 and 64 bit arithmetic computed in 32 bits.
+    #[derive(Clone, Copy)]
+    pub struct EmulatedDesc {
+        pub spirv: &'static [u8],
+        pub features: Features,
+    }
+
     #[derive(Clone, Copy)]
     pub struct KernelDescArgs {
         pub name: &'static str,
         pub spirv: &'static [u8],
         pub features: Features,
+        pub emulated: Option<EmulatedDesc>,
         pub safe: bool,
         pub spec_descs: &'static [SpecDesc],
         pub slice_descs: &'static [SliceDesc],
@@ -805,6 +1476,7 @@ pub mod __private {
                 name,
                 spirv,
                 features,
+                emulated,
                 safe,
                 spec_descs,
                 slice_descs,
@@ -814,12 +1486,34 @@ pub mod __private {
                 name,
                 spirv,
                 features,
+                emulated,
                 safe,
                 spec_descs,
                 slice_descs,
                 push_descs,
             }
         }
+        pub(super) fn name(&self) -> &'static str {
+            self.name
+        }
+        pub(super) fn features(&self) -> Features {
+            self.features
+        }
+        pub(super) fn emulated_features(&self) -> Option<Features> {
+            self.emulated.map(|emulated| emulated.features)
+        }
+        pub(super) fn safe(&self) -> bool {
+            self.safe
+        }
+        pub(super) fn spec_descs(&self) -> &'static [SpecDesc] {
+            self.spec_descs
+        }
+        pub(super) fn slice_descs(&self) -> &'static [SliceDesc] {
+            self.slice_descs
+        }
+        pub(super) fn push_descs(&self) -> &'static [PushDesc] {
+            self.push_descs
+        }
         const fn check_declaration(
             &self,
             safety: Safety,
@@ -870,6 +1564,20 @@ pub mod __private {
         }
     }
 
+    // Collects the kernels within `module_path`, including submodules.
+    // Module descriptors only carry metadata, their spirv is empty.
+    pub fn module_kernels(module_path: &str, kernels: &[KernelDesc]) -> Vec<KernelInfo> {
+        kernels
+            .iter()
+            .filter(|desc| {
+                desc.name
+                    .strip_prefix(module_path)
+                    .map_or(false, |name| name.starts_with("::"))
+            })
+            .map(|desc| KernelInfo { desc: *desc })
+            .collect()
+    }
+
     #[derive(Clone, Copy)]
     pub enum Safety {
         Safe,
@@ -899,12 +1607,26 @@ pub mod __private {
         }
     }
 
+    const fn element_layout_const_eq(a: Option<ElementLayout>, b: Option<ElementLayout>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                scalar_type_const_eq(a.scalar_type, b.scalar_type)
+                    && a.size == b.size
+                    && a.align == b.align
+            }
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
     #[derive(Clone, Copy, Debug)]
     pub struct SliceDesc {
         pub name: &'static str,
         pub scalar_type: ScalarType,
         pub mutable: bool,
         pub item: bool,
+        /// The layout of non-scalar elements.
+        pub element: Option<ElementLayout>,
     }
 
     impl SliceDesc {
@@ -913,6 +1635,16 @@ pub mod __private {
                 && scalar_type_const_eq(self.scalar_type, other.scalar_type)
                 && self.mutable == other.mutable
                 && self.item == other.item
+                && element_layout_const_eq(self.element, other.element)
+        }
+        /// The size of an element in bytes.
+        #[cfg(feature = "device")]
+        fn width(&self) -> usize {
+            if let Some(element) = self.element.as_ref() {
+                element.size
+            } else {
+                self.scalar_type.size()
+            }
         }
     }
 
@@ -949,10 +1681,12 @@ pub mod __private {
     #[cfg_attr(not(feature = "device"), allow(dead_code))]
     #[derive(Clone)]
     pub struct KernelBuilder {
-        id: usize,
+        id: KernelId,
         desc: Arc<super::KernelDesc>,
+        emulated: Option<Arc<super::KernelDesc>>,
+        emulate: bool,
         spec_consts: Vec<ScalarElem>,
-        threads: Option<u32>,
+        threads: Option<[u32; 3]>,
     }
 
     impl KernelBuilder {
@@ -961,6 +1695,7 @@ pub mod __private {
                 name,
                 spirv,
                 features,
+                emulated,
                 safe: _,
                 spec_descs,
                 slice_descs,
@@ -971,24 +1706,76 @@ pub mod __private {
                 name: name.into(),
                 spirv,
                 features,
-                threads: 0,
+                threads: [0; 3],
+                spec_threads: true,
                 spec_descs,
-                slice_descs,
-                push_descs,
+                slice_descs: slice_descs.into(),
+                push_descs: push_descs.into(),
+                slice_offsets: true,
+            };
+            let emulated = if let Some(EmulatedDesc { spirv, features }) = emulated {
+                let spirv = decode_spirv(name, spirv)?;
+                Some(Arc::new(super::KernelDesc {
+                    spirv,
+                    features,
+                    ..desc.clone()
+                }))
+            } else {
+                None
             };
             Ok(Self {
-                id: name.as_ptr() as usize,
+                id: KernelId::Static(name.as_ptr() as usize),
                 desc: desc.into(),
+                emulated,
+                emulate: false,
                 spec_consts: Vec::new(),
                 threads: None,
             })
         }
-        pub fn with_threads(self, threads: u32) -> Self {
+        #[cfg(feature = "device")]
+        pub(super) fn from_spirv(
+            name: Cow<'static, str>,
+            spirv: &[u8],
+            args: &[SpirvArg],
+        ) -> Result<Self> {
+            let reflection = super::spirv::reflect(&name, spirv, args)?;
+            let id = KernelId::Spirv {
+                name: name.clone(),
+                spirv: reflection.spirv.as_slice().into(),
+            };
+            let desc = super::KernelDesc {
+                name,
+                spirv: reflection.spirv,
+                features: reflection.features,
+                threads: reflection.threads,
+                spec_threads: false,
+                spec_descs: &[],
+                slice_descs: reflection.slice_descs.into(),
+                push_descs: reflection.push_descs.into(),
+                slice_offsets: reflection.slice_offsets,
+            };
+            Ok(Self {
+                id,
+                desc: desc.into(),
+                emulated: None,
+                emulate: false,
+                spec_consts: Vec::new(),
+                threads: None,
+            })
+        }
+        pub fn with_threads(self, threads: [u32; 3]) -> Self {
             Self {
                 threads: Some(threads),
                 ..self
             }
         }
+        /// Prefer the emulated variant, if any, even if the device supports the native features.
+        pub fn emulate(self) -> Self {
+            Self {
+                emulate: true,
+                ..self
+            }
+        }
         pub fn specialize(self, spec_consts: &[ScalarElem]) -> Self {
             debug_assert_eq!(spec_consts.len(), self.desc.spec_descs.len());
             #[cfg(debug_assertions)]
@@ -1009,28 +1796,51 @@ pub mod __private {
                 }
                 #[cfg(feature = "device")]
                 DeviceInner::Device(device) => {
-                    let desc = &self.desc;
-                    let name = &desc.name;
-                    let features = desc.features;
                     let info = device.info();
                     let device_features = info.features();
-                    if !device_features.contains(features) {
-                        bail!("Kernel {name} requires {features:?}, {device:?} has {device_features:?}!");
+                    // prefer native arithmetic, fall back to the emulated variant if available
+                    let native = device_features.contains(self.desc.features);
+                    let emulated_desc = self
+                        .emulated
+                        .as_ref()
+                        .filter(|desc| device_features.contains(desc.features));
+                    let (desc, emulated) = match emulated_desc {
+                        Some(desc) if self.emulate || !native => (desc, true),
+                        _ if native => (&self.desc, false),
+                        _ => {
+                            let name = &self.desc.name;
+                            let features = self.desc.features;
+                            bail!("Kernel {name} requires {features:?}, {device:?} has {device_features:?}!");
+                        }
+                    };
+                    let name = &desc.name;
+                    let threads = if desc.spec_threads {
+                        self.threads.unwrap_or([info.default_threads(), 1, 1])
+                    } else {
+                        desc.threads
+                    };
+                    let max_threads_xyz = info.max_threads_xyz();
+                    if threads.contains(&0)
+                        || threads.iter().zip(max_threads_xyz).any(|(a, b)| *a > b)
+                    {
+                        bail!("Kernel {name} threads {threads:?} must be between 1 and max_threads_xyz {max_threads_xyz:?}!");
                     }
-                    let threads = self.threads.unwrap_or(info.default_threads());
                     let max_threads = info.max_threads();
-                    if threads > max_threads {
-                        bail!("Kernel {name} threads {threads} is greater than max_threads {max_threads}!");
+                    if threads.iter().map(|x| u64::from(*x)).product::<u64>()
+                        > u64::from(max_threads)
+                    {
+                        bail!("Kernel {name} threads {threads:?} is greater than max_threads {max_threads}!");
                     }
                     let spec_bytes = self
                         .spec_consts
                         .iter()
                         .flat_map(|x| x.as_bytes())
                         .copied()
-                        .chain(threads.to_ne_bytes())
+                        .chain(threads.iter().flat_map(|x| x.to_ne_bytes()))
                         .collect();
                     let key = KernelKey {
-                        id: self.id,
+                        id: self.id.clone(),
+                        emulated,
                         spec_bytes,
                     };
                     let debug_printf = info.debug_printf();
@@ -1051,27 +1861,93 @@ pub mod __private {
         }
     }
 
+    // Validates arguments against the descriptors of the kernel.
+    #[cfg(feature = "device")]
+    fn check_args(
+        desc: &super::KernelDesc,
+        slices: &[KernelSliceArg],
+        push_consts: &[ScalarElem],
+    ) -> Result<()> {
+        let kernel_name = &desc.name;
+        if slices.len() != desc.slice_descs.len() {
+            bail!(
+                "Kernel `{kernel_name}` expected {} slices, found {}!",
+                desc.slice_descs.len(),
+                slices.len()
+            );
+        }
+        if push_consts.len() != desc.push_descs.len() {
+            bail!(
+                "Kernel `{kernel_name}` expected {} push constants, found {}!",
+                desc.push_descs.len(),
+                push_consts.len()
+            );
+        }
+        for (slice, slice_desc) in slices.iter().zip(desc.slice_descs.iter()) {
+            let slice_name = &slice_desc.name;
+            let scalar_type = slice.scalar_type();
+            if scalar_type != slice_desc.scalar_type {
+                bail!(
+                    "Kernel `{kernel_name}`.`{slice_name}` expected {:?}, found {scalar_type:?}!",
+                    slice_desc.scalar_type
+                );
+            }
+            if slice.element != slice_desc.element {
+                bail!(
+                    "Kernel `{kernel_name}`.`{slice_name}` expected element {:?}, found {:?}!",
+                    slice_desc.element,
+                    slice.element,
+                );
+            }
+            if slice_desc.mutable && !slice.mutable() {
+                bail!("Kernel `{kernel_name}`.`{slice_name}` expected a mutable slice!");
+            }
+        }
+        for (push, push_desc) in push_consts.iter().zip(desc.push_descs.iter()) {
+            let push_name = &push_desc.name;
+            let scalar_type = push.scalar_type();
+            if scalar_type != push_desc.scalar_type {
+                bail!(
+                    "Kernel `{kernel_name}`.`{push_name}` expected {:?}, found {scalar_type:?}!",
+                    push_desc.scalar_type
+                );
+            }
+        }
+        Ok(())
+    }
+
     pub enum WithGroups<const G: bool> {}
 
     #[derive(Clone)]
     pub struct Kernel {
         #[cfg(feature = "device")]
         inner: RawKernel,
-        threads: u32,
+        threads: [u32; 3],
         #[cfg(feature = "device")]
-        groups: Option<u32>,
+        groups: Option<[u32; 3]>,
     }
 
     impl Kernel {
-        pub fn threads(&self) -> u32 {
+        pub fn threads(&self) -> [u32; 3] {
             self.threads
         }
-        pub fn with_global_threads(self, global_threads: u32) -> Self {
+        pub(super) fn name(&self) -> &str {
             #[cfg(feature = "device")]
             {
-                let desc = &self.inner.desc();
-                let threads = desc.threads;
-                let groups = global_threads / threads + u32::from(global_threads % threads != 0);
+                &self.inner.desc().name
+            }
+            #[cfg(not(feature = "device"))]
+            {
+                unreachable!()
+            }
+        }
+        pub fn with_global_threads(self, global_threads: [u32; 3]) -> Self {
+            #[cfg(feature = "device")]
+            {
+                let threads = self.threads;
+                let groups = [0, 1, 2].map(|i| {
+                    global_threads[i] / threads[i] + u32::from(global_threads[i] % threads[i] != 0)
+                });
                 self.with_groups(groups)
             }
             #[cfg(not(feature = "device"))]
@@ -1080,7 +1956,7 @@ pub mod __private {
                 unreachable!()
             }
         }
-        pub fn with_groups(self, groups: u32) -> Self {
+        pub fn with_groups(self, groups: [u32; 3]) -> Self {
             #[cfg(feature = "device")]
             {
                 Self {
@@ -1103,22 +1979,21 @@ pub mod __private {
             {
                 let desc = &self.inner.desc();
                 let kernel_name = &desc.name;
+                check_args(desc, slices, push_consts)?;
                 let mut buffers = Vec::with_capacity(desc.slice_descs.len());
                 let mut items: Option<u32> = None;
                 let device = self.inner.device();
                 let mut push_bytes = Vec::with_capacity(desc.push_consts_range() as usize);
-                debug_assert_eq!(push_consts.len(), desc.push_descs.len());
                 for (push, push_desc) in push_consts.iter().zip(desc.push_descs.iter()) {
-                    debug_assert_eq!(push.scalar_type(), push_desc.scalar_type);
-                    debug_assert_eq!(push_bytes.len() % push.scalar_type().size(), 0);
+                    while push_bytes.len() % push.scalar_type().size() != 0 {
+                        push_bytes.push(0);
+                    }
                     push_bytes.extend_from_slice(push.as_bytes());
                 }
                 while push_bytes.len() % 4 != 0 {
                     push_bytes.push(0);
                 }
                 for (slice, slice_desc) in slices.iter().zip(desc.slice_descs.iter()) {
-                    debug_assert_eq!(slice.scalar_type(), slice_desc.scalar_type);
-                    debug_assert!(!slice_desc.mutable || slice.mutable());
                     let slice_name = &slice_desc.name;
                     if slice.len() == 0 {
                         bail!("Kernel `{kernel_name}`.`{slice_name}` is empty!");
@@ -1135,33 +2010,56 @@ pub mod __private {
                         );
                     }
                     buffers.push(buffer.clone());
+                    let width = slice_desc.width();
+                    if buffer.offset() % width != 0 || buffer.len() % width != 0 {
+                        bail!(
+                            "Kernel `{kernel_name}`.`{slice_name}` offset {} and len {} in bytes are not multiples of the element size {width}!",
+                            buffer.offset(),
+                            buffer.len(),
+                        );
+                    }
+                    let offset = buffer.offset() / width;
+                    // the whole buffer is bound, including padding
+                    if !desc.slice_offsets && (offset != 0 || buffer.len() != buffer.bound_len()) {
+                        let len = buffer.len() / width;
+                        let bound_len = buffer.bound_len() / width;
+                        bail!("Kernel `{kernel_name}`.`{slice_name}` has offset {offset} and len {len}, but the kernel does not read slice offsets or lengths, so it must span its entire buffer of {bound_len} elements, including padding!");
+                    }
+                    let len = buffer.len() / width;
                     if slice_desc.item {
                         items.replace(if let Some(items) = items {
-                            items.min(slice.len() as u32)
+                            items.min(len as u32)
                         } else {
-                            slice.len() as u32
+                            len as u32
                         });
                     }
-                    let width = slice_desc.scalar_type.size();
-                    let offset = buffer.offset() / width;
-                    let len = buffer.len() / width;
                     push_bytes.extend_from_slice(&offset.to_u32().unwrap().to_ne_bytes());
                     push_bytes.extend_from_slice(&len.to_u32().unwrap().to_ne_bytes());
                 }
                 let info = self.inner.device().info().clone();
-                let max_groups = info.max_groups();
+                let max_groups_xyz = info.max_groups_xyz();
                 let groups = if let Some(groups) = self.groups {
-                    if groups > max_groups {
-                        bail!("Kernel `{kernel_name}` groups {groups} is greater than max_groups {max_groups}!");
+                    if groups.iter().zip(max_groups_xyz).any(|(a, b)| *a > b) {
+                        bail!("Kernel `{kernel_name}` groups {groups:?} is greater than max_groups_xyz {max_groups_xyz:?}!");
                     }
                     groups
                 } else if let Some(items) = items {
-                    let threads = self.threads;
+                    let threads = self.threads[0];
                     let groups = items / threads + u32::from(items % threads != 0);
-                    groups.min(max_groups)
+                    [groups.min(max_groups_xyz[0]), 1, 1]
                 } else {
                     unreachable!("groups not provided!")
                 };
+                // ids are computed in 32 bits
+                let global_threads = groups
+                    .iter()
+                    .chain(self.threads.iter())
+                    .map(|x| u64::from(*x))
+                    .product::<u64>();
+                if global_threads > u64::from(u32::MAX) {
+                    let threads = self.threads;
+                    bail!("Kernel `{kernel_name}` groups {groups:?} with threads {threads:?} is greater than u32::MAX global threads!");
+                }
                 let debug_printf_panic = if info.debug_printf() {
                     Some(Arc::new(AtomicBool::default()))
                 } else {
@@ -1203,52 +2101,6 @@ pub mod __private {
             }
         }
     }
-
-    #[doc(hidden)]
-    pub enum KernelSliceArg<'a> {
-        Slice(ScalarSlice<'a>),
-        SliceMut(ScalarSliceMut<'a>),
-    }
-
-    #[cfg(feature = "device")]
-    impl KernelSliceArg<'_> {
-        fn scalar_type(&self) -> ScalarType {
-            match self {
-                Self::Slice(x) => x.scalar_type(),
-                Self::SliceMut(x) => x.scalar_type(),
-            }
-        }
-        fn mutable(&self) -> bool {
-            match self {
-                Self::Slice(_) => false,
-                Self::SliceMut(_) => true,
-            }
-        }
-        fn device_buffer(&self) -> Option<&DeviceBuffer> {
-            match self {
-                Self::Slice(x) => x.device_buffer(),
-                Self::SliceMut(x) => x.device_buffer_mut(),
-            }
-        }
-        fn len(&self) -> usize {
-            match self {
-                Self::Slice(x) => x.len(),
-                Self::SliceMut(x) => x.len(),
-            }
-        }
-    }
-
-    impl<'a, T: Scalar> From<Slice<'a, T>> for KernelSliceArg<'a> {
-        fn from(slice: Slice<'a, T>) -> Self {
-            Self::Slice(slice.into())
-        }
-    }
-
-    impl<'a, T: Scalar> From<SliceMut<'a, T>> for KernelSliceArg<'a> {
-        fn from(slice: SliceMut<'a, T>) -> Self {
-            Self::SliceMut(slice.into())
-        }
-    }
 }
 
 pub(crate) use __private::{PushDesc, SliceDesc, SpecDesc};
@@ -0,0 +1,355 @@
+use super::{PushDesc, SliceDesc, SpirvArg};
+use crate::{device::Features, scalar::ScalarType};
+use anyhow::{bail, format_err, Result};
+use fxhash::FxHashMap;
+use rspirv::{
+    binary::Assemble,
+    dr::{Instruction, Operand},
+    spirv::{BuiltIn, Capability, Decoration, ExecutionMode, ExecutionModel, Op, StorageClass},
+};
+
+pub(super) struct SpirvReflection {
+    pub(super) spirv: Vec<u32>,
+    pub(super) features: Features,
+    pub(super) threads: [u32; 3],
+    pub(super) slice_descs: Vec<SliceDesc>,
+    pub(super) push_descs: Vec<PushDesc>,
+    pub(super) slice_offsets: bool,
+}
+
+// Whether a SPIR-V type is compatible with `scalar_type`.
+//
+// bf16 and fp8 have no SPIR-V type, so they match integers of the same width. Signedness is not
+// checked.
+fn type_matches(inst: Option<&Instruction>, scalar_type: ScalarType) -> bool {
+    use ScalarType::*;
+
+    let width = scalar_type.size() as u32 * 8;
+    match inst.map(|inst| (inst.class.opcode, inst.operands.first())) {
+        Some((Op::TypeFloat, Some(Operand::LiteralInt32(w)))) => {
+            *w == width && matches!(scalar_type, F16 | F32 | F64)
+        }
+        Some((Op::TypeInt, Some(Operand::LiteralInt32(w)))) => {
+            *w == width && !matches!(scalar_type, F16 | F32 | F64 | C32 | C64)
+        }
+        _ => false,
+    }
+}
+
+fn capability_features(capability: Capability) -> Features {
+    match capability {
+        Capability::Int8 => Features::INT8,
+        Capability::Int16 => Features::INT16,
+        Capability::Int64 => Features::INT64,
+        Capability::Float16 => Features::FLOAT16,
+        Capability::Float64 => Features::FLOAT64,
+        Capability::StorageBuffer8BitAccess => Features::BUFFER8,
+        Capability::StorageBuffer16BitAccess => Features::BUFFER16,
+        Capability::StoragePushConstant8 => Features::PUSH_CONSTANT8,
+        Capability::StoragePushConstant16 => Features::PUSH_CONSTANT16,
+        Capability::Int64Atomics => {
+            Features::BUFFER_INT64_ATOMICS.union(Features::GROUP_INT64_ATOMICS)
+        }
+        Capability::GroupNonUniform => Features::SUBGROUP_BASIC,
+        Capability::GroupNonUniformVote => Features::SUBGROUP_VOTE,
+        Capability::GroupNonUniformArithmetic => Features::SUBGROUP_ARITHMETIC,
+        Capability::GroupNonUniformBallot => Features::SUBGROUP_BALLOT,
+        Capability::GroupNonUniformShuffle => Features::SUBGROUP_SHUFFLE,
+        Capability::GroupNonUniformShuffleRelative => Features::SUBGROUP_SHUFFLE_RELATIVE,
+        Capability::GroupNonUniformClustered => Features::SUBGROUP_CLUSTERED,
+        Capability::GroupNonUniformQuad => Features::SUBGROUP_QUAD,
+        Capability::AtomicFloat32AddEXT => {
+            Features::BUFFER_FLOAT32_ATOMIC_ADD.union(Features::GROUP_FLOAT32_ATOMIC_ADD)
+        }
+        Capability::AtomicFloat32MinMaxEXT => {
+            Features::BUFFER_FLOAT32_ATOMIC_MIN_MAX.union(Features::GROUP_FLOAT32_ATOMIC_MIN_MAX)
+        }
+        _ => Features::empty(),
+    }
+}
+
+pub(super) fn reflect(name: &str, spirv: &[u8], args: &[SpirvArg]) -> Result<SpirvReflection> {
+    let module = rspirv::dr::load_bytes(spirv)
+        .map_err(|e| format_err!("Kernel `{name}` failed to load SPIR-V! {e}"))?;
+    let entry_id = module.entry_points.iter().find_map(|inst| {
+        if let [Operand::ExecutionModel(ExecutionModel::GLCompute), Operand::IdRef(id), Operand::LiteralString(entry), ..] =
+            inst.operands.as_slice()
+        {
+            if entry == "main" {
+                return Some(*id);
+            }
+        }
+        None
+    });
+    let entry_id = if let Some(entry_id) = entry_id {
+        entry_id
+    } else {
+        bail!("Kernel `{name}` expected a GLCompute entry point `main`!");
+    };
+    let mut features = Features::empty();
+    for inst in module.capabilities.iter() {
+        if let [Operand::Capability(capability)] = inst.operands.as_slice() {
+            features |= capability_features(*capability);
+        }
+    }
+    let defs: FxHashMap<u32, &Instruction> = module
+        .types_global_values
+        .iter()
+        .filter_map(|inst| Some((inst.result_id?, inst)))
+        .collect();
+    let decoration = |id: u32, decoration: Decoration| {
+        module.annotations.iter().find_map(|inst| {
+            if inst.class.opcode == Op::Decorate {
+                if let [Operand::IdRef(target), Operand::Decoration(d), extra @ ..] =
+                    inst.operands.as_slice()
+                {
+                    if *target == id && *d == decoration {
+                        return Some(extra);
+                    }
+                }
+            }
+            None
+        })
+    };
+    let member_decoration = |id: u32, member: u32, decoration: Decoration| {
+        module.annotations.iter().find_map(|inst| {
+            if inst.class.opcode == Op::MemberDecorate {
+                if let [Operand::IdRef(target), Operand::LiteralInt32(m), Operand::Decoration(d), extra @ ..] =
+                    inst.operands.as_slice()
+                {
+                    if *target == id && *m == member && *d == decoration {
+                        return Some(extra);
+                    }
+                }
+            }
+            None
+        })
+    };
+    let constant_u32 = |id: &Operand| {
+        let inst = defs.get(&id.id_ref_any()?)?;
+        match (inst.class.opcode, inst.operands.as_slice()) {
+            (Op::Constant | Op::SpecConstant, [Operand::LiteralInt32(x)]) => Some(*x),
+            _ => None,
+        }
+    };
+    let constant_xyz = |ids: &[Operand]| -> Option<[u32; 3]> {
+        if let [x, y, z] = ids {
+            Some([constant_u32(x)?, constant_u32(y)?, constant_u32(z)?])
+        } else {
+            None
+        }
+    };
+    // The WorkgroupSize builtin takes precedence over the execution mode.
+    let workgroup_size = module.annotations.iter().find_map(|inst| {
+        if let [Operand::IdRef(id), Operand::Decoration(Decoration::BuiltIn), Operand::BuiltIn(BuiltIn::WorkgroupSize)] =
+            inst.operands.as_slice()
+        {
+            let inst = defs.get(id)?;
+            if matches!(
+                inst.class.opcode,
+                Op::ConstantComposite | Op::SpecConstantComposite
+            ) {
+                return constant_xyz(&inst.operands);
+            }
+        }
+        None
+    });
+    let threads = workgroup_size.or_else(|| {
+        module.execution_modes.iter().find_map(|inst| {
+            match inst.operands.as_slice() {
+                [Operand::IdRef(id), Operand::ExecutionMode(ExecutionMode::LocalSize), Operand::LiteralInt32(x), Operand::LiteralInt32(y), Operand::LiteralInt32(z)]
+                    if *id == entry_id =>
+                {
+                    Some([*x, *y, *z])
+                }
+                [Operand::IdRef(id), Operand::ExecutionMode(ExecutionMode::LocalSizeId), ids @ ..]
+                    if *id == entry_id =>
+                {
+                    constant_xyz(ids)
+                }
+                _ => None,
+            }
+        })
+    });
+    let threads = if let Some(threads) = threads {
+        threads
+    } else {
+        bail!("Kernel `{name}` does not declare a constant local size!");
+    };
+    let slice_args: Vec<_> = args
+        .iter()
+        .filter_map(|arg| match arg {
+            SpirvArg::Slice {
+                name,
+                scalar_type,
+                mutable,
+            } => Some((*name, *scalar_type, *mutable)),
+            SpirvArg::Push { .. } => None,
+        })
+        .collect();
+    let push_args: Vec<_> = args
+        .iter()
+        .filter_map(|arg| match arg {
+            SpirvArg::Push { name, scalar_type } => Some((*name, *scalar_type)),
+            SpirvArg::Slice { .. } => None,
+        })
+        .collect();
+    let mut buffers = FxHashMap::default();
+    let mut push_block = None;
+    for inst in module.types_global_values.iter() {
+        if inst.class.opcode != Op::Variable {
+            continue;
+        }
+        let storage_class =
+            if let Some(Operand::StorageClass(storage_class)) = inst.operands.first() {
+                *storage_class
+            } else {
+                continue;
+            };
+        let (var_id, pointee) = match (
+            inst.result_id,
+            inst.result_type.and_then(|ty| defs.get(&ty)),
+        ) {
+            (Some(var_id), Some(ptr)) if ptr.class.opcode == Op::TypePointer => {
+                if let Some(Operand::IdRef(pointee)) = ptr.operands.get(1) {
+                    (var_id, *pointee)
+                } else {
+                    continue;
+                }
+            }
+            _ => continue,
+        };
+        match storage_class {
+            StorageClass::StorageBuffer => (),
+            StorageClass::Uniform if decoration(pointee, Decoration::BufferBlock).is_some() => (),
+            StorageClass::PushConstant => {
+                push_block.replace(pointee);
+                continue;
+            }
+            StorageClass::Uniform | StorageClass::UniformConstant => {
+                bail!("Kernel `{name}` has an unsupported descriptor, only storage buffers are supported!");
+            }
+            _ => continue,
+        }
+        let set = decoration(var_id, Decoration::DescriptorSet).and_then(|x| x.first());
+        let binding = decoration(var_id, Decoration::Binding).and_then(|x| x.first());
+        let binding = match (set, binding) {
+            (Some(Operand::LiteralInt32(0)), Some(Operand::LiteralInt32(binding))) => *binding,
+            _ => bail!("Kernel `{name}` storage buffers must be in descriptor set 0!"),
+        };
+        let readonly = decoration(var_id, Decoration::NonWritable).is_some()
+            || member_decoration(pointee, 0, Decoration::NonWritable).is_some();
+        buffers.insert(binding, (pointee, readonly));
+    }
+    if buffers.len() != slice_args.len() {
+        bail!(
+            "Kernel `{name}` expected {} slices, found {} storage buffers!",
+            slice_args.len(),
+            buffers.len()
+        );
+    }
+    let mut slice_descs = Vec::with_capacity(slice_args.len());
+    for (binding, (slice_name, scalar_type, mutable)) in slice_args.iter().copied().enumerate() {
+        let (pointee, readonly) =
+            if let Some(buffer) = buffers.get(&u32::try_from(binding).unwrap()).copied() {
+                buffer
+            } else {
+                bail!("Kernel `{name}`.`{slice_name}` expected binding {binding}!");
+            };
+        let element = defs
+            .get(&pointee)
+            .filter(|inst| inst.class.opcode == Op::TypeStruct)
+            .and_then(|inst| match inst.operands.as_slice() {
+                [Operand::IdRef(array)] => defs.get(array),
+                _ => None,
+            })
+            .filter(|inst| inst.class.opcode == Op::TypeRuntimeArray)
+            .and_then(|inst| inst.operands.first()?.id_ref_any());
+        if !type_matches(element.and_then(|id| defs.get(&id).copied()), scalar_type) {
+            bail!("Kernel `{name}`.`{slice_name}` expected a runtime array of {scalar_type:?} at binding {binding}!");
+        }
+        if !mutable && !readonly {
+            bail!("Kernel `{name}`.`{slice_name}` is not mutable, expected a readonly buffer at binding {binding}!");
+        }
+        slice_descs.push(SliceDesc {
+            name: slice_name,
+            scalar_type,
+            mutable,
+            item: false,
+            element: None,
+        });
+    }
+    let push_members: Vec<_> = if let Some(push_block) = push_block {
+        let operands = defs
+            .get(&push_block)
+            .filter(|inst| inst.class.opcode == Op::TypeStruct)
+            .map(|inst| inst.operands.as_slice())
+            .unwrap_or_default();
+        operands
+            .iter()
+            .enumerate()
+            .map(|(member, ty)| {
+                let offset = member_decoration(push_block, member as u32, Decoration::Offset)
+                    .and_then(|x| x.first());
+                let offset = if let Some(Operand::LiteralInt32(offset)) = offset {
+                    Some(*offset)
+                } else {
+                    None
+                };
+                (
+                    offset,
+                    ty.id_ref_any().and_then(|id| defs.get(&id).copied()),
+                )
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    // Push constants are followed by the offset and len of each slice.
+    let mut push_descs = Vec::with_capacity(push_args.len());
+    let mut offset = 0;
+    for (index, (push_name, scalar_type)) in push_args.iter().copied().enumerate() {
+        let size = scalar_type.size() as u32;
+        while offset % size != 0 {
+            offset += 1;
+        }
+        match push_members.get(index) {
+            Some((Some(member_offset), ty))
+                if *member_offset == offset && type_matches(*ty, scalar_type) => {}
+            _ => {
+                bail!("Kernel `{name}`.`{push_name}` expected a {scalar_type:?} push constant at offset {offset}!");
+            }
+        }
+        offset += size;
+        push_descs.push(PushDesc {
+            name: push_name,
+            scalar_type,
+        });
+    }
+    while offset % 4 != 0 {
+        offset += 1;
+    }
+    let slice_members = &push_members[push_args.len()..];
+    let slice_offsets = if slice_members.is_empty() {
+        false
+    } else if slice_members.len() == 2 * slice_args.len()
+        && slice_members
+            .iter()
+            .zip(0..)
+            .all(|((member_offset, ty), i)| {
+                *member_offset == Some(offset + 4 * i) && type_matches(*ty, ScalarType::U32)
+            })
+    {
+        true
+    } else {
+        bail!("Kernel `{name}` has unexpected push constants, expected {} push constants, optionally followed by a u32 offset and len for each slice!", push_args.len());
+    };
+    Ok(SpirvReflection {
+        spirv: module.assemble(),
+        features,
+        threads,
+        slice_descs,
+        push_descs,
+        slice_offsets,
+    })
+}
@@ -52,9 +52,15 @@ pub use krnl_core::half;
 #[doc(no_inline)]
 pub use krnl_core::scalar;
 
+/// Parallel algorithms.
+pub mod algorithms;
 /// Buffers.
 pub mod buffer;
 /// Devices.
 pub mod device;
 /// Kernels.
 pub mod kernel;
+/// Linear algebra.
+pub mod linalg;
+/// Testing utilities.
+pub mod testing;
@@ -0,0 +1,390 @@
+/*!
+# Example
+```
+# use krnl::{anyhow::Result, buffer::{Buffer, Slice}, device::Device, linalg::Gemm};
+# fn main() -> Result<()> {
+# let device = Device::host();
+// [1 2] * [5 6] = [19 22]
+// [3 4]   [7 8]   [43 50]
+let a = Slice::from([1f32, 2., 3., 4.].as_slice()).to_device(device.clone())?;
+let b = Slice::from([5f32, 6., 7., 8.].as_slice()).to_device(device.clone())?;
+let mut c = Buffer::zeros(device, 4)?;
+Gemm::new(2, 2, 2).dispatch(a.as_slice(), b.as_slice(), c.as_slice_mut())?;
+assert_eq!(c.into_vec()?, [19., 22., 43., 50.]);
+# Ok(())
+# }
+```
+*/
+
+#[cfg(doc)]
+use crate::device::{error::DeviceLost, Features};
+use crate::{
+    buffer::{ScalarSliceMut, Slice, SliceMut},
+    scalar::{Scalar, ScalarType},
+};
+#[cfg(feature = "device")]
+use crate::{device::Device, macros::module};
+use anyhow::{bail, format_err, Result};
+use dry::macro_for;
+use half::{bf16, f16};
+#[cfg(feature = "device")]
+use paste::paste;
+
+/** General matrix multiply.
+
+`c = alpha * op(a) * op(b) + beta * c`
+
+Matrices are contiguous and row major. `op(a)` is `m x k` and `op(b)` is `k x n`, `c` is `m x n`. When transposed,
+`a` is stored as `k x m` and `b` as `n x k`.
+
+Batched multiplies are stored contiguously, ie `a` has `batch * m * k` elements.
+
+Implemented for [`f16`], [`bf16`], [`f32`], and [`f64`]. [`f16`] and [`bf16`] are accumulated as [`f32`].
+
+Devices use tiles of `a` and `b` in group memory, with the tile size specialized for the device's
+[default threads](crate::device::DeviceInfo::default_threads).
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Gemm<T> {
+    m: usize,
+    k: usize,
+    n: usize,
+    batch: usize,
+    transpose_a: bool,
+    transpose_b: bool,
+    alpha: T,
+    beta: T,
+}
+
+impl<T: Scalar> Gemm<T> {
+    /// Multiplies `m x k` by `k x n`, with `alpha` = 1 and `beta` = 0.
+    pub fn new(m: usize, k: usize, n: usize) -> Self {
+        Self {
+            m,
+            k,
+            n,
+            batch: 1,
+            transpose_a: false,
+            transpose_b: false,
+            alpha: T::one(),
+            beta: T::zero(),
+        }
+    }
+    /// Multiplies `batch` matrices. Defaults to 1.
+    pub fn with_batch(self, batch: usize) -> Self {
+        Self { batch, ..self }
+    }
+    /// Transposes `a`.
+    pub fn with_transpose_a(self, transpose_a: bool) -> Self {
+        Self {
+            transpose_a,
+            ..self
+        }
+    }
+    /// Transposes `b`.
+    pub fn with_transpose_b(self, transpose_b: bool) -> Self {
+        Self {
+            transpose_b,
+            ..self
+        }
+    }
+    /// Scales `op(a) * op(b)`. Defaults to 1.
+    pub fn with_alpha(self, alpha: T) -> Self {
+        Self { alpha, ..self }
+    }
+    /// Scales `c`. Defaults to 0.
+    ///
+    /// If `beta` is 0, `c` is not read, so it may be uninitialized or NaN.
+    pub fn with_beta(self, beta: T) -> Self {
+        Self { beta, ..self }
+    }
+    /** Computes `c = alpha * op(a) * op(b) + beta * c`.
+
+    # Errors
+    - `T` is not a float.
+    - `a`, `b`, or `c` are not on the same device.
+    - `a`, `b`, or `c` do not have the expected lengths.
+    - The lengths overflow `usize`, or exceed [`u32::MAX`] on a device.
+    - [`DeviceLost`]
+    - The kernel could not be dispatched.
+        - This may require [`Features`] for the type. */
+    pub fn dispatch(&self, a: Slice<T>, b: Slice<T>, c: SliceMut<T>) -> Result<()> {
+        use ScalarType::*;
+        if !matches!(T::SCALAR_TYPE, F16 | BF16 | F32 | F64) {
+            bail!("Gemm is not implemented for {:?}!", T::SCALAR_TYPE);
+        }
+        let device = c.device();
+        for (name, x_device) in [("a", a.device()), ("b", b.device())] {
+            if x_device != device {
+                bail!("Expected {name} on {device:?}, found {x_device:?}!");
+            }
+        }
+        let Self { m, k, n, batch, .. } = *self;
+        let product = |x: usize, y: usize| {
+            batch
+                .checked_mul(x)
+                .and_then(|len| len.checked_mul(y))
+                .ok_or_else(|| {
+                    format_err!("Gemm with batch {batch}, m {m}, k {k} and n {n} is too large!")
+                })
+        };
+        for (name, len, expected) in [
+            ("a", a.len(), product(m, k)?),
+            ("b", b.len(), product(k, n)?),
+            ("c", c.len(), product(m, n)?),
+        ] {
+            if len != expected {
+                bail!("Expected {name} with len {expected}, found {len}!");
+            }
+        }
+        if c.is_empty() {
+            return Ok(());
+        }
+        macro_for!($X in [f16, bf16, f32, f64] {
+            if T::SCALAR_TYPE == $X::SCALAR_TYPE {
+                let gemm = Gemm::<$X> {
+                    alpha: self.alpha.cast(),
+                    beta: self.beta.cast(),
+                    ..Gemm::new(m, k, n)
+                }
+                .with_batch(batch)
+                .with_transpose_a(self.transpose_a)
+                .with_transpose_b(self.transpose_b);
+                let a = Slice::<$X>::try_from(a.as_scalar_slice()).ok().unwrap();
+                let b = Slice::<$X>::try_from(b.as_scalar_slice()).ok().unwrap();
+                let c = SliceMut::<$X>::try_from(ScalarSliceMut::from(c)).ok().unwrap();
+                return <$X as GemmImpl>::gemm(&gemm, a, b, c);
+            }
+        });
+        unreachable!()
+    }
+}
+
+trait GemmImpl: Scalar {
+    fn gemm(gemm: &Gemm<Self>, a: Slice<Self>, b: Slice<Self>, c: SliceMut<Self>) -> Result<()>;
+}
+
+/// Tile size for `threads`, the largest power of 2 with `tile * tile <= threads`.
+#[cfg(feature = "device")]
+fn gemm_tile(device: &Device) -> u32 {
+    let threads = device.info().unwrap().default_threads();
+    let mut tile = 1;
+    while (tile * 2) * (tile * 2) <= threads {
+        tile *= 2;
+    }
+    tile
+}
+
+macro_rules! impl_gemm {
+    ($($X:ident => $A:ident),* $(,)?) => {
+        $(
+            impl GemmImpl for $X {
+                #[allow(unused_mut)]
+                fn gemm(gemm: &Gemm<Self>, a: Slice<Self>, b: Slice<Self>, mut c: SliceMut<Self>) -> Result<()> {
+                    let Gemm {
+                        m,
+                        k,
+                        n,
+                        batch,
+                        transpose_a,
+                        transpose_b,
+                        alpha,
+                        beta,
+                    } = *gemm;
+                    let alpha = alpha.cast::<$A>();
+                    let beta = beta.cast::<$A>();
+                    if let Some((a, b)) = a.as_host_slice().zip(b.as_host_slice()) {
+                        let c = c.as_host_slice_mut().unwrap();
+                        for batch_index in 0..batch {
+                            let a = &a[batch_index * m * k..(batch_index + 1) * m * k];
+                            let b = &b[batch_index * k * n..(batch_index + 1) * k * n];
+                            let c = &mut c[batch_index * m * n..(batch_index + 1) * m * n];
+                            for row in 0..m {
+                                for col in 0..n {
+                                    let mut acc = $A::default();
+                                    for i in 0..k {
+                                        let a = if transpose_a { a[i * m + row] } else { a[row * k + i] };
+                                        let b = if transpose_b { b[col * k + i] } else { b[i * n + col] };
+                                        acc += a.cast::<$A>() * b.cast::<$A>();
+                                    }
+                                    let c = &mut c[row * n + col];
+                                    let mut y = alpha * acc;
+                                    if beta != 0. {
+                                        y += beta * (*c).cast::<$A>();
+                                    }
+                                    *c = y.cast();
+                                }
+                            }
+                        }
+                        return Ok(());
+                    }
+                    #[cfg(feature = "device")]
+                    {
+                        // the kernel indexes with u32
+                        for x in [batch, m, k, n, a.len(), b.len(), c.len()] {
+                            if x > u32::MAX as usize {
+                                bail!("Gemm with batch {batch}, m {m}, k {k} and n {n} exceeds u32::MAX on {:?}!", c.device());
+                            }
+                        }
+                        let device = c.device();
+                        let tile = gemm_tile(&device);
+                        let tiles = |x: usize| (x + tile as usize - 1) / tile as usize;
+                        let tiles = batch * tiles(m) * tiles(n);
+                        let max_groups = device.info().unwrap().max_groups() as usize;
+                        let groups = tiles.min(max_groups) as u32;
+                        let builder = paste! {
+                            kernels::[<gemm_ $X>]::builder()?
+                        };
+                        builder
+                            .specialize(tile, transpose_a as u32, transpose_b as u32)
+                            .with_threads(tile * tile)
+                            .build(device)?
+                            .with_groups(groups)
+                            .dispatch(
+                                batch.try_into()?,
+                                m.try_into()?,
+                                k.try_into()?,
+                                n.try_into()?,
+                                alpha,
+                                beta,
+                                a,
+                                b,
+                                c,
+                            )
+                    }
+                    #[cfg(not(feature = "device"))]
+                    {
+                        unreachable!()
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_gemm! {
+    f16 => f32,
+    bf16 => f32,
+    f32 => f32,
+    f64 => f64,
+}
+
+#[cfg(feature = "device")]
+#[module]
+#[krnl(crate=crate)]
+mod kernels {
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl_core;
+    use krnl_core::macros::kernel;
+    #[cfg(target_arch = "spirv")]
+    use krnl_core::{
+        buffer::UnsafeIndex,
+        half::{bf16, f16},
+        scalar::Scalar,
+        spirv_std::arch::workgroup_memory_barrier_with_group_sync as group_barrier,
+    };
+    use paste::paste;
+
+    macro_rules! impl_gemm {
+        ($($X:ident => $A:ident),* $(,)?) => {
+            $(
+                paste! {
+                    // Each group computes `TILE x TILE` tiles of `c`, one element per thread.
+                    #[kernel]
+                    pub fn [<gemm_ $X>]<const TILE: u32, const TRANSPOSE_A: u32, const TRANSPOSE_B: u32>(
+                        batch: u32,
+                        m: u32,
+                        k: u32,
+                        n: u32,
+                        alpha: $A,
+                        beta: $A,
+                        #[global] a: Slice<$X>,
+                        #[global] b: Slice<$X>,
+                        #[group] a_tile: UnsafeSlice<$A, { (TILE * TILE) as usize }>,
+                        #[group] b_tile: UnsafeSlice<$A, { (TILE * TILE) as usize }>,
+                        #[global] c: UnsafeSlice<$X>,
+                    ) {
+                        let tile = TILE as usize;
+                        let (m, k, n) = (m as usize, k as usize, n as usize);
+                        let tiles_m = (m + tile - 1) / tile;
+                        let tiles_n = (n + tile - 1) / tile;
+                        let tiles = batch as usize * tiles_m * tiles_n;
+                        let thread_id = kernel.thread_id();
+                        let (ty, tx) = (thread_id / tile, thread_id % tile);
+                        let mut tile_id = kernel.group_id();
+                        while tile_id < tiles {
+                            let batch_index = tile_id / (tiles_m * tiles_n);
+                            let tile_m = (tile_id / tiles_n) % tiles_m;
+                            let tile_n = tile_id % tiles_n;
+                            let a_offset = batch_index * m * k;
+                            let b_offset = batch_index * k * n;
+                            let row = tile_m * tile + ty;
+                            let col = tile_n * tile + tx;
+                            let mut acc = $A::default();
+                            let mut i0 = 0;
+                            while i0 < k {
+                                let a_col = i0 + tx;
+                                let a_value = if row < m && a_col < k {
+                                    if TRANSPOSE_A == 1 {
+                                        a[a_offset + a_col * m + row]
+                                    } else {
+                                        a[a_offset + row * k + a_col]
+                                    }
+                                    .cast::<$A>()
+                                } else {
+                                    $A::default()
+                                };
+                                let b_row = i0 + ty;
+                                let b_value = if b_row < k && col < n {
+                                    if TRANSPOSE_B == 1 {
+                                        b[b_offset + col * k + b_row]
+                                    } else {
+                                        b[b_offset + b_row * n + col]
+                                    }
+                                    .cast::<$A>()
+                                } else {
+                                    $A::default()
+                                };
+                                unsafe {
+                                    *a_tile.unsafe_index_mut(thread_id) = a_value;
+                                    *b_tile.unsafe_index_mut(thread_id) = b_value;
+                                    group_barrier();
+                                }
+                                let mut i = 0;
+                                while i < tile {
+                                    unsafe {
+                                        acc += *a_tile.unsafe_index(ty * tile + i)
+                                            * *b_tile.unsafe_index(i * tile + tx);
+                                    }
+                                    i += 1;
+                                }
+                                unsafe {
+                                    group_barrier();
+                                }
+                                i0 += tile;
+                            }
+                            if row < m && col < n {
+                                unsafe {
+                                    let c = c.unsafe_index_mut(batch_index * m * n + row * n + col);
+                                    let mut y = alpha * acc;
+                                    if beta != 0. {
+                                        y += beta * (*c).cast::<$A>();
+                                    }
+                                    *c = y.cast();
+                                }
+                            }
+                            tile_id += kernel.groups();
+                        }
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_gemm! {
+        f16 => f32,
+        bf16 => f32,
+        f32 => f32,
+        f64 => f64,
+    }
+}
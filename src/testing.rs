@@ -0,0 +1,220 @@
+/*!
+# Example
+```
+# use krnl::{anyhow::Result, buffer::Buffer, device::Device};
+use krnl::testing::{assert_buffer_close, assert_buffer_eq};
+# fn main() -> Result<()> {
+# let device = Device::host();
+let x = Buffer::from(vec![1f32, 2., 3.]).into_device(device)?;
+let y = Buffer::from(vec![1f32, 2., 3.0001]);
+assert_buffer_eq!(x, x.to_device(Device::host())?);
+// |x - y| <= atol + rtol * |y|
+assert_buffer_close!(x, y, 1e-3, 0.);
+# Ok(())
+# }
+```
+*/
+
+use crate::{
+    buffer::Slice,
+    scalar::{Scalar, ScalarElem, ScalarType},
+};
+use anyhow::{bail, Result};
+use std::fmt::Write;
+
+/// The number of mismatches shown in errors.
+const MAX_MISMATCHES: usize = 8;
+
+/** Tolerance for [`check_buffer_close`].
+
+Elements `a` and `b` are close if `|a - b| <= atol + rtol * |b|`, or for floats if they are at most `ulps` apart. The latter is useful for [`f16`](crate::half::f16) and [`bf16`](crate::half::bf16), which may not represent the result of an operation exactly. The real and imaginary parts of complex numbers are compared separately. */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tolerance {
+    /// Relative tolerance.
+    pub rtol: f64,
+    /// Absolute tolerance.
+    pub atol: f64,
+    /// Units in the last place.
+    pub ulps: u64,
+}
+
+impl Tolerance {
+    /// Creates a new tolerance with `ulps` = 0.
+    pub fn new(rtol: f64, atol: f64) -> Self {
+        Self {
+            rtol,
+            atol,
+            ulps: 0,
+        }
+    }
+    /// Sets `ulps`.
+    pub fn with_ulps(self, ulps: u64) -> Self {
+        Self { ulps, ..self }
+    }
+    fn is_close<T: Scalar>(&self, a: T, b: T) -> bool {
+        if a == b {
+            return true;
+        }
+        // complex parts are compared separately
+        match (a.scalar_elem(), b.scalar_elem()) {
+            (ScalarElem::C32(a), ScalarElem::C32(b)) => {
+                return self.is_close(a.re(), b.re()) && self.is_close(a.im(), b.im());
+            }
+            (ScalarElem::C64(a), ScalarElem::C64(b)) => {
+                return self.is_close(a.re(), b.re()) && self.is_close(a.im(), b.im());
+            }
+            _ => (),
+        }
+        let (a_f64, b_f64) = (a.cast::<f64>(), b.cast::<f64>());
+        if a_f64.is_nan() || b_f64.is_nan() {
+            return a_f64.is_nan() && b_f64.is_nan();
+        }
+        if a_f64.is_infinite() || b_f64.is_infinite() {
+            return false;
+        }
+        if (a_f64 - b_f64).abs() <= self.atol + self.rtol * b_f64.abs() {
+            return true;
+        }
+        is_float::<T>() && ulp_distance(a, b) <= self.ulps
+    }
+}
+
+fn is_float<T: Scalar>() -> bool {
+    use ScalarType::*;
+    matches!(T::SCALAR_TYPE, F16 | BF16 | F32 | F64 | F8E4M3 | F8E5M2)
+}
+
+/// The number of representable floats between `a` and `b`.
+fn ulp_distance<T: Scalar>(a: T, b: T) -> u64 {
+    fn ordered<T: Scalar>(x: T) -> i128 {
+        let bits = x.scalar_elem().to_scalar_bits().cast::<u64>();
+        let sign = 1u64 << (T::SCALAR_TYPE.size() * 8 - 1);
+        if bits & sign != 0 {
+            -i128::from(bits & !sign)
+        } else {
+            i128::from(bits)
+        }
+    }
+    (ordered(a) - ordered(b))
+        .unsigned_abs()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+/** Checks that `a` and `b` are equal.
+
+Both are copied to the host. Elements are compared with `==`, so NaN is never equal.
+
+# Errors
+- The lengths are not equal.
+- Any elements are not equal, with the first mismatches.
+- Either could not be copied to the host. */
+pub fn check_buffer_eq<T: Scalar>(a: Slice<T>, b: Slice<T>) -> Result<()> {
+    check_buffers(a, b, "equal", |a, b| a == b)
+}
+
+/** Checks that `a` and `b` are close.
+
+Both are copied to the host. NaN is close to NaN, and infinities must be equal.
+
+See [`Tolerance`].
+
+# Errors
+- The lengths are not equal.
+- Any elements are not close, with the first mismatches.
+- Either could not be copied to the host. */
+pub fn check_buffer_close<T: Scalar>(a: Slice<T>, b: Slice<T>, tolerance: Tolerance) -> Result<()> {
+    check_buffers(a, b, "close", |a, b| tolerance.is_close(a, b))
+}
+
+fn check_buffers<T: Scalar>(
+    a: Slice<T>,
+    b: Slice<T>,
+    relation: &str,
+    f: impl Fn(T, T) -> bool,
+) -> Result<()> {
+    if a.len() != b.len() {
+        bail!(
+            "Expected buffers with equal lengths, found {} and {}!",
+            a.len(),
+            b.len()
+        );
+    }
+    let (a, b) = (a.to_vec()?, b.to_vec()?);
+    let mismatches: Vec<usize> = (0..a.len()).filter(|i| !f(a[*i], b[*i])).collect();
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    let mut msg = format!(
+        "Expected buffers to be {relation}, {} of {} elements differ:",
+        mismatches.len(),
+        a.len()
+    );
+    for i in mismatches.iter().copied().take(MAX_MISMATCHES) {
+        write!(&mut msg, "\n    [{i}]: {} != {}", a[i], b[i]).unwrap();
+        if is_float::<T>() {
+            write!(
+                &mut msg,
+                " (diff {}, {} ulps)",
+                (a[i].cast::<f64>() - b[i].cast::<f64>()).abs(),
+                ulp_distance(a[i], b[i])
+            )
+            .unwrap();
+        }
+    }
+    if mismatches.len() > MAX_MISMATCHES {
+        write!(&mut msg, "\n    ...").unwrap();
+    }
+    bail!(msg)
+}
+
+/** Asserts that two buffers are equal.
+
+Accepts any [`BufferBase`](crate::buffer::BufferBase), on the host or a device, with optional format arguments like [`assert_eq!`].
+
+See [`check_buffer_eq`].
+
+# Panics
+If the buffers are not equal, with the first mismatching indices. */
+#[macro_export]
+macro_rules! assert_buffer_eq {
+    ($a:expr, $b:expr $(,)?) => {
+        if let Err(e) = $crate::testing::check_buffer_eq($a.as_slice(), $b.as_slice()) {
+            panic!("{e}");
+        }
+    };
+    ($a:expr, $b:expr, $($arg:tt)+) => {
+        if let Err(e) = $crate::testing::check_buffer_eq($a.as_slice(), $b.as_slice()) {
+            panic!("{}\n{e}", format_args!($($arg)+));
+        }
+    };
+}
+
+/** Asserts that two buffers are close.
+
+Accepts any [`BufferBase`](crate::buffer::BufferBase), on the host or a device.
+- `assert_buffer_close!(a, b, rtol, atol)`
+- `assert_buffer_close!(a, b, rtol, atol, ulps = n)`
+
+See [`check_buffer_close`].
+
+# Panics
+If the buffers are not close, with the first mismatching indices. */
+#[macro_export]
+macro_rules! assert_buffer_close {
+    ($a:expr, $b:expr, $rtol:expr, $atol:expr $(,)?) => {
+        $crate::assert_buffer_close!($a, $b, $rtol, $atol, ulps = 0)
+    };
+    ($a:expr, $b:expr, $rtol:expr, $atol:expr, ulps = $ulps:expr $(,)?) => {
+        if let Err(e) = $crate::testing::check_buffer_close(
+            $a.as_slice(),
+            $b.as_slice(),
+            $crate::testing::Tolerance::new($rtol, $atol).with_ulps($ulps),
+        ) {
+            panic!("{e}");
+        }
+    };
+}
+
+#[doc(inline)]
+pub use crate::{assert_buffer_close, assert_buffer_eq};
@@ -1,15 +1,21 @@
 use dry::macro_for;
 use half::{bf16, f16};
-#[cfg(feature = "device")]
-use krnl::buffer::Buffer;
-use krnl::{buffer::Slice, device::Device, scalar::Scalar};
 #[cfg(not(target_family = "wasm"))]
-use krnl::{device::Features, scalar::ScalarType};
+use krnl::device::Features;
+use krnl::krnl_core;
+use krnl::{
+    algorithms,
+    buffer::{Buffer, Element, IndexScalar, ScalarBuffer, Slice},
+    device::Device,
+    linalg::Gemm,
+    scalar::{c32, c64, f8e4m3, f8e5m2, Scalar, ScalarType},
+    testing::{self, assert_buffer_close, assert_buffer_eq, Tolerance},
+};
 #[cfg(not(target_family = "wasm"))]
 use libtest_mimic::{Arguments, Trial};
 use paste::paste;
 #[cfg(not(target_family = "wasm"))]
-use std::{mem::size_of, str::FromStr};
+use std::{io::Cursor, mem::size_of, str::FromStr};
 #[cfg(target_family = "wasm")]
 use wasm_bindgen_test::wasm_bindgen_test as test;
 
@@ -83,8 +89,48 @@ fn buffer_tests(device: &Device, device2: Option<&Device>) -> impl IntoIterator<
     let mut tests = Vec::new();
 
     tests.push(device_test(device, "buffer_from_vec", buffer_from_vec));
+    tests.push(device_test(device, "buffer_element", buffer_element));
+    #[cfg(feature = "device")]
+    tests.push(device_test(device, "kernel_from_spirv", kernel_from_spirv));
 
     if device.is_device() {
+        tests.push(Trial::test("kernel_dispatch_dyn_device", {
+            let device = device.clone();
+            let device2 = device2.cloned();
+            move || {
+                kernel_dispatch_dyn(device, device2);
+                Ok(())
+            }
+        }));
+        tests.push(device_test(
+            device,
+            "kernel_global_threads_overflow",
+            kernel_global_threads_overflow,
+        ));
+        tests.push(device_test(device, "kernel_histogram", kernel_histogram));
+        tests.push(
+            device_test(device, "kernel_histogram_u64", kernel_histogram_u64).with_ignored_flag(
+                !features.contains(Features::INT64 | Features::BUFFER_INT64_ATOMICS),
+            ),
+        );
+        let subgroup_ballot = Features::SUBGROUP_BASIC | Features::SUBGROUP_BALLOT;
+        let subgroup_arithmetic = Features::SUBGROUP_BASIC | Features::SUBGROUP_ARITHMETIC;
+        tests.push(
+            device_test(
+                device,
+                "kernel_subgroup_broadcast",
+                kernel_subgroup_broadcast,
+            )
+            .with_ignored_flag(!features.contains(subgroup_ballot)),
+        );
+        tests.push(
+            device_test(device, "kernel_subgroup_reduce", kernel_subgroup_reduce)
+                .with_ignored_flag(!features.contains(subgroup_arithmetic)),
+        );
+        tests.push(
+            device_test(device, "kernel_subgroup_ballot", kernel_subgroup_ballot)
+                .with_ignored_flag(!features.contains(subgroup_ballot)),
+        );
         #[cfg(feature = "device")]
         tests.push(Trial::test("device_buffer_too_large", {
             let device = device.clone();
@@ -106,7 +152,7 @@ fn buffer_tests(device: &Device, device2: Option<&Device>) -> impl IntoIterator<
         );
     }
 
-    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, f8e4m3, f8e5m2] {
         paste! {
             {
                 let ignore = if device.is_host() {
@@ -138,14 +184,17 @@ fn buffer_tests(device: &Device, device2: Option<&Device>) -> impl IntoIterator<
                 U32 | I32 | F32 => Features::empty(),
                 U64 | I64 => Features::INT64,
                 F64 => Features::INT64 | Features::FLOAT64,
+                C32 => Features::empty(),
+                C64 => Features::INT64 | Features::FLOAT64,
+                F8E4M3 | F8E5M2 => Features::INT8 | Features::BUFFER8,
                 _ => unreachable!(),
             }
         }
         features(x).union(features(y))
     }
 
-    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
-        macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+    macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
+        macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
             {
                 let ignore = !device.is_host() && !features.contains(buffer_cast_features($X::SCALAR_TYPE, $Y::SCALAR_TYPE));
                 paste! {
@@ -158,12 +207,192 @@ fn buffer_tests(device: &Device, device2: Option<&Device>) -> impl IntoIterator<
         });
     });
 
+    macro_for!($T in [c32, c64] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<buffer_fill_ $T>]), [<buffer_fill>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+            }
+        }
+    });
+
+    if device.is_host() {
+        tests.push(Trial::test("scalar_complex", || {
+            scalar_complex();
+            Ok(())
+        }));
+    }
+
+    macro_for!($T in [f8e4m3, f8e5m2] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<algorithms_radix_sort_ $T>]), [<algorithms_radix_sort>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+            }
+        }
+    });
+
+    #[cfg(feature = "safetensors")]
+    macro_for!($T in [f8e4m3, f8e5m2] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<buffer_safetensors_ $T>]), [<buffer_safetensors>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+            }
+        }
+    });
+
+    if device.is_host() {
+        tests.push(Trial::test("scalar_fp8", || {
+            scalar_fp8();
+            Ok(())
+        }));
+        tests.push(Trial::test("unsafe_slice_atomics", || {
+            unsafe_slice_atomics();
+            Ok(())
+        }));
+        tests.push(Trial::test("subgroup", || {
+            subgroup();
+            Ok(())
+        }));
+        tests.push(Trial::test("group_slice", || {
+            group_slice();
+            Ok(())
+        }));
+        tests.push(Trial::test("math", || {
+            math();
+            Ok(())
+        }));
+    }
+
+    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<buffer_reduce_ $T>]), [<buffer_reduce>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+            }
+        }
+    });
+
+    fn buffer_elementwise_features(ty: ScalarType) -> Features {
+        let push_constant_features = match ty.size() {
+            1 => Features::PUSH_CONSTANT8,
+            2 => Features::PUSH_CONSTANT16,
+            _ => Features::empty(),
+        };
+        buffer_cast_features(ty, ty).union(push_constant_features)
+    }
+
+    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_elementwise_features($T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<buffer_elementwise_ $T>]), [<buffer_elementwise>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+            }
+        }
+    });
+
+    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<algorithms_scan_ $T>]), [<algorithms_scan>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+                let trial = device_test(device, stringify!([<algorithms_compact_ $T>]), [<algorithms_compact>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+                let trial = device_test(device, stringify!([<algorithms_radix_sort_ $T>]), [<algorithms_radix_sort>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+            }
+        }
+    });
+
+    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<buffer_arange_ $T>]), [<buffer_arange>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+                let trial = device_test(device, stringify!([<buffer_permute_ $T>]), [<buffer_permute>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+                let trial = device_test(device, stringify!([<testing_check_buffer_ $T>]), [<testing_check_buffer>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+                let trial = device_test(device, stringify!([<buffer_npy_ $T>]), [<buffer_npy>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+                #[cfg(feature = "safetensors")]
+                {
+                    let trial = device_test(device, stringify!([<buffer_safetensors_ $T>]), [<buffer_safetensors>]::<$T>);
+                    tests.push(trial.with_ignored_flag(ignore));
+                }
+                #[cfg(feature = "serde")]
+                {
+                    let trial = device_test(device, stringify!([<buffer_serde_ $T>]), [<buffer_serde>]::<$T>);
+                    tests.push(trial.with_ignored_flag(ignore));
+                }
+                #[cfg(feature = "ndarray")]
+                {
+                    let trial = device_test(device, stringify!([<buffer_ndarray_ $T>]), [<buffer_ndarray>]::<$T>);
+                    tests.push(trial.with_ignored_flag(ignore));
+                }
+            }
+        }
+    });
+
+    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        macro_for!($I in [u32, u64] {
+            {
+                let mut index_features = buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE);
+                if $I::SCALAR_TYPE == ScalarType::U64 {
+                    index_features |= Features::INT64;
+                }
+                let ignore = !device.is_host() && !features.contains(index_features);
+                paste! {
+                    let trial = device_test(device, stringify!([<buffer_index_ $T _ $I>]), [<buffer_index>]::<$T, $I>);
+                    tests.push(trial.with_ignored_flag(ignore));
+                }
+            }
+        });
+    });
+
+    macro_for!($T in [f16, bf16, f32, f64] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<linalg_gemm_ $T>]), [<linalg_gemm>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+            }
+        }
+    });
+
+    tests.push(device_test(
+        device,
+        "buffer_random_philox",
+        buffer_random_philox,
+    ));
+
+    macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+        {
+            let ignore = !device.is_host() && !features.contains(buffer_cast_features($T::SCALAR_TYPE, $T::SCALAR_TYPE));
+            paste! {
+                let trial = device_test(device, stringify!([<buffer_random_ $T>]), [<buffer_random>]::<$T>);
+                tests.push(trial.with_ignored_flag(ignore));
+            }
+        }
+    });
+
     tests
 }
 
 fn buffer_test_lengths() -> impl ExactSizeIterator<Item = usize> {
     [0, 1, 3, 4, 16, 67, 157].into_iter()
 }
+fn buffer_reduce_test_lengths() -> impl ExactSizeIterator<Item = usize> {
+    [0, 1, 3, 4, 16, 67, 157, 4_321].into_iter()
+}
 fn buffer_transfer_test_lengths() -> impl ExactSizeIterator<Item = usize> {
     #[cfg(not(miri))]
     {
@@ -194,6 +423,548 @@ fn buffer_from_vec(device: Device) {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, krnl::macros::Element)]
+#[repr(C)]
+struct Particle {
+    position: [f32; 3],
+    mass: f32,
+}
+
+fn buffer_element(device: Device) {
+    assert_eq!(Particle::LAYOUT.scalar_type, ScalarType::U32);
+    assert_eq!(Particle::LAYOUT.size, 16);
+    let n = buffer_transfer_test_lengths().last().unwrap();
+    let x = (0..n)
+        .map(|i| Particle {
+            position: [i as f32, 1., 2.],
+            mass: 10. + i as f32,
+        })
+        .collect::<Vec<_>>();
+    for n in buffer_transfer_test_lengths() {
+        let x = &x[..n];
+        let buffer = Slice::from(x).to_device(device.clone()).unwrap();
+        assert_eq!(buffer.len(), n);
+        let scalar_buffer = ScalarBuffer::from(buffer);
+        assert_eq!(scalar_buffer.len(), 4 * n);
+        let buffer = Buffer::<Particle>::try_from(scalar_buffer).unwrap();
+        if let Some(slice) = buffer.slice(1..) {
+            assert_eq!(slice.to_vec().unwrap(), &x[1..]);
+        }
+        assert_eq!(buffer.into_vec().unwrap(), x);
+    }
+}
+
+// y[i] += alpha * x[i]
+//
+// With slice_offsets, the push constants are followed by the offset and len of x and y.
+#[cfg(feature = "device")]
+fn saxpy_spirv(slice_offsets: bool) -> Vec<u8> {
+    use rspirv::{
+        binary::Assemble,
+        dr::{Builder, Operand},
+        spirv::{
+            AddressingModel, BuiltIn, Capability, Decoration, ExecutionMode, ExecutionModel,
+            FunctionControl, MemoryModel, SelectionControl, StorageClass,
+        },
+    };
+
+    let mut b = Builder::new();
+    b.set_version(1, 3);
+    b.capability(Capability::Shader);
+    b.memory_model(AddressingModel::Logical, MemoryModel::GLSL450);
+    let void = b.type_void();
+    let fn_ty = b.type_function(void, []);
+    let bool_ty = b.type_bool();
+    let u32_ty = b.type_int(32, 0);
+    let f32_ty = b.type_float(32);
+    let uvec3 = b.type_vector(u32_ty, 3);
+    let uvec3_ptr = b.type_pointer(None, StorageClass::Input, uvec3);
+    let global_id = b.variable(uvec3_ptr, None, StorageClass::Input, None);
+    b.decorate(
+        global_id,
+        Decoration::BuiltIn,
+        [Operand::BuiltIn(BuiltIn::GlobalInvocationId)],
+    );
+    let array_ty = b.type_runtime_array(f32_ty);
+    b.decorate(
+        array_ty,
+        Decoration::ArrayStride,
+        [Operand::LiteralInt32(4)],
+    );
+    let buffer_ty = b.type_struct([array_ty]);
+    b.decorate(buffer_ty, Decoration::Block, []);
+    b.member_decorate(buffer_ty, 0, Decoration::Offset, [Operand::LiteralInt32(0)]);
+    let buffer_ptr = b.type_pointer(None, StorageClass::StorageBuffer, buffer_ty);
+    let x = b.variable(buffer_ptr, None, StorageClass::StorageBuffer, None);
+    let y = b.variable(buffer_ptr, None, StorageClass::StorageBuffer, None);
+    for (binding, var) in [x, y].into_iter().enumerate() {
+        b.decorate(var, Decoration::DescriptorSet, [Operand::LiteralInt32(0)]);
+        b.decorate(
+            var,
+            Decoration::Binding,
+            [Operand::LiteralInt32(binding as u32)],
+        );
+    }
+    b.decorate(x, Decoration::NonWritable, []);
+    let push_members = if slice_offsets {
+        vec![f32_ty, u32_ty, u32_ty, u32_ty, u32_ty]
+    } else {
+        vec![f32_ty]
+    };
+    let push_ty = b.type_struct(push_members.iter().copied());
+    b.decorate(push_ty, Decoration::Block, []);
+    for member in 0..push_members.len() as u32 {
+        b.member_decorate(
+            push_ty,
+            member,
+            Decoration::Offset,
+            [Operand::LiteralInt32(4 * member)],
+        );
+    }
+    let push_ptr = b.type_pointer(None, StorageClass::PushConstant, push_ty);
+    let push = b.variable(push_ptr, None, StorageClass::PushConstant, None);
+    let f32_buffer_ptr = b.type_pointer(None, StorageClass::StorageBuffer, f32_ty);
+    let f32_push_ptr = b.type_pointer(None, StorageClass::PushConstant, f32_ty);
+    let u32_push_ptr = b.type_pointer(None, StorageClass::PushConstant, u32_ty);
+    let members: Vec<_> = (0..5).map(|i| b.constant_u32(u32_ty, i)).collect();
+    let zero = members[0];
+    let main = b
+        .begin_function(void, None, FunctionControl::NONE, fn_ty)
+        .unwrap();
+    b.begin_block(None).unwrap();
+    let id = b.load(uvec3, None, global_id, None, []).unwrap();
+    let i = b.composite_extract(u32_ty, None, id, [0]).unwrap();
+    let load_push_u32 = |b: &mut Builder, member: u32| {
+        let ptr = b
+            .access_chain(u32_push_ptr, None, push, [members[member as usize]])
+            .unwrap();
+        b.load(u32_ty, None, ptr, None, []).unwrap()
+    };
+    let (x_index, y_index, len) = if slice_offsets {
+        let x_offset = load_push_u32(&mut b, 1);
+        let y_offset = load_push_u32(&mut b, 3);
+        let y_len = load_push_u32(&mut b, 4);
+        let x_index = b.i_add(u32_ty, None, x_offset, i).unwrap();
+        let y_index = b.i_add(u32_ty, None, y_offset, i).unwrap();
+        (x_index, y_index, y_len)
+    } else {
+        let len = b.array_length(u32_ty, None, y, 0).unwrap();
+        (i, i, len)
+    };
+    let in_bounds = b.u_less_than(bool_ty, None, i, len).unwrap();
+    let body = b.id();
+    let merge = b.id();
+    b.selection_merge(merge, SelectionControl::NONE).unwrap();
+    b.branch_conditional(in_bounds, body, merge, []).unwrap();
+    b.begin_block(Some(body)).unwrap();
+    let alpha_ptr = b.access_chain(f32_push_ptr, None, push, [zero]).unwrap();
+    let alpha = b.load(f32_ty, None, alpha_ptr, None, []).unwrap();
+    let x_ptr = b
+        .access_chain(f32_buffer_ptr, None, x, [zero, x_index])
+        .unwrap();
+    let x_value = b.load(f32_ty, None, x_ptr, None, []).unwrap();
+    let y_ptr = b
+        .access_chain(f32_buffer_ptr, None, y, [zero, y_index])
+        .unwrap();
+    let y_value = b.load(f32_ty, None, y_ptr, None, []).unwrap();
+    let ax = b.f_mul(f32_ty, None, alpha, x_value).unwrap();
+    let y_value = b.f_add(f32_ty, None, y_value, ax).unwrap();
+    b.store(y_ptr, y_value, None, []).unwrap();
+    b.branch(merge).unwrap();
+    b.begin_block(Some(merge)).unwrap();
+    b.ret().unwrap();
+    b.end_function().unwrap();
+    b.entry_point(ExecutionModel::GLCompute, main, "main", [global_id]);
+    b.execution_mode(main, ExecutionMode::LocalSize, [64, 1, 1]);
+    bytemuck::cast_slice(&b.module().assemble()).to_vec()
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn histogram_bins(n: usize, bins: usize) -> (Vec<u32>, Vec<u32>) {
+    let x: Vec<u32> = (0..n).map(|i| ((i * 7) % bins) as u32).collect();
+    let mut counts = vec![0; bins];
+    for x in x.iter() {
+        counts[*x as usize] += 1;
+    }
+    (x, counts)
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn kernel_histogram(device: Device) {
+    use krnlc_tests::atomics::{histogram, histogram_compare_exchange};
+
+    let (x, counts_true) = histogram_bins(1000, 10);
+    let x = Slice::from(x.as_slice()).to_device(device.clone()).unwrap();
+    let mut counts = Buffer::<u32>::zeros(device.clone(), counts_true.len()).unwrap();
+    histogram::builder()
+        .unwrap()
+        .build(device.clone())
+        .unwrap()
+        .dispatch(x.as_slice(), counts.as_slice_mut())
+        .unwrap();
+    assert_eq!(counts.to_vec().unwrap(), counts_true);
+    let mut counts = Buffer::<u32>::zeros(device.clone(), counts_true.len()).unwrap();
+    histogram_compare_exchange::builder()
+        .unwrap()
+        .build(device)
+        .unwrap()
+        .dispatch(x.as_slice(), counts.as_slice_mut())
+        .unwrap();
+    assert_eq!(counts.to_vec().unwrap(), counts_true);
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn kernel_histogram_u64(device: Device) {
+    use krnlc_tests::atomics::histogram_u64;
+
+    let (x, counts_true) = histogram_bins(1000, 10);
+    let counts_true: Vec<u64> = counts_true.into_iter().map(u64::from).collect();
+    let x = Slice::from(x.as_slice()).to_device(device.clone()).unwrap();
+    let mut counts = Buffer::<u64>::zeros(device.clone(), counts_true.len()).unwrap();
+    histogram_u64::builder()
+        .unwrap()
+        .build(device)
+        .unwrap()
+        .dispatch(x.as_slice(), counts.as_slice_mut())
+        .unwrap();
+    assert_eq!(counts.to_vec().unwrap(), counts_true);
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn kernel_global_threads_overflow(device: Device) {
+    use krnlc_tests::kernels::threads_xy;
+
+    let kernel = threads_xy::builder()
+        .unwrap()
+        .build(device.clone())
+        .unwrap();
+    assert_eq!(kernel.threads(), [8, 8]);
+    let mut y = Buffer::<u32>::zeros(device, 1).unwrap();
+    // Vulkan guarantees at least 65535 groups per dimension, and 65535^2 * 64 global threads
+    // overflows u32
+    let error = kernel
+        .with_groups([u32::from(u16::MAX); 2])
+        .dispatch(y.as_slice_mut())
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("u32::MAX global threads"),
+        "{error}"
+    );
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn kernel_dispatch_dyn(device: Device, device2: Option<Device>) {
+    use krnl::scalar::ScalarElem;
+    use krnlc_tests::kernels::{element_update, Particle};
+
+    let n = 100;
+    let x_vec: Vec<Particle> = (0..n)
+        .map(|i| Particle {
+            position: [i as f32, 1., 2.],
+            mass: 0.5,
+        })
+        .collect();
+    let x = Slice::from(x_vec.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let mut y = Buffer::<Particle>::zeros(device.clone(), n).unwrap();
+    let kernel = element_update::builder()
+        .unwrap()
+        .build(device.clone())
+        .unwrap()
+        .with_global_threads(n as u32);
+    let dt = ScalarElem::F32(2.);
+    // count
+    assert!(kernel.dispatch_dyn(&[x.as_slice().into()], &[dt]).is_err());
+    assert!(kernel
+        .dispatch_dyn(&[x.as_slice().into(), y.as_slice_mut().into()], &[])
+        .is_err());
+    // push constant type
+    assert!(kernel
+        .dispatch_dyn(
+            &[x.as_slice().into(), y.as_slice_mut().into()],
+            &[ScalarElem::U32(2)]
+        )
+        .is_err());
+    // element type with the same scalar type
+    let x_u32 = Buffer::<u32>::zeros(device.clone(), 4 * n).unwrap();
+    assert!(kernel
+        .dispatch_dyn(&[x_u32.as_slice().into(), y.as_slice_mut().into()], &[dt])
+        .is_err());
+    let x_array = Buffer::<[f32; 4]>::zeros(device.clone(), n).unwrap();
+    assert!(kernel
+        .dispatch_dyn(&[x_array.as_slice().into(), y.as_slice_mut().into()], &[dt])
+        .is_err());
+    // mutability
+    assert!(kernel
+        .dispatch_dyn(&[x.as_slice().into(), y.as_slice().into()], &[dt])
+        .is_err());
+    // host
+    let x_host = Slice::from(x_vec.as_slice());
+    assert!(kernel
+        .dispatch_dyn(&[x_host.into(), y.as_slice_mut().into()], &[dt])
+        .is_err());
+    // foreign device
+    if let Some(device2) = device2 {
+        let x2 = x.to_device(device2).unwrap();
+        assert!(kernel
+            .dispatch_dyn(&[x2.as_slice().into(), y.as_slice_mut().into()], &[dt])
+            .is_err());
+    }
+    kernel
+        .dispatch_dyn(&[x.as_slice().into(), y.as_slice_mut().into()], &[dt])
+        .unwrap();
+    let y_true: Vec<Particle> = x_vec
+        .iter()
+        .map(|x| Particle {
+            position: [x.position[0], 0., 2.],
+            mass: x.mass,
+        })
+        .collect();
+    let y = y.to_vec().unwrap();
+    for (y, y_true) in y.iter().zip(y_true.iter()) {
+        assert_eq!(y.position, y_true.position);
+        assert_eq!(y.mass, y_true.mass);
+    }
+}
+
+// Groups the values of x by subgroup.
+#[cfg(not(target_family = "wasm"))]
+fn subgroups(ids: &[u32], x: &[u32]) -> std::collections::BTreeMap<u32, Vec<u32>> {
+    let mut subgroups = std::collections::BTreeMap::<u32, Vec<u32>>::new();
+    for (id, x) in ids.iter().zip(x) {
+        subgroups.entry(*id).or_default().push(*x);
+    }
+    subgroups
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn kernel_subgroup_broadcast(device: Device) {
+    use krnlc_tests::subgroup::broadcast_u32;
+
+    let n = 1024;
+    let x_vec: Vec<u32> = (0..n as u32).map(|x| x * 3 + 1).collect();
+    let x = Slice::from(x_vec.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let mut ids = Buffer::<u32>::zeros(device.clone(), n).unwrap();
+    let mut y = Buffer::<u32>::zeros(device.clone(), n).unwrap();
+    broadcast_u32::builder()
+        .unwrap()
+        .build(device)
+        .unwrap()
+        .with_global_threads(n as u32)
+        .dispatch(x.as_slice(), ids.as_slice_mut(), y.as_slice_mut())
+        .unwrap();
+    let ids = ids.to_vec().unwrap();
+    let x_subgroups = subgroups(&ids, &x_vec);
+    let y_subgroups = subgroups(&ids, &y.to_vec().unwrap());
+    for (x, y) in x_subgroups.values().zip(y_subgroups.values()) {
+        assert!(x.contains(&y[0]));
+        assert!(y.iter().all(|y| *y == y[0]));
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn kernel_subgroup_reduce(device: Device) {
+    use krnlc_tests::subgroup::reduce_u32;
+
+    let n = 1024;
+    let x_vec: Vec<u32> = (0..n as u32).map(|x| (x * 7) % 100).collect();
+    let x = Slice::from(x_vec.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let mut ids = Buffer::<u32>::zeros(device.clone(), n).unwrap();
+    let mut sum = Buffer::<u32>::zeros(device.clone(), n).unwrap();
+    let mut max = Buffer::<f32>::zeros(device.clone(), n).unwrap();
+    reduce_u32::builder()
+        .unwrap()
+        .build(device)
+        .unwrap()
+        .with_global_threads(n as u32)
+        .dispatch(
+            x.as_slice(),
+            ids.as_slice_mut(),
+            sum.as_slice_mut(),
+            max.as_slice_mut(),
+        )
+        .unwrap();
+    let ids = ids.to_vec().unwrap();
+    let sum = sum.to_vec().unwrap();
+    let max = max.to_vec().unwrap();
+    let x_subgroups = subgroups(&ids, &x_vec);
+    for (i, id) in ids.iter().enumerate() {
+        let x = &x_subgroups[id];
+        assert_eq!(sum[i], x.iter().sum::<u32>());
+        assert_eq!(max[i], *x.iter().max().unwrap() as f32);
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn kernel_subgroup_ballot(device: Device) {
+    use krnlc_tests::subgroup::ballot_u32;
+
+    let n = 1024;
+    let x_vec: Vec<u32> = (0..n as u32).map(|x| (x * x) % 5).collect();
+    let x = Slice::from(x_vec.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let mut ids = Buffer::<u32>::zeros(device.clone(), n).unwrap();
+    let mut y = Buffer::<u32>::zeros(device.clone(), n).unwrap();
+    ballot_u32::builder()
+        .unwrap()
+        .build(device)
+        .unwrap()
+        .with_global_threads(n as u32)
+        .dispatch(x.as_slice(), ids.as_slice_mut(), y.as_slice_mut())
+        .unwrap();
+    let ids = ids.to_vec().unwrap();
+    let y = y.to_vec().unwrap();
+    let x_subgroups = subgroups(&ids, &x_vec);
+    for (i, id) in ids.iter().enumerate() {
+        let odd = x_subgroups[id].iter().filter(|x| *x % 2 == 1).count();
+        assert_eq!(y[i] as usize, odd);
+    }
+}
+
+#[cfg(feature = "device")]
+fn kernel_from_spirv(device: Device) {
+    use krnl::{
+        kernel::{KernelBuilder, SpirvArg},
+        scalar::ScalarElem,
+    };
+
+    let spirv = saxpy_spirv(false);
+    let args = [
+        SpirvArg::Slice {
+            name: "x",
+            scalar_type: ScalarType::F32,
+            mutable: false,
+        },
+        SpirvArg::Slice {
+            name: "y",
+            scalar_type: ScalarType::F32,
+            mutable: true,
+        },
+        SpirvArg::Push {
+            name: "alpha",
+            scalar_type: ScalarType::F32,
+        },
+    ];
+    assert!(KernelBuilder::from_spirv("saxpy", &spirv, &args[..2]).is_err());
+    let alpha_u8 = SpirvArg::Push {
+        name: "alpha",
+        scalar_type: ScalarType::U8,
+    };
+    assert!(KernelBuilder::from_spirv("saxpy", &spirv, &[args[0], args[1], alpha_u8]).is_err());
+    let y_immutable = SpirvArg::Slice {
+        name: "y",
+        scalar_type: ScalarType::F32,
+        mutable: false,
+    };
+    assert!(KernelBuilder::from_spirv("saxpy", &spirv, &[args[0], y_immutable, args[2]]).is_err());
+    assert!(KernelBuilder::from_spirv("saxpy", &spirv[4..], &args).is_err());
+    let builder = KernelBuilder::from_spirv("saxpy", &spirv, &args).unwrap();
+    assert_eq!(builder.features(), Features::empty());
+    if device.is_host() {
+        assert!(builder.build(device).is_err());
+        return;
+    }
+    let kernel = builder.build(device.clone()).unwrap();
+    assert_eq!(kernel.threads(), [64, 1, 1]);
+    // without slice offsets, the shader sees the entire buffer, padded to 256 bytes
+    let n = 128;
+    let x = Slice::from(vec![1f32; n].as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let mut y = Slice::from(vec![2f32; n].as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    unsafe {
+        assert!(kernel
+            .dispatch(
+                &[x.as_slice().into(), y.as_slice_mut().into()],
+                &[ScalarElem::F32(0.5)]
+            )
+            .is_err());
+    }
+    let kernel = kernel.with_global_threads([n as u32, 1, 1]);
+    unsafe {
+        assert!(kernel
+            .dispatch(
+                &[x.as_slice().into(), y.as_slice_mut().into()],
+                &[ScalarElem::U32(1)]
+            )
+            .is_err());
+        assert!(kernel
+            .dispatch(
+                &[x.as_slice().into(), y.as_slice().into()],
+                &[ScalarElem::F32(0.5)]
+            )
+            .is_err());
+        assert!(kernel
+            .dispatch(&[x.as_slice().into()], &[ScalarElem::F32(0.5)])
+            .is_err());
+        kernel
+            .dispatch(
+                &[x.as_slice().into(), y.as_slice_mut().into()],
+                &[ScalarElem::F32(0.5)],
+            )
+            .unwrap();
+    }
+    assert_eq!(y.to_vec().unwrap(), vec![2.5f32; n]);
+    let x = Slice::from(vec![1f32; 100].as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    unsafe {
+        assert!(kernel
+            .dispatch(
+                &[x.as_slice().into(), y.as_slice_mut().into()],
+                &[ScalarElem::F32(0.5)]
+            )
+            .is_err());
+        assert!(kernel
+            .dispatch(
+                &[
+                    x.slice(..64).unwrap().into(),
+                    y.slice_mut(..64).unwrap().into()
+                ],
+                &[ScalarElem::F32(0.5)]
+            )
+            .is_err());
+    }
+    // slices offset, with lengths that are not a multiple of 64
+    let builder = KernelBuilder::from_spirv("saxpy_offsets", &saxpy_spirv(true), &args).unwrap();
+    let n = 100;
+    let kernel = builder
+        .build(device.clone())
+        .unwrap()
+        .with_global_threads([n as u32 - 3, 1, 1]);
+    let x_vec: Vec<f32> = (0..n).map(|x| x as f32).collect();
+    let x = Slice::from(x_vec.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let mut y = Slice::from(vec![1f32; n].as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    unsafe {
+        kernel
+            .dispatch(
+                &[
+                    x.slice(3..).unwrap().into(),
+                    y.slice_mut(..n - 3).unwrap().into(),
+                ],
+                &[ScalarElem::F32(2.)],
+            )
+            .unwrap();
+    }
+    let mut y_true = vec![1f32; n];
+    for (y, x) in y_true.iter_mut().zip(&x_vec[3..]) {
+        *y += 2. * x;
+    }
+    assert_eq!(y.to_vec().unwrap(), y_true);
+}
+
 #[cfg(feature = "device")]
 fn device_buffer_too_large(device: Device) {
     use krnl::buffer::error::DeviceBufferTooLarge;
@@ -266,7 +1037,7 @@ fn buffer_cast<X: Scalar, Y: Scalar>(device: Device) {
 }
 
 fn buffer_bitcast<X: Scalar, Y: Scalar>(device: Device) {
-    let x_host = vec![0u64; 16];
+    let x_host = vec![0u64; 32];
     let x_host: &[X] = &bytemuck::cast_slice(&x_host)[..16];
     let x = Slice::from(x_host).to_device(device).unwrap();
     for i in 0..=16 {
@@ -282,23 +1053,1288 @@ fn buffer_bitcast<X: Scalar, Y: Scalar>(device: Device) {
     }
 }
 
-#[test]
-fn buffer_from_vec_host() {
-    buffer_from_vec(Device::host());
+fn scalar_complex() {
+    use krnl_core::num_traits::Num;
+
+    let a = c32::new(1., 2.);
+    let b = c32::new(3., -4.);
+    assert_eq!(a + b, c32::new(4., -2.));
+    assert_eq!(a - b, c32::new(-2., 6.));
+    assert_eq!(a * b, c32::new(11., 2.));
+    assert_eq!((a * b) / b, a);
+    assert_eq!(-a, c32::new(-1., -2.));
+    assert_eq!(a.conj(), c32::new(1., -2.));
+    assert_eq!(b.norm(), 5.);
+    assert_eq!(c32::I * c32::I, -c32::ONE);
+    assert!(a < b && c32::new(1., 1.) < a);
+    assert_eq!(a.to_string(), "1+2i");
+    assert_eq!(b.to_string(), "3-4i");
+    assert_eq!(a.cast::<f32>(), 1.);
+    assert_eq!(a.cast::<u8>(), 1);
+    assert_eq!(2u8.cast::<c64>(), c64::new(2., 0.));
+    assert_eq!(a.cast::<c64>(), c64::new(1., 2.));
+    assert_eq!(3.5f32.cast::<c64>(), c64::new(3.5, 0.));
+    assert_eq!(a.scalar_elem().scalar_type(), ScalarType::C32);
+    assert_eq!(ScalarType::C64.size(), 16);
+    assert_eq!("c64".parse::<ScalarType>(), Ok(ScalarType::C64));
+    for (s, expected) in [
+        ("1+2i", a),
+        ("3-4i", b),
+        ("-1.5", c32::new(-1.5, 0.)),
+        ("2.5i", c32::new(0., 2.5)),
+        ("-i", c32::new(0., -1.)),
+        ("1 + i", c32::new(1., 1.)),
+        ("1e-1-2E+1i", c32::new(0.1, -20.)),
+    ] {
+        assert_eq!(c32::from_str_radix(s, 10).unwrap(), expected, "{s}");
+    }
+    assert_eq!(
+        c64::from_str_radix("-1-1i", 10).unwrap(),
+        c64::new(-1., -1.)
+    );
+    assert_eq!(
+        c32::from_str_radix("10+ai", 16).unwrap(),
+        c32::new(16., 10.)
+    );
+    for s in ["", "i1", "1+2", "1+2j", "+2i+", "1++2i", "1+-2i", "+i3"] {
+        c32::from_str_radix(s, 10).unwrap_err();
+    }
 }
 
-#[cfg(target_family = "wasm")]
-macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
-    paste! {
-        #[test]
-        fn [<buffer_fill_ $T _host>]() {
-            buffer_fill::<$T>(Device::host());
+fn scalar_fp8() {
+    for bits in 0..=u8::MAX {
+        let x = f8e4m3::from_bits(bits);
+        if !x.is_nan() {
+            assert_eq!(f8e4m3::from_f32(x.to_f32()).to_bits(), bits);
+        }
+        // e5m2 is the upper byte of f16
+        let y = f8e5m2::from_bits(bits);
+        let y_f16 = f16::from_bits(u16::from(bits) << 8);
+        if y_f16.is_nan() {
+            assert!(y.is_nan());
+        } else {
+            assert_eq!(y.to_f16().to_bits(), y_f16.to_bits());
+            assert_eq!(f8e5m2::from_f16(y_f16).to_bits(), bits);
         }
     }
-});
+    assert_eq!(f8e4m3::MAX.to_f32(), 448.);
+    assert_eq!(f8e5m2::MAX.to_f32(), 57344.);
+    assert_eq!(f8e4m3::EPSILON.to_f32(), 0.125);
+    assert_eq!(f8e5m2::EPSILON.to_f32(), 0.25);
+    assert_eq!(f8e4m3::from_f32(1.0625), f8e4m3::ONE);
+    assert_eq!(f8e4m3::from_f32(1.1875).to_f32(), 1.25);
+    assert_eq!(f8e4m3::from_f64(1.0625 + 1e-12).to_f32(), 1.125);
+    assert_eq!(f8e4m3::from_f32(2f32.powi(-10)).to_bits(), 0);
+    assert_eq!(f8e4m3::from_f32(3. * 2f32.powi(-10)).to_bits(), 2);
+    assert!(f8e4m3::from_f32(500.).is_nan());
+    assert!(f8e4m3::from_f32(f32::INFINITY).is_nan());
+    assert_eq!(f8e5m2::from_f32(65536.), f8e5m2::INFINITY);
+    assert_eq!(f8e5m2::from_f32(f32::NEG_INFINITY), f8e5m2::NEG_INFINITY);
+    assert_eq!(f8e4m3::from_f32(-0.), f8e4m3::ZERO);
+    assert_eq!((f8e4m3::ONE + f8e4m3::ONE).to_f32(), 2.);
+    assert_eq!(3u8.cast::<f8e4m3>().to_f32(), 3.);
+    assert_eq!(f8e4m3::from_f32(1.5).cast::<f8e5m2>().to_f32(), 1.5);
+    assert_eq!(f8e5m2::from_f32(-2.).cast::<i32>(), -2);
+    assert_eq!(f8e4m3::ONE.to_string(), "1");
+    assert_eq!("f8e5m2".parse::<ScalarType>(), Ok(ScalarType::F8E5M2));
+}
+
+fn unsafe_slice_atomics() {
+    use krnl_core::buffer::UnsafeSlice;
+
+    let mut x = [5u32, 0b1100];
+    let slice = UnsafeSlice::from(x.as_mut_slice());
+    assert_eq!(slice.atomic_add(0, 2), 5);
+    assert_eq!(slice.atomic_min(0, 3), 7);
+    assert_eq!(slice.atomic_max(0, 4), 3);
+    assert_eq!(slice.atomic_and(1, 0b1010), 0b1100);
+    assert_eq!(slice.atomic_or(1, 0b0001), 0b1000);
+    assert_eq!(slice.atomic_xor(1, 0b1111), 0b1001);
+    assert_eq!(slice.atomic_exchange(1, 9), 0b0110);
+    assert_eq!(slice.compare_exchange(1, 9, 10), Ok(9));
+    assert_eq!(slice.compare_exchange(1, 9, 11), Err(10));
+    assert_eq!(x, [4, 10]);
+    let mut y = [-1i64];
+    let slice = UnsafeSlice::from(y.as_mut_slice());
+    assert_eq!(slice.atomic_min(0, -3), -1);
+    assert_eq!(slice.atomic_add(0, 5), -3);
+    assert_eq!(y, [2]);
+    let mut z = [1f32];
+    let slice = UnsafeSlice::from(z.as_mut_slice());
+    assert_eq!(slice.atomic_add(0, 0.5), 1.);
+    assert_eq!(slice.atomic_max(0, 3.), 1.5);
+    assert_eq!(slice.atomic_min(0, -1.), 3.);
+    assert_eq!(slice.atomic_exchange(0, 2.), -1.);
+    assert_eq!(z, [2.]);
+}
+
+// On the host, each thread is its own subgroup.
+fn subgroup() {
+    use krnl_core::subgroup;
+
+    assert_eq!(subgroup::reduce_add(3u8), 3);
+    assert_eq!(subgroup::reduce_min(-2i64), -2);
+    assert_eq!(subgroup::reduce_max(f16::ONE), f16::ONE);
+    assert_eq!(subgroup::inclusive_scan(1.5f32), 1.5);
+    assert_eq!(subgroup::exclusive_scan(7u32), 0);
+    assert_eq!(subgroup::exclusive_scan(c32::new(1., 2.)), c32::ZERO);
+    assert_eq!(subgroup::reduce_min(c64::new(1., 2.)), c64::new(1., 2.));
+    assert_eq!(subgroup::broadcast(5i16, 0), 5);
+    assert_eq!(subgroup::shuffle(bf16::ONE, 0), bf16::ONE);
+    assert_eq!(subgroup::ballot(true).to_array(), [1, 0, 0, 0]);
+    assert!(subgroup::all(true) && !subgroup::any(false));
+}
+
+// Thread 0 of a group of 2 threads.
+fn group_slice() {
+    use krnl_core::buffer::{GroupSlice, UnsafeSlice};
+
+    let mut x = [0u32; 3];
+    let x_group =
+        unsafe { GroupSlice::from_unsafe_slice(UnsafeSlice::from(x.as_mut_slice()), 0, 2) };
+    assert_eq!(x_group.thread_slots(), 2);
+    x_group.write(1);
+    x_group.write_strided(1, 2);
+    let x_group = x_group.barrier();
+    assert_eq!([x_group[0], x_group[1], x_group[2]], [1, 0, 2]);
+    let x_group = x_group.barrier();
+    x_group.write(3);
+    let x_group = x_group.barrier();
+    assert_eq!(x_group[0], 3);
+}
+
+fn math() {
+    use krnl_core::math;
+
+    let check = |output: f64, expected: f64, tol: f64| {
+        assert!(
+            (output - expected).abs() <= tol * expected.abs().max(1.),
+            "{output:?} != {expected:?}"
+        );
+    };
+    for x in [-3.5f64, -1., -0.25, 0., 0.5, 1., 2.5, 10.] {
+        check(math::exp(x), x.exp(), 1e-15);
+        check(math::tanh(x), x.tanh(), 1e-15);
+        check(math::sin(x), x.sin(), 1e-15);
+        check(math::cos(x), x.cos(), 1e-15);
+        check(math::fma(x, 2., 1.), x.mul_add(2., 1.), 1e-15);
+        check(math::exp(x as f32) as f64, x.exp(), 1e-6);
+        check(math::exp(bf16::from_f64(x)).to_f64(), x.exp(), 1e-2);
+        if x > 0. {
+            check(math::log(x), x.ln(), 1e-15);
+            check(math::sqrt(x), x.sqrt(), 1e-15);
+            check(math::rsqrt(x), x.sqrt().recip(), 1e-15);
+            check(math::pow(x, 1.5), x.powf(1.5), 1e-15);
+            check(math::log(f16::from_f64(x)).to_f64(), x.ln(), 1e-2);
+        }
+    }
+    check(math::erf(0.5f64), 0.5204998778130465, 1e-15);
+    check(math::erf(-2f32) as f64, -0.9953222650189527, 1e-6);
+    check(math::erf(bf16::ONE).to_f64(), 0.8427007929497149, 1e-2);
+    assert!(math::isnan(f64::NAN) && !math::isnan(1f32));
+    assert!(math::isnan(f8e4m3::NAN) && math::isnan(math::log(-f16::ONE)));
+    assert!(math::isinf(f32::NEG_INFINITY) && math::isinf(bf16::INFINITY));
+    assert!(!math::isinf(f64::NAN) && !math::isinf(f8e5m2::ONE));
+}
+
+fn buffer_reduce<T: Scalar>(device: Device) {
+    let is_float = matches!(
+        T::SCALAR_TYPE,
+        ScalarType::F16 | ScalarType::BF16 | ScalarType::F32 | ScalarType::F64
+    );
+    let check = |output: T, expected: f64| {
+        let output = output.cast::<f64>();
+        assert!(
+            (output - expected).abs() <= 0.01 * expected.abs().max(1.),
+            "{output:?} != {expected:?}"
+        );
+    };
+    let n = buffer_reduce_test_lengths().last().unwrap();
+    let x = (0..n)
+        .map(|i| T::from_usize((i * 7 + 3) % 5).unwrap())
+        .collect::<Vec<_>>();
+    for n in buffer_reduce_test_lengths() {
+        let x = &x[..n];
+        let x_device = Slice::from(x).to_device(device.clone()).unwrap();
+        let sum = x.iter().map(|x| x.cast::<i64>()).sum::<i64>();
+        if is_float {
+            check(x_device.sum().unwrap(), sum as f64);
+        } else {
+            assert_eq!(x_device.sum().unwrap(), sum.cast::<T>());
+        }
+        if n == 0 {
+            x_device.min().unwrap_err();
+            x_device.max().unwrap_err();
+            x_device.argmin().unwrap_err();
+            x_device.argmax().unwrap_err();
+            x_device.mean().unwrap_err();
+            continue;
+        }
+        let min = x
+            .iter()
+            .copied()
+            .fold(x[0], |a, b| if b < a { b } else { a });
+        let max = x
+            .iter()
+            .copied()
+            .fold(x[0], |a, b| if b > a { b } else { a });
+        assert_eq!(x_device.min().unwrap(), min);
+        assert_eq!(x_device.max().unwrap(), max);
+        assert_eq!(
+            x_device.argmin().unwrap(),
+            x.iter().position(|x| *x == min).unwrap()
+        );
+        assert_eq!(
+            x_device.argmax().unwrap(),
+            x.iter().position(|x| *x == max).unwrap()
+        );
+        let mean = sum as f64 / n as f64;
+        if is_float {
+            check(x_device.mean().unwrap(), mean);
+        } else {
+            assert_eq!(x_device.mean().unwrap(), mean.cast::<T>());
+        }
+    }
+}
+
+fn buffer_elementwise<T: Scalar>(device: Device) {
+    let is_float = matches!(
+        T::SCALAR_TYPE,
+        ScalarType::F16 | ScalarType::BF16 | ScalarType::F32 | ScalarType::F64
+    );
+    let is_signed = is_float || T::from_i32(-1).is_some();
+    let check = |output: Buffer<T>, expected: &[f64]| {
+        let output = output.into_vec().unwrap();
+        assert_eq!(output.len(), expected.len());
+        for (i, (output, expected)) in output.into_iter().zip(expected).enumerate() {
+            if is_float {
+                let output = output.cast::<f64>();
+                assert!(
+                    (output - expected).abs() <= 0.01 * expected.abs().max(1.),
+                    "i: {i}, {output:?} != {expected:?}"
+                );
+            } else {
+                assert_eq!(output, (*expected as i64).cast::<T>(), "i: {i}");
+            }
+        }
+    };
+    let n = buffer_test_lengths().last().unwrap();
+    let a = (10..20)
+        .cycle()
+        .map(|x| T::from_u32(x).unwrap())
+        .take(n)
+        .collect::<Vec<_>>();
+    let b = (1..7)
+        .cycle()
+        .map(|x| T::from_u32(x).unwrap())
+        .take(n)
+        .collect::<Vec<_>>();
+    let c = (-5..5)
+        .cycle()
+        .map(|x| T::from_i32(if is_signed { x } else { x + 5 }).unwrap())
+        .take(n)
+        .collect::<Vec<_>>();
+    let to_f64 = |x: &[T]| x.iter().map(|x| x.cast::<f64>()).collect::<Vec<_>>();
+    for n in buffer_test_lengths() {
+        let (a, b, c) = (&a[..n], &b[..n], &c[..n]);
+        let (a_f64, b_f64, c_f64) = (to_f64(a), to_f64(b), to_f64(c));
+        let a = Slice::from(a).to_device(device.clone()).unwrap();
+        let b = Slice::from(b).to_device(device.clone()).unwrap();
+        let c = Slice::from(c).to_device(device.clone()).unwrap();
+        let binary = |f: fn(f64, f64) -> f64| {
+            a_f64
+                .iter()
+                .zip(&b_f64)
+                .map(|(a, b)| f(*a, *b))
+                .collect::<Vec<_>>()
+        };
+        let unary =
+            |x: &[f64], f: &dyn Fn(f64) -> f64| x.iter().copied().map(f).collect::<Vec<_>>();
+        check(a.add(&b.as_slice()).unwrap(), &binary(|a, b| a + b));
+        check(a.sub(&b.as_slice()).unwrap(), &binary(|a, b| a - b));
+        check(a.mul(&b.as_slice()).unwrap(), &binary(|a, b| a * b));
+        if is_float {
+            check(a.div(&b.as_slice()).unwrap(), &binary(|a, b| a / b));
+        } else {
+            check(
+                a.div(&b.as_slice()).unwrap(),
+                &binary(|a, b| (a / b).trunc()),
+            );
+        }
+        let mut y = b.to_owned().unwrap();
+        y.axpy(T::from_u32(2).unwrap(), &a.as_slice()).unwrap();
+        check(y, &binary(|a, b| 2. * a + b));
+        check(
+            a.scale(T::from_u32(3).unwrap()).unwrap(),
+            &unary(&a_f64, &|x| 3. * x),
+        );
+        check(c.abs().unwrap(), &unary(&c_f64, &f64::abs));
+        check(c.relu().unwrap(), &unary(&c_f64, &|x| x.max(0.)));
+        check(
+            a.clamp(T::from_u32(12).unwrap(), T::from_u32(17).unwrap())
+                .unwrap(),
+            &unary(&a_f64, &|x| x.clamp(12., 17.)),
+        );
+        if is_float {
+            check(b.exp().unwrap(), &unary(&b_f64, &f64::exp));
+            check(a.log().unwrap(), &unary(&a_f64, &f64::ln));
+        } else {
+            a.exp().unwrap_err();
+            a.log().unwrap_err();
+        }
+    }
+}
+
+fn algorithms_scan<T: Scalar>(device: Device) {
+    let is_float = matches!(
+        T::SCALAR_TYPE,
+        ScalarType::F16 | ScalarType::BF16 | ScalarType::F32 | ScalarType::F64
+    );
+    let check = |output: T, expected: i64, name: &str| {
+        if is_float {
+            let (output, expected) = (output.cast::<f64>(), expected as f64);
+            assert!(
+                (output - expected).abs() <= 0.01 * expected.abs().max(1.),
+                "{name}: {output:?} != {expected:?}"
+            );
+        } else {
+            assert_eq!(output, expected.cast::<T>(), "{name}");
+        }
+    };
+    let n = buffer_reduce_test_lengths().last().unwrap();
+    let x = (0..n)
+        .map(|i| T::from_usize((i * 7 + 3) % 5).unwrap())
+        .collect::<Vec<_>>();
+    for n in buffer_reduce_test_lengths() {
+        let x = &x[..n];
+        let x_device = Slice::from(x).to_device(device.clone()).unwrap();
+        let inclusive = algorithms::inclusive_scan(x_device.as_slice())
+            .unwrap()
+            .into_vec()
+            .unwrap();
+        let exclusive = algorithms::exclusive_scan(x_device.as_slice())
+            .unwrap()
+            .into_vec()
+            .unwrap();
+        assert_eq!(inclusive.len(), n);
+        assert_eq!(exclusive.len(), n);
+        let mut sum = 0i64;
+        for (i, x) in x.iter().enumerate() {
+            check(exclusive[i], sum, &format!("exclusive[{i}]"));
+            sum += x.cast::<i64>();
+            check(inclusive[i], sum, &format!("inclusive[{i}]"));
+        }
+    }
+}
+
+fn algorithms_compact<T: Scalar>(device: Device) {
+    let n = buffer_reduce_test_lengths().last().unwrap();
+    let x = (0..n)
+        .map(|i| T::from_usize(i % 100).unwrap())
+        .collect::<Vec<_>>();
+    let mask = (0..n).map(|i| (i * 5 % 3) as u32).collect::<Vec<_>>();
+    for n in buffer_reduce_test_lengths() {
+        let (x, mask) = (&x[..n], &mask[..n]);
+        let expected: Vec<T> = x
+            .iter()
+            .zip(mask)
+            .filter(|(_, mask)| **mask != 0)
+            .map(|(x, _)| *x)
+            .collect();
+        let x = Slice::from(x).to_device(device.clone()).unwrap();
+        let mask = Slice::from(mask).to_device(device.clone()).unwrap();
+        let y = algorithms::compact(x.as_slice(), mask.as_slice())
+            .unwrap()
+            .into_vec()
+            .unwrap();
+        assert_eq!(y, expected);
+    }
+}
+
+fn algorithms_radix_sort<T: Scalar>(device: Device) {
+    let is_signed = matches!(
+        T::SCALAR_TYPE,
+        ScalarType::I8
+            | ScalarType::I16
+            | ScalarType::I32
+            | ScalarType::I64
+            | ScalarType::F16
+            | ScalarType::BF16
+            | ScalarType::F32
+            | ScalarType::F64
+            | ScalarType::F8E4M3
+            | ScalarType::F8E5M2
+    );
+    let n = buffer_reduce_test_lengths().last().unwrap();
+    let keys = (0..n)
+        .map(|i| {
+            let key = (i * 7_919 % 201) as i64;
+            if is_signed {
+                T::from_i64(key - 100).unwrap()
+            } else {
+                T::from_i64(key).unwrap()
+            }
+        })
+        .collect::<Vec<_>>();
+    let values = (0..n as u32).collect::<Vec<_>>();
+    for n in buffer_reduce_test_lengths() {
+        let keys = &keys[..n];
+        let mut expected: Vec<(T, u32)> =
+            keys.iter().copied().zip(values.iter().copied()).collect();
+        expected.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let keys_device = Slice::from(keys).to_device(device.clone()).unwrap();
+        let values_device = Slice::from(&values[..n]).to_device(device.clone()).unwrap();
+        let sorted = algorithms::radix_sort(keys_device.as_slice())
+            .unwrap()
+            .into_vec()
+            .unwrap();
+        let (sorted_keys, sorted_values) =
+            algorithms::radix_sort_pairs(keys_device.as_slice(), values_device.as_slice()).unwrap();
+        let sorted_keys = sorted_keys.into_vec().unwrap();
+        let sorted_values = sorted_values.into_vec().unwrap();
+        for (i, (key, value)) in expected.into_iter().enumerate() {
+            assert_eq!(sorted[i], key);
+            assert_eq!(sorted_keys[i], key);
+            assert_eq!(sorted_values[i], value);
+        }
+    }
+}
+
+fn buffer_arange<T: Scalar>(device: Device) {
+    let is_float = matches!(
+        T::SCALAR_TYPE,
+        ScalarType::F16 | ScalarType::BF16 | ScalarType::F32 | ScalarType::F64
+    );
+    let start = T::from_u32(7).unwrap();
+    let step = T::from_u32(3).unwrap();
+    for n in buffer_test_lengths() {
+        let x = Buffer::arange(device.clone(), start, step, n)
+            .unwrap()
+            .into_vec()
+            .unwrap();
+        let host = Buffer::arange(Device::host(), start, step, n)
+            .unwrap()
+            .into_vec()
+            .unwrap();
+        assert_eq!(x, host);
+        assert_eq!(x.len(), n);
+        for (i, x) in x.iter().enumerate() {
+            let expected = 7 + 3 * i as i64;
+            if is_float {
+                let x = x.cast::<f64>();
+                assert!((x - expected as f64).abs() <= 0.01 * expected as f64);
+            } else {
+                assert_eq!(*x, expected.cast::<T>());
+            }
+        }
+        if is_float {
+            let end = T::from_u32(100).unwrap();
+            let x = Buffer::linspace(device.clone(), start, end, n)
+                .unwrap()
+                .into_vec()
+                .unwrap();
+            assert_eq!(x.len(), n);
+            if let Some(last) = x.last() {
+                assert_eq!(*last, if n == 1 { start } else { end });
+            }
+            for (i, x) in x.iter().enumerate().take(n.saturating_sub(1)) {
+                let expected = 7. + 93. * i as f64 / (n - 1).max(1) as f64;
+                let x = x.cast::<f64>();
+                assert!((x - expected).abs() <= 0.01 * expected, "{x} != {expected}");
+            }
+        } else {
+            Buffer::linspace(device.clone(), start, step, n).unwrap_err();
+        }
+    }
+}
+
+fn buffer_index<T: Scalar, I: IndexScalar>(device: Device) {
+    let len = 67;
+    let x_host: Vec<T> = (0..len).map(|i| T::from_usize(i % 100).unwrap()).collect();
+    let x = Slice::from(x_host.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let indices = |n: usize| -> Vec<I> {
+        (0..n)
+            .map(|i| I::from_usize(i * 7 % len).unwrap())
+            .collect()
+    };
+    for n in buffer_test_lengths() {
+        let indices_host = indices(n);
+        let indices = Slice::from(indices_host.as_slice())
+            .to_device(device.clone())
+            .unwrap();
+        let y = x.gather(&indices.as_slice()).unwrap().into_vec().unwrap();
+        let expected: Vec<T> = indices_host
+            .iter()
+            .map(|i| x_host[i.to_usize().unwrap()])
+            .collect();
+        assert_eq!(y, expected);
+    }
+    let indices_host = indices(len);
+    let indices = Slice::from(indices_host.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let mut y = Buffer::<T>::zeros(device.clone(), len).unwrap();
+    y.scatter(&indices.as_slice(), &x.as_slice()).unwrap();
+    let y = y.into_vec().unwrap();
+    for (i, x) in indices_host.iter().zip(x_host.iter()) {
+        assert_eq!(y[i.to_usize().unwrap()], *x);
+    }
+    for n in buffer_test_lengths() {
+        let indices_host = indices(3 * n);
+        let values_host: Vec<T> = (0..3 * n).map(|i| T::from_usize(i % 5).unwrap()).collect();
+        let scatter_add = |device: Device| {
+            let indices = Slice::from(indices_host.as_slice())
+                .to_device(device.clone())
+                .unwrap();
+            let values = Slice::from(values_host.as_slice())
+                .to_device(device.clone())
+                .unwrap();
+            let mut y = Slice::from(x_host.as_slice()).to_device(device).unwrap();
+            y.scatter_add(&indices.as_slice(), &values.as_slice())
+                .unwrap();
+            y.into_vec().unwrap()
+        };
+        assert_eq!(scatter_add(device.clone()), scatter_add(Device::host()));
+    }
+    {
+        // Runs of each index span several chunks of the sorted segmented reduction.
+        let n = 1000;
+        let indices_host: Vec<I> = (0..n).map(|i| I::from_usize(i % 10).unwrap()).collect();
+        let values_host: Vec<T> = (0..n)
+            .map(|i| T::from_usize(usize::from(i < 20)).unwrap())
+            .collect();
+        let indices = Slice::from(indices_host.as_slice())
+            .to_device(device.clone())
+            .unwrap();
+        let values = Slice::from(values_host.as_slice())
+            .to_device(device.clone())
+            .unwrap();
+        let mut y = Buffer::<T>::zeros(device.clone(), len).unwrap();
+        y.scatter_add(&indices.as_slice(), &values.as_slice())
+            .unwrap();
+        let y = y.into_vec().unwrap();
+        for (i, y) in y.iter().enumerate() {
+            let expected = T::from_usize(if i < 10 { 2 } else { 0 }).unwrap();
+            assert_eq!(*y, expected);
+        }
+    }
+    let out_of_range = Slice::from([I::from_usize(len).unwrap()].as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    x.gather(&out_of_range.as_slice()).unwrap_err();
+    let mut y = Buffer::<T>::zeros(device, len).unwrap();
+    y.scatter(&out_of_range.as_slice(), &x.slice(..1).unwrap())
+        .unwrap_err();
+    y.scatter_add(&out_of_range.as_slice(), &x.slice(..1).unwrap())
+        .unwrap_err();
+}
+
+fn buffer_permute<T: Scalar>(device: Device) {
+    fn permute<T: Copy>(x: &[T], shape: &[usize], axes: &[usize]) -> Vec<T> {
+        let mut strides = vec![1; shape.len()];
+        for i in (1..shape.len()).rev() {
+            strides[i - 1] = strides[i] * shape[i];
+        }
+        (0..x.len())
+            .map(|mut i| {
+                let mut index = 0;
+                for axis in axes.iter().rev() {
+                    index += (i % shape[*axis]) * strides[*axis];
+                    i /= shape[*axis];
+                }
+                x[index]
+            })
+            .collect()
+    }
+    let cases: &[(&[usize], &[usize])] = &[
+        (&[1, 1], &[1, 0]),
+        (&[3, 5], &[1, 0]),
+        (&[17, 33], &[1, 0]),
+        (&[64, 100], &[1, 0]),
+        (&[2, 3, 4], &[0, 2, 1]),
+        (&[2, 3, 4], &[2, 0, 1]),
+        (&[2, 3, 4], &[0, 1, 2]),
+        (&[2, 3, 1, 5], &[0, 2, 3, 1]),
+        (&[3, 17, 19, 2], &[0, 3, 1, 2]),
+        (&[2, 3, 4, 5], &[3, 2, 1, 0]),
+        (&[0, 4], &[1, 0]),
+    ];
+    for (shape, axes) in cases {
+        let len = shape.iter().product::<usize>();
+        let x_host: Vec<T> = (0..len).map(|i| T::from_usize(i % 100).unwrap()).collect();
+        let x = Slice::from(x_host.as_slice())
+            .to_device(device.clone())
+            .unwrap();
+        let expected = Buffer::from(permute(&x_host, shape, axes));
+        let y = x.permute(shape, axes).unwrap();
+        assert_buffer_eq!(y, expected, "{shape:?} {axes:?}");
+        if let [rows, cols] = shape {
+            let y = x.transpose_2d(*rows, *cols).unwrap();
+            assert_buffer_eq!(y, expected);
+        }
+    }
+    let x = Buffer::<T>::zeros(device, 6).unwrap();
+    x.permute(&[2, 2], &[1, 0]).unwrap_err();
+    x.permute(&[2, 3], &[0, 0]).unwrap_err();
+    x.permute(&[2, 3], &[1]).unwrap_err();
+    // the product wraps to 6
+    x.permute(&[3, usize::MAX / 2 + 2, 2], &[2, 1, 0])
+        .unwrap_err();
+}
+
+fn buffer_npy<T: Scalar>(device: Device) {
+    let shape = [2, 3, 4];
+    let x_host: Vec<T> = (0..24).map(|i| T::from_usize(i).unwrap()).collect();
+    let x = ScalarBuffer::from(
+        Slice::from(x_host.as_slice())
+            .to_device(device.clone())
+            .unwrap(),
+    );
+    if T::SCALAR_TYPE == ScalarType::BF16 {
+        x.write_npy(&shape, Vec::new()).unwrap_err();
+        return;
+    }
+    x.write_npy(&[5, 5], Vec::new()).unwrap_err();
+    let mut npy = Vec::new();
+    x.write_npy(&shape, &mut npy).unwrap();
+    assert_eq!(&npy[..6], b"\x93NUMPY");
+    let (y, y_shape) = ScalarBuffer::read_npy(device.clone(), npy.as_slice()).unwrap();
+    assert_eq!(y_shape, shape);
+    assert_buffer_eq!(
+        Buffer::<T>::try_from(y).unwrap(),
+        Slice::from(x_host.as_slice())
+    );
+    {
+        // big endian, fortran order
+        let kind = match T::SCALAR_TYPE {
+            ScalarType::U8 | ScalarType::U16 | ScalarType::U32 | ScalarType::U64 => 'u',
+            ScalarType::I8 | ScalarType::I16 | ScalarType::I32 | ScalarType::I64 => 'i',
+            _ => 'f',
+        };
+        let dict = format!(
+            "{{'descr': '>{kind}{}', 'fortran_order': True, 'shape': (2, 3, 4), }}",
+            size_of::<T>()
+        );
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        npy.extend_from_slice(&(dict.len() as u16 + 1).to_le_bytes());
+        npy.extend_from_slice(dict.as_bytes());
+        npy.push(b'\n');
+        for i in 0..x_host.len() {
+            let elem = x_host[(i % 2) * 12 + (i / 2 % 3) * 4 + i / 6].scalar_elem();
+            npy.extend(elem.as_bytes().iter().rev());
+        }
+        let (y, y_shape) = ScalarBuffer::read_npy(device.clone(), npy.as_slice()).unwrap();
+        assert_eq!(y_shape, shape);
+        assert_buffer_eq!(
+            Buffer::<T>::try_from(y).unwrap(),
+            Slice::from(x_host.as_slice())
+        );
+        // the shape overflows
+        let dict = dict
+            .replace("(2, 3, 4)", &format!("({}, 2)", usize::MAX))
+            .replace('>', "<");
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        npy.extend_from_slice(&(dict.len() as u16 + 1).to_le_bytes());
+        npy.extend_from_slice(dict.as_bytes());
+        npy.push(b'\n');
+        ScalarBuffer::read_npy(device.clone(), npy.as_slice()).unwrap_err();
+    }
+    let mut npz = Vec::new();
+    ScalarBuffer::write_npz(
+        [
+            ("x", x.as_scalar_slice(), shape.as_slice()),
+            ("y", x.slice(..6).unwrap(), [6].as_slice()),
+            ("z", x.slice(..0).unwrap(), [0, 2].as_slice()),
+        ],
+        &mut npz,
+    )
+    .unwrap();
+    let arrays = ScalarBuffer::read_npz(device, Cursor::new(npz)).unwrap();
+    let names: Vec<&str> = arrays.iter().map(|(name, _, _)| name.as_str()).collect();
+    assert_eq!(names, ["x", "y", "z"]);
+    let shapes: Vec<&[usize]> = arrays
+        .iter()
+        .map(|(_, _, shape)| shape.as_slice())
+        .collect();
+    assert_eq!(shapes, [shape.as_slice(), &[6], &[0, 2]]);
+    for ((_, y, _), x) in arrays.into_iter().zip([
+        x.as_scalar_slice(),
+        x.slice(..6).unwrap(),
+        x.slice(..0).unwrap(),
+    ]) {
+        assert_buffer_eq!(
+            Buffer::<T>::try_from(y).unwrap(),
+            Slice::<T>::try_from(x).unwrap()
+        );
+    }
+}
+
+#[cfg(feature = "ndarray")]
+fn buffer_ndarray<T: Scalar>(device: Device) {
+    use ndarray::{s, Array, ShapeBuilder};
+
+    let x_host: Vec<T> = (0..24).map(|i| T::from_usize(i).unwrap()).collect();
+    let x_array = Array::from_shape_vec([2, 3, 4], x_host.clone()).unwrap();
+    let x = Buffer::from_array(device.clone(), &x_array).unwrap();
+    assert_eq!(x.device(), device);
+    assert_eq!(x.to_array([2, 3, 4]).unwrap(), x_array);
+    assert_eq!(x.to_array([4, 3, 2].f()).unwrap(), x_array.t());
+    x.to_array([2, 3]).unwrap_err();
+    let y = Buffer::from_array(device.clone(), &x_array.t()).unwrap();
+    assert_eq!(y.to_array([4, 3, 2]).unwrap(), x_array.t());
+    if device.is_host() {
+        assert_eq!(
+            x.as_array([6, 4]).unwrap(),
+            x_array.view().into_shape([6, 4]).unwrap()
+        );
+        let mut x = x;
+        x.as_array_mut([2, 12]).unwrap()[[1, 0]] = T::zero();
+        assert_eq!(x.as_host_slice().unwrap()[12], T::zero());
+        let slice = Slice::try_from(x_array.view()).unwrap();
+        assert_eq!(slice.as_host_slice().unwrap(), x_host.as_slice());
+        Slice::try_from(x_array.t()).unwrap_err();
+        let z = Buffer::from(x_array.clone().reversed_axes());
+        assert_eq!(z.as_host_slice().unwrap()[1], x_host[12]);
+        let z = Buffer::from(x_array.clone().slice_move(s![1.., 1.., ..]));
+        assert_eq!(z.as_host_slice().unwrap(), &x_host[16..]);
+        let z = Buffer::from(x_array.clone().slice_move(s![.., 1..2, ..]));
+        assert_eq!(
+            z.as_host_slice().unwrap(),
+            [&x_host[4..8], &x_host[16..20]].concat()
+        );
+        let z = Buffer::from(x_array.clone().slice_move(s![..1, ..1, ..]));
+        assert_eq!(z.as_host_slice().unwrap(), &x_host[..4]);
+        let z = Buffer::from(x_array.clone().slice_move(s![.., .., 4..]));
+        assert!(z.is_empty());
+    } else {
+        x.as_array([24]).unwrap_err();
+    }
+}
+
+#[cfg(feature = "serde")]
+fn buffer_serde<T: Scalar>(device: Device) {
+    use krnl::buffer::{BufferSeed, Compressed};
+    use serde::de::DeserializeSeed;
+
+    let x_host: Vec<T> = (0..101).map(|i| T::from_usize(i).unwrap()).collect();
+    let x = Slice::from(x_host.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let string = serde_json::to_string(&x).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&string);
+    let y = BufferSeed::<Buffer<T>>::new(device.clone())
+        .deserialize(&mut deserializer)
+        .unwrap();
+    assert_eq!(y.device(), device);
+    assert_buffer_eq!(y, Slice::from(x_host.as_slice()));
+    let string = serde_json::to_string(&Compressed(&x)).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&string);
+    let Compressed(y) = BufferSeed::<Compressed<ScalarBuffer>>::new(device.clone())
+        .deserialize(&mut deserializer)
+        .unwrap();
+    assert_eq!(y.device(), device);
+    assert_buffer_eq!(
+        Buffer::<T>::try_from(y).unwrap(),
+        Slice::from(x_host.as_slice())
+    );
+    let string = serde_json::to_string(&x).unwrap();
+    if T::SCALAR_TYPE == ScalarType::U8 {
+        BufferSeed::<Buffer<u16>>::new(device.clone())
+            .deserialize(&mut serde_json::Deserializer::from_str(&string))
+            .unwrap_err();
+    } else {
+        BufferSeed::<Buffer<u8>>::new(device.clone())
+            .deserialize(&mut serde_json::Deserializer::from_str(&string))
+            .unwrap_err();
+    }
+    // The len is too large to allocate, so deserializing fails before reading the data.
+    let mut value: serde_json::Value = serde_json::from_str(&string).unwrap();
+    value[1] = serde_json::Value::from(usize::MAX / size_of::<T>());
+    let string = serde_json::to_string(&value).unwrap();
+    BufferSeed::<Buffer<T>>::new(device.clone())
+        .deserialize(&mut serde_json::Deserializer::from_str(&string))
+        .unwrap_err();
+    value[1] = serde_json::Value::from(usize::MAX);
+    let string = serde_json::to_string(&value).unwrap();
+    BufferSeed::<Buffer<T>>::new(device)
+        .deserialize(&mut serde_json::Deserializer::from_str(&string))
+        .unwrap_err();
+}
+
+#[cfg(feature = "safetensors")]
+fn buffer_safetensors<T: Scalar>(device: Device) {
+    let x_host: Vec<T> = (0..24).map(|i| T::from_usize(i).unwrap()).collect();
+    let x = ScalarBuffer::from(
+        Slice::from(x_host.as_slice())
+            .to_device(device.clone())
+            .unwrap(),
+    );
+    let tensors = [
+        ("x", x.as_scalar_slice(), [2, 3, 4].as_slice()),
+        ("y", x.slice(..6).unwrap(), [6].as_slice()),
+        ("z", x.slice(..0).unwrap(), [0, 2].as_slice()),
+    ];
+    let mut bytes = Vec::new();
+    ScalarBuffer::write_safetensors(tensors.iter().cloned(), &mut bytes).unwrap();
+    let header_len = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+    assert_eq!(header_len % 8, 0);
+    let check = |output: Vec<(String, ScalarBuffer, Vec<usize>)>| {
+        assert_eq!(output.len(), tensors.len());
+        for ((name, y, shape), (x_name, x, x_shape)) in output.into_iter().zip(tensors.iter()) {
+            assert_eq!(name, *x_name);
+            assert_eq!(shape, *x_shape);
+            assert_eq!(y.device(), device);
+            assert_buffer_eq!(
+                Buffer::<T>::try_from(y).unwrap(),
+                Slice::<T>::try_from(x.clone()).unwrap()
+            );
+        }
+    };
+    check(ScalarBuffer::read_safetensors(device.clone(), &bytes).unwrap());
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let path = std::env::temp_dir().join(format!(
+            "krnl_buffer_safetensors_{}_{:?}.safetensors",
+            T::SCALAR_TYPE.name(),
+            std::thread::current().id()
+        ));
+        std::fs::write(&path, &bytes).unwrap();
+        let output = ScalarBuffer::load_safetensors(device.clone(), &path);
+        std::fs::remove_file(&path).unwrap();
+        check(output.unwrap());
+    }
+    ScalarBuffer::write_safetensors([("x", x.as_scalar_slice(), [5].as_slice())], Vec::new())
+        .unwrap_err();
+    ScalarBuffer::write_safetensors(
+        [
+            ("x", x.as_scalar_slice(), [24].as_slice()),
+            ("x", x.as_scalar_slice(), [24].as_slice()),
+        ],
+        Vec::new(),
+    )
+    .unwrap_err();
+    {
+        // unaligned with metadata
+        let dtype = T::SCALAR_TYPE.as_str();
+        let header = format!(
+            r#"{{"__metadata__":{{"format":"pt"}},"b":{{"dtype":"{dtype}","shape":[2],"data_offsets":[1,{}]}},"a":{{"dtype":"BOOL","shape":[1],"data_offsets":[0,1]}}}}"#,
+            1 + 2 * size_of::<T>()
+        );
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.push(1);
+        for x in &x_host[..2] {
+            bytes.extend_from_slice(x.scalar_elem().as_bytes());
+        }
+        let output = ScalarBuffer::read_safetensors(device.clone(), &bytes).unwrap();
+        let names: Vec<&str> = output.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(output[0].1.scalar_type(), ScalarType::U8);
+        assert_buffer_eq!(
+            Slice::<T>::try_from(output[1].1.as_scalar_slice()).unwrap(),
+            Slice::from(&x_host[..2])
+        );
+        bytes.pop();
+        ScalarBuffer::read_safetensors(device.clone(), &bytes).unwrap_err();
+    }
+    {
+        // shape overflows
+        let dtype = T::SCALAR_TYPE.as_str();
+        for shape in [
+            format!("[{}, 2]", usize::MAX),
+            format!("[{}]", usize::MAX / 2 + 1),
+        ] {
+            let header =
+                format!(r#"{{"x":{{"dtype":"{dtype}","shape":{shape},"data_offsets":[0,0]}}}}"#);
+            let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+            bytes.extend_from_slice(header.as_bytes());
+            ScalarBuffer::read_safetensors(device.clone(), &bytes).unwrap_err();
+        }
+    }
+}
+
+fn testing_check_buffer<T: Scalar>(device: Device) {
+    let is_float = matches!(
+        T::SCALAR_TYPE,
+        ScalarType::F16 | ScalarType::BF16 | ScalarType::F32 | ScalarType::F64
+    );
+    let x_host: Vec<T> = (0..20).map(|i| T::from_usize(i + 10).unwrap()).collect();
+    let x = Slice::from(x_host.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    assert_buffer_eq!(x, Buffer::from(x_host.clone()));
+    assert_buffer_close!(x, Buffer::from(x_host.clone()), 0., 0.);
+    let mut y_host = x_host.clone();
+    y_host[3] = T::from_u32(100).unwrap();
+    y_host[17] = T::from_u32(1).unwrap();
+    let y = Slice::from(y_host.as_slice())
+        .to_device(device.clone())
+        .unwrap();
+    let error = testing::check_buffer_eq(x.as_slice(), y.as_slice())
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("2 of 20"), "{error}");
+    assert!(error.contains("[3]: 13 != 100"), "{error}");
+    assert!(error.contains("[17]: 27 != 1"), "{error}");
+    testing::check_buffer_eq(x.slice(..2).unwrap(), y.as_slice()).unwrap_err();
+    testing::check_buffer_close(x.as_slice(), y.as_slice(), Tolerance::new(0., 90.)).unwrap();
+    testing::check_buffer_close(x.as_slice(), y.as_slice(), Tolerance::new(0.1, 1.)).unwrap_err();
+    if is_float {
+        let mantissa_bits = match T::SCALAR_TYPE {
+            ScalarType::F16 => 10,
+            ScalarType::BF16 => 7,
+            ScalarType::F32 => 23,
+            _ => 52,
+        };
+        // 2 ulps above 1
+        let next = T::from_f64(1. + 2. * 2f64.powi(-mantissa_bits)).unwrap();
+        let x = Buffer::from_elem(device, 4, T::one()).unwrap();
+        let y = Buffer::from(vec![next; 4]);
+        let close = |tolerance| testing::check_buffer_close(x.as_slice(), y.as_slice(), tolerance);
+        close(Tolerance::new(0., 0.)).unwrap_err();
+        close(Tolerance::new(0., 0.).with_ulps(1)).unwrap_err();
+        assert_buffer_close!(x, y, 0., 0., ulps = 2);
+        assert_buffer_close!(x, y, 0.02, 0.);
+    }
+}
+
+fn linalg_gemm<T: Scalar>(device: Device) {
+    let value = |i: usize, modulus: usize| T::from_i64((i % modulus) as i64 - 2).unwrap();
+    for (m, k, n) in [(1, 1, 1), (3, 5, 7), (17, 33, 9), (64, 64, 64)] {
+        for batch in [1, 3] {
+            for (transpose_a, transpose_b) in
+                [(false, false), (true, false), (false, true), (true, true)]
+            {
+                let a: Vec<T> = (0..batch * m * k).map(|i| value(i, 7)).collect();
+                let b: Vec<T> = (0..batch * k * n).map(|i| value(i, 5)).collect();
+                let c: Vec<T> = (0..batch * m * n).map(|i| value(i, 3)).collect();
+                let mut expected = vec![0f64; c.len()];
+                for batch_index in 0..batch {
+                    for row in 0..m {
+                        for col in 0..n {
+                            let mut acc = 0f64;
+                            for i in 0..k {
+                                let a_index = if transpose_a {
+                                    i * m + row
+                                } else {
+                                    row * k + i
+                                };
+                                let b_index = if transpose_b {
+                                    col * k + i
+                                } else {
+                                    i * n + col
+                                };
+                                acc += a[batch_index * m * k + a_index].cast::<f64>()
+                                    * b[batch_index * k * n + b_index].cast::<f64>();
+                            }
+                            let c_index = batch_index * m * n + row * n + col;
+                            expected[c_index] = 2. * acc + 0.5 * c[c_index].cast::<f64>();
+                        }
+                    }
+                }
+                let a = Slice::from(a.as_slice()).to_device(device.clone()).unwrap();
+                let b = Slice::from(b.as_slice()).to_device(device.clone()).unwrap();
+                let mut c = Slice::from(c.as_slice()).to_device(device.clone()).unwrap();
+                Gemm::new(m, k, n)
+                    .with_batch(batch)
+                    .with_transpose_a(transpose_a)
+                    .with_transpose_b(transpose_b)
+                    .with_alpha(T::from_f32(2.).unwrap())
+                    .with_beta(T::from_f32(0.5).unwrap())
+                    .dispatch(a.as_slice(), b.as_slice(), c.as_slice_mut())
+                    .unwrap();
+                let c = c.into_vec().unwrap();
+                for (c, expected) in c.iter().zip(expected) {
+                    let c = c.cast::<f64>();
+                    assert!(
+                        (c - expected).abs() <= 0.01 * expected.abs().max(1.),
+                        "{m}x{k}x{n} batch {batch} transpose ({transpose_a}, {transpose_b}): {c} != {expected}"
+                    );
+                }
+            }
+        }
+    }
+    Gemm::new(2, 2, 2)
+        .dispatch(
+            Buffer::<T>::zeros(device.clone(), 3).unwrap().as_slice(),
+            Buffer::<T>::zeros(device.clone(), 4).unwrap().as_slice(),
+            Buffer::<T>::zeros(device.clone(), 4)
+                .unwrap()
+                .as_slice_mut(),
+        )
+        .unwrap_err();
+    // a has usize::MAX * 2 elements
+    let empty = Buffer::<T>::zeros(device, 0).unwrap();
+    Gemm::new(usize::MAX, 2, 0)
+        .dispatch(
+            empty.as_slice(),
+            empty.as_slice(),
+            Buffer::<T>::zeros(empty.device(), 0)
+                .unwrap()
+                .as_slice_mut(),
+        )
+        .unwrap_err();
+}
+
+fn buffer_random_philox(device: Device) {
+    // Philox4x32-10 known answer for a zero key and counter.
+    let x = Buffer::<u32>::random_uniform(device.clone(), 1, 0, u32::MAX, 0, 0)
+        .unwrap()
+        .into_vec()
+        .unwrap();
+    assert_eq!(x, [0x6627_e8d5]);
+    // the fixed point Box-Muller transform is a standard normal
+    let n = 10_000;
+    let x = Buffer::<f32>::random_normal(device, n, 0., 1., 1, 0)
+        .unwrap()
+        .into_vec()
+        .unwrap();
+    let mean = x.iter().map(|x| f64::from(*x)).sum::<f64>() / n as f64;
+    let variance = x
+        .iter()
+        .map(|x| (f64::from(*x) - mean).powi(2))
+        .sum::<f64>()
+        / n as f64;
+    assert!(mean.abs() < 0.05, "{mean}");
+    assert!((variance - 1.).abs() < 0.05, "{variance}");
+}
+
+fn buffer_random<T: Scalar>(device: Device) {
+    let is_float = matches!(
+        T::SCALAR_TYPE,
+        ScalarType::F16 | ScalarType::BF16 | ScalarType::F32 | ScalarType::F64
+    );
+    let (low, high) = if is_float {
+        (T::zero(), T::one())
+    } else {
+        (T::from_u32(3).unwrap(), T::from_u32(100).unwrap())
+    };
+    let seed = 0x0123_4567_89AB_CDEF;
+    let offset = u64::from(u32::MAX) - 10;
+    for n in buffer_reduce_test_lengths() {
+        let uniform = |device: Device, offset: u64| {
+            Buffer::random_uniform(device, n, low, high, seed, offset)
+                .unwrap()
+                .into_vec()
+                .unwrap()
+        };
+        // x is exact in [0, 1)
+        let x = uniform(device.clone(), offset);
+        assert_eq!(x, uniform(Device::host(), offset));
+        assert!(x.iter().all(|x| low <= *x && *x < high));
+        if n > 0 {
+            assert_eq!(x[1..], uniform(device.clone(), offset + 1)[..n - 1]);
+        }
+        let bernoulli = |device: Device| {
+            Buffer::<T>::random_bernoulli(device, n, 0.25, seed, offset)
+                .unwrap()
+                .into_vec()
+                .unwrap()
+        };
+        let x = bernoulli(device.clone());
+        assert_eq!(x, bernoulli(Device::host()));
+        assert!(x.iter().all(|x| *x == T::zero() || *x == T::one()));
+        if is_float {
+            let (low, high) = (T::from_f32(-1.5).unwrap(), T::from_f32(2.5).unwrap());
+            let uniform = |device: Device| {
+                Buffer::random_uniform(device, n, low, high, seed, offset)
+                    .unwrap()
+                    .into_vec()
+                    .unwrap()
+            };
+            let x = uniform(device.clone());
+            assert!(x.iter().all(|x| low <= *x && *x < high));
+            assert_eq!(x, uniform(Device::host()));
+            let normal = |device: Device| {
+                Buffer::<T>::random_normal(
+                    device,
+                    n,
+                    T::one(),
+                    T::from_u32(2).unwrap(),
+                    seed,
+                    offset,
+                )
+                .unwrap()
+                .into_vec()
+                .unwrap()
+            };
+            assert_eq!(normal(device.clone()), normal(Device::host()));
+        } else {
+            Buffer::<T>::random_normal(device.clone(), n, low, high, seed, offset).unwrap_err();
+        }
+    }
+    Buffer::random_uniform(device, 1, high, low, seed, offset).unwrap_err();
+}
+
+#[test]
+fn buffer_from_vec_host() {
+    buffer_from_vec(Device::host());
+}
+
+#[cfg(target_family = "wasm")]
+#[test]
+fn buffer_element_host() {
+    buffer_element(Device::host());
+}
+
+#[cfg(target_family = "wasm")]
+#[test]
+fn buffer_random_philox_host() {
+    buffer_random_philox(Device::host());
+}
+
+#[cfg(target_family = "wasm")]
+macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+    paste! {
+        #[test]
+        fn [<buffer_fill_ $T _host>]() {
+            buffer_fill::<$T>(Device::host());
+        }
+        #[test]
+        fn [<buffer_reduce_ $T _host>]() {
+            buffer_reduce::<$T>(Device::host());
+        }
+        #[test]
+        fn [<buffer_elementwise_ $T _host>]() {
+            buffer_elementwise::<$T>(Device::host());
+        }
+        #[test]
+        fn [<buffer_index_ $T _u32_host>]() {
+            buffer_index::<$T, u32>(Device::host());
+        }
+        #[test]
+        fn [<buffer_index_ $T _u64_host>]() {
+            buffer_index::<$T, u64>(Device::host());
+        }
+        #[test]
+        fn [<buffer_arange_ $T _host>]() {
+            buffer_arange::<$T>(Device::host());
+        }
+        #[test]
+        fn [<buffer_permute_ $T _host>]() {
+            buffer_permute::<$T>(Device::host());
+        }
+        #[test]
+        fn [<testing_check_buffer_ $T _host>]() {
+            testing_check_buffer::<$T>(Device::host());
+        }
+        #[test]
+        fn [<buffer_npy_ $T _host>]() {
+            buffer_npy::<$T>(Device::host());
+        }
+        #[cfg(feature = "safetensors")]
+        #[test]
+        fn [<buffer_safetensors_ $T _host>]() {
+            buffer_safetensors::<$T>(Device::host());
+        }
+        #[cfg(feature = "serde")]
+        #[test]
+        fn [<buffer_serde_ $T _host>]() {
+            buffer_serde::<$T>(Device::host());
+        }
+        #[cfg(feature = "ndarray")]
+        #[test]
+        fn [<buffer_ndarray_ $T _host>]() {
+            buffer_ndarray::<$T>(Device::host());
+        }
+        #[test]
+        fn [<buffer_random_ $T _host>]() {
+            buffer_random::<$T>(Device::host());
+        }
+        #[test]
+        fn [<algorithms_scan_ $T _host>]() {
+            algorithms_scan::<$T>(Device::host());
+        }
+        #[test]
+        fn [<algorithms_compact_ $T _host>]() {
+            algorithms_compact::<$T>(Device::host());
+        }
+        #[test]
+        fn [<algorithms_radix_sort_ $T _host>]() {
+            algorithms_radix_sort::<$T>(Device::host());
+        }
+    }
+});
+
+#[cfg(target_family = "wasm")]
+macro_for!($T in [f16, bf16, f32, f64] {
+    paste! {
+        #[test]
+        fn [<linalg_gemm_ $T _host>]() {
+            linalg_gemm::<$T>(Device::host());
+        }
+    }
+});
+
+#[cfg(target_family = "wasm")]
+macro_for!($T in [c32, c64] {
+    paste! {
+        #[test]
+        fn [<buffer_fill_ $T _host>]() {
+            buffer_fill::<$T>(Device::host());
+        }
+    }
+});
+
+#[cfg(target_family = "wasm")]
+#[test]
+fn scalar_complex_host() {
+    scalar_complex();
+}
+
+#[cfg(target_family = "wasm")]
+macro_for!($T in [f8e4m3, f8e5m2] {
+    paste! {
+        #[test]
+        fn [<buffer_fill_ $T _host>]() {
+            buffer_fill::<$T>(Device::host());
+        }
+        #[test]
+        fn [<algorithms_radix_sort_ $T _host>]() {
+            algorithms_radix_sort::<$T>(Device::host());
+        }
+        #[cfg(feature = "safetensors")]
+        #[test]
+        fn [<buffer_safetensors_ $T _host>]() {
+            buffer_safetensors::<$T>(Device::host());
+        }
+    }
+});
+
+#[cfg(target_family = "wasm")]
+#[test]
+fn scalar_fp8_host() {
+    scalar_fp8();
+}
+
+#[cfg(target_family = "wasm")]
+#[test]
+fn unsafe_slice_atomics_host() {
+    unsafe_slice_atomics();
+}
+
+#[cfg(target_family = "wasm")]
+#[test]
+fn subgroup_host() {
+    subgroup();
+}
+
+#[cfg(target_family = "wasm")]
+#[test]
+fn group_slice_host() {
+    group_slice();
+}
+
+#[cfg(target_family = "wasm")]
+#[test]
+fn math_host() {
+    math();
+}
 
-macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
-    macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
+macro_for!($X in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
+    macro_for!($Y in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64, c32, c64, f8e4m3, f8e5m2] {
         paste! {
             #[test]
             fn [<buffer_cast_ $X _ $Y _host>]() {
@@ -87,6 +87,48 @@ mod kernels {
 #[allow(dead_code)]
 enum WithGroups {}
 
+/**
+```no_run
+use krnl::macros::module;
+
+#[module]
+#[krnl(no_build)]
+mod kernels {
+    use krnl::{macros::kernel, device::Device, anyhow::Result};
+
+    #[kernel(threads(16, 16))]
+    fn threads_2d() {}
+
+    fn test_threads_2d(device: Device) -> Result<()> {
+        let kernel = threads_2d::builder()?.with_threads([8, 8]).build(device)?;
+        let [_threads_x, _threads_y] = kernel.threads();
+        kernel.with_groups([2, 2]).dispatch()
+    }
+
+    #[kernel(threads(4, 4, 4))]
+    fn threads_3d() {}
+
+    fn test_threads_3d(device: Device) -> Result<()> {
+        threads_3d::builder()?.build(device)?.with_global_threads([10, 10, 10]).dispatch()
+    }
+}
+```
+```compile_fail
+use krnl::macros::module;
+
+#[module]
+#[krnl(no_build)]
+mod kernels {
+    use krnl::{macros::kernel, buffer::SliceMut, anyhow::Result};
+
+    #[kernel(threads(16, 16))]
+    fn threads_item(#[item] y: &mut u32) {}
+}
+```
+*/
+#[allow(dead_code)]
+enum Threads {}
+
 #[module]
 pub mod kernels {
     use dry::macro_for;
@@ -94,7 +136,7 @@ pub mod kernels {
     use krnl::device::Features;
     #[cfg(not(target_arch = "spirv"))]
     use krnl::krnl_core;
-    use krnl_core::macros::kernel;
+    use krnl_core::macros::{kernel, Element};
     #[cfg(target_arch = "spirv")]
     use krnl_core::{
         buffer::UnsafeIndex,
@@ -119,6 +161,49 @@ pub mod kernels {
         specs::builder().unwrap().specialize(10u32, 1.5f32);
     }
 
+    #[test]
+    fn test_kernels() {
+        use krnl::scalar::ScalarType;
+
+        let kernel = |name: &str| {
+            let name = format!("{}::{name}", module_path!());
+            *kernel_infos()
+                .iter()
+                .find(|kernel| kernel.name() == name)
+                .unwrap()
+        };
+        let empty = kernel("empty");
+        assert!(empty.safe());
+        assert_eq!(empty.features(), Features::empty());
+        assert!(empty.spec_consts().is_empty());
+        assert!(empty.slices().is_empty());
+        assert!(empty.push_consts().is_empty());
+        let specs = kernel("specs");
+        let spec_consts: Vec<_> = specs
+            .spec_consts()
+            .iter()
+            .map(|spec| (spec.name(), spec.scalar_type()))
+            .collect();
+        assert_eq!(
+            spec_consts,
+            [("X", ScalarType::U32), ("Y", ScalarType::F32)]
+        );
+        let basic = kernel("basic_u32");
+        assert_eq!(basic.slices().len(), 1);
+        let a = basic.slices()[0];
+        assert_eq!(
+            (a.name(), a.scalar_type(), a.mutable(), a.item()),
+            ("a", ScalarType::U32, true, true)
+        );
+        assert_eq!(basic.push_consts().len(), 1);
+        let a_push = basic.push_consts()[0];
+        assert_eq!(
+            (a_push.name(), a_push.scalar_type()),
+            ("a_push", ScalarType::U32)
+        );
+        assert!(!kernel("group_n").safe());
+    }
+
     macro_for!($A in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
         paste! {
             #[kernel]
@@ -174,6 +259,303 @@ pub mod kernels {
     #[allow(non_snake_case)]
     #[kernel]
     fn attribute(fooBar: u32) {}
+
+    #[kernel(threads(8, 8))]
+    pub fn threads_xy(#[global] y: UnsafeSlice<u32>) {
+        let [id_x, id_y, _] = kernel.global_id_xyz();
+        let [threads_x, _, _] = kernel.global_threads_xyz();
+        let index = id_y * threads_x + id_x;
+        if index < y.len() {
+            unsafe {
+                *y.unsafe_index_mut(index) = kernel.global_id() as u32;
+            }
+        }
+    }
+
+    #[test]
+    fn test_threads_xy() {
+        let builder = threads_xy::builder().unwrap();
+        assert_eq!(builder.__features(), Features::empty());
+    }
+
+    #[kernel(threads(8))]
+    fn group_slice(
+        #[global] x: Slice<f32>,
+        #[group] x_group: GroupSlice<f32, 8>,
+        #[global] y: UnsafeSlice<f32>,
+    ) {
+        let thread_id = kernel.thread_id();
+        x_group.write(x[kernel.global_id()]);
+        let x_group = x_group.barrier();
+        let value = x_group[(thread_id + 1) % x_group.len()];
+        unsafe {
+            *y.unsafe_index_mut(kernel.global_id()) = value;
+        }
+    }
+
+    #[test]
+    fn test_group_slice() {
+        group_slice::builder().unwrap();
+    }
+
+    #[derive(Clone, Copy, Element)]
+    #[repr(C)]
+    pub struct Particle {
+        pub position: [f32; 3],
+        pub mass: f32,
+    }
+
+    #[kernel]
+    pub fn element_update(
+        dt: f32,
+        #[global] x: Slice<Particle>,
+        #[global] y: UnsafeSlice<Particle>,
+    ) {
+        let global_id = kernel.global_id();
+        if global_id < x.len() && global_id < y.len() {
+            let mut particle = x[global_id];
+            particle.position[1] -= particle.mass * dt;
+            unsafe {
+                *y.unsafe_index_mut(global_id) = particle;
+            }
+        }
+    }
+
+    #[test]
+    fn test_element_update() {
+        use krnl::buffer::Element;
+
+        let builder = element_update::builder().unwrap();
+        assert_eq!(builder.__features(), Features::empty());
+        let name = format!("{}::element_update", module_path!());
+        let kernel = kernel_infos()
+            .iter()
+            .find(|kernel| kernel.name() == name)
+            .unwrap();
+        for (slice, mutable) in kernel.slices().iter().zip([false, true]) {
+            assert_eq!(slice.mutable(), mutable);
+            assert_eq!(slice.element(), Some(Particle::LAYOUT));
+        }
+    }
+
+    #[kernel]
+    fn emulated_u8(a: u8, #[item] x: u8, #[item] y: &mut u8) {
+        *y = x.wrapping_mul(3).wrapping_add(a) >> 1;
+    }
+
+    #[kernel]
+    fn emulated_f16(#[item] x: f16, #[item] y: &mut f16) {
+        *y = x * x + x;
+    }
+
+    #[kernel]
+    fn emulated_u64(a: u64, #[item] x: u64, #[item] y: &mut u64) {
+        let z = x.wrapping_mul(a).wrapping_add(x << 33) ^ (x >> 7);
+        *y = if z < x { z } else { z.wrapping_sub(x) };
+    }
+
+    #[kernel]
+    fn emulated_i64(a: i64, #[item] x: i64, #[item] y: &mut i64) {
+        let z = x.wrapping_neg().wrapping_mul(a) >> 3;
+        *y = if z > x { z } else { x.wrapping_sub(a) };
+    }
+
+    #[kernel]
+    fn emulated_casts(#[item] x: i32, #[item] y: &mut i64, #[item] z: &mut i32) {
+        let w = (x as i64).wrapping_mul(x as u32 as u64 as i64);
+        *y = w.wrapping_add(x as i8 as i64);
+        *z = (w as i8) as i32 + (w as u16) as i32;
+    }
+
+    #[kernel]
+    fn div_u64(#[item] x: u64, #[item] y: &mut u64) {
+        *y = x / 3;
+    }
+
+    #[cfg(test)]
+    fn kernel_info(name: &str) -> krnl::kernel::KernelInfo {
+        let name = format!("{}::{name}", module_path!());
+        *kernel_infos()
+            .iter()
+            .find(|kernel| kernel.name() == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_emulated() {
+        for (name, emulated) in [
+            ("emulated_u8", Features::INT8),
+            ("emulated_f16", Features::FLOAT16),
+            ("emulated_u64", Features::INT64),
+            ("emulated_i64", Features::INT64),
+            ("emulated_casts", Features::INT64),
+        ] {
+            let kernel = kernel_info(name);
+            assert!(kernel.features().contains(emulated), "{name}");
+            let emulated_features = kernel.emulated_features().unwrap();
+            assert!(!emulated_features.intersects(emulated), "{name}");
+        }
+        let div = kernel_info("div_u64");
+        assert!(div.features().contains(Features::INT64));
+        assert_eq!(div.emulated_features(), None);
+    }
+
+    #[test]
+    fn test_emulated_dispatch() {
+        use krnl::{buffer::Buffer, device::Device, krnl_core::half::f16};
+
+        let Ok(device) = Device::builder().build() else {
+            return;
+        };
+        let device_features = device.info().unwrap().features();
+        let supported = |name: &str| {
+            kernel_info(name)
+                .emulated_features()
+                .map_or(false, |features| device_features.contains(features))
+        };
+        if supported("emulated_u8") {
+            let x: Vec<u8> = (0..=255).collect();
+            let mut y = Buffer::zeros(device.clone(), x.len()).unwrap();
+            let x_buffer = Buffer::from_vec(x.clone())
+                .into_device(device.clone())
+                .unwrap();
+            emulated_u8::builder()
+                .unwrap()
+                .__emulate()
+                .build(device.clone())
+                .unwrap()
+                .dispatch(200, x_buffer.as_slice(), y.as_slice_mut())
+                .unwrap();
+            let y_true: Vec<u8> = x
+                .iter()
+                .map(|x| x.wrapping_mul(3).wrapping_add(200) >> 1)
+                .collect();
+            assert_eq!(y.to_vec().unwrap(), y_true);
+        }
+        if supported("emulated_f16") {
+            let x: Vec<f16> = (0..100).map(|x| f16::from_f32(x as f32 / 10.)).collect();
+            let mut y = Buffer::zeros(device.clone(), x.len()).unwrap();
+            let x_buffer = Buffer::from_vec(x.clone())
+                .into_device(device.clone())
+                .unwrap();
+            emulated_f16::builder()
+                .unwrap()
+                .__emulate()
+                .build(device.clone())
+                .unwrap()
+                .dispatch(x_buffer.as_slice(), y.as_slice_mut())
+                .unwrap();
+            let y_true: Vec<f16> = x
+                .iter()
+                .map(|x| f16::from_f32(x.to_f32() * x.to_f32() + x.to_f32()))
+                .collect();
+            for (y, y_true) in y.to_vec().unwrap().into_iter().zip(y_true) {
+                assert!((y.to_f32() - y_true.to_f32()).abs() <= y_true.to_f32() * 0.01);
+            }
+        }
+        // values crossing the 32 bit boundary
+        let x: Vec<u64> = (0..200u64)
+            .map(|x| x.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (x << 31))
+            .collect();
+        if supported("emulated_u64") {
+            let a = 0xFFFF_FFFF_0000_0003;
+            let mut y = Buffer::zeros(device.clone(), x.len()).unwrap();
+            let x_buffer = Buffer::from_vec(x.clone())
+                .into_device(device.clone())
+                .unwrap();
+            emulated_u64::builder()
+                .unwrap()
+                .__emulate()
+                .build(device.clone())
+                .unwrap()
+                .dispatch(a, x_buffer.as_slice(), y.as_slice_mut())
+                .unwrap();
+            let y_true: Vec<u64> = x
+                .iter()
+                .map(|&x| {
+                    let z = x.wrapping_mul(a).wrapping_add(x << 33) ^ (x >> 7);
+                    if z < x {
+                        z
+                    } else {
+                        z.wrapping_sub(x)
+                    }
+                })
+                .collect();
+            assert_eq!(y.to_vec().unwrap(), y_true);
+        }
+        if supported("emulated_i64") {
+            let a = -0x1_0000_0005;
+            let x: Vec<i64> = x.iter().map(|x| *x as i64).collect();
+            let mut y = Buffer::zeros(device.clone(), x.len()).unwrap();
+            let x_buffer = Buffer::from_vec(x.clone())
+                .into_device(device.clone())
+                .unwrap();
+            emulated_i64::builder()
+                .unwrap()
+                .__emulate()
+                .build(device.clone())
+                .unwrap()
+                .dispatch(a, x_buffer.as_slice(), y.as_slice_mut())
+                .unwrap();
+            let y_true: Vec<i64> = x
+                .iter()
+                .map(|&x| {
+                    let z = x.wrapping_neg().wrapping_mul(a) >> 3;
+                    if z > x {
+                        z
+                    } else {
+                        x.wrapping_sub(a)
+                    }
+                })
+                .collect();
+            assert_eq!(y.to_vec().unwrap(), y_true);
+        }
+        if supported("emulated_casts") {
+            let x: Vec<i32> = x.iter().map(|x| *x as i32).collect();
+            let mut y = Buffer::<i64>::zeros(device.clone(), x.len()).unwrap();
+            let mut z = Buffer::<i32>::zeros(device.clone(), x.len()).unwrap();
+            let x_buffer = Buffer::from_vec(x.clone())
+                .into_device(device.clone())
+                .unwrap();
+            emulated_casts::builder()
+                .unwrap()
+                .__emulate()
+                .build(device.clone())
+                .unwrap()
+                .dispatch(x_buffer.as_slice(), y.as_slice_mut(), z.as_slice_mut())
+                .unwrap();
+            let (y_true, z_true): (Vec<i64>, Vec<i32>) = x
+                .iter()
+                .map(|&x| {
+                    let w = (x as i64).wrapping_mul(x as u32 as u64 as i64);
+                    (
+                        w.wrapping_add(x as i8 as i64),
+                        (w as i8) as i32 + (w as u16) as i32,
+                    )
+                })
+                .unzip();
+            assert_eq!(y.to_vec().unwrap(), y_true);
+            assert_eq!(z.to_vec().unwrap(), z_true);
+        }
+    }
+
+    macro_for!($T in [bf16, f32, f64] {
+        paste! {
+            #[kernel]
+            fn [<math_ $T>](#[item] x: $T, #[item] y: &mut $T) {
+                use krnl_core::math;
+
+                let z = math::fma(math::exp(x), math::tanh(x), math::erf(x));
+                let z = math::pow(math::sin(z), math::cos(x)) + math::log(x);
+                *y = if math::isnan(z) || math::isinf(z) { x } else { z * math::rsqrt(x) };
+            }
+
+            #[test]
+            fn [<test_math_ $T>]() {
+                [<math_ $T>]::builder().unwrap();
+            }
+        }
+    });
 }
 
 macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
@@ -191,6 +573,7 @@ macro_for!($T in [u8, i8, u16, i16, f16, bf16, u32, i32, f32, u64, i64, f64] {
                 #[test]
                 fn test_foo() {
                     foo::builder().unwrap();
+                    assert_eq!(super::kernel_infos().len(), 1);
                 }
             }
         }
@@ -238,7 +621,51 @@ mod dependency {
 }
 
 #[module]
-mod subgroup {
+pub mod atomics {
+    #[cfg(not(target_arch = "spirv"))]
+    use krnl::krnl_core;
+    use krnl_core::macros::kernel;
+
+    #[kernel]
+    pub fn histogram(#[item] x: u32, #[global] counts: UnsafeSlice<u32>) {
+        counts.atomic_add(x as usize, 1);
+    }
+
+    #[kernel]
+    pub fn histogram_compare_exchange(#[item] x: u32, #[global] counts: UnsafeSlice<u32>) {
+        let index = x as usize;
+        let mut count = 0;
+        while let Err(previous) = counts.compare_exchange(index, count, count + 1) {
+            count = previous;
+        }
+    }
+
+    #[kernel]
+    pub fn histogram_u64(#[item] x: u32, #[global] counts: UnsafeSlice<u64>) {
+        counts.atomic_add(x as usize, 1);
+    }
+
+    #[test]
+    fn test_histogram() {
+        use krnl::device::Features;
+
+        assert_eq!(
+            histogram::builder().unwrap().__features(),
+            Features::empty()
+        );
+        assert_eq!(
+            histogram_compare_exchange::builder().unwrap().__features(),
+            Features::empty()
+        );
+        assert!(histogram_u64::builder()
+            .unwrap()
+            .__features()
+            .contains(Features::INT64.union(Features::BUFFER_INT64_ATOMICS)));
+    }
+}
+
+#[module]
+pub mod subgroup {
     #[cfg(not(target_arch = "spirv"))]
     use krnl::krnl_core;
     use krnl_core::macros::kernel;
@@ -327,4 +754,83 @@ mod subgroup {
             Features::SUBGROUP_BASIC.union(Features::SUBGROUP_CLUSTERED)
         );
     }
+
+    #[kernel(threads(64))]
+    pub fn broadcast_u32(
+        #[global] x: Slice<u32>,
+        #[global] ids: UnsafeSlice<u32>,
+        #[global] y: UnsafeSlice<u32>,
+    ) {
+        use krnl_core::{buffer::UnsafeIndex, subgroup};
+
+        let global_id = kernel.global_id();
+        let value = subgroup::broadcast(x[global_id], 0);
+        unsafe {
+            *ids.unsafe_index_mut(global_id) =
+                (kernel.group_id() * kernel.subgroups() + kernel.subgroup_id()) as u32;
+            *y.unsafe_index_mut(global_id) = value;
+        }
+    }
+
+    #[kernel(threads(64))]
+    pub fn reduce_u32(
+        #[global] x: Slice<u32>,
+        #[global] ids: UnsafeSlice<u32>,
+        #[global] sum: UnsafeSlice<u32>,
+        #[global] max: UnsafeSlice<f32>,
+    ) {
+        use krnl_core::{buffer::UnsafeIndex, subgroup};
+
+        let global_id = kernel.global_id();
+        let x = x[global_id];
+        let x_sum = subgroup::reduce_add(x);
+        let x_max = subgroup::reduce_max(x as f32);
+        unsafe {
+            *ids.unsafe_index_mut(global_id) =
+                (kernel.group_id() * kernel.subgroups() + kernel.subgroup_id()) as u32;
+            *sum.unsafe_index_mut(global_id) = x_sum;
+            *max.unsafe_index_mut(global_id) = x_max;
+        }
+    }
+
+    #[kernel(threads(64))]
+    pub fn ballot_u32(
+        #[global] x: Slice<u32>,
+        #[global] ids: UnsafeSlice<u32>,
+        #[global] y: UnsafeSlice<u32>,
+    ) {
+        use krnl_core::{buffer::UnsafeIndex, subgroup};
+
+        let global_id = kernel.global_id();
+        let mask = subgroup::ballot(x[global_id] % 2 == 1);
+        let count =
+            mask.x.count_ones() + mask.y.count_ones() + mask.z.count_ones() + mask.w.count_ones();
+        unsafe {
+            *ids.unsafe_index_mut(global_id) =
+                (kernel.group_id() * kernel.subgroups() + kernel.subgroup_id()) as u32;
+            *y.unsafe_index_mut(global_id) = count;
+        }
+    }
+
+    #[test]
+    fn test_subgroup_ops() {
+        use krnl::device::Features;
+
+        for (features, features_true) in [
+            (
+                broadcast_u32::builder().unwrap().__features(),
+                Features::SUBGROUP_BALLOT,
+            ),
+            (
+                reduce_u32::builder().unwrap().__features(),
+                Features::SUBGROUP_ARITHMETIC,
+            ),
+            (
+                ballot_u32::builder().unwrap().__features(),
+                Features::SUBGROUP_BALLOT,
+            ),
+        ] {
+            assert!(features.contains(Features::SUBGROUP_BASIC.union(features_true)));
+        }
+    }
 }